@@ -104,37 +104,42 @@ fn process_context(ctx: &mut Ctx) {
 
         let strat = item.strategy.clone();
         item.strategy = match &item.strategy[..] {
-            "single" => format!("children().find_map({}::cast).unwrap()", item.r#type),
-            "nested_single" => format!("children().find(|i| i.kind() == SyntaxKind::{}).unwrap().children().find_map({}::cast).unwrap()", item.kind, item.r#type),
+            "single" => format!(
+                "children().find_map({0}::cast).unwrap_or_else(|| {0}::missing(&self.cst))",
+                item.r#type
+            ),
+            "nested_single" => format!(
+                "children().find(|i| i.kind() == SyntaxKind::{}).and_then(|i| i.children().find_map({1}::cast))\
+                .unwrap_or_else(|| {1}::missing(&self.cst))",
+                item.kind, item.r#type
+            ),
 
             "opt_single" => format!("children().find_map({}::cast)", item.r#type),
             "nested_opt_single" => format!(
-                "children().find(|i| i.kind() == SyntaxKind::{}).map(|i| i.children().find_map({}::cast)).flatten()",
+                "children().find(|i| i.kind() == SyntaxKind::{}).and_then(|i| i.children().find_map({}::cast))",
                 item.kind, item.r#type
             ),
 
             "list" => format!("children().filter_map({}::cast)", item.r#type),
             "nested_list" => format!(
-                "children().filter(|i| i.kind() == SyntaxKind::{}).map(|i| i.children().find_map({}::cast).unwrap())",
+                "children().filter(|i| i.kind() == SyntaxKind::{}).map(|i| i.children().find_map({1}::cast)\
+                .unwrap_or_else(|| {1}::missing(&i)))",
                 item.kind, item.r#type
             ),
 
-            "token" => "children_with_tokens()\
-            .find(|c| c.as_token().map(Token::kind).as_ref().map(SyntaxKind::is_token) == Some(true))\
-            .unwrap().as_token().unwrap().kind()".to_string(),
-            "nested_token" => format!("children().find(|i| i.kind() == SyntaxKind::{}).unwrap().children_with_tokens()\
-            .find(|c| c.as_token().map(Token::kind).as_ref().map(SyntaxKind::is_token) == Some(true))\
-            .unwrap().as_token().unwrap().kind()", item.kind),
-            "nested_token_list" => format!("children().filter(|i| i.kind() == SyntaxKind::{}).map(|c| c.children_with_tokens()\
-            .find(|c| c.as_token().map(Token::kind).as_ref().map(SyntaxKind::is_token) == Some(true)))\
-            .flatten().map(|c| c.as_token().unwrap().kind())", item.kind),
-
-            "ident" => "children_with_tokens()\
-            .find(|c| c.as_token().map(Token::kind) == Some(SyntaxKind::Identifier))\
-            .unwrap().as_token().unwrap().text().clone()".to_string(),
-            "ident_list" => "children_with_tokens()\
-            .filter(|c| c.as_token().map(Token::kind) == Some(SyntaxKind::Identifier))\
-            .map(|c| c.as_token().unwrap().text().clone())".to_string(),
+            "token" => "children_with_tokens().filter_map(|c| c.into_token()).map(|t| t.kind())\
+            .find(SyntaxKind::is_token).unwrap_or(SyntaxKind::Error)".to_string(),
+            "nested_token" => format!("children().find(|i| i.kind() == SyntaxKind::{}).and_then(|i| i.children_with_tokens()\
+            .filter_map(|c| c.into_token()).map(|t| t.kind()).find(SyntaxKind::is_token))\
+            .unwrap_or(SyntaxKind::Error)", item.kind),
+            "nested_token_list" => format!("children().filter(|i| i.kind() == SyntaxKind::{}).filter_map(|i| i.children_with_tokens()\
+            .filter_map(|c| c.into_token()).map(|t| t.kind()).find(SyntaxKind::is_token))", item.kind),
+
+            "ident" => "children_with_tokens().filter_map(|c| c.into_token())\
+            .find(|t| t.kind() == SyntaxKind::Identifier).map(|t| t.text().clone())\
+            .unwrap_or_default()".to_string(),
+            "ident_list" => "children_with_tokens().filter_map(|c| c.into_token())\
+            .filter(|t| t.kind() == SyntaxKind::Identifier).map(|t| t.text().clone())".to_string(),
 
             _ => item.strategy.clone()
         };
@@ -16,6 +16,14 @@ impl {{ name }} {
     pub fn cst(&self) -> CSTNode {
         self.cst.clone()
     }
+
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self { cst: parent.clone() }
+    }
     {% for item in items %}
     pub fn {{ item.name }}(&self) -> {{ item.type }} {
         self.cst.{{ item.strategy }}
@@ -1,6 +1,8 @@
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Expression {
     {% for item in items %}{{ item.name }}({{ item.type }}),{% endfor %}
+    /// An expression the parser did not produce, containing the parent's node.
+    Error(CSTNode),
 }
 
 impl Expression {
@@ -8,7 +10,7 @@ impl Expression {
     pub fn cast(node: CSTNode) -> Option<Self> {
         {% for item in items %}
         if node.kind() == SyntaxKind::{{ item.kind }} {
-            return Some(Self::{{ item.name }}({{ item.type }}::cast(node).unwrap()))
+            return {{ item.type }}::cast(node).map(Self::{{ item.name }})
         }{% endfor %}
         None
     }
@@ -16,6 +18,12 @@ impl Expression {
     pub fn cst(&self) -> CSTNode {
         match self {
             {% for item in items %}Self::{{ item.name }}(inner) => inner.cst(),{% endfor %}
+            Self::Error(cst) => cst.clone(),
         }
     }
+
+    /// Stands in for an expression the parser did not produce.
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self::Error(parent.clone())
+    }
 }
\ No newline at end of file
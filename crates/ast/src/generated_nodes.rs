@@ -2,7 +2,7 @@
 
 use crate::CSTNode;
 use common::{ModPath, ModulePath};
-use parser::{ParseResult, Token};
+use parser::ParseResult;
 use smol_str::SmolStr;
 use std::rc::Rc;
 use syntax::kind::SyntaxKind;
@@ -26,15 +26,23 @@ impl GenericIdent {
         self.cst.clone()
     }
 
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
     pub fn name(&self) -> SmolStr {
         self.cst
             .children_with_tokens()
-            .find(|c| c.as_token().map(Token::kind) == Some(SyntaxKind::Identifier))
-            .unwrap()
-            .as_token()
-            .unwrap()
-            .text()
-            .clone()
+            .filter_map(|c| c.into_token())
+            .find(|t| t.kind() == SyntaxKind::Identifier)
+            .map(|t| t.text().clone())
+            .unwrap_or_default()
     }
     pub fn type_args(&self) -> impl Iterator<Item = Type> + '_ {
         self.cst.children().filter_map(Type::cast)
@@ -60,15 +68,23 @@ impl DeclName {
         self.cst.clone()
     }
 
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
     pub fn name(&self) -> SmolStr {
         self.cst
             .children_with_tokens()
-            .find(|c| c.as_token().map(Token::kind) == Some(SyntaxKind::Identifier))
-            .unwrap()
-            .as_token()
-            .unwrap()
-            .text()
-            .clone()
+            .filter_map(|c| c.into_token())
+            .find(|t| t.kind() == SyntaxKind::Identifier)
+            .map(|t| t.text().clone())
+            .unwrap_or_default()
     }
     pub fn type_parameters(&self) -> impl Iterator<Item = TypeParameter> + '_ {
         self.cst.children().filter_map(TypeParameter::cast)
@@ -94,15 +110,23 @@ impl TypeParameter {
         self.cst.clone()
     }
 
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
     pub fn name(&self) -> SmolStr {
         self.cst
             .children_with_tokens()
-            .find(|c| c.as_token().map(Token::kind) == Some(SyntaxKind::Identifier))
-            .unwrap()
-            .as_token()
-            .unwrap()
-            .text()
-            .clone()
+            .filter_map(|c| c.into_token())
+            .find(|t| t.kind() == SyntaxKind::Identifier)
+            .map(|t| t.text().clone())
+            .unwrap_or_default()
     }
     pub fn bound(&self) -> Option<Type> {
         self.cst.children().find_map(Type::cast)
@@ -127,6 +151,16 @@ impl Type {
     pub fn cst(&self) -> CSTNode {
         self.cst.clone()
     }
+
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -147,6 +181,16 @@ impl Literal {
     pub fn cst(&self) -> CSTNode {
         self.cst.clone()
     }
+
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -176,6 +220,109 @@ impl Module {
     pub fn imports(&self) -> impl Iterator<Item = Import> + '_ {
         self.cst.children().filter_map(Import::cast)
     }
+    pub fn inits(&self) -> impl Iterator<Item = ModuleInit> + '_ {
+        self.cst.children().filter_map(ModuleInit::cast)
+    }
+    pub fn globals(&self) -> impl Iterator<Item = LazyGlobal> + '_ {
+        self.cst.children().filter_map(LazyGlobal::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct LazyGlobal {
+    pub cst: CSTNode,
+}
+impl LazyGlobal {
+    #[allow(unused)]
+    pub fn cast(node: CSTNode) -> Option<Self> {
+        if let SyntaxKind::GlobalDecl = node.kind() {
+            Some(Self { cst: node })
+        } else {
+            None
+        }
+    }
+
+    pub fn cst(&self) -> CSTNode {
+        self.cst.clone()
+    }
+
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
+    pub fn name(&self) -> SmolStr {
+        self.cst
+            .children_with_tokens()
+            .filter_map(|c| c.into_token())
+            .find(|t| t.kind() == SyntaxKind::Identifier)
+            .map(|t| t.text().clone())
+            .unwrap_or_default()
+    }
+    pub fn _type(&self) -> Option<Type> {
+        self.cst.children().find_map(Type::cast)
+    }
+    pub fn initializer(&self) -> Expression {
+        self.cst
+            .children()
+            .find(|i| i.kind() == SyntaxKind::Initializer)
+            .and_then(|i| i.children().find_map(Expression::cast))
+            .unwrap_or_else(|| Expression::missing(&self.cst))
+    }
+    pub fn modifiers(&self) -> impl Iterator<Item = SyntaxKind> + '_ {
+        self.cst
+            .children()
+            .filter(|i| i.kind() == SyntaxKind::Modifier)
+            .filter_map(|i| {
+                i.children_with_tokens()
+                    .filter_map(|c| c.into_token())
+                    .map(|t| t.kind())
+                    .find(SyntaxKind::is_token)
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ModuleInit {
+    pub cst: CSTNode,
+}
+impl ModuleInit {
+    #[allow(unused)]
+    pub fn cast(node: CSTNode) -> Option<Self> {
+        if let SyntaxKind::InitDecl = node.kind() {
+            Some(Self { cst: node })
+        } else {
+            None
+        }
+    }
+
+    pub fn cst(&self) -> CSTNode {
+        self.cst.clone()
+    }
+
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
+    pub fn body(&self) -> Expression {
+        self.cst
+            .children()
+            .find_map(Expression::cast)
+            .unwrap_or_else(|| Expression::missing(&self.cst))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -197,37 +344,43 @@ impl Adt {
         self.cst.clone()
     }
 
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
     pub fn kind(&self) -> SyntaxKind {
         self.cst
             .children_with_tokens()
-            .find(|c| {
-                c.as_token()
-                    .map(Token::kind)
-                    .as_ref()
-                    .map(SyntaxKind::is_token)
-                    == Some(true)
-            })
-            .map(|t| t.as_token().unwrap().kind())
+            .filter_map(|c| c.into_token())
+            .map(|t| t.kind())
+            .find(SyntaxKind::is_token)
             .unwrap_or(SyntaxKind::EnumCase)
     }
     pub fn name(&self) -> DeclName {
-        self.cst.children().find_map(DeclName::cast).unwrap()
+        self.cst
+            .children()
+            .find_map(DeclName::cast)
+            .unwrap_or_else(|| DeclName::missing(&self.cst))
     }
     pub fn modifiers(&self) -> impl Iterator<Item = SyntaxKind> + '_ {
         self.cst
             .children()
             .filter(|i| i.kind() == SyntaxKind::Modifier)
-            .map(|c| {
-                c.children_with_tokens().find(|c| {
-                    c.as_token()
-                        .map(Token::kind)
-                        .as_ref()
-                        .map(SyntaxKind::is_token)
-                        == Some(true)
-                })
+            .filter_map(|i| {
+                i.children_with_tokens()
+                    .filter_map(|c| c.into_token())
+                    .map(|t| t.kind())
+                    .find(SyntaxKind::is_token)
             })
-            .flatten()
-            .map(|c| c.as_token().unwrap().kind())
+    }
+    pub fn annotations(&self) -> impl Iterator<Item = Annotation> + '_ {
+        self.cst.children().filter_map(Annotation::cast)
     }
     pub fn members(&self) -> impl Iterator<Item = Variable> + '_ {
         self.cst.children().filter_map(Variable::cast)
@@ -241,6 +394,54 @@ impl Adt {
     pub fn cases(&self) -> impl Iterator<Item = Adt> + '_ {
         self.cst.children().filter_map(Adt::cast)
     }
+    pub fn discriminant(&self) -> Option<Literal> {
+        self.cst.children().find_map(Literal::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct Annotation {
+    pub cst: CSTNode,
+}
+impl Annotation {
+    #[allow(unused)]
+    pub fn cast(node: CSTNode) -> Option<Self> {
+        if let SyntaxKind::Annotation = node.kind() {
+            Some(Self { cst: node })
+        } else {
+            None
+        }
+    }
+
+    pub fn cst(&self) -> CSTNode {
+        self.cst.clone()
+    }
+
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
+    pub fn name(&self) -> SmolStr {
+        self.cst
+            .children_with_tokens()
+            .filter_map(|c| c.into_token())
+            .find(|t| t.kind() == SyntaxKind::Identifier)
+            .map(|t| t.text().clone())
+            .unwrap_or_default()
+    }
+    pub fn arguments(&self) -> impl Iterator<Item = Type> + '_ {
+        self.cst.children().filter_map(Type::cast)
+    }
+    pub fn literals(&self) -> impl Iterator<Item = Literal> + '_ {
+        self.cst.children().filter_map(Literal::cast)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -264,34 +465,41 @@ impl Function {
         self.cst.clone()
     }
 
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
     pub fn sig(&self) -> FunctionSignature {
         self.cst
             .children()
             .find_map(FunctionSignature::cast)
-            .unwrap()
+            .unwrap_or_else(|| FunctionSignature::missing(&self.cst))
     }
     pub fn modifiers(&self) -> impl Iterator<Item = SyntaxKind> + '_ {
         self.cst
             .children()
             .filter(|i| i.kind() == SyntaxKind::Modifier)
-            .map(|c| {
-                c.children_with_tokens().find(|c| {
-                    c.as_token()
-                        .map(Token::kind)
-                        .as_ref()
-                        .map(SyntaxKind::is_token)
-                        == Some(true)
-                })
+            .filter_map(|i| {
+                i.children_with_tokens()
+                    .filter_map(|c| c.into_token())
+                    .map(|t| t.kind())
+                    .find(SyntaxKind::is_token)
             })
-            .flatten()
-            .map(|c| c.as_token().unwrap().kind())
+    }
+    pub fn annotations(&self) -> impl Iterator<Item = Annotation> + '_ {
+        self.cst.children().filter_map(Annotation::cast)
     }
     pub fn body(&self) -> Option<Expression> {
         self.cst
             .children()
             .find(|i| i.kind() == SyntaxKind::FunctionBody)
-            .map(|i| i.children().find_map(Expression::cast))
-            .flatten()
+            .and_then(|i| i.children().find_map(Expression::cast))
     }
 }
 
@@ -314,8 +522,21 @@ impl FunctionSignature {
         self.cst.clone()
     }
 
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
     pub fn name(&self) -> DeclName {
-        self.cst.children().find_map(DeclName::cast).unwrap()
+        self.cst
+            .children()
+            .find_map(DeclName::cast)
+            .unwrap_or_else(|| DeclName::missing(&self.cst))
     }
     pub fn ret_type(&self) -> Option<Type> {
         self.cst.children().find_map(Type::cast)
@@ -344,18 +565,29 @@ impl Parameter {
         self.cst.clone()
     }
 
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
     pub fn name(&self) -> SmolStr {
         self.cst
             .children_with_tokens()
-            .find(|c| c.as_token().map(Token::kind) == Some(SyntaxKind::Identifier))
-            .unwrap()
-            .as_token()
-            .unwrap()
-            .text()
-            .clone()
+            .filter_map(|c| c.into_token())
+            .find(|t| t.kind() == SyntaxKind::Identifier)
+            .map(|t| t.text().clone())
+            .unwrap_or_default()
     }
     pub fn _type(&self) -> Type {
-        self.cst.children().find_map(Type::cast).unwrap()
+        self.cst
+            .children()
+            .find_map(Type::cast)
+            .unwrap_or_else(|| Type::missing(&self.cst))
     }
     pub fn maybe_type(&self) -> Option<Type> {
         self.cst.children().find_map(Type::cast)
@@ -380,6 +612,16 @@ impl Import {
     pub fn cst(&self) -> CSTNode {
         self.cst.clone()
     }
+
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -401,23 +643,29 @@ impl IfaceImpl {
         self.cst.clone()
     }
 
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
     pub fn implementor(&self) -> Type {
         self.cst
             .children()
             .find(|i| i.kind() == SyntaxKind::Implementor)
-            .unwrap()
-            .children()
-            .find_map(Type::cast)
-            .unwrap()
+            .and_then(|i| i.children().find_map(Type::cast))
+            .unwrap_or_else(|| Type::missing(&self.cst))
     }
     pub fn iface(&self) -> Type {
         self.cst
             .children()
             .find(|i| i.kind() == SyntaxKind::Implementing)
-            .unwrap()
-            .children()
-            .find_map(Type::cast)
-            .unwrap()
+            .and_then(|i| i.children().find_map(Type::cast))
+            .unwrap_or_else(|| Type::missing(&self.cst))
     }
     pub fn methods(&self) -> impl Iterator<Item = Function> + '_ {
         self.cst.children().filter_map(Function::cast)
@@ -426,11 +674,16 @@ impl IfaceImpl {
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Expression {
+    Array(Array),
+    ArrayRepeat(ArrayRepeat),
     Binary(Binary),
     Block(Block),
     Break(Break),
     Call(Call),
+    Defer(Defer),
+    Destructure(Destructure),
     For(ForExpr),
+    FuncDef(Function),
     Get(Get),
     GetNullable(Get),
     GetStatic(GetStatic),
@@ -440,75 +693,105 @@ pub enum Expression {
     LiteralClosure(Function),
     Prefix(Prefix),
     Return(Return),
+    Try(Try),
+    Tuple(Tuple),
     Variable(GenericIdent),
     VarDef(Variable),
     When(When),
+    /// An expression the parser did not produce, containing the parent's node.
+    Error(CSTNode),
 }
 
 impl Expression {
     #[allow(unused)]
     pub fn cast(node: CSTNode) -> Option<Self> {
+        if node.kind() == SyntaxKind::ArrayLiteral {
+            return Array::cast(node).map(Self::Array);
+        }
+        if node.kind() == SyntaxKind::ArrayRepeatLiteral {
+            return ArrayRepeat::cast(node).map(Self::ArrayRepeat);
+        }
         if node.kind() == SyntaxKind::BinaryExpr {
-            return Some(Self::Binary(Binary::cast(node).unwrap()));
+            return Binary::cast(node).map(Self::Binary);
         }
         if node.kind() == SyntaxKind::Block {
-            return Some(Self::Block(Block::cast(node).unwrap()));
+            return Block::cast(node).map(Self::Block);
         }
         if node.kind() == SyntaxKind::BreakExpr {
-            return Some(Self::Break(Break::cast(node).unwrap()));
+            return Break::cast(node).map(Self::Break);
         }
         if node.kind() == SyntaxKind::CallExpr {
-            return Some(Self::Call(Call::cast(node).unwrap()));
+            return Call::cast(node).map(Self::Call);
+        }
+        if node.kind() == SyntaxKind::DeferExpr {
+            return Defer::cast(node).map(Self::Defer);
+        }
+        if node.kind() == SyntaxKind::Destructure {
+            return Destructure::cast(node).map(Self::Destructure);
         }
         if node.kind() == SyntaxKind::ForExpr {
-            return Some(Self::For(ForExpr::cast(node).unwrap()));
+            return ForExpr::cast(node).map(Self::For);
+        }
+        if node.kind() == SyntaxKind::FunctionDecl {
+            return Function::cast(node).map(Self::FuncDef);
         }
         if node.kind() == SyntaxKind::GetExpr {
-            return Some(Self::Get(Get::cast(node).unwrap()));
+            return Get::cast(node).map(Self::Get);
         }
         if node.kind() == SyntaxKind::GetNullableExpr {
-            return Some(Self::GetNullable(Get::cast(node).unwrap()));
+            return Get::cast(node).map(Self::GetNullable);
         }
         if node.kind() == SyntaxKind::GetStaticExpr {
-            return Some(Self::GetStatic(GetStatic::cast(node).unwrap()));
+            return GetStatic::cast(node).map(Self::GetStatic);
         }
         if node.kind() == SyntaxKind::Grouping {
-            return Some(Self::Grouping(Grouping::cast(node).unwrap()));
+            return Grouping::cast(node).map(Self::Grouping);
         }
         if node.kind() == SyntaxKind::IfExpr {
-            return Some(Self::If(IfExpr::cast(node).unwrap()));
+            return IfExpr::cast(node).map(Self::If);
         }
         if node.kind() == SyntaxKind::Literal {
-            return Some(Self::Literal(Literal::cast(node).unwrap()));
+            return Literal::cast(node).map(Self::Literal);
         }
         if node.kind() == SyntaxKind::ClosureLiteral {
-            return Some(Self::LiteralClosure(Function::cast(node).unwrap()));
+            return Function::cast(node).map(Self::LiteralClosure);
         }
         if node.kind() == SyntaxKind::PrefixExpr {
-            return Some(Self::Prefix(Prefix::cast(node).unwrap()));
+            return Prefix::cast(node).map(Self::Prefix);
         }
         if node.kind() == SyntaxKind::ReturnExpr {
-            return Some(Self::Return(Return::cast(node).unwrap()));
+            return Return::cast(node).map(Self::Return);
+        }
+        if node.kind() == SyntaxKind::TryExpr {
+            return Try::cast(node).map(Self::Try);
+        }
+        if node.kind() == SyntaxKind::TupleLiteral {
+            return Tuple::cast(node).map(Self::Tuple);
         }
         if node.kind() == SyntaxKind::Ident {
-            return Some(Self::Variable(GenericIdent::cast(node).unwrap()));
+            return GenericIdent::cast(node).map(Self::Variable);
         }
         if node.kind() == SyntaxKind::Variable {
-            return Some(Self::VarDef(Variable::cast(node).unwrap()));
+            return Variable::cast(node).map(Self::VarDef);
         }
         if node.kind() == SyntaxKind::WhenExpr {
-            return Some(Self::When(When::cast(node).unwrap()));
+            return When::cast(node).map(Self::When);
         }
         None
     }
 
     pub fn cst(&self) -> CSTNode {
         match self {
+            Self::Array(inner) => inner.cst(),
+            Self::ArrayRepeat(inner) => inner.cst(),
             Self::Binary(inner) => inner.cst(),
             Self::Block(inner) => inner.cst(),
             Self::Break(inner) => inner.cst(),
             Self::Call(inner) => inner.cst(),
+            Self::Defer(inner) => inner.cst(),
+            Self::Destructure(inner) => inner.cst(),
             Self::For(inner) => inner.cst(),
+            Self::FuncDef(inner) => inner.cst(),
             Self::Get(inner) => inner.cst(),
             Self::GetNullable(inner) => inner.cst(),
             Self::GetStatic(inner) => inner.cst(),
@@ -518,11 +801,19 @@ impl Expression {
             Self::LiteralClosure(inner) => inner.cst(),
             Self::Prefix(inner) => inner.cst(),
             Self::Return(inner) => inner.cst(),
+            Self::Try(inner) => inner.cst(),
+            Self::Tuple(inner) => inner.cst(),
             Self::Variable(inner) => inner.cst(),
             Self::VarDef(inner) => inner.cst(),
             Self::When(inner) => inner.cst(),
+            Self::Error(cst) => cst.clone(),
         }
     }
+
+    /// Stands in for an expression the parser did not produce.
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self::Error(parent.clone())
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -544,30 +835,31 @@ impl Variable {
         self.cst.clone()
     }
 
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
     pub fn kind(&self) -> SyntaxKind {
         self.cst
             .children_with_tokens()
-            .find(|c| {
-                c.as_token()
-                    .map(Token::kind)
-                    .as_ref()
-                    .map(SyntaxKind::is_token)
-                    == Some(true)
-            })
-            .unwrap()
-            .as_token()
-            .unwrap()
-            .kind()
+            .filter_map(|c| c.into_token())
+            .map(|t| t.kind())
+            .find(SyntaxKind::is_token)
+            .unwrap_or(SyntaxKind::Error)
     }
     pub fn name(&self) -> SmolStr {
         self.cst
             .children_with_tokens()
-            .find(|c| c.as_token().map(Token::kind) == Some(SyntaxKind::Identifier))
-            .unwrap()
-            .as_token()
-            .unwrap()
-            .text()
-            .clone()
+            .filter_map(|c| c.into_token())
+            .find(|t| t.kind() == SyntaxKind::Identifier)
+            .map(|t| t.text().clone())
+            .unwrap_or_default()
     }
     pub fn _type(&self) -> Option<Type> {
         self.cst.children().find_map(Type::cast)
@@ -576,33 +868,70 @@ impl Variable {
         self.cst
             .children()
             .find(|i| i.kind() == SyntaxKind::Initializer)
-            .unwrap()
-            .children()
-            .find_map(Expression::cast)
-            .unwrap()
+            .and_then(|i| i.children().find_map(Expression::cast))
+            .unwrap_or_else(|| Expression::missing(&self.cst))
     }
     pub fn maybe_initializer(&self) -> Option<Expression> {
         self.cst
             .children()
             .find(|i| i.kind() == SyntaxKind::Initializer)
-            .map(|i| i.children().find_map(Expression::cast))
-            .flatten()
+            .and_then(|i| i.children().find_map(Expression::cast))
     }
     pub fn modifiers(&self) -> impl Iterator<Item = SyntaxKind> + '_ {
         self.cst
             .children()
             .filter(|i| i.kind() == SyntaxKind::Modifier)
-            .map(|c| {
-                c.children_with_tokens().find(|c| {
-                    c.as_token()
-                        .map(Token::kind)
-                        .as_ref()
-                        .map(SyntaxKind::is_token)
-                        == Some(true)
-                })
+            .filter_map(|i| {
+                i.children_with_tokens()
+                    .filter_map(|c| c.into_token())
+                    .map(|t| t.kind())
+                    .find(SyntaxKind::is_token)
             })
-            .flatten()
-            .map(|c| c.as_token().unwrap().kind())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct Destructure {
+    pub cst: CSTNode,
+}
+impl Destructure {
+    #[allow(unused)]
+    pub fn cast(node: CSTNode) -> Option<Self> {
+        if let SyntaxKind::Destructure = node.kind() {
+            Some(Self { cst: node })
+        } else {
+            None
+        }
+    }
+
+    pub fn cst(&self) -> CSTNode {
+        self.cst.clone()
+    }
+
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = SmolStr> + '_ {
+        self.cst
+            .children_with_tokens()
+            .filter_map(|c| c.into_token())
+            .filter(|t| t.kind() == SyntaxKind::Identifier)
+            .map(|t| t.text().clone())
+    }
+    pub fn initializer(&self) -> Expression {
+        self.cst
+            .children()
+            .find(|i| i.kind() == SyntaxKind::Initializer)
+            .and_then(|i| i.children().find_map(Expression::cast))
+            .unwrap_or_else(|| Expression::missing(&self.cst))
     }
 }
 
@@ -625,8 +954,134 @@ impl Grouping {
         self.cst.clone()
     }
 
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
     pub fn inner(&self) -> Expression {
-        self.cst.children().find_map(Expression::cast).unwrap()
+        self.cst
+            .children()
+            .find_map(Expression::cast)
+            .unwrap_or_else(|| Expression::missing(&self.cst))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct Tuple {
+    pub cst: CSTNode,
+}
+impl Tuple {
+    #[allow(unused)]
+    pub fn cast(node: CSTNode) -> Option<Self> {
+        if let SyntaxKind::TupleLiteral = node.kind() {
+            Some(Self { cst: node })
+        } else {
+            None
+        }
+    }
+
+    pub fn cst(&self) -> CSTNode {
+        self.cst.clone()
+    }
+
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = Expression> + '_ {
+        self.cst.children().filter_map(Expression::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct Array {
+    pub cst: CSTNode,
+}
+impl Array {
+    #[allow(unused)]
+    pub fn cast(node: CSTNode) -> Option<Self> {
+        if let SyntaxKind::ArrayLiteral = node.kind() {
+            Some(Self { cst: node })
+        } else {
+            None
+        }
+    }
+
+    pub fn cst(&self) -> CSTNode {
+        self.cst.clone()
+    }
+
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = Expression> + '_ {
+        self.cst.children().filter_map(Expression::cast)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct ArrayRepeat {
+    pub cst: CSTNode,
+}
+impl ArrayRepeat {
+    #[allow(unused)]
+    pub fn cast(node: CSTNode) -> Option<Self> {
+        if let SyntaxKind::ArrayRepeatLiteral = node.kind() {
+            Some(Self { cst: node })
+        } else {
+            None
+        }
+    }
+
+    pub fn cst(&self) -> CSTNode {
+        self.cst.clone()
+    }
+
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
+    pub fn value(&self) -> Expression {
+        self.cst
+            .children()
+            .next()
+            .and_then(Expression::cast)
+            .unwrap_or_else(|| Expression::missing(&self.cst))
+    }
+    pub fn length(&self) -> Literal {
+        self.cst
+            .children()
+            .nth(1)
+            .and_then(Literal::cast)
+            .unwrap_or_else(|| Literal::missing(&self.cst))
     }
 }
 
@@ -649,39 +1104,41 @@ impl Binary {
         self.cst.clone()
     }
 
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
     pub fn operator(&self) -> SyntaxKind {
         self.cst
             .children()
             .find(|i| i.kind() == SyntaxKind::Operator)
-            .unwrap()
-            .children_with_tokens()
-            .find(|c| {
-                c.as_token()
-                    .map(Token::kind)
-                    .as_ref()
-                    .map(SyntaxKind::is_token)
-                    == Some(true)
+            .and_then(|i| {
+                i.children_with_tokens()
+                    .filter_map(|c| c.into_token())
+                    .map(|t| t.kind())
+                    .find(SyntaxKind::is_token)
             })
-            .unwrap()
-            .as_token()
-            .unwrap()
-            .kind()
+            .unwrap_or(SyntaxKind::Error)
     }
     pub fn left(&self) -> Expression {
         self.cst
             .children()
             .next()
-            .map(Expression::cast)
-            .unwrap()
-            .unwrap()
+            .and_then(Expression::cast)
+            .unwrap_or_else(|| Expression::missing(&self.cst))
     }
     pub fn right(&self) -> Expression {
         self.cst
             .children()
             .nth(2)
-            .map(Expression::cast)
-            .unwrap()
-            .unwrap()
+            .and_then(Expression::cast)
+            .unwrap_or_else(|| Expression::missing(&self.cst))
     }
 }
 
@@ -704,31 +1161,34 @@ impl Prefix {
         self.cst.clone()
     }
 
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
     pub fn operator(&self) -> SyntaxKind {
         self.cst
             .children()
             .find(|i| i.kind() == SyntaxKind::Operator)
-            .unwrap()
-            .children_with_tokens()
-            .find(|c| {
-                c.as_token()
-                    .map(Token::kind)
-                    .as_ref()
-                    .map(SyntaxKind::is_token)
-                    == Some(true)
+            .and_then(|i| {
+                i.children_with_tokens()
+                    .filter_map(|c| c.into_token())
+                    .map(|t| t.kind())
+                    .find(SyntaxKind::is_token)
             })
-            .unwrap()
-            .as_token()
-            .unwrap()
-            .kind()
+            .unwrap_or(SyntaxKind::Error)
     }
     pub fn right(&self) -> Expression {
         self.cst
             .children()
             .nth(1)
-            .map(Expression::cast)
-            .unwrap()
-            .unwrap()
+            .and_then(Expression::cast)
+            .unwrap_or_else(|| Expression::missing(&self.cst))
     }
 }
 
@@ -751,20 +1211,32 @@ impl Call {
         self.cst.clone()
     }
 
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
     pub fn callee(&self) -> Expression {
         self.cst
             .children()
             .find(|i| i.kind() == SyntaxKind::Callee)
-            .unwrap()
-            .children()
-            .find_map(Expression::cast)
-            .unwrap()
+            .and_then(|i| i.children().find_map(Expression::cast))
+            .unwrap_or_else(|| Expression::missing(&self.cst))
     }
     pub fn args(&self) -> impl Iterator<Item = Expression> + '_ {
         self.cst
             .children()
             .filter(|i| i.kind() == SyntaxKind::CallArgument)
-            .map(|i| i.children().find_map(Expression::cast).unwrap())
+            .map(|i| {
+                i.children()
+                    .find_map(Expression::cast)
+                    .unwrap_or_else(|| Expression::missing(&i))
+            })
     }
 }
 
@@ -787,17 +1259,28 @@ impl Get {
         self.cst.clone()
     }
 
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
     pub fn callee(&self) -> Expression {
         self.cst
             .children()
             .find(|i| i.kind() == SyntaxKind::Callee)
-            .unwrap()
-            .children()
-            .find_map(Expression::cast)
-            .unwrap()
+            .and_then(|i| i.children().find_map(Expression::cast))
+            .unwrap_or_else(|| Expression::missing(&self.cst))
     }
     pub fn property(&self) -> GenericIdent {
-        self.cst.children().find_map(GenericIdent::cast).unwrap()
+        self.cst
+            .children()
+            .find_map(GenericIdent::cast)
+            .unwrap_or_else(|| GenericIdent::missing(&self.cst))
     }
 }
 
@@ -820,24 +1303,30 @@ impl GetStatic {
         self.cst.clone()
     }
 
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
     pub fn callee(&self) -> Expression {
         self.cst
             .children()
             .find(|i| i.kind() == SyntaxKind::Callee)
-            .unwrap()
-            .children()
-            .find_map(Expression::cast)
-            .unwrap()
+            .and_then(|i| i.children().find_map(Expression::cast))
+            .unwrap_or_else(|| Expression::missing(&self.cst))
     }
     pub fn property(&self) -> SmolStr {
         self.cst
             .children_with_tokens()
-            .find(|c| c.as_token().map(Token::kind) == Some(SyntaxKind::Identifier))
-            .unwrap()
-            .as_token()
-            .unwrap()
-            .text()
-            .clone()
+            .filter_map(|c| c.into_token())
+            .find(|t| t.kind() == SyntaxKind::Identifier)
+            .map(|t| t.text().clone())
+            .unwrap_or_default()
     }
 }
 
@@ -860,6 +1349,16 @@ impl Block {
         self.cst.clone()
     }
 
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
     pub fn expressions(&self) -> impl Iterator<Item = Expression> + '_ {
         self.cst.children().filter_map(Expression::cast)
     }
@@ -884,30 +1383,35 @@ impl IfExpr {
         self.cst.clone()
     }
 
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
     pub fn condition(&self) -> Expression {
         self.cst
             .children()
             .find(|i| i.kind() == SyntaxKind::ExprCondition)
-            .unwrap()
-            .children()
-            .find_map(Expression::cast)
-            .unwrap()
+            .and_then(|i| i.children().find_map(Expression::cast))
+            .unwrap_or_else(|| Expression::missing(&self.cst))
     }
     pub fn then_branch(&self) -> Expression {
         self.cst
             .children()
             .find(|i| i.kind() == SyntaxKind::ExprBody)
-            .unwrap()
-            .children()
-            .find_map(Expression::cast)
-            .unwrap()
+            .and_then(|i| i.children().find_map(Expression::cast))
+            .unwrap_or_else(|| Expression::missing(&self.cst))
     }
     pub fn else_branch(&self) -> Option<Expression> {
         self.cst
             .children()
             .find(|i| i.kind() == SyntaxKind::ExprElse)
-            .map(|i| i.children().find_map(Expression::cast))
-            .flatten()
+            .and_then(|i| i.children().find_map(Expression::cast))
     }
 }
 
@@ -930,12 +1434,21 @@ impl ForExpr {
         self.cst.clone()
     }
 
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
     pub fn condition(&self) -> Option<Expression> {
         self.cst
             .children()
             .find(|i| i.kind() == SyntaxKind::ExprCondition)
-            .map(|i| i.children().find_map(Expression::cast))
-            .flatten()
+            .and_then(|i| i.children().find_map(Expression::cast))
     }
     pub fn iter_cond(&self) -> Option<ForIterCond> {
         self.cst.children().find_map(ForIterCond::cast)
@@ -944,17 +1457,14 @@ impl ForExpr {
         self.cst
             .children()
             .find(|i| i.kind() == SyntaxKind::ExprBody)
-            .unwrap()
-            .children()
-            .find_map(Expression::cast)
-            .unwrap()
+            .and_then(|i| i.children().find_map(Expression::cast))
+            .unwrap_or_else(|| Expression::missing(&self.cst))
     }
     pub fn else_branch(&self) -> Option<Expression> {
         self.cst
             .children()
             .find(|i| i.kind() == SyntaxKind::ExprElse)
-            .map(|i| i.children().find_map(Expression::cast))
-            .flatten()
+            .and_then(|i| i.children().find_map(Expression::cast))
     }
 }
 
@@ -977,18 +1487,29 @@ impl ForIterCond {
         self.cst.clone()
     }
 
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
     pub fn name(&self) -> SmolStr {
         self.cst
             .children_with_tokens()
-            .find(|c| c.as_token().map(Token::kind) == Some(SyntaxKind::Identifier))
-            .unwrap()
-            .as_token()
-            .unwrap()
-            .text()
-            .clone()
+            .filter_map(|c| c.into_token())
+            .find(|t| t.kind() == SyntaxKind::Identifier)
+            .map(|t| t.text().clone())
+            .unwrap_or_default()
     }
     pub fn iterator(&self) -> Expression {
-        self.cst.children().find_map(Expression::cast).unwrap()
+        self.cst
+            .children()
+            .find_map(Expression::cast)
+            .unwrap_or_else(|| Expression::missing(&self.cst))
     }
 }
 
@@ -1011,6 +1532,16 @@ impl Return {
         self.cst.clone()
     }
 
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
     pub fn value(&self) -> Option<Expression> {
         self.cst.children().find_map(Expression::cast)
     }
@@ -1035,11 +1566,95 @@ impl Break {
         self.cst.clone()
     }
 
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
     pub fn value(&self) -> Option<Expression> {
         self.cst.children().find_map(Expression::cast)
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct Defer {
+    pub cst: CSTNode,
+}
+impl Defer {
+    #[allow(unused)]
+    pub fn cast(node: CSTNode) -> Option<Self> {
+        if let SyntaxKind::DeferExpr = node.kind() {
+            Some(Self { cst: node })
+        } else {
+            None
+        }
+    }
+
+    pub fn cst(&self) -> CSTNode {
+        self.cst.clone()
+    }
+
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
+    pub fn value(&self) -> Expression {
+        self.cst
+            .children()
+            .find_map(Expression::cast)
+            .unwrap_or_else(|| Expression::missing(&self.cst))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct Try {
+    pub cst: CSTNode,
+}
+impl Try {
+    #[allow(unused)]
+    pub fn cast(node: CSTNode) -> Option<Self> {
+        if let SyntaxKind::TryExpr = node.kind() {
+            Some(Self { cst: node })
+        } else {
+            None
+        }
+    }
+
+    pub fn cst(&self) -> CSTNode {
+        self.cst.clone()
+    }
+
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
+    pub fn value(&self) -> Expression {
+        self.cst
+            .children()
+            .find_map(Expression::cast)
+            .unwrap_or_else(|| Expression::missing(&self.cst))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[repr(transparent)]
 pub struct When {
@@ -1059,14 +1674,22 @@ impl When {
         self.cst.clone()
     }
 
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
     pub fn condition(&self) -> Expression {
         self.cst
             .children()
             .find(|i| i.kind() == SyntaxKind::ExprCondition)
-            .unwrap()
-            .children()
-            .find_map(Expression::cast)
-            .unwrap()
+            .and_then(|i| i.children().find_map(Expression::cast))
+            .unwrap_or_else(|| Expression::missing(&self.cst))
     }
     pub fn branches(&self) -> impl Iterator<Item = WhenBranch> + '_ {
         self.cst.children().filter_map(WhenBranch::cast)
@@ -1075,8 +1698,7 @@ impl When {
         self.cst
             .children()
             .find(|i| i.kind() == SyntaxKind::ExprElse)
-            .map(|i| i.children().find_map(Expression::cast))
-            .flatten()
+            .and_then(|i| i.children().find_map(Expression::cast))
     }
 }
 
@@ -1099,22 +1721,28 @@ impl WhenBranch {
         self.cst.clone()
     }
 
+    /// Stands in for a child the parser did not produce, using the parent's node.
+    /// The parser reports an error for all incomplete syntax,
+    /// so this node is never lowered.
+    #[allow(unused)]
+    pub(crate) fn missing(parent: &CSTNode) -> Self {
+        Self {
+            cst: parent.clone(),
+        }
+    }
+
     pub fn condition(&self) -> Expression {
         self.cst
             .children()
             .find(|i| i.kind() == SyntaxKind::ExprCondition)
-            .unwrap()
-            .children()
-            .find_map(Expression::cast)
-            .unwrap()
+            .and_then(|i| i.children().find_map(Expression::cast))
+            .unwrap_or_else(|| Expression::missing(&self.cst))
     }
     pub fn branch(&self) -> Expression {
         self.cst
             .children()
             .find(|i| i.kind() == SyntaxKind::ExprBody)
-            .unwrap()
-            .children()
-            .find_map(Expression::cast)
-            .unwrap()
+            .and_then(|i| i.children().find_map(Expression::cast))
+            .unwrap_or_else(|| Expression::missing(&self.cst))
     }
 }
@@ -3,11 +3,22 @@ pub use literal::LiteralType;
 use smol_str::SmolStr;
 pub use types::TypeE;
 
+use error::{Error, ErrorSpan, GErr};
 use parser::{Node, Token};
 use syntax::kind::SyntaxKind;
 
 pub type CSTNode = Node;
 
+/// Returns an error for a node that is missing parts it should have.
+/// The parser reports an error for all incomplete syntax,
+/// so this only occurs when it produced an unexpected tree.
+fn malformed(cst: &CSTNode) -> Error {
+    Error {
+        index: ErrorSpan::Span(cst.text_range()),
+        kind: GErr::E011,
+    }
+}
+
 mod generated_nodes;
 mod literal;
 mod types;
@@ -30,6 +41,31 @@ impl Import {
             })
             .map(|c| c.as_token().unwrap().text().clone())
     }
+
+    /// The names imported by a selective import like `import foo/(a, b)`.
+    /// Empty for all other imports.
+    pub fn selected(&self) -> Vec<SmolStr> {
+        Self::list_names(&self.cst)
+    }
+
+    /// The names excluded from a wildcard import with `hiding (a, b)`.
+    pub fn hidden(&self) -> Vec<SmolStr> {
+        self.cst
+            .children()
+            .find(|c| c.kind() == SyntaxKind::ImportHiding)
+            .map(|hiding| Self::list_names(&hiding))
+            .unwrap_or_default()
+    }
+
+    fn list_names(node: &CSTNode) -> Vec<SmolStr> {
+        node.children()
+            .filter(|c| c.kind() == SyntaxKind::ImportList)
+            .flat_map(|list| list.children_with_tokens().collect::<Vec<_>>())
+            .filter_map(|c| c.into_token())
+            .filter(|t| t.kind() == SyntaxKind::Identifier)
+            .map(|t| t.text().clone())
+            .collect()
+    }
 }
 
 impl Function {
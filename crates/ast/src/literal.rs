@@ -4,7 +4,8 @@
  * This file is under the Apache 2.0 license. See LICENSE in the root of this repository for details.
  */
 
-use crate::Literal;
+use crate::{malformed, Literal};
+use error::Res;
 use smol_str::SmolStr;
 use syntax::kind::SyntaxKind;
 
@@ -19,10 +20,16 @@ pub enum LiteralType {
 }
 
 impl Literal {
-    #[allow(unused)]
-    pub fn get(&self) -> (SmolStr, LiteralType) {
-        let child = self.cst.children_with_tokens().next().unwrap();
-        let token = child.as_token().unwrap();
+    pub fn get(&self) -> Res<(SmolStr, LiteralType)> {
+        if self.cst.kind() != SyntaxKind::Literal {
+            return Err(malformed(&self.cst));
+        }
+        let token = self
+            .cst
+            .children_with_tokens()
+            .next()
+            .and_then(|child| child.into_token())
+            .ok_or_else(|| malformed(&self.cst))?;
         let kind = match token.kind() {
             SyntaxKind::Null => LiteralType::Null,
             SyntaxKind::False => LiteralType::False,
@@ -30,8 +37,8 @@ impl Literal {
             SyntaxKind::Int => LiteralType::Int,
             SyntaxKind::Float => LiteralType::Float,
             SyntaxKind::String => LiteralType::String,
-            _ => panic!("AST encountered unknown CST literal"),
+            _ => return Err(malformed(&self.cst)),
         };
-        (token.text().clone(), kind)
+        Ok((token.text().clone(), kind))
     }
 }
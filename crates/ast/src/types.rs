@@ -1,16 +1,22 @@
-use crate::{Type, Variable};
+use crate::{malformed, Destructure, Type, Variable};
+use error::Res;
 use parser::Token;
 use smol_str::SmolStr;
 use syntax::kind::SyntaxKind;
 
 impl Type {
-    pub fn get(&self) -> TypeE {
-        if self.cst.last_token().unwrap().kind() == SyntaxKind::QuestionMark {
-            return TypeE::Nullable(self.cst.first_child().map(Self::cast).unwrap().unwrap());
+    /// Returns what kind of type this is.
+    /// Errors if the type is incomplete.
+    pub fn get(&self) -> Res<TypeE> {
+        if self.cst.kind() != SyntaxKind::Type {
+            return Err(malformed(&self.cst));
+        }
+        let token = self.cst.first_token().ok_or_else(|| malformed(&self.cst))?;
+        if self.cst.last_token().map(|t| t.kind()) == Some(SyntaxKind::QuestionMark) {
+            return Ok(TypeE::Nullable(self.inner()?));
         }
 
-        let token = self.cst.first_token().unwrap();
-        match token.kind() {
+        Ok(match token.kind() {
             SyntaxKind::Identifier if self.cst.first_child().is_none() => {
                 // No children => only identifier
                 TypeE::Ident(token.text().clone())
@@ -25,20 +31,51 @@ impl Type {
                 }
             }
 
-            SyntaxKind::Star => {
-                TypeE::RawPtr(self.cst.first_child().map(Self::cast).unwrap().unwrap())
+            SyntaxKind::Star => TypeE::RawPtr(self.inner()?),
+
+            SyntaxKind::Weak => TypeE::Weak(self.inner()?),
+
+            SyntaxKind::LeftBracket => {
+                let len = self
+                    .cst
+                    .children_with_tokens()
+                    .filter_map(|c| c.into_token())
+                    .find(|t| t.kind() == SyntaxKind::Int)
+                    .map(|t| t.text().clone())
+                    .unwrap_or_default();
+                TypeE::Array {
+                    ty: self.inner()?,
+                    len,
+                }
             }
 
             SyntaxKind::LeftParen => {
                 let mut types: Vec<_> = self.cst.children().filter_map(Type::cast).collect();
+                let has_ret_type = self
+                    .cst
+                    .children_with_tokens()
+                    .any(|c| c.as_token().map(Token::kind) == Some(SyntaxKind::Colon));
+                if types.len() > 1 && !has_ret_type {
+                    // Multiple types without a return type, like '(i64, String)'
+                    return Ok(TypeE::Tuple(types));
+                }
+
                 TypeE::Closure {
                     ret_type: types.pop(),
                     params: types,
                 }
             }
 
-            _ => panic!("Cannot parse type"),
-        }
+            _ => return Err(malformed(&self.cst)),
+        })
+    }
+
+    /// Returns the type inside of this one, like the `T` in `*T`.
+    fn inner(&self) -> Res<Type> {
+        self.cst
+            .first_child()
+            .and_then(Self::cast)
+            .ok_or_else(|| malformed(&self.cst))
     }
 }
 
@@ -46,6 +83,12 @@ pub enum TypeE {
     Ident(SmolStr),
     Nullable(Type),
     RawPtr(Type),
+    Weak(Type),
+    Tuple(Vec<Type>),
+    Array {
+        ty: Type,
+        len: SmolStr,
+    },
 
     Closure {
         params: Vec<Type>,
@@ -65,3 +108,11 @@ impl Variable {
             .any(|c| c.as_token().map(Token::kind) == Some(SyntaxKind::Var))
     }
 }
+
+impl Destructure {
+    pub fn mutable(&self) -> bool {
+        self.cst
+            .children_with_tokens()
+            .any(|c| c.as_token().map(Token::kind) == Some(SyntaxKind::Var))
+    }
+}
@@ -1,8 +1,12 @@
 use indexmap::map::IndexMap;
 use lazy_static::lazy_static;
 use std::{
+    alloc::{GlobalAlloc, Layout, System},
     fmt,
-    sync::Mutex,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
     time::{Duration, Instant},
 };
 
@@ -28,6 +32,17 @@ mod bench_macro {
                 $bench
             }
         }};
+
+        // Records the statistics of a pass in the given `Option<Profile>`, if any.
+        // The pass must return the number of nodes it processed.
+        ($profile:expr, $name:expr, $bench:expr) => {{
+            match $profile.as_mut() {
+                Some(profile) => profile.measure($name, || $bench),
+                None => {
+                    $bench;
+                }
+            }
+        }};
     }
 }
 
@@ -117,3 +132,140 @@ impl fmt::Display for Bench {
         )
     }
 }
+
+/// The number of allocations made through [CountingAllocator].
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+/// If [CountingAllocator] counts allocations, see [count_allocations].
+static COUNTING: AtomicBool = AtomicBool::new(false);
+
+/// The system allocator, counting all allocations made while
+/// counting is enabled with [count_allocations].
+/// Drivers can install it with `#[global_allocator]` to have
+/// allocation counts in [Profile]s; they are 0 otherwise.
+pub struct CountingAllocator;
+
+impl CountingAllocator {
+    fn count() {
+        if COUNTING.load(Ordering::Relaxed) {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        Self::count();
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        Self::count();
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        Self::count();
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+/// Enables or disables counting allocations in [CountingAllocator].
+/// Disabled by default, as counting slows down every allocation.
+pub fn count_allocations(enable: bool) {
+    COUNTING.store(enable, Ordering::Relaxed);
+}
+
+/// Returns the number of allocations made so far, see [CountingAllocator].
+pub fn allocations() -> usize {
+    ALLOCATIONS.load(Ordering::Relaxed)
+}
+
+/// Statistics of a compiler pass, see [Profile].
+#[derive(Clone, Debug)]
+pub struct PassStats {
+    pub name: &'static str,
+    /// The wall time the pass took.
+    pub time: Duration,
+    /// The number of allocations made during the pass.
+    pub allocations: usize,
+    /// The number of modules or declarations the pass processed;
+    /// 0 for passes over the whole program.
+    pub nodes: usize,
+}
+
+/// Statistics of every pass of a compilation, in the order they ran in.
+/// Unlike [Benches], they are recorded for a single compilation
+/// and only when requested. Displayed as a table.
+#[derive(Clone, Debug, Default)]
+pub struct Profile {
+    pub passes: Vec<PassStats>,
+}
+
+impl Profile {
+    /// Runs the given pass, which returns the number of nodes it processed,
+    /// and records its statistics.
+    pub fn measure<F: FnOnce() -> usize>(&mut self, name: &'static str, pass: F) {
+        let allocations_before = allocations();
+        let start = Instant::now();
+        let nodes = pass();
+        self.passes.push(PassStats {
+            name,
+            time: start.elapsed(),
+            allocations: allocations() - allocations_before,
+            nodes,
+        });
+    }
+
+    /// The total time of all passes.
+    pub fn total_time(&self) -> Duration {
+        self.passes.iter().map(|pass| pass.time).sum()
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .passes
+            .iter()
+            .map(|pass| pass.name.len())
+            .max()
+            .unwrap_or(0)
+            .max(5);
+        writeln!(
+            f,
+            "{:<width$}  {:>10}  {:>11}  {:>6}",
+            "pass",
+            "time (ms)",
+            "allocations",
+            "nodes",
+            width = width
+        )?;
+        for pass in &self.passes {
+            writeln!(
+                f,
+                "{:<width$}  {:>10.3}  {:>11}  {:>6}",
+                pass.name,
+                pass.time.as_secs_f64() * 1000.0,
+                pass.allocations,
+                pass.nodes,
+                width = width
+            )?;
+        }
+        write!(
+            f,
+            "{:<width$}  {:>10.3}  {:>11}  {:>6}",
+            "total",
+            self.total_time().as_secs_f64() * 1000.0,
+            self.passes
+                .iter()
+                .map(|pass| pass.allocations)
+                .sum::<usize>(),
+            self.passes.iter().map(|pass| pass.nodes).sum::<usize>(),
+            width = width
+        )
+    }
+}
@@ -1,9 +1,11 @@
 mod arena;
 mod bench;
+mod log;
 mod module;
 mod mutrc;
 
 pub use arena::*;
 pub use bench::*;
+pub use log::*;
 pub use module::*;
 pub use mutrc::*;
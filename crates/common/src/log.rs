@@ -0,0 +1,47 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// The verbosity of internal compiler output, from least to most verbose.
+/// Messages are only written if their level is at most the one set with [set_log_level].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Nothing is written.
+    Off,
+    /// Internal errors of the compiler, like generating invalid LLVM IR.
+    Error,
+    /// Problems the compiler can continue after.
+    Warn,
+    /// Progress of the compiler, like statistics.
+    Info,
+    /// Details useful when debugging the compiler, like the passes run.
+    Debug,
+    /// Everything, including very frequent messages.
+    Trace,
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Error as u8);
+
+/// Sets the most verbose level written; [Level::Error] by default.
+/// Drivers can set it from a command line flag, while library consumers
+/// can use [Level::Off] to keep the compiler from writing anything.
+pub fn set_log_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed)
+}
+
+/// Returns if messages of the given level are written.
+pub fn log_enabled(level: Level) -> bool {
+    level != Level::Off && level as u8 <= LEVEL.load(Ordering::Relaxed)
+}
+
+#[macro_use]
+mod log_macro {
+    /// Writes a message of the given level to stderr, if enabled.
+    /// Used for all internal output of the compiler, instead of printing to stdout.
+    #[macro_export]
+    macro_rules! log {
+        ($level:expr, $($arg:tt)+) => {{
+            if $crate::log_enabled($level) {
+                eprintln!($($arg)+);
+            }
+        }};
+    }
+}
@@ -0,0 +1,631 @@
+//! Tests of the compiler's library interface, for behavior
+//! that cannot be observed from the output of a gelix program.
+
+use error::ErrorSpan;
+use gelixrs::{
+    EmitOptions, FileSources, GIRFlags, Limits, ModPath, ModulePath, SourceFile, SourceProvider,
+};
+use gir_nodes::{Expr, Literal};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::Duration,
+};
+use syntax::kind::SyntaxKind;
+
+/// A program made of the given modules, by path like `shapes/square`,
+/// and the standard library of this repository.
+struct Program(&'static [(&'static str, &'static str)]);
+
+impl SourceProvider for Program {
+    fn sources(&self) -> Vec<SourceFile> {
+        let std = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../std");
+        let mut sources = FileSources(vec![std]).sources();
+        for (name, code) in self.0 {
+            let mut path = ModPath::new();
+            for part in name.split('/') {
+                path.push(part.into());
+            }
+            sources.push(SourceFile {
+                path,
+                name: name.to_string(),
+                code: code.as_bytes().to_vec(),
+            });
+        }
+        sources
+    }
+}
+
+/// Returns if compiling the program failed with the given error code.
+fn fails_with(program: &Program, code: &str) -> bool {
+    match gelixrs::compile_gir_from_sources(program, GIRFlags::default()) {
+        Ok(_) => false,
+        Err(errors) => errors
+            .iter()
+            .flat_map(|errors| &errors.errors)
+            .any(|error| error.kind.as_ref() == code),
+    }
+}
+
+/// The serialization format of GIR is consumed by other tools,
+/// so it must not change by accident.
+#[test]
+fn serialize_gir_expressions() {
+    let expr = Expr::Block(vec![
+        Expr::none_const(),
+        Expr::ret(Expr::binary(
+            SyntaxKind::Plus,
+            Expr::Literal(Literal::I64(5)),
+            Expr::Literal(Literal::Bool(true)),
+        )),
+    ]);
+
+    let json = serde_json::to_string(&expr).unwrap();
+    assert_eq!(
+        json,
+        r#"{"Block":[{"Literal":"None"},{"Return":{"Binary":{"left":{"Literal":{"I64":5}},"operator":"Plus","right":{"Literal":{"Bool":true}}}}}]}"#
+    );
+}
+
+/// Every error and warning must have an extended description,
+/// as `GErr::explain` panics otherwise.
+#[test]
+fn explain_all_error_codes() {
+    let kinds = include_str!("../../error/src/kinds.rs");
+    let enum_body = kinds
+        .split("pub enum GErr {")
+        .nth(1)
+        .and_then(|rest| rest.split("\n}").next())
+        .unwrap();
+    let codes: Vec<_> = enum_body
+        .lines()
+        .filter_map(|line| line.strip_prefix("    "))
+        .filter_map(|line| line.get(..4))
+        .filter(|code| {
+            code.starts_with(|c| c == 'E' || c == 'W')
+                && code[1..].chars().all(|c| c.is_ascii_digit())
+        })
+        .collect();
+
+    assert!(codes.len() > 100, "found only {} error codes", codes.len());
+    for code in codes {
+        assert!(
+            error::explain(code).is_some(),
+            "{} has no explanation",
+            code
+        );
+    }
+}
+
+#[test]
+fn demangle_symbols() {
+    let cases = [
+        ("_G4main_4main", "main::main"),
+        (
+            "_G3std6string_6String8toString",
+            "std/string::String::toString",
+        ),
+        ("_G4main_8identityIP3i64E", "main::identity[i64]"),
+        // Identifiers are length-prefixed, so they can contain digits and underscores
+        ("_G3a_12b2_5f_2_3", "a_1/b2::f_2_3"),
+        (
+            "_G4main_3mapIN4main_3BoxIQP3i64EEWN3std6string_6StringEE",
+            "main::map[Box[i64?], weak String]",
+        ),
+    ];
+    for (symbol, name) in &cases {
+        assert_eq!(gelixrs::demangle(symbol).as_deref(), Some(*name));
+    }
+
+    for symbol in &[
+        "main",
+        "_G4main",
+        "_G4main_9toolong",
+        // A length this large overflows when added to the position of the identifier
+        "_G4main_18446744073709551615x",
+        "_G4main_4mainIX3fooE",
+    ] {
+        assert_eq!(gelixrs::demangle(symbol), None);
+    }
+}
+
+/// Every mangled symbol of a compiled program demangles to the name of its function.
+#[test]
+fn demangle_compiled_symbols() {
+    let program = Program(&[(
+        "test",
+        r#"
+class Counter {
+    var count = 0
+    func increment() = count = count + 1
+}
+
+func identity[T](value: T) -> T = value
+
+func main() {
+    val counter = Counter()
+    counter.increment()
+    identity(5)
+    identity(counter)
+}
+"#,
+    )]);
+    let gir = gelixrs::compile_gir_from_sources(&program, GIRFlags::default()).unwrap();
+    let options = EmitOptions {
+        source_map: true,
+        ..EmitOptions::default()
+    };
+    let (_, emitted) = gelixrs::compile_ir_emit(gelixrs::ir_context(), gir, &options).unwrap();
+
+    let functions = emitted.source_map.unwrap().functions;
+    let names: Vec<_> = functions
+        .iter()
+        .filter(|function| function.symbol != "main")
+        .map(|function| {
+            assert_ne!(function.name, function.symbol, "not demangled");
+            function.name.as_str()
+        })
+        .collect();
+    for name in &[
+        "test::Counter::increment",
+        "test::identity[i64]",
+        "test::identity[Counter]",
+    ] {
+        assert!(names.contains(name), "{} missing in {:?}", name, names);
+    }
+}
+
+/// The source map gives the span of every function without its doc comment
+/// and the lines of its definition in the LLVM IR.
+#[test]
+fn source_map_functions() {
+    let program = Program(&[(
+        "test",
+        r#"/// Adds two numbers.
+func add(a: i64, b: i64) -> i64 {
+    a + b
+}
+
+func main() {
+    add(1, 2)
+}
+"#,
+    )]);
+    let gir = gelixrs::compile_gir_from_sources(&program, GIRFlags::default()).unwrap();
+    let options = EmitOptions {
+        ir: true,
+        source_map: true,
+        ..EmitOptions::default()
+    };
+    let (_, emitted) = gelixrs::compile_ir_emit(gelixrs::ir_context(), gir, &options).unwrap();
+    let ir: Vec<_> = emitted.ir.unwrap().lines().map(str::to_string).collect();
+
+    let source_map = emitted.source_map.unwrap();
+    let add = source_map
+        .functions
+        .iter()
+        .find(|function| function.name == "test::add")
+        .unwrap();
+    assert_eq!(add.module, "test");
+
+    let span = add.source.as_ref().unwrap();
+    assert_eq!((span.start.line, span.start.column), (2, 1));
+    assert_eq!((span.end.line, span.end.column), (4, 1));
+
+    let lines = add.ir.as_ref().unwrap();
+    assert!(ir[lines.first - 1].starts_with("define "));
+    assert!(ir[lines.first - 1].contains(&add.symbol));
+    assert_eq!(ir[lines.last - 1], "}");
+}
+
+/// Sealed interfaces can only be implemented by modules of their own package.
+#[test]
+fn sealed_interface_foreign_implementor() {
+    const SHAPE: &str = r#"
+sealed interface Shape {
+    func area() -> i64
+}
+"#;
+    let same_package = Program(&[
+        ("shapes/shape", SHAPE),
+        (
+            "shapes/square",
+            r#"
+import shapes/shape/Shape
+
+class Square {
+    val side: i64
+    construct(side)
+}
+
+impl Shape for Square {
+    func area() -> i64 = this.side * this.side
+}
+
+func main() {}
+"#,
+        ),
+    ]);
+    assert!(!fails_with(&same_package, "E349"));
+
+    let foreign = Program(&[
+        ("shapes/shape", SHAPE),
+        (
+            "test",
+            r#"
+import shapes/shape/Shape
+
+class Circle {
+    val radius: i64
+    construct(radius)
+}
+
+impl Shape for Circle {
+    func area() -> i64 = 3 * this.radius * this.radius
+}
+
+func main() {}
+"#,
+        ),
+    ]);
+    assert!(fails_with(&foreign, "E349"));
+}
+
+/// Mixed line endings are warned about without failing compilation.
+#[test]
+fn mixed_line_endings_warning() {
+    let program = Program(&[("test", "func main() {\r\n    print(5)\n}\n")]);
+    let gir = gelixrs::compile_gir_from_sources(&program, GIRFlags::default()).unwrap();
+    let warning = gir
+        .warnings
+        .iter()
+        .flat_map(|warnings| &warnings.errors)
+        .find(|warning| warning.kind.as_ref() == "W003")
+        .unwrap();
+    assert!(warning.kind.is_warning());
+}
+
+/// Warnings are reported in a stable order, by module and position.
+#[test]
+fn warnings_are_sorted() {
+    let shadowing = r#"
+func main() {
+    val a = 1
+    {
+        val a = 2
+        {
+            val a = 3
+        }
+    }
+}
+"#;
+    let program = Program(&[("b", shadowing), ("a", shadowing), ("c", shadowing)]);
+    let flags = GIRFlags {
+        warn_shadowing: true,
+        ..GIRFlags::default()
+    };
+    let gir = gelixrs::compile_gir_from_sources(&program, flags).unwrap();
+
+    let origins = gir.warnings.iter().map(|w| &w.origin).collect::<Vec<_>>();
+    let mut sorted = origins.clone();
+    sorted.sort();
+    assert_eq!(origins, sorted);
+    for warnings in &gir.warnings {
+        let starts = warnings
+            .errors
+            .iter()
+            .map(|warning| match &warning.index {
+                ErrorSpan::Span(span) => span.start,
+                _ => 0,
+            })
+            .collect::<Vec<_>>();
+        assert!(starts.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+}
+
+/// Programs can be compiled from sources in memory, by module path.
+#[test]
+fn compile_from_sources_in_memory() {
+    let path = |name: &str| -> ModulePath {
+        let mut path = ModPath::new();
+        for part in name.split('/') {
+            path.push(part.into());
+        }
+        Rc::new(path)
+    };
+    let mut program = HashMap::new();
+    program.insert(
+        path("test"),
+        r#"
+import shapes/square/Square
+
+func main() {
+    print(Square(2).area())
+}
+"#
+        .to_string(),
+    );
+    program.insert(
+        path("shapes/square"),
+        r#"
+class Square {
+    val side: i64
+    construct(side)
+    func area() -> i64 = this.side * this.side
+}
+"#
+        .to_string(),
+    );
+
+    let std = FileSources(vec![Path::new(env!("CARGO_MANIFEST_DIR")).join("../../std")]);
+    let gir = gelixrs::compile_gir_from_sources(&(program, std), GIRFlags::default()).unwrap();
+    let paths = gir
+        .modules
+        .iter()
+        .map(|module| module.borrow().path.to_string())
+        .collect::<Vec<_>>();
+    assert!(paths.contains(&"test".to_string()));
+    assert!(paths.contains(&"shapes/square".to_string()));
+}
+
+/// Files are read in the order of their names, independent of the file system.
+#[test]
+fn file_sources_sorted() {
+    let std = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../std");
+    let names = FileSources(vec![std])
+        .sources()
+        .into_iter()
+        .map(|source| PathBuf::from(source.name))
+        .collect::<Vec<_>>();
+    let mut sorted = names.clone();
+    sorted.sort();
+    assert_eq!(names, sorted);
+}
+
+/// Returns the lines of the first function in the IR whose definition
+/// contains all given parts, from its definition to its closing brace.
+fn ir_function<'a>(ir: &'a str, parts: &[&str]) -> Vec<&'a str> {
+    ir.lines()
+        .skip_while(|line| {
+            !(line.starts_with("define ") && parts.iter().all(|part| line.contains(part)))
+        })
+        .take_while(|line| *line != "}")
+        .collect()
+}
+
+/// Bulk copies lower to memmove, fills of single bytes to memset
+/// and fills of larger values to a loop.
+#[test]
+fn bulk_memory_lowering() {
+    let program = Program(&[(
+        "test",
+        r#"
+import std/memory/+
+
+func main() {
+    val numbers = allocate[i64](4)
+    fill_ptr(numbers, 4, 7)
+    copy_ptr(numbers, allocate[i64](4), 4)
+
+    val bytes = allocate[u8](4)
+    fill_ptr(bytes, 4, 0u8)
+}
+"#,
+    )]);
+    let gir = gelixrs::compile_gir_from_sources(&program, GIRFlags::default()).unwrap();
+    let options = EmitOptions {
+        ir: true,
+        ..EmitOptions::default()
+    };
+    let (_, emitted) = gelixrs::compile_ir_emit(gelixrs::ir_context(), gir, &options).unwrap();
+    let ir = emitted.ir.unwrap();
+    let calls = |body: &[&str], callee: &str| {
+        body.iter()
+            .any(|line| line.contains(&format!("call void @{}(", callee)))
+    };
+
+    let memmove = ir_function(&ir, &["gelixrs_memmove", "i64*"]);
+    assert!(!memmove.is_empty());
+    assert!(calls(&memmove, "llvm.memmove.p0i8.p0i8.i64"));
+
+    let fill_bytes = ir_function(&ir, &["gelixrs_fill", "i8*"]);
+    assert!(!fill_bytes.is_empty());
+    assert!(calls(&fill_bytes, "llvm.memset.p0i8.i64"));
+
+    let fill_numbers = ir_function(&ir, &["gelixrs_fill", "i64*"]);
+    assert!(!fill_numbers.is_empty());
+    assert!(!calls(&fill_numbers, "llvm.memset.p0i8.i64"));
+    assert!(fill_numbers
+        .iter()
+        .any(|line| line.starts_with("fill-loop")));
+}
+
+/// Identical string literals are interned and array literals of numbers
+/// are emitted as constants. Counted relative to a program with the same
+/// calls, but without repeated strings or constant arrays, to leave out
+/// the standard library.
+#[test]
+fn constant_stats() {
+    let constants = |program: &Program| {
+        let gir = gelixrs::compile_gir_from_sources(program, GIRFlags::default()).unwrap();
+        let options = EmitOptions::default();
+        let (_, emitted) = gelixrs::compile_ir_emit(gelixrs::ir_context(), gir, &options).unwrap();
+        emitted.constants
+    };
+    let repeated = constants(&Program(&[(
+        "test",
+        r#"
+func main() {
+    print("gelix-constant")
+    print("gelix-constant")
+    print("gelix-constant")
+    val two = 2
+    val numbers = [1, 2, 3]
+}
+"#,
+    )]));
+    let distinct = constants(&Program(&[(
+        "test",
+        r#"
+func main() {
+    print("gelix-constant-1")
+    print("gelix-constant-2")
+    print("gelix-constant-3")
+    val two = 2
+    val numbers = [1, two, 3]
+}
+"#,
+    )]));
+
+    assert_eq!(repeated.string_literals, distinct.string_literals);
+    assert_eq!(repeated.interned_strings, distinct.interned_strings + 2);
+    assert_eq!(
+        repeated.interned_bytes,
+        distinct.interned_bytes + 2 * "gelix-constant\0".len()
+    );
+    assert_eq!(repeated.constant_arrays, distinct.constant_arrays + 1);
+    assert_eq!(repeated.elided_stores, distinct.elided_stores + 3);
+}
+
+/// Switches comparing a value against dense integer literals are
+/// marked to be compiled to a jump table, sparse ones are not.
+#[test]
+fn dense_switch_jump_table() {
+    let program = Program(&[(
+        "test",
+        r#"
+func dense(n: i64) -> String {
+    return when (n) {
+        0 -> "zero"
+        1 -> "one"
+        2 -> "two"
+        3 -> "three"
+        5 -> "five"
+        else -> "other"
+    }
+}
+
+func sparse(n: i64) -> String {
+    return when (n) {
+        0 -> "zero"
+        100 -> "hundred"
+        10000 -> "ten thousand"
+        1000000 -> "million"
+        else -> "other"
+    }
+}
+
+func main() {}
+"#,
+    )]);
+    let gir = gelixrs::compile_gir_from_sources(&program, GIRFlags::default()).unwrap();
+    let options = EmitOptions {
+        gir: true,
+        ..EmitOptions::default()
+    };
+    let (_, emitted) = gelixrs::compile_ir_emit(gelixrs::ir_context(), gir, &options).unwrap();
+    let declaration = |path: &str| {
+        emitted
+            .gir
+            .iter()
+            .find(|(name, _)| name == path)
+            .map(|(_, gir)| gir.clone())
+            .unwrap()
+    };
+
+    let dense = declaration("test/dense");
+    assert!(dense.contains("switch table {"));
+    let sparse = declaration("test/sparse");
+    assert!(sparse.contains("switch {"));
+    assert!(!sparse.contains("switch table {"));
+}
+
+/// Returns the messages of all E357 errors of compiling
+/// a small program with the given limits.
+fn limit_errors(limits: Limits) -> Vec<String> {
+    let program = Program(&[(
+        "test",
+        r#"
+func first[T](a: T, b: T) -> T = a
+
+func main() {
+    print(first(1, 2))
+    print(first("a", "b"))
+}
+"#,
+    )]);
+    let flags = GIRFlags {
+        limits,
+        ..GIRFlags::default()
+    };
+    match gelixrs::compile_gir_from_sources(&program, flags) {
+        Ok(_) => vec![],
+        Err(errors) => errors
+            .iter()
+            .flat_map(|errors| &errors.errors)
+            .filter(|error| error.kind.as_ref() == "E357")
+            .map(|error| error.kind.fmt())
+            .collect(),
+    }
+}
+
+/// Programs within the limits compile.
+#[test]
+fn limits_not_exceeded() {
+    let limits = Limits {
+        max_modules: Some(1),
+        max_instantiations: Some(100_000),
+        max_expressions: Some(1_000_000),
+        timeout: Some(Duration::from_secs(600)),
+    };
+    assert!(limit_errors(limits).is_empty());
+}
+
+#[test]
+fn limit_max_modules() {
+    let errors = limit_errors(Limits {
+        max_modules: Some(0),
+        ..Limits::default()
+    });
+    assert_eq!(
+        errors,
+        ["Compilation aborted: The program exceeds the limit on modules (0)."]
+    );
+}
+
+#[test]
+fn limit_max_instantiations() {
+    let errors = limit_errors(Limits {
+        max_instantiations: Some(1),
+        ..Limits::default()
+    });
+    assert_eq!(
+        errors,
+        ["Compilation aborted: The program exceeds the limit on instantiations (1)."]
+    );
+}
+
+#[test]
+fn limit_max_expressions() {
+    let errors = limit_errors(Limits {
+        max_expressions: Some(10),
+        ..Limits::default()
+    });
+    assert_eq!(
+        errors,
+        ["Compilation aborted: The program exceeds the limit on expressions (10)."]
+    );
+}
+
+#[test]
+fn limit_timeout() {
+    let errors = limit_errors(Limits {
+        timeout: Some(Duration::from_nanos(0)),
+        ..Limits::default()
+    });
+    assert_eq!(
+        errors,
+        ["Compilation aborted: The program exceeds the limit on compile time (0ms)."]
+    );
+}
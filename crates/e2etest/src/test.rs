@@ -4,16 +4,30 @@
  * This file is under the Apache 2.0 license. See LICENSE in the root of this repository for details.
  */
 
+#![feature(c_variadic)]
+
 use std::{
-    collections::HashSet, env, ffi::CStr, fs, fs::read_to_string, io, mem, os::raw::c_char, panic,
-    path::PathBuf, process, sync::Mutex,
+    collections::HashSet,
+    env,
+    ffi::{CStr, VaList},
+    fs,
+    fs::read_to_string,
+    io, mem,
+    os::raw::c_char,
+    panic,
+    path::PathBuf,
+    process, ptr,
+    sync::Mutex,
 };
 
 use ansi_term::{Color, Style};
 use common::bench;
-use gelixrs::{ir_context, CompiledGIR, CompiledIR, Context, Errors, GIRFlags, BENCH};
+use gelixrs::{
+    ir_context, Allocator, CompiledGIR, CompiledIR, Context, Errors, GIRFlags, LiteralTypes,
+    Optimizations, Tracer, BENCH,
+};
 use lazy_static::lazy_static;
-use std::{io::Write, panic::AssertUnwindSafe};
+use std::{cell::RefCell, io::Write, panic::AssertUnwindSafe, rc::Rc};
 use structopt::StructOpt;
 
 lazy_static! {
@@ -43,6 +57,10 @@ enum Failure {
     Subprocess,
     Segfault,
     Leak(usize),
+    /// The binary exited with a nonzero exit code, with the given output.
+    Exit(i32, String),
+    /// The type checking trace did not contain the given lines.
+    Trace(Vec<String>),
 }
 
 impl PartialEq for Failure {
@@ -60,8 +78,33 @@ extern "C" fn test_puts(string: *const c_char) {
         .push_str(&format!("{}\n", string.to_str().unwrap_or("INVALID_UTF8")));
 }
 
+/// Replacement for printf, which formats using the C library
+/// so that any format and amount of arguments is supported.
+unsafe extern "C" fn test_printf(format: *const c_char, mut args: ...) -> i32 {
+    let mut measured = args.clone();
+    let len = vsnprintf(ptr::null_mut(), 0, format, measured.as_va_list());
+    if len < 0 {
+        return len;
+    }
+
+    let mut output = vec![0u8; len as usize + 1];
+    vsnprintf(
+        output.as_mut_ptr() as *mut c_char,
+        output.len(),
+        format,
+        args.as_va_list(),
+    );
+    output.pop(); // Null terminator
+    RESULT
+        .lock()
+        .unwrap()
+        .push_str(&String::from_utf8_lossy(&output));
+    len
+}
+
 extern "C" {
     fn malloc(x: i64) -> i64;
+    fn vsnprintf(buf: *mut c_char, size: usize, format: *const c_char, args: VaList) -> i32;
 }
 
 #[allow(clippy::let_and_return)] // Remove once todo is fixed.
@@ -106,6 +149,10 @@ struct Opt {
     /// Print each test name before running it
     #[structopt(long)]
     verbose: bool,
+
+    /// Run all GIR optimizations on the tests
+    #[structopt(long = "gir-opt")]
+    gir_opt: bool,
 }
 
 type TestRes = Result<String, Failure>;
@@ -122,7 +169,59 @@ struct FailedTest {
     rel_path: String,
     count: usize,
     result: TestRes,
-    expected: TestRes,
+    expected: Expected,
+}
+
+/// What a test expects, given by the comments at its start.
+#[derive(Debug)]
+struct Expected {
+    result: TestRes,
+    /// The codes of all errors the test must fail with, in any order,
+    /// given after `// C-ERR` or `// P-ERR`. Any errors if empty.
+    codes: Vec<String>,
+    /// Text the message of one of the errors must contain, given with `// MESSAGE`.
+    message: Option<String>,
+    /// The exit code the test must exit with, given with `// EXIT`.
+    /// Tests with this are always run as a binary; the output is still compared.
+    /// `// EXIT 0` can be used to run a test as a binary without it failing.
+    exit: Option<i32>,
+    /// Compiler flags to compile the test with, given with `// FLAGS`
+    /// in the same format as the CLI. Tests with this compile std along with them.
+    flags: Option<String>,
+    /// Text the trace of type checking decisions must contain, given with
+    /// one `// TRACE` per line. Tests with this compile std along with them.
+    trace: Vec<String>,
+}
+
+impl Expected {
+    fn matches(&self, result: &TestRes) -> bool {
+        match result {
+            Err(Failure::Parse(errors)) | Err(Failure::Compile(errors))
+                if *result == self.result =>
+            {
+                let errors = errors
+                    .iter()
+                    .flat_map(|errs| errs.errors.iter())
+                    .collect::<Vec<_>>();
+                let mut codes = errors
+                    .iter()
+                    .map(|err| err.kind.as_ref().to_string())
+                    .collect::<Vec<_>>();
+                codes.sort();
+                let mut expected = self.codes.clone();
+                expected.sort();
+
+                (expected.is_empty() || codes == expected)
+                    && self.message.as_ref().map_or(true, |message| {
+                        errors.iter().any(|err| err.kind.fmt().contains(message))
+                    })
+            }
+            Err(Failure::Exit(code, output)) => {
+                self.exit == Some(*code) && self.result.as_ref().ok() == Some(output)
+            }
+            _ => *result == self.result,
+        }
+    }
 }
 
 fn main() {
@@ -212,9 +311,24 @@ fn run_test(path: PathBuf, run: &mut TestRun) {
     }
 
     let expected = get_expected_result(path.clone());
-    let result = catch_unwind_silent(|| exec(path.clone(), run)).unwrap_or(Err(Failure::Panic));
+    let binary = expected.exit.is_some();
+    let flags = expected.flags.as_deref();
+    let mut result = if expected.trace.is_empty() {
+        catch_unwind_silent(|| exec(path.clone(), run, binary, flags))
+    } else {
+        catch_unwind_silent(|| exec_traced(path.clone(), run, &expected.trace))
+    }
+    .unwrap_or(Err(Failure::Panic));
+
+    // Optimizer tests also need to pass with all optimizations,
+    // which is what the CLI uses by default
+    let optimize_test = path.parent().and_then(|dir| dir.file_name()) == Some("optimize".as_ref());
+    if optimize_test && flags.is_none() && expected.matches(&result) {
+        result = catch_unwind_silent(|| exec(path.clone(), run, binary, Some("--gir-opt all")))
+            .unwrap_or(Err(Failure::Panic));
+    }
 
-    if result == expected {
+    if expected.matches(&result) {
         print!("{}", GREEN_BOLD.paint("."));
     } else {
         let rel_path = relative_path(&path);
@@ -229,32 +343,75 @@ fn run_test(path: PathBuf, run: &mut TestRun) {
     io::stdout().flush().unwrap();
 }
 
-fn exec(path: PathBuf, run: &mut TestRun) -> TestRes {
+fn exec(path: PathBuf, run: &mut TestRun, binary: bool, flags: Option<&str>) -> TestRes {
     clear_state();
 
-    let gir = if run.options.no_cache {
+    let gir = if let Some(flags) = flags {
+        let code = gelixrs::parse_source(vec![path, std_mod()]).map_err(Failure::Parse)?;
+        gelixrs::compile_gir(code, parse_flags(flags, gir_flags(run)))
+    } else if run.options.no_cache {
         let code = gelixrs::parse_source(vec![path, std_mod()]).map_err(Failure::Parse)?;
-        gelixrs::compile_gir(code, GIRFlags::default())
+        gelixrs::compile_gir(code, gir_flags(run))
     } else {
         maybe_compile_stdlib(run)?;
         let std = run.gir_stdlib.as_ref().unwrap();
 
         let code = gelixrs::parse_source(vec![path]).map_err(Failure::Parse)?;
-        gelixrs::compile_gir_cached_std(code, std, GIRFlags::default())
+        gelixrs::compile_gir_cached_std(code, std, gir_flags(run))
     }
     .map_err(Failure::Compile)?;
     let module = gelixrs::compile_ir(run.ir_context.clone(), gir);
 
-    if !run.options.no_jit {
+    if !run.options.no_jit && !binary {
         bench!("jit", exec_jit(module))
     } else {
         bench!("bin", exec_bin(module))
     }
 }
 
+/// Compiles and runs the test while tracing type checking,
+/// failing if the trace misses any of the given lines.
+fn exec_traced(path: PathBuf, run: &mut TestRun, lines: &[String]) -> TestRes {
+    clear_state();
+
+    let trace = TraceBuffer::default();
+    let code = gelixrs::parse_source(vec![path, std_mod()]).map_err(Failure::Parse)?;
+    let gir = gelixrs::compile_gir_traced(code, gir_flags(run), Tracer::new(trace.clone()))
+        .map_err(Failure::Compile)?;
+
+    let trace = trace.0.borrow();
+    let trace = String::from_utf8_lossy(&trace);
+    let missing = lines
+        .iter()
+        .filter(|line| !trace.contains(line.as_str()))
+        .cloned()
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        return Err(Failure::Trace(missing));
+    }
+
+    let module = gelixrs::compile_ir(run.ir_context.clone(), gir);
+    bench!("jit", exec_jit(module))
+}
+
+/// A tracer output that can still be read once compilation is done.
+#[derive(Clone, Default)]
+struct TraceBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl Write for TraceBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 fn exec_jit(module: CompiledIR) -> TestRes {
     let mut jit = gelixrs::JIT::new(module);
     jit.link_fn("puts", test_puts as usize);
+    jit.link_fn("printf", test_printf as usize);
     jit.link_fn("malloc", test_malloc as usize);
     jit.link_fn("free", test_free as usize);
     unsafe { jit.call("main").ok_or(Failure::IR)? }
@@ -278,10 +435,11 @@ fn exec_bin(module: CompiledIR) -> TestRes {
     let output = process::Command::new(tmp_file.as_os_str())
         .output()
         .map_err(|_| Failure::Subprocess)?;
-    if output.status.success() {
-        Ok(String::from_utf8_lossy(&output.stdout).into())
-    } else {
-        Err(Failure::Segfault)
+    let stdout = String::from_utf8_lossy(&output.stdout).into();
+    match output.status.code() {
+        Some(0) => Ok(stdout),
+        Some(code) => Err(Failure::Exit(code, stdout)),
+        None => Err(Failure::Segfault),
     }
 }
 
@@ -289,22 +447,49 @@ fn clear_state() {
     MALLOC_LIST.lock().unwrap().clear();
 }
 
-fn get_expected_result(mut path: PathBuf) -> TestRes {
+fn get_expected_result(mut path: PathBuf) -> Expected {
     // If the test is a directory, the wanted result is in a file 'expected' in the dir
     if path.is_dir() {
         path.push("expected");
     }
 
     let code = read_to_string(path).expect("Couldn't get wanted result");
-    if code.starts_with("// P-ERR") {
+    // Lines like `// MESSAGE text` at the start of the test
+    let directive = |name: &str| {
+        code.lines()
+            .take_while(|line| line.starts_with("//"))
+            .find_map(|line| line.strip_prefix("// ")?.strip_prefix(name))
+            .map(str::trim)
+    };
+    let codes = directive("P-ERR")
+        .or_else(|| directive("C-ERR"))
+        .map(|codes| codes.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default();
+
+    let result = if code.starts_with("// P-ERR") {
         Err(Failure::Parse(vec![]))
     } else if code.starts_with("// C-ERR") {
         Err(Failure::Compile(vec![]))
     } else if code.starts_with("// LEAK") {
         Err(Failure::Leak(1))
     } else {
-        let split = code.split("*/").next().unwrap();
+        // Directives like `// EXIT` can come before the expected output
+        let output = &code[code.find("/*").unwrap_or(0)..];
+        let split = output.split("*/").next().unwrap();
         Ok(split[3..].to_string())
+    };
+    Expected {
+        result,
+        codes,
+        message: directive("MESSAGE").map(str::to_string),
+        exit: directive("EXIT").map(|code| code.parse().expect("Invalid exit code")),
+        flags: directive("FLAGS").map(str::to_string),
+        trace: code
+            .lines()
+            .take_while(|line| line.starts_with("//"))
+            .filter_map(|line| line.strip_prefix("// TRACE "))
+            .map(str::to_string)
+            .collect(),
     }
 }
 
@@ -313,7 +498,8 @@ fn maybe_compile_stdlib(run: &mut TestRun) -> Result<(), Failure> {
         let code = gelixrs::parse_source(vec![std_mod()]).map_err(Failure::Parse)?;
         let flags = GIRFlags {
             library: true,
-            ..GIRFlags::default()
+            executable: false,
+            ..gir_flags(run)
         };
         let gir = gelixrs::compile_gir(code, flags).map_err(Failure::Compile)?;
         run.gir_stdlib = Some(gir);
@@ -321,6 +507,37 @@ fn maybe_compile_stdlib(run: &mut TestRun) -> Result<(), Failure> {
     Ok(())
 }
 
+fn gir_flags(run: &TestRun) -> GIRFlags {
+    GIRFlags {
+        executable: true,
+        optimizations: if run.options.gir_opt {
+            Optimizations::all()
+        } else {
+            Optimizations::default()
+        },
+        ..GIRFlags::default()
+    }
+}
+
+/// Applies flags given in the same format as the CLI, like `--atomic-rc --allocator arena`.
+fn parse_flags(args: &str, mut flags: GIRFlags) -> GIRFlags {
+    let mut args = args.split_whitespace();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().expect("Missing flag value");
+        match arg {
+            "--refcount" => flags.refcounting = true,
+            "--atomic-rc" => flags.atomic_refcounts = true,
+            "--leak-report" => flags.leak_report = true,
+            "--warn-shadowing" => flags.warn_shadowing = true,
+            "--allocator" => flags.allocator = value().parse::<Allocator>().unwrap(),
+            "--gir-opt" => flags.optimizations = value().parse::<Optimizations>().unwrap(),
+            "--literal-types" => flags.literal_types = value().parse::<LiteralTypes>().unwrap(),
+            _ => panic!("Unknown test flag {}", arg),
+        }
+    }
+    flags
+}
+
 fn std_mod() -> PathBuf {
     let mut std_mod = PathBuf::from(
         env::current_dir()
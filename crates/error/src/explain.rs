@@ -0,0 +1,1015 @@
+/// Returns an extended description of the error or warning with the given code,
+/// like `E301`, usually including an example of code causing it.
+/// Meant to be shown by the driver or in an editor when hovering an error.
+/// Returns `None` for unknown codes.
+pub fn explain(code: &str) -> Option<&'static str> {
+    Some(match code {
+        "E001" => {
+            r#"The parser expected a specific token, but found something else.
+This is usually a missing bracket or parenthesis.
+
+    func main( {    // Expected ')' after parameters
+    }"#
+        }
+        "E002" => {
+            r#"Only declarations are allowed at the top level of a file,
+like functions, classes, enums, interfaces, impl blocks and imports.
+Statements like variable definitions must be placed inside a function.
+
+    val a = 5       // Not allowed here
+    func main() {}"#
+        }
+        "E003" => {
+            r#"The parser expected a type, like after ':' in a variable or parameter.
+
+    func f(a: ) {}"#
+        }
+        "E004" => {
+            r#"Classes, enums and interfaces can only contain fields,
+constructors and methods.
+
+    class A {
+        import std/io
+    }"#
+        }
+        "E005" => {
+            r#"A field must either have a type or a default value, or both.
+
+    class A {
+        val a
+    }"#
+        }
+        "E006" => {
+            r#"A modifier was used on a declaration that does not support it,
+like 'value' on an interface or 'extern' on a class.
+
+    extern class A {}"#
+        }
+        "E007" => {
+            r#"A 'when' expression can only have one 'else' branch,
+as only one can ever be taken.
+
+    when (a) {
+        1 -> "one"
+        else -> "other"
+        else -> "still other"
+    }"#
+        }
+        "E008" => {
+            r#"The parser expected an expression, like after an operator or '='.
+
+    val a = 5 +"#
+        }
+        "E009" => {
+            r#"Annotations like '@inline' can only be applied to classes, enums,
+interfaces, functions, methods and closures.
+
+    @inline
+    import std/io"#
+        }
+        "E010" => {
+            r#"An expression or type is nested deeper than the compiler supports,
+like thousands of nested parentheses or type arguments.
+This is almost always generated code; split it into multiple
+variables or type aliases."#
+        }
+        "E011" => {
+            r#"A part of the syntax tree was incomplete or malformed in a way
+the parser did not report. This usually accompanies another parser error;
+if it does not, please report it as a compiler bug."#
+        }
+        "E012" => {
+            r#"Global variables are only supported as immutable lazy globals,
+whose initializer runs when they are first read.
+
+    val a = 5          // lazy val a = 5 works
+    lazy var b = 6"#
+        }
+        "E013" => {
+            r#"The source contains text that is not valid gelix syntax:
+a character gelix does not use, a string missing its closing quote
+or a block comment missing its closing '*/'.
+Strings and block comments without an end continue until the end of the file.
+
+    val a = "unterminated
+    val b = 5 # 2"#
+        }
+        "E014" => {
+            r#"Some operators cannot follow each other without parentheses,
+as it would be unclear how they are grouped:
+comparisons like '==' and '<' cannot be chained, neither can 'is',
+and a prefix operator like '!' directly in front of 'is' would
+apply to the value, not the result of the check.
+
+    a < b < c       // write (a < b) and (b < c)
+    a == b == c     // write (a == b) == c
+    !a is String    // write !(a is String)"#
+        }
+        "E015" => {
+            r#"A source file contains bytes that are not valid UTF-8, which is
+the only encoding gelix accepts. This is usually caused by a file saved
+in a different encoding like Latin-1 or UTF-16; convert it to UTF-8.
+A leading byte order mark (BOM) is allowed and ignored."#
+        }
+
+        "E100" => {
+            r#"Two declarations in the same module have the same name.
+Rename one of them.
+
+    func a() {}
+    class a {}"#
+        }
+        "E101" => {
+            r#"Every program needs a 'main' function in the file being compiled,
+which is run when the program starts.
+
+    func notMain() {}"#
+        }
+        "E102" => {
+            r#"An import refers to a module that does not exist.
+Module paths are relative to the compiled file, with 'std' being the standard library.
+
+    import std/does_not_exist"#
+        }
+        "E103" => {
+            r#"An import refers to a declaration that does not exist in the module.
+
+    import std/io/doesNotExist"#
+        }
+        "E104" => {
+            r#"A module can only have one 'init' block, which runs when the module
+is first used. Merge all blocks into one.
+
+    init { a() }
+    init { b() }"#
+        }
+        "E105" => {
+            r#"A name was imported from multiple modules, so it is unclear
+which one is meant. Use 'hiding' on one of the imports, or import only
+the declarations needed from a module.
+
+    import a/+
+    import b/+      // Both define 'helper'"#
+        }
+
+        "E200" => {
+            r#"The target of an assignment cannot be assigned to,
+like an immutable variable, a 'val' field or a function.
+
+    val a = 5
+    a = 6"#
+        }
+        "E201" => {
+            r#"The value assigned has a different type than the variable or field.
+
+    var a = 5
+    a = "five""#
+        }
+        "E202" => {
+            r#"An operator was used on types that do not support it.
+Operators on classes require implementing the matching interface, like Add.
+
+    val a = "a" * true"#
+        }
+        "E203" => {
+            r#"Methods cannot be called in a constructor until all fields have been
+initialized, as the method could read an uninitialized field.
+
+    class A {
+        val a: i64
+        construct() {
+            this.print()
+            this.a = 5
+        }
+    }"#
+        }
+        "E204" => {
+            r#"A field was called like a method. Fields holding closures need to be
+read into a variable first.
+
+    class A {
+        val a = 5
+    }
+    A().a()"#
+        }
+        "E205" => {
+            r#"Weak references cannot be captured by closures, as they might
+become invalid while the closure is alive. Upgrade them to a strong reference first."#
+        }
+        "E206" => {
+            r#"A variable was used that is not defined in the current scope.
+
+    func main() {
+        print(a)
+    }"#
+        }
+        "E207" => {
+            r#"'break' can only be used inside loops.
+
+    func main() {
+        break
+    }"#
+        }
+        "E208" => {
+            r#"A variable was defined twice in the same scope.
+Use a new name, or assign to the existing 'var' instead.
+
+    val a = 5
+    val a = 6"#
+        }
+        "E209" => {
+            r#"When a loop is used as a value, its 'break' expressions must
+have the same type as its body.
+
+    val a = for (true) {
+        break "b"
+        5
+    }"#
+        }
+        "E210" => {
+            r#"The type has no field or method with the given name.
+
+    val a = "a".doesNotExist"#
+        }
+        "E211" => r#"Generic methods cannot be read as a value, only called directly."#,
+        "E212" => {
+            r#"A 'return' expression returns a value of a different type
+than the function's return type.
+
+    func a() -> i64 {
+        return "a"
+    }"#
+        }
+        "E213" => {
+            r#"Type arguments can only be given to functions and types,
+not to local variables.
+
+    val a = 5
+    a[i64]"#
+        }
+        "E214" => {
+            r#"The type of something could not be inferred from its usage.
+Specify it explicitly, like with type arguments on a call.
+
+    val a = Array()     // Array[i64]() works"#
+        }
+        "E215" => {
+            r#"Only functions, methods, closures and types can be called.
+
+    val a = 5
+    a()"#
+        }
+        "E216" => {
+            r#"A function was called with the wrong number of arguments.
+
+    func a(b: i64) {}
+    a(1, 2)"#
+        }
+        "E218" => {
+            r#"A call argument has a different type than the parameter,
+and cannot be converted to it.
+
+    func a(b: i64) {}
+    a("b")"#
+        }
+        "E219" => {
+            r#"No constructor of the class takes the given arguments.
+
+    class A {
+        construct(a: i64)
+    }
+    A("a")"#
+        }
+        "E220" => {
+            r#"Conditions of 'if' and 'for' must be booleans.
+Numbers are not implicitly converted.
+
+    if (1) print("a")"#
+        }
+        "E221" => {
+            r#"Methods cannot be read as a value, only called.
+Wrap the call in a closure instead.
+
+    val m = "a".len"#
+        }
+        "E222" => {
+            r#"A field was read in a constructor before it was initialized.
+
+    class A {
+        val a: i64
+        val b: i64
+        construct() {
+            this.b = this.a
+            this.a = 5
+        }
+    }"#
+        }
+        "E223" => {
+            r#"The enum has no case with the given name.
+
+    enum A { B, C }
+    A:D"#
+        }
+        "E224" => {
+            r#"Static access with ':' is only supported on enums, to get their cases,
+and to call functions generated for classes, like 'from_json' of classes deriving Json.
+
+    class A {}
+    A:B"#
+        }
+        "E225" => {
+            r#"Static access with ':' is only supported on types, not values.
+
+    val a = 5
+    a:b"#
+        }
+        "E227" => {
+            r#"'!' negates booleans and cannot be used on other types.
+
+    val a = !5"#
+        }
+        "E228" => {
+            r#"'-' negates numbers and cannot be used on unsigned integers or
+other types.
+
+    val a = -5u32"#
+        }
+        "E229" => {
+            r#"The branch values of a 'when' expression must have the same type
+as the value compared against.
+
+    when (5) {
+        "five" -> 5
+    }"#
+        }
+        "E230" => {
+            r#"The type cannot be stored in a variable, like 'None',
+the type of expressions that produce no value.
+
+    val a = print("a")"#
+        }
+        "E231" => {
+            r#"A string ends in the middle of an escape sequence.
+
+    val a = "a\""#
+        }
+        "E232" => {
+            r#"A string contains an unknown escape sequence.
+Supported are \n, \r, \t, \\, \0, \" and \u followed by a hex code point.
+
+    val a = "\q""#
+        }
+        "E233" => {
+            r#"A number literal is too big or too small for its type.
+
+    val a = 300u8"#
+        }
+        "E234" => r#"Fields cannot be weak references."#,
+        "E235" => {
+            r#"A class or enum defines the same field twice.
+
+    class A {
+        val a = 5
+        val a = 6
+    }"#
+        }
+        "E236" => {
+            r#"A field and a method cannot have the same name, as calls
+would be ambiguous.
+
+    class A {
+        val a = 5
+        func a() {}
+    }"#
+        }
+        "E237" => {
+            r#"The value iterated with 'for' is not an iterator.
+It must implement the interface Iter or ToIter.
+
+    for (a in 5) {}"#
+        }
+        "E238" => {
+            r#"String literals require the standard library's String class,
+so they cannot be used when compiling without it."#
+        }
+        "E239" => {
+            r#"A type argument does not fulfill the bound of its type parameter.
+
+    func a[T: Number](b: T) {}
+    a[String]("b")"#
+        }
+        "E240" => {
+            r#"A private field or method was used outside of its class.
+
+    class A {
+        priv val a = 5
+    }
+    A().a"#
+        }
+        "E241" => {
+            r#"'?.' accesses members of nullable values and cannot be used
+on values that can never be null; use '.' instead.
+
+    "a"?.len()"#
+        }
+        "E242" => {
+            r#"'and' and 'or' can only be used on booleans.
+
+    val a = 1 and 2"#
+        }
+        "E243" => {
+            r#"Deferred expressions run when the scope is exited and cannot
+exit it themselves.
+
+    defer return 5"#
+        }
+        "E244" => {
+            r#"When an 'if' expression is used as a value, both branches
+must have the same type.
+
+    val a = if (b) 5 else "five""#
+        }
+        "E245" => {
+            r#"Only tuples can be destructured into multiple variables.
+
+    val (a, b) = 5"#
+        }
+        "E246" => {
+            r#"Destructuring a tuple needs exactly one variable per element.
+
+    val (a, b) = (1, 2, 3)"#
+        }
+        "E247" => {
+            r#"Functions defined inside other functions cannot use their variables.
+Use a closure instead.
+
+    val a = 5
+    func b() -> i64 = a"#
+        }
+        "E248" => {
+            r#"Numbers of different types can only be combined when one can be
+converted to the other without losing precision or sign.
+Use an explicit cast instead.
+
+    val a = 5i64 + 5u64"#
+        }
+        "E249" => {
+            r#"An array was indexed with a literal that is outside of the array.
+
+    val a = [1, 2, 3]
+    a[3]"#
+        }
+        "E250" => {
+            r#"Arrays can only be indexed with integers.
+
+    val a = [1, 2, 3]
+    a["1"]"#
+        }
+        "E251" => {
+            r#"All elements of an array literal must have the same type.
+
+    val a = [1, "2"]"#
+        }
+        "E252" => {
+            r#"The mask of a vector shuffle must be an array literal of
+2, 4, 8 or 16 integer literals, each smaller than the combined
+length of both vectors."#
+        }
+        "E253" => {
+            r#"Elements of an array literal with different types are stored as an interface
+they all implement. When there are several, it is unclear which one to use;
+cast one of the elements to the intended interface instead.
+
+    interface Shape { func area() -> f64 }
+    interface Named { func name() -> String }
+    // Circle and Square implement both
+    val a = [Circle(), Square()]
+
+    val b = [cast[Shape](Circle()), Square()]     // [Shape; 2]"#
+        }
+        "E254" => {
+            r#"A generic type can only be used as the same generic type if all type arguments
+are equal, even if they could be cast to each other on their own.
+Otherwise, the value could be used to store values of the wider type argument,
+which the original type does not allow:
+
+    enum Shape {
+        Circle
+        Square
+    }
+
+    func add_square(shapes: Array[Shape]) = shapes.push(Shape:Square)
+
+    val circles = Array[Shape:Circle](1)
+    add_square(circles)     // circles would now contain a square
+
+Values like a `Shape:Circle` can still be used as `Shape` or `Shape?`,
+and a `Shape:Circle?` as `Shape?`."#
+        }
+        "E255" => {
+            r#"'defer' runs its expression once the enclosing block is exited,
+so it must be used directly inside one. Branches of 'if' and 'when'
+without braces are not blocks:
+
+    if (a) defer cleanup()
+
+Use braces to defer to the end of the branch instead:
+
+    if (a) {
+        defer cleanup()
+    }"#
+        }
+        "E256" => {
+            r#"Dividing integers by 0 is not possible, which is checked for
+every division. Vectors of integers cannot be divided, since this
+would have to check every element:
+
+    val a: vec4i32 = [1, 2, 3, 4]
+    a / 2
+
+Convert the vector to a fixed-size array and divide each element instead."#
+        }
+
+        "E300" => {
+            r#"A type was used that is not defined or imported.
+Values of the unknown type are not checked any further,
+so code using them reports no errors until the type is fixed.
+
+    val a: DoesNotExist = 5"#
+        }
+        "E301" => {
+            r#"Functions cannot be used as types. Use a closure type instead.
+
+    func a() {}
+    func b(c: a) {}     // (): None works"#
+        }
+        "E302" => {
+            r#"A type can only be made nullable once.
+
+    val a: String?? = null"#
+        }
+        "E303" => {
+            r#"Interfaces cannot be value types, as their implementations
+have different sizes.
+
+    value interface A {}"#
+        }
+        "E304" => {
+            r#"Type arguments were given to a type without type parameters.
+
+    val a: i64[String] = 5"#
+        }
+        "E305" => r#"A program can only have one 'main' function."#,
+        "E306" => {
+            r#"A type implements the same interface twice, possibly in different modules.
+The error shows where the interface was implemented first.
+
+    impl ToString for A {}
+    impl ToString for A {}"#
+        }
+        "E307" => {
+            r#"Only interfaces can be implemented with 'impl'.
+
+    class B {}
+    impl B for A {}"#
+        }
+        "E308" => {
+            r#"Functions cannot return weak references, as the value they point to
+might be freed once the function returns."#
+        }
+        "E309" => {
+            r#"All fields without a default value must be initialized
+by every constructor.
+
+    class A {
+        val a: i64
+        construct() {}
+    }"#
+        }
+        "E310" => {
+            r#"The value of a function body has a different type than
+the function's return type.
+
+    func a() -> i64 = "a""#
+        }
+        "E311" => {
+            r#"The type of a field's default value could not be inferred.
+Specify the type of the field explicitly."#
+        }
+        "E312" => {
+            r#"A class has two constructors with the same parameter types,
+so calls would be ambiguous.
+
+    class A {
+        construct(a: i64)
+        construct(b: i64)
+    }"#
+        }
+        "E313" => {
+            r#"An impl block defines a method that is not part of the interface.
+
+    interface A {}
+    impl A for B {
+        func c() {}
+    }"#
+        }
+        "E314" => {
+            r#"An impl block is missing methods of the interface that have no
+default implementation.
+
+    interface A {
+        func b()
+    }
+    impl A for C {}"#
+        }
+        "E315" => {
+            r#"A method in an impl block has a different return type than
+in the interface.
+
+    interface A {
+        func b() -> i64
+    }
+    impl A for C {
+        func b() -> String = "b"
+    }"#
+        }
+        "E316" => {
+            r#"A method in an impl block has different parameter types than
+in the interface.
+
+    interface A {
+        func b(c: i64)
+    }
+    impl A for C {
+        func b(c: String) {}
+    }"#
+        }
+        "E317" => {
+            r#"A constructor parameter meant to set a field refers to a field
+that does not exist.
+
+    class A {
+        construct(b)
+    }"#
+        }
+        "E318" => {
+            r#"A declaration has more than one visibility modifier.
+
+    priv pub func a() {}"#
+        }
+        "E319" => {
+            r#"A class or interface defines two methods with the same name.
+Methods cannot be overloaded.
+
+    class A {
+        func b() {}
+        func b(c: i64) {}
+    }"#
+        }
+        "E320" => {
+            r#"Enums that define fields shared by all cases cannot also have
+cases with their own data in parentheses."#
+        }
+        "E321" => {
+            r#"A generic type or function was given the wrong number of
+type arguments.
+
+    val a: Array[i64, i64] = Array()"#
+        }
+        "E322" => {
+            r#"An annotation was applied to a kind of declaration it does not support,
+like an annotation for classes and enums on a function.
+
+    @derive(Hash)
+    func a() {}"#
+        }
+        "E323" => {
+            r#"The interface cannot be derived automatically.
+Only Hash, Equal, ToString and Json support '@derive'; implement others manually."#
+        }
+        "E324" => {
+            r#"Interfaces can only be derived on classes without type parameters.
+
+    @derive(ToString)
+    class A[T] {}"#
+        }
+        "E325" => {
+            r#"Deriving an interface requires all fields to implement it too.
+
+    @derive(Equal)
+    class A {
+        val b = B()     // B does not implement Equal
+    }"#
+        }
+        "E326" => {
+            r#"A function returning 'Never' must never return, like by
+always calling exit or looping forever.
+
+    func a() -> Never {}"#
+        }
+        "E327" => {
+            r#"The 'main' function must return either nothing or an i64,
+which is used as the exit code.
+
+    func main() -> String = "a""#
+        }
+        "E328" => {
+            r#"'@inline_ir' needs a single string argument containing the
+LLVM IR definition of the function.
+
+    @inline_ir
+    extern func a() -> i64"#
+        }
+        "E329" => {
+            r#"'@inline_ir' can only be used on extern functions without
+type parameters, as their body is replaced with the given IR.
+
+    @inline_ir("...")
+    func a() {}"#
+        }
+        "E330" => {
+            r#"A closure sent to another thread captures a reference counted
+value, which is not thread-safe. Mark the type with '@send' or compile
+with atomic refcounts."#
+        }
+        "E331" => {
+            r#"Only closure literals can be sent to another thread,
+as only their captured variables can be checked. Compiling with
+atomic refcounts lifts this restriction."#
+        }
+        "E332" => {
+            r#"'destroy' methods are called automatically when an instance is freed,
+so they cannot take parameters or return a value.
+
+    class A {
+        func destroy(a: i64) {}
+    }"#
+        }
+        "E333" => {
+            r#"Weak references can only point to non-nullable reference classes,
+as only those are reference counted.
+
+    val a: weak i64 = 5"#
+        }
+        "E334" => {
+            r#"Tuples must have at least 2 elements, and not more than the
+standard library provides tuple types for.
+
+    val a: (i64) = (5)"#
+        }
+        "E335" => {
+            r#"The length of an array type must be a positive integer literal.
+
+    val a: [i64; -1] = []"#
+        }
+        "E336" => {
+            r#"SIMD vector types are provided by the standard library
+and require importing 'std/simd'."#
+        }
+        "E337" => {
+            r#"'@inline' and '@noinline' take no arguments, and a function
+can only have one of them.
+
+    @inline
+    @noinline
+    func a() {}"#
+        }
+        "E338" => {
+            r#"Annotations like '@pure' and '@cold' take no arguments and
+can only be used once per function.
+
+    @cold("rarely")
+    func a() {}"#
+        }
+        "E339" => {
+            r#"Functions marked '@pure' can only assign to their own local variables,
+not to fields or global state.
+
+    @pure
+    func a(b: B) {
+        b.c = 5
+    }"#
+        }
+        "E340" => {
+            r#"Functions marked '@pure' can only call other pure functions.
+
+    @pure
+    func a() {
+        print("a")
+    }"#
+        }
+        "E341" => {
+            r#"A generic declaration instantiates itself with type arguments that
+grow every time, which would require infinitely many instances.
+
+    class Wrap[T] {}
+    func nest[T]() {
+        nest[Wrap[T]]()
+    }"#
+        }
+        "E342" => {
+            r#"'@link' names the system library an extern function is defined in,
+which is passed to the linker. It takes a single string with the name of
+the library as given to the linker, without 'lib' prefix or extension,
+and can only be used once on extern functions.
+
+    @link
+    extern func sqrt(x: f64) -> f64     // @link("m") works"#
+        }
+        "E343" => {
+            r#"The type of a lazy global without an explicit type comes from its
+initializer. Initializers are type checked before any function, in the
+order the globals are declared; a lazy global used by something type checked
+before that, like an earlier lazy global, needs its type given explicitly.
+
+    lazy val a = b + 1
+    lazy val b = 5        // lazy val b: i64 = 5 works"#
+        }
+        "E344" => {
+            r#"A lazy global cannot hold a weak reference, as the value
+it points to could be gone by the time it is read.
+
+    lazy val a: weak Node = Node()"#
+        }
+        "E345" => {
+            r#"Every case of an enum needs a different discriminant, as it is used
+to tell cases apart at runtime and to convert integers back to cases.
+Cases without an explicit discriminant get the one of the previous case plus one.
+
+    enum Color {
+        Red = 1
+        Green     // Is 2
+        Blue = 2
+    }"#
+        }
+        "E346" => {
+            r#"'@flags' makes an enum a set of flags, where every case is its own bit.
+Since a set is stored in a single i64, only simple cases are allowed,
+their discriminants are assigned automatically and there can be at most 64.
+
+    @flags
+    enum Permission {
+        Read
+        Write = 4                   // Discriminants are assigned automatically
+        Custom(val name: String)    // Only simple cases are allowed
+    }"#
+        }
+        "E347" => {
+            r#"A method defined in an enum case overrides the method of the same name
+on the enum, and is called instead of it when the value is of that case.
+Since callers only see the enum method, both need the same parameter
+and return types.
+
+    enum Shape {
+        func area() -> f64 = 0.0
+
+        Square {
+            val side = 2.0
+            func area() -> i64 = 4     // Must return f64
+        }
+    }"#
+        }
+        "E348" => {
+            r#"Deriving Json generates a function reading the class from JSON,
+which creates the class with a constructor taking each field in order.
+The parameters must have the names of the fields, like 'construct(x, y)'.
+
+    @derive(Json)
+    class Point {
+        val x: i64
+        val y: i64
+        construct(xy: i64) {    // Must take x and y
+            this.x = xy
+            this.y = xy
+        }
+    }"#
+        }
+        "E349" => {
+            r#"An interface declared 'sealed' can only be implemented by modules of
+the same package as the interface, like 'std' for an interface in 'std/ops'.
+This allows the package to add methods to the interface later
+without breaking code outside of it.
+
+    // In lib/shapes:
+    sealed interface Shape {
+        func area() -> f64
+    }
+
+    // In main:
+    impl Shape for Circle { ... }   // Not in the 'lib' package"#
+        }
+        "E350" => {
+            r#"An interface can only be implemented in the package declaring either
+the interface or the implementing type. Otherwise, two unrelated packages
+could both implement it for the same type. Types that are not classes or
+enums, like primitives, belong to the 'std' package.
+
+    // In main, with ToString and String declared in 'std':
+    impl ToString for String { ... }"#
+        }
+        "E351" => {
+            r#"A method in an impl block has more parameters than in the interface.
+
+    interface A {
+        func b(c: i64)
+    }
+    impl A for C {
+        func b(c: i64, d: i64) {}
+    }"#
+        }
+        "E352" => {
+            r#"A method in an impl block is missing parameters of the interface method.
+
+    interface A {
+        func b(c: i64, d: i64)
+    }
+    impl A for C {
+        func b(c: i64) {}
+    }"#
+        }
+        "E353" => {
+            r#"An interface method with 'Self' in its signature was called on a value
+of the interface type. 'Self' stands for the implementing type, which is not
+known for interface values. Use a type parameter bound on the interface instead.
+
+    interface Combine {
+        func combine(other: Self) -> Self
+    }
+
+    func combine(a: Combine, b: Combine) = a.combine(b)             // Error
+    func combine[T: Combine](a: T, b: T) -> T = a.combine(b)        // Ok"#
+        }
+        "E354" => {
+            r#"The '?' operator was used on a value that is not a 'Res'.
+It only propagates the errors of 'Res' values; use '??' for nullable values.
+
+    val a: String? = null
+    val b = a?              // Error
+    val c = a ?? "default"  // Ok"#
+        }
+        "E355" => {
+            r#"The '?' operator was used in a function that does not return a 'Res',
+or one whose error type does not fit the error of the value.
+On an error, '?' returns it from the function, which requires a 'Res' return type.
+
+    func parse(text: String) -> Res[i64, String] = ...
+
+    func twice(text: String) -> i64 = parse(text)? * 2     // Error
+    func twice(text: String) -> Res[i64, String] {         // Ok
+        val number = parse(text)?
+        Res[i64, String]:Ok(number * 2)
+    }"#
+        }
+        "E356" => {
+            r#"A call with the placeholder '_' as an argument creates a closure
+taking the missing arguments, but the callee cannot be turned into one.
+This is only possible for closures and functions with all type arguments known;
+methods and generic functions without explicit type arguments are not supported.
+
+    func add(a: i64, b: i64) -> i64 = a + b
+    val add_five = add(_, 5)       // Ok, (i64): i64
+    val b = "text".contains(_)     // Error"#
+        }
+        "E357" => {
+            r#"Compilation was aborted because the program exceeded one of the limits
+set by whoever runs the compiler, like a service compiling programs it
+does not trust. The limits are on the number of modules, the number of
+instances of generic declarations, the number of expressions and the
+time spent compiling. There is no problem with the program itself;
+make it smaller or ask for higher limits."#
+        }
+        "E358" => {
+            r#"The LLVM IR given with '@inline_ir' could not be parsed by LLVM,
+or it does not define the function it is given to. The error contains
+the message of LLVM, which points at the problem inside the IR.
+
+    @inline_ir("define i64 @b() {\n  ret i64 0\n}")
+    extern func a() -> i64    // Defines 'b' instead of 'a'"#
+        }
+
+        "W001" => {
+            r#"A local variable has the same name as a variable of an outer scope,
+making the outer one inaccessible. Only reported with --warn-shadowing.
+
+    val a = 5
+    if (true) {
+        val a = 6
+    }"#
+        }
+        "W002" => {
+            r#"An annotation is not known to the compiler and has no effect.
+This is usually caused by a typo in its name.
+
+    @colt
+    func a() {}"#
+        }
+        "W003" => {
+            r#"A source file uses both Unix (\n) and Windows (\r\n) line endings.
+This is usually caused by editing a file with different editors or
+copying text between files. Both are whitespace, so the file still
+compiles; convert it to use one of them to silence this warning."#
+        }
+
+        _ => return None,
+    })
+}
@@ -27,6 +27,23 @@ pub enum GErr {
     E007,
     // Expected expression
     E008,
+    // Annotations are only allowed on ADTs, functions and methods
+    E009,
+    // Expression or type nested too deeply
+    E010,
+    // Incomplete syntax tree
+    E011,
+    // Global variable that is not 'lazy val'
+    E012,
+    // Invalid token
+    E013(&'static str),
+    // Ambiguous operators without parentheses
+    E014 {
+        first: SmolStr,
+        second: SmolStr,
+    },
+    // Source file is not valid UTF-8
+    E015(String),
 
     // Already defined name
     E100(SmolStr),
@@ -36,6 +53,14 @@ pub enum GErr {
     E102,
     // Unknown declaration
     E103,
+    // Multiple init blocks in one module
+    E104,
+    // Name imported from multiple modules with '+'
+    E105 {
+        name: SmolStr,
+        first: String,
+        second: String,
+    },
 
     // Cannot assign to
     E200(&'static str),
@@ -49,8 +74,11 @@ pub enum GErr {
     E204,
     // This variable may not be captured
     E205,
-    // Undefined variable
-    E206(SmolStr),
+    // Undefined variable, with the most similar name in scope
+    E206 {
+        name: SmolStr,
+        suggestion: Option<SmolStr>,
+    },
     // Break is only allowed in loops
     E207,
     // Cannot redefine variable in same scope
@@ -60,8 +88,8 @@ pub enum GErr {
         expected: String,
         was: String,
     },
-    // Unknown field or method
-    E210,
+    // Unknown field or method, with the most similar one of the type
+    E210(Option<SmolStr>),
     // Can only call generic methods directly
     E211,
     // Return expr was different than function ret type
@@ -95,7 +123,7 @@ pub enum GErr {
     E222,
     // Unknown enum case
     E223,
-    // Static access is only supported on enum types
+    // Static access is only supported on enum types and generated functions
     E224,
     // Static access is not supported on values
     E225,
@@ -133,9 +161,59 @@ pub enum GErr {
     E240,
     // '?.' can only be used with nullable values
     E241,
+    // Logic operators can only be used on booleans
+    E242,
+    // Deferred expressions cannot exit early
+    E243,
+    // If expression used as value has branches of different types
+    E244 {
+        first: String,
+        second: String,
+    },
+    // Only tuples can be destructured
+    E245(String),
+    // Destructuring needs one variable per tuple element
+    E246 {
+        expected: usize,
+        was: usize,
+    },
+    // Nested functions cannot capture variables
+    E247(SmolStr),
+    // Binary operator on numbers that cannot be widened to a common type
+    E248 {
+        left: String,
+        right: String,
+    },
+    // Array index is a literal outside of the array
+    E249 {
+        index: i128,
+        len: u64,
+    },
+    // Array index is not an integer
+    E250,
+    // Array literal elements have different types
+    E251,
+    // Vector shuffle mask is not a literal or out of range
+    E252,
+    // Array literal elements implement several common interfaces
+    E253(Vec<SmolStr>),
+    // Generic type used with different but castable type arguments
+    E254 {
+        expected: String,
+        was: String,
+    },
+    // Defer used outside of a block
+    E255,
+    // Integer vector divided, which cannot check lanes for 0
+    E256,
 
-    // Unknown type
-    E300(String),
+    // Unknown type, with the most similar type in scope
+    // and a module declaring a type of the name
+    E300 {
+        name: String,
+        suggestion: Option<SmolStr>,
+        module: Option<String>,
+    },
     // Cannot use function as type
     E301,
     // Nullable cannot be applied multiple times
@@ -146,8 +224,8 @@ pub enum GErr {
     E304,
     // Can't define main multiple times
     E305,
-    // Interface already defined for type
-    E306,
+    // Interface already defined for type, with the location of the existing impl
+    E306(String),
     // Only interfaces can be implemented
     E307,
     // Cannot return a weak reference
@@ -174,6 +252,7 @@ pub enum GErr {
     },
     // Incorrect parameter type on interface method
     E316 {
+        name: SmolStr,
         expected: String,
         was: String,
     },
@@ -187,6 +266,108 @@ pub enum GErr {
     E320,
     // Incorrect amount of type parameters
     E321,
+    // Annotation applied to the wrong kind of declaration
+    E322(SmolStr),
+    // Interface cannot be derived
+    E323(SmolStr),
+    // Interfaces can only be derived on non-generic classes
+    E324,
+    // Field does not implement derived interface
+    E325 {
+        iface: SmolStr,
+        field: SmolStr,
+    },
+    // Function returning 'Never' may return
+    E326,
+    // main must return either nothing or i64
+    E327,
+    // inline_ir requires a single string argument
+    E328,
+    // inline_ir is only allowed on non-generic extern functions
+    E329,
+    // Captured variable cannot be sent to another thread
+    E330(SmolStr),
+    // Only closure literals can be sent to another thread
+    E331,
+    // destroy must not have parameters or a return type
+    E332,
+    // Weak references can only point to classes
+    E333,
+    // Tuple has an unsupported amount of elements
+    E334(usize),
+    // Array length is not a positive integer
+    E335,
+    // SIMD vector type used without importing std/simd
+    E336,
+    // Invalid 'inline' or 'noinline' annotation
+    E337,
+    // Annotation without arguments like 'pure' or 'cold' used incorrectly
+    E338(SmolStr),
+    // Pure function assigns to something other than its local variables
+    E339,
+    // Pure function calls a function that is not pure
+    E340,
+    // Declaration instantiated with endlessly growing type arguments
+    E341 {
+        limit: usize,
+        chain: String,
+    },
+    // Invalid 'link' annotation
+    E342,
+    // Type of lazy global used before it is known
+    E343(SmolStr),
+    // Lazy global is a weak reference
+    E344,
+    // Duplicate enum discriminant
+    E345(u64),
+    // Invalid 'flags' annotation or flags enum case
+    E346,
+    // Enum case method overrides enum method with a different signature
+    E347(SmolStr),
+    // Class deriving Json has no constructor taking all its fields
+    E348(SmolStr),
+    // Sealed interface implemented outside of its package
+    E349(SmolStr),
+    // Neither interface nor implementor are declared in the current package
+    E350 {
+        iface: SmolStr,
+        implementor: String,
+    },
+    // Parameter of interface method impl not declared by the interface
+    E351(SmolStr),
+    // Parameter of interface method missing in impl
+    E352 {
+        name: SmolStr,
+        ty: String,
+    },
+    // Interface method using 'Self' called on an interface value
+    E353(SmolStr),
+    // '?' used on a value that is not a Res
+    E354(String),
+    // '?' used in a function not returning a Res with a fitting error type
+    E355 {
+        was: String,
+        ret_type: String,
+    },
+    // Placeholder '_' used in a call that cannot be partially applied
+    E356(String),
+    // Compilation exceeded a resource limit
+    E357 {
+        resource: &'static str,
+        limit: String,
+    },
+    // LLVM IR given with '@inline_ir' is invalid
+    E358 {
+        name: SmolStr,
+        message: String,
+    },
+
+    // Variable shadows a variable of an outer scope
+    W001(SmolStr),
+    // Unknown annotation
+    W002(SmolStr),
+    // Source file mixes line endings
+    W003,
 }
 
 impl GErr {
@@ -194,11 +375,29 @@ impl GErr {
         match self {
             E001 { want, after } => format!("Expected {} after {}.", want, after),
             E006 { modifier, on } => format!("Cannot have '{:?}' modifier on {}.", modifier, on),
+            E013(what) => format!("Invalid token: {}.", what),
+            E014 { first, second } => format!(
+                "'{}' and '{}' are ambiguous without parentheses. Add parentheses to group them.",
+                first, second
+            ),
+            E015(file) => format!("File '{}' is not valid UTF-8.", file),
 
             E100(name) => format!("Name {} already defined in this module", name),
+            E105 {
+                name,
+                first,
+                second,
+            } => format!(
+                "'{}' is ambiguous, since it is imported from both '{}' and '{}'. Use 'hiding' or a selective import to resolve this.",
+                name, first, second
+            ),
 
             E200(name) => format!("Cannot assign to {}", name),
-            E206(name) => format!("Variable '{}' is not defined", name),
+            E206 { name, suggestion } => format!(
+                "Variable '{}' is not defined.{}",
+                name,
+                Self::did_you_mean(suggestion)
+            ),
             E208(name) => format!("Cannot redefine variable '{}' in the same scope.", name),
             E209 { expected, was } => format!(
                 "Break expressions and for body must have same type (Expected {}, was {}).",
@@ -208,6 +407,9 @@ impl GErr {
                 "Return expression in function has wrong type (Expected {}, was {}).",
                 expected, was
             ),
+            E210(suggestion) => {
+                format!("Unknown field or method.{}", Self::did_you_mean(suggestion))
+            }
             E215(thing) => format!("'{}' cannot be called.", thing),
             E216 { expected, was } => format!(
                 "Incorrect amount of function arguments. (Expected {}; got {}).",
@@ -229,8 +431,43 @@ impl GErr {
                 argument,
                 bound
             ),
+            E244 { first, second } => format!(
+                "Branches of if expression used as a value have different types ({} and {}).",
+                first, second
+            ),
+            E245(ty) => format!("Only tuples can be destructured (was {}).", ty),
+            E246 { expected, was } => format!(
+                "Wrong amount of variables when destructuring tuple (Expected {}, was {}).",
+                expected, was
+            ),
+            E247(name) => format!(
+                "Nested functions cannot capture variables (tried to use '{}'), use a closure instead.",
+                name
+            ),
+            E248 { left, right } => format!(
+                "Cannot implicitly convert between '{}' and '{}' without losing precision or sign, use an explicit cast.",
+                left, right
+            ),
+            E249 { index, len } => format!(
+                "Index {} is out of bounds for array of length {}.",
+                index, len
+            ),
 
-            E300(name) => format!("Unknown type '{}'.", name),
+            E300 {
+                name,
+                module: Some(module),
+                ..
+            } => format!(
+                "Unknown type '{}'. It exists in {}; add 'import {}/{}' to use it.",
+                name, module, module, name
+            ),
+            E300 {
+                name, suggestion, ..
+            } => format!(
+                "Unknown type '{}'.{}",
+                name,
+                Self::did_you_mean(suggestion)
+            ),
             E309(names) => {
                 let mut str = self.fmt_list(
                     "Cannot have uninitialized fields after constructor (Missing: ",
@@ -239,6 +476,22 @@ impl GErr {
                 str.push_str(").");
                 str
             }
+            E253(names) => {
+                let mut str = self.fmt_list(
+                    "Array literal elements have different types that all implement several interfaces: ",
+                    names,
+                );
+                str.push_str("; cast one of the elements to the intended one.");
+                str
+            }
+            E254 { expected, was } => format!(
+                "Type arguments of generic types must match exactly; '{}' cannot be used as '{}' even though its type arguments can be cast.",
+                was, expected
+            ),
+            E306(location) => format!(
+                "Interface already defined for type (first implemented at {}).",
+                location
+            ),
             E310 { expected, was } => format!(
                 "Body type does not match function return type (Expected {}, was {}).",
                 expected, was
@@ -252,15 +505,106 @@ impl GErr {
                 "Incorrect return type on interface method (Expected {}, was {}).",
                 expected, was
             ),
-            E316 { expected, was } => format!(
-                "Incorrect parameter type on interface method (Expected {}, was {}).",
-                expected, was
+            E316 {
+                name,
+                expected,
+                was,
+            } => format!(
+                "Incorrect type of parameter '{}' on interface method (Expected {}, was {}).",
+                name, expected, was
+            ),
+
+            E322(name) => format!(
+                "Annotation '{}' cannot be applied to this kind of declaration.",
+                name
+            ),
+            E323(name) => format!("Cannot derive '{}'.", name),
+            E325 { iface, field } => format!(
+                "Cannot derive '{}', as field '{}' does not implement it.",
+                iface, field
+            ),
+            E330(name) => format!(
+                "Cannot send captured variable '{}' to another thread, as its type is reference counted. Mark the type with '@send' or compile with atomic refcounts.",
+                name
+            ),
+            E334(max) => format!("Tuples must have between 2 and {} elements.", max),
+            E338(name) => format!(
+                "'{}' takes no arguments and can only be used once per function.",
+                name
+            ),
+            E341 { limit, chain } => format!(
+                "Type arguments grow endlessly, exceeding the instantiation depth limit of {} (Instantiated by: {}).",
+                limit, chain
+            ),
+            E343(name) => format!(
+                "Type of lazy global '{}' is not known yet; give it a type explicitly.",
+                name
+            ),
+            E345(value) => format!("Multiple enum cases have the discriminant {}.", value),
+            E347(name) => format!(
+                "Method '{}' of an enum case must have the same parameter and return types as the enum method it overrides.",
+                name
+            ),
+            E348(name) => format!(
+                "Cannot derive 'Json', as class '{}' has no constructor taking exactly its fields.",
+                name
             ),
+            E349(name) => format!(
+                "Interface '{}' is sealed and can only be implemented in its own package.",
+                name
+            ),
+            E350 { iface, implementor } => format!(
+                "Cannot implement '{}' for '{}', as neither is declared in this package.",
+                iface, implementor
+            ),
+            E351(name) => format!(
+                "Parameter '{}' is not a parameter of the interface method.",
+                name
+            ),
+            E352 { name, ty } => format!(
+                "Missing parameter '{}: {}' of the interface method.",
+                name, ty
+            ),
+            E353(name) => format!(
+                "Method '{}' uses 'Self' and cannot be called on interface values.",
+                name
+            ),
+            E354(ty) => format!(
+                "'?' can only be used on values of type 'Res', not '{}'.",
+                ty
+            ),
+            E355 { was, ret_type } => format!(
+                "Cannot return the error of '{}' with '?' from a function returning '{}'.",
+                was, ret_type
+            ),
+            E356(ty) => format!(
+                "Cannot partially apply '{}'; only functions without type parameters and closures can be.",
+                ty
+            ),
+            E357 { resource, limit } => format!(
+                "Compilation aborted: The program exceeds the limit on {} ({}).",
+                resource, limit
+            ),
+            E358 { name, message } => format!("Invalid inline IR of '{}': {}", name, message),
+
+            W001(name) => format!("Variable '{}' shadows a variable of an outer scope.", name),
+            W002(name) => format!("Unknown annotation '{}' is ignored.", name),
 
             _ => self.msg().to_string(),
         }
     }
 
+    /// An extended description of this error with an example, see [crate::explain].
+    pub fn explain(&self) -> &'static str {
+        crate::explain(self.as_ref()).unwrap()
+    }
+
+    /// If this is a warning instead of an error.
+    /// Warnings do not stop compilation.
+    pub fn is_warning(&self) -> bool {
+        self.as_ref().starts_with('W')
+    }
+
     fn fmt_list(&self, start: &str, list: &[SmolStr]) -> String {
         let mut buf = start.to_string();
         buf.push_str(&list[0]);
@@ -270,6 +614,14 @@ impl GErr {
         buf
     }
 
+    /// Suggests a name the user might have meant instead of an unknown one.
+    fn did_you_mean(suggestion: &Option<SmolStr>) -> String {
+        match suggestion {
+            Some(name) => format!(" Did you mean '{}'?", name),
+            None => String::new(),
+        }
+    }
+
     fn msg(&self) -> &str {
         match self {
             E002 => "Expected top-level declaration.",
@@ -278,10 +630,15 @@ impl GErr {
             E005 => "Expected ':' or '=' after ADT member name.",
             E007 => "'when' expression can only have 1 'else' branch.",
             E008 => "Expected expression.",
+            E009 => "Annotations can only be applied to classes, enums, interfaces, functions, methods and closures.",
+            E010 => "Expression or type is nested too deeply.",
+            E011 => "Incomplete or malformed syntax.",
+            E012 => "Global variables must be declared with 'lazy val'.",
 
             E101 => "Could not find main function.",
             E102 => "Unknown module.",
             E103 => "Unresolved import.",
+            E104 => "Modules can only have one 'init' block.",
 
             E201 => "Value is a different type than assignment target.",
             E202 => "No implementation of operator found for types.",
@@ -289,7 +646,6 @@ impl GErr {
             E204 => "Fields cannot be called.",
             E205 => "This variable may not be captured (weak reference)",
             E207 => "Break is only allowed in loops.",
-            E210 => "Unknown field or method.",
             E211 => "Can only call generic methods directly.",
             E213 => "Cannot use type arguments on local variables.",
             E214 => "Cannot infer types (please specify explicitly).",
@@ -298,7 +654,7 @@ impl GErr {
             E221 => "Cannot get ADT method (must be called).",
             E222 => "Cannot get uninitialized ADT member.",
             E223 => "Unknown enum case.",
-            E224 => "Static access is only supported on enum types.",
+            E224 => "Static access is only supported on enum types and functions generated for classes.",
             E225 => "Static access is not supported on values.",
             E227 => "'!' can only be used on boolean values.",
             E228 => "'-' can only be used on signed integers and floats.",
@@ -312,13 +668,19 @@ impl GErr {
             E238 => "Cannot use string literals with no_std enabled.",
             E240 => "Field is not visible.",
             E241 => "'?.' can only be used with nullable values.",
+            E242 => "Logic operators ('and', 'or') can only be used on booleans.",
+            E243 => "Deferred expressions cannot contain 'return', '?' or 'break'.",
+            E250 => "Array indices must be integers.",
+            E251 => "All elements of an array literal must have the same type.",
+            E252 => "Shuffle mask must be an array literal of 2, 4, 8 or 16 integer literals within the vectors.",
+            E255 => "'defer' can only be used directly inside a block.",
+            E256 => "Integer vectors cannot be divided, as any element could be 0.",
 
             E301 => "Functions cannot be used as types.",
             E302 => "Nullable cannot be applied multiple times.",
             E303 => "'value' modifier cannot be applied to interfaces.",
             E304 => "Type does not take type arguments.",
             E305 => "Can't define main multiple times.",
-            E306 => "Interface already defined for type.",
             E307 => "Only interfaces can be implemented.",
             E308 => "Cannot return a weak reference.",
             E311 => "Cannot infer type of member with default value (specify type explicitly).",
@@ -329,6 +691,24 @@ impl GErr {
             E319 => "Method with same name already defined.",
             E320 => "Cannot use data cases with enums that have fields.",
             E321 => "Incorrect amount of type parameters.",
+            E324 => "Interfaces can only be derived on non-generic classes.",
+            E326 => "Functions returning 'Never' must not be able to return.",
+            E327 => "The main function must return either nothing or i64.",
+            E328 => "'inline_ir' requires a single string argument containing LLVM IR.",
+            E329 => "'inline_ir' can only be used on extern functions without type parameters.",
+            E331 => "Only closure literals can be sent to another thread, unless compiling with atomic refcounts.",
+            E332 => "'destroy' methods must not have parameters or a return type.",
+            E333 => "'weak' can only be used on non-nullable reference classes.",
+            E335 => "Array length must be a positive integer literal.",
+            E336 => "SIMD vector types require importing 'std/simd'.",
+            E337 => "'inline' and 'noinline' take no arguments and can only be used once per function.",
+            E339 => "Pure functions can only assign to their own local variables.",
+            E340 => "Pure functions can only call other pure functions.",
+            E342 => "'link' requires a single string argument naming a library and can only be used once on extern functions.",
+            E344 => "Lazy globals cannot be weak references.",
+            E346 => "'flags' takes no arguments and can only be used on enums with up to 64 simple cases without explicit discriminants.",
+
+            W003 => "File mixes Unix (\\n) and Windows (\\r\\n) line endings.",
 
             _ => unreachable!(),
         }
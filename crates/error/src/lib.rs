@@ -4,6 +4,7 @@
  * This file is under the Apache 2.0 license. See LICENSE in the root of this repository for details.
  */
 
+mod explain;
 mod kinds;
 
 use std::{
@@ -14,9 +15,10 @@ use std::{
 
 use ansi_term::{
     ANSIString, ANSIStrings,
-    Color::{Blue, Red},
+    Color::{Blue, Red, Yellow},
     Style,
 };
+pub use explain::explain;
 pub use kinds::GErr;
 use lexer::{Lexer, Span};
 use std::fmt::Debug;
@@ -35,7 +37,12 @@ pub struct Errors {
 
 impl Display for Errors {
     fn fmt(&self, f: &mut Formatter) -> Result<(), FmtErr> {
-        writeln!(f, "Errors inside {}:", self.origin)?;
+        let kind = if self.errors.iter().all(|e| e.kind.is_warning()) {
+            "Warnings"
+        } else {
+            "Errors"
+        };
+        writeln!(f, "{} inside {}:", kind, self.origin)?;
         for err in &self.errors {
             writeln!(f, "{}\n", err.to_string(self.src.as_ref(), &self.origin))?;
         }
@@ -64,14 +71,23 @@ impl Error {
         let dimmed = regular.dimmed();
         let italic = regular.italic();
         let red_ul = Red.underline();
+        let label = if self.kind.is_warning() {
+            Yellow
+                .bold()
+                .paint(format!("Warning[{}]", self.kind.as_ref()))
+        } else {
+            Red.bold().paint(format!("Error[{}]", self.kind.as_ref()))
+        };
 
+        // Errors without a span, like on compiler-generated code, only get a header
+        let source = source.filter(|_| !matches!(self.index, ErrorSpan::None));
         if let Some(source) = source {
             let span = self.index.get_span(source);
             let (line, start, len) = span_to_info(source, span);
 
             let result = format!(
                 "\n{}: {}\n{} {} L{}:{}",
-                Red.bold().paint(format!("Error[{}]", self.kind.as_ref())),
+                label,
                 bold.paint(&self.kind.fmt()),
                 Blue.dimmed().paint("-->"),
                 italic.paint(origin),
@@ -94,6 +110,16 @@ impl Error {
                 .collect::<String>();
             let line_end = line_str.chars().skip(start + len - 1).collect::<String>();
 
+            // Carets under the marked text, keeping tabs so they line up with the source.
+            // Spans continuing on the next lines are only marked until the end of the first,
+            // empty spans like the end of the file get a single caret
+            let caret_indent = line_start
+                .chars()
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect::<String>();
+            let carets = "^".repeat(line_marked.chars().count().max(1));
+            let caret_color = if self.kind.is_warning() { Yellow } else { Red };
+
             let formatted: &[ANSIString<'a>] = &[
                 regular.paint(result),
                 dimmed.paint(format!("\n     |\n{:4} | ", line - 1)),
@@ -102,6 +128,9 @@ impl Error {
                 regular.paint(line_start),
                 red_ul.paint(line_marked),
                 regular.paint(line_end),
+                dimmed.paint("\n     | "),
+                regular.paint(caret_indent),
+                caret_color.bold().paint(carets),
                 dimmed.paint(format!("\n{:4} | ", line + 1)),
                 regular.paint(next_line),
                 dimmed.paint("\n     |"),
@@ -111,7 +140,7 @@ impl Error {
         } else {
             format!(
                 "\n{}: {}\n{} {}",
-                Red.bold().paint(format!("Error[{}]", self.kind.as_ref())),
+                label,
                 bold.paint(&self.kind.fmt()),
                 Blue.dimmed().paint("-->"),
                 italic.paint(origin),
@@ -120,16 +149,16 @@ impl Error {
     }
 }
 
+/// Returns the line and column of the start of the span, both starting at 1,
+/// and the length of the span. Columns and length count characters, not bytes,
+/// to match the text shown in editors.
 fn span_to_info(src: &str, span: Span) -> (usize, usize, usize) {
-    let (line, line_offset) = src[0..span.start]
-        .lines()
-        .rev()
-        .skip(1)
-        .fold((0, 0), |(lc, offs), line| (lc + 1, offs + line.len() + 1));
+    let before = &src[..span.start];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
     (
-        line + 1,
-        span.start - line_offset + 1,
-        span.end - span.start,
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+        src[span].chars().count(),
     )
 }
 
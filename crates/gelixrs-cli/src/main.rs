@@ -4,10 +4,21 @@
  * This file is under the Apache 2.0 license. See LICENSE in the root of this repository for details.
  */
 
-use gelixrs::{stem_to_smol, GIRFlags};
-use std::path::PathBuf;
+use gelixrs::{
+    log, stem_to_smol, Allocator, CountingAllocator, EmitOptions, FileSources, GIRFlags, Level,
+    Limits, LiteralTypes, Manifest, Optimizations, OutputKind, StdLibrary, Tracer,
+};
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 use structopt::StructOpt;
 
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
 #[derive(StructOpt, Debug, Default)]
 #[structopt(name = "gelixrs", about = "A compiler for the gelix language.")]
 struct Opt {
@@ -35,23 +46,121 @@ struct Opt {
     #[structopt(long = "no-std")]
     no_std: bool,
 
-    /// Path of the resulting executable
+    /// Warn about local variables shadowing variables of an outer scope
+    #[structopt(long = "warn-shadowing")]
+    warn_shadowing: bool,
+
+    /// Free class instances once unreferenced, running their destroy methods (experimental)
+    #[structopt(long = "refcount")]
+    refcount: bool,
+
+    /// Use atomic refcounts, required when sharing values between threads
+    #[structopt(long = "atomic-rc")]
+    atomic_rc: bool,
+
+    /// Print all class instances still alive at exit, useful for finding reference cycles
+    #[structopt(long = "leak-report")]
+    leak_report: bool,
+
+    /// Allocator to use for class instances; 'malloc' or 'arena'.
+    /// 'arena' never frees memory and is only suited for short-lived programs
+    #[structopt(long, default_value = "malloc")]
+    allocator: Allocator,
+
+    /// Optimizations to run on GIR before LLVM; 'all', 'none' or a comma-separated
+    /// list of 'licm', 'cse' and 'inline'. Mostly useful with LLVM optimizations turned off
+    #[structopt(long = "gir-opt", default_value = "all")]
+    gir_opt: Optimizations,
+
+    /// Types of number literals without a type suffix, when not inferred otherwise;
+    /// a comma-separated list like 'i32,f32'. Does not affect the standard library
+    #[structopt(long = "literal-types", default_value = "i64,f64")]
+    literal_types: LiteralTypes,
+
+    /// Directory to write the GIR and LLVM IR of the program to while compiling
+    #[structopt(long, parse(from_os_str))]
+    emit: Option<PathBuf>,
+
+    /// File to write a JSON manifest to, listing all produced files and all modules
+    /// with their dependencies and content hashes. Meant for build systems
+    #[structopt(long, parse(from_os_str))]
+    manifest: Option<PathBuf>,
+
+    /// File to write a JSON source map to, mapping the symbols of all compiled functions
+    /// and their lines in the LLVM IR back to gelix source. Meant for attributing profiler output
+    /// to functions; individual instructions are not mapped
+    #[structopt(long = "source-map", parse(from_os_str))]
+    source_map: Option<PathBuf>,
+
+    /// File to write all type checking decisions to, like inserted casts
+    /// and resolved methods. Useful for understanding why a value has a certain type
+    #[structopt(long, parse(from_os_str))]
+    trace: Option<PathBuf>,
+
+    /// Print the wall time, allocation count and processed nodes of every
+    /// compiler pass as a table. Useful for finding slow passes
+    #[structopt(long)]
+    profile: bool,
+
+    /// Abort compilation if the program has more modules than this,
+    /// not counting the standard library
+    #[structopt(long = "max-modules")]
+    max_modules: Option<usize>,
+
+    /// Abort compilation if the program requires more instances
+    /// of generic declarations than this
+    #[structopt(long = "max-instantiations")]
+    max_instantiations: Option<usize>,
+
+    /// Abort compilation if the program has more expressions than this
+    #[structopt(long = "max-expressions")]
+    max_expressions: Option<usize>,
+
+    /// Abort compilation if generating GIR takes longer than this many milliseconds.
+    /// Meant for compiling untrusted programs together with the other limits;
+    /// IR generation and LLVM are not limited
+    #[structopt(long)]
+    timeout: Option<u64>,
+
+    /// Path of the resulting file
     #[structopt(short, long)]
     output: Option<PathBuf>,
 
+    /// Type of the resulting file; 'bin' for an executable, 'obj' for an object file,
+    /// 'staticlib' or 'sharedlib' for a library exporting all public functions
+    #[structopt(long = "output-type", default_value = "bin")]
+    output_type: OutputKind,
+
     /// The level of optimization to use with clang
     #[structopt(short = "O", default_value = "3")]
     optimize_level: usize,
 
+    /// Write internal compiler output to stderr; once for statistics,
+    /// twice for details useful when debugging the compiler, like the passes run
+    #[structopt(short = "v", parse(from_occurrences))]
+    verbose: u8,
+
+    /// Print an extended description of an error code like 'E301' and exit
+    #[structopt(long)]
+    explain: Option<String>,
+
     /// File to compile
-    #[structopt(parse(from_os_str))]
-    file: PathBuf,
+    #[structopt(parse(from_os_str), required_unless = "explain")]
+    file: Option<PathBuf>,
 }
 
 fn main() {
-    run(Opt::from_args()).map_err(|e| println!("{}", e)).ok();
+    let args = Opt::from_args();
+    gelixrs::set_log_level(match args.verbose {
+        0 => Level::Warn,
+        1 => Level::Info,
+        2 => Level::Debug,
+        _ => Level::Trace,
+    });
+    run(args).map_err(|e| println!("{}", e)).ok();
     if cfg!(debug_assertions) {
-        println!(
+        log!(
+            Level::Info,
             "\nCompiler benchmark results:\n{}",
             gelixrs::BENCH.lock().unwrap()
         )
@@ -59,18 +168,29 @@ fn main() {
 }
 
 fn run(args: Opt) -> Result<(), &'static str> {
-    if !args.file.exists() {
+    if let Some(code) = &args.explain {
+        let explanation = gelixrs::explain(&code.to_uppercase()).ok_or("Unknown error code.")?;
+        println!("{}", explanation);
+        return Ok(());
+    }
+
+    let file = args.file.clone().ok_or("Missing file to compile.")?;
+    if !file.exists() {
         return Err("Given path does not exist.");
     }
 
-    let modules = if !args.no_std {
-        let std_mod = gelixrs::find_std_module()?;
-        vec![args.file.clone(), std_mod]
+    let std = if !args.no_std {
+        Some(gelixrs::find_std()?)
     } else {
-        vec![args.file.clone()]
+        None
     };
+    let mut modules = vec![file.clone()];
+    if let Some(StdLibrary::Dir(std_mod)) = &std {
+        modules.push(std_mod.clone());
+    }
 
-    let code = gelixrs::parse_source(modules).map_err(|errors| {
+    let sources = (FileSources(vec![file.clone()]), std);
+    let code = gelixrs::parse_modules(&sources).map_err(|errors| {
         for file in errors {
             println!("{} error(s):\n{}", file.errors.len(), file);
             println!();
@@ -79,7 +199,7 @@ fn run(args: Opt) -> Result<(), &'static str> {
     })?;
 
     if args.parse {
-        let stem = stem_to_smol(&args.file);
+        let stem = stem_to_smol(&file);
         for module in code.iter().filter(|m| m.path.index(0).unwrap() == &stem) {
             println!("{:#?}\n\n", module);
         }
@@ -87,19 +207,48 @@ fn run(args: Opt) -> Result<(), &'static str> {
     }
 
     let gir_flags = GIRFlags {
+        library: args.output_type.is_library(),
+        executable: args.output_type == OutputKind::Executable,
         no_std: args.no_std,
         no_prelude: args.no_std,
+        warn_shadowing: args.warn_shadowing,
+        refcounting: args.refcount,
+        atomic_refcounts: args.atomic_rc,
+        leak_report: args.leak_report,
+        allocator: args.allocator,
+        optimizations: args.gir_opt,
+        literal_types: args.literal_types,
+        limits: Limits {
+            max_modules: args.max_modules,
+            max_instantiations: args.max_instantiations,
+            max_expressions: args.max_expressions,
+            timeout: args.timeout.map(Duration::from_millis),
+        },
         ..GIRFlags::default()
     };
-    let gir = gelixrs::compile_gir(code, gir_flags).map_err(|errors| {
+    let gir = match &args.trace {
+        Some(path) => {
+            let file = File::create(path).map_err(|_| "Failed to create trace file.")?;
+            gelixrs::compile_gir_traced(code, gir_flags, Tracer::new(BufWriter::new(file)))
+        }
+        None if args.profile => gelixrs::compile_gir_profiled(code, gir_flags),
+        None => gelixrs::compile_gir(code, gir_flags),
+    };
+    let gir = gir.map_err(|errors| {
         for error in errors {
             println!("{}\n", error);
         }
         "GIR generator encountered errors. Exiting."
     })?;
+    for warning in &gir.warnings {
+        println!("{}\n", warning);
+    }
+    if let Some(profile) = &gir.profile {
+        println!("{}\n", profile);
+    }
 
     if args.gir || args.gir_all {
-        let stem = stem_to_smol(&args.file);
+        let stem = stem_to_smol(&file);
         for module in gir
             .modules
             .iter()
@@ -110,41 +259,93 @@ fn run(args: Opt) -> Result<(), &'static str> {
         return Ok(());
     }
 
-    let module = gelixrs::compile_ir(gelixrs::ir_context(), gir);
+    let libraries = gir.link_libraries();
+    let mut manifest = args
+        .manifest
+        .as_ref()
+        .map(|_| Manifest::new(&gir, &modules));
+
+    let emit = EmitOptions {
+        gir: args.emit.is_some(),
+        ir: args.emit.is_some(),
+        source_map: args.source_map.is_some(),
+        dir: args.emit.clone(),
+        ..EmitOptions::default()
+    };
+    let (module, emitted) = gelixrs::compile_ir_emit(gelixrs::ir_context(), gir, &emit)
+        .map_err(|_| "Failed to write emitted GIR and IR. Exiting.")?;
+    log!(Level::Info, "Constant data:\n{}\n", emitted.constants);
+    if let (Some(source_map), Some(path)) = (&emitted.source_map, &args.source_map) {
+        source_map
+            .write(path)
+            .map_err(|_| "Failed to write source map. Exiting.")?;
+        add_artifact(&mut manifest, "source-map", path)?;
+    }
+    if let (Some(manifest), Some(dir)) = (&mut manifest, &args.emit) {
+        manifest
+            .add_artifact("gir", &dir.join("program.gir"))
+            .and_then(|_| manifest.add_artifact("llvm-ir", &dir.join("program.ll")))
+            .map_err(|_| "Failed to read emitted GIR and IR. Exiting.")?;
+    }
 
     if args.ir {
-        match args.output {
+        match &args.output {
             Some(file) => {
                 module.print_to_file(file).ok();
+                add_artifact(&mut manifest, "llvm-ir", file)?;
             }
             None => module.print_to_stderr(),
         };
-        return Ok(());
+        return write_manifest(manifest, &args);
     }
 
     if args.run {
-        println!(
-            "Compiled successfully, running '{}'...",
-            args.file.display()
-        );
+        println!("Compiled successfully, running '{}'...", file.display());
         let mut engine = gelixrs::JIT::new(module);
         unsafe {
             engine.call("main");
         }
         println!("\nProgram exited, exiting compiler.");
-        return Ok(());
+        return write_manifest(manifest, &args);
     }
 
-    let result = gelixrs::produce_binary(
+    let output = args.output.as_ref().ok_or("Missing output location.")?;
+    let result = gelixrs::produce_output(
         module,
-        args.output.ok_or("Missing output location.")?.as_os_str(),
+        output.as_os_str(),
         args.optimize_level,
+        args.output_type,
+        &libraries,
     );
 
     if let Err(err) = result {
         println!("Error: {}", err);
+        Ok(())
     } else {
         println!("Compilation successful!");
+        add_artifact(&mut manifest, args.output_type.name(), output)?;
+        write_manifest(manifest, &args)
+    }
+}
+
+fn add_artifact(
+    manifest: &mut Option<Manifest>,
+    kind: &'static str,
+    path: &Path,
+) -> Result<(), &'static str> {
+    match manifest {
+        Some(manifest) => manifest
+            .add_artifact(kind, path)
+            .map_err(|_| "Failed to read produced file for manifest."),
+        None => Ok(()),
+    }
+}
+
+fn write_manifest(manifest: Option<Manifest>, args: &Opt) -> Result<(), &'static str> {
+    match (manifest, &args.manifest) {
+        (Some(manifest), Some(path)) => manifest
+            .write(path)
+            .map_err(|_| "Failed to write manifest."),
+        _ => Ok(()),
     }
-    Ok(())
 }
@@ -0,0 +1,57 @@
+//! The standard library compiled into the compiler, so that it
+//! works without the standard library being installed.
+
+use common::ModPath;
+use smol_str::SmolStr;
+
+use crate::parse_stage::SourceFile;
+
+/// The sources of all modules of the standard library by module path.
+const MODULES: &[(&str, &str)] = &[
+    ("std/atomic", include_str!("../../../std/atomic.gel")),
+    (
+        "std/collections/arena",
+        include_str!("../../../std/collections/arena.gel"),
+    ),
+    (
+        "std/collections/array",
+        include_str!("../../../std/collections/array.gel"),
+    ),
+    (
+        "std/collections/map",
+        include_str!("../../../std/collections/map.gel"),
+    ),
+    (
+        "std/collections",
+        include_str!("../../../std/collections/module.gel"),
+    ),
+    ("std/hash", include_str!("../../../std/hash.gel")),
+    (
+        "std/intrinsics",
+        include_str!("../../../std/intrinsics.gel"),
+    ),
+    ("std/io", include_str!("../../../std/io.gel")),
+    ("std/iter", include_str!("../../../std/iter.gel")),
+    ("std/json", include_str!("../../../std/json.gel")),
+    ("std/math", include_str!("../../../std/math.gel")),
+    ("std/memory", include_str!("../../../std/memory.gel")),
+    ("std/ops", include_str!("../../../std/ops.gel")),
+    ("std/prelude", include_str!("../../../std/prelude.gel")),
+    ("std/process", include_str!("../../../std/process.gel")),
+    ("std/simd", include_str!("../../../std/simd.gel")),
+    ("std/string", include_str!("../../../std/string.gel")),
+    ("std/thread", include_str!("../../../std/thread.gel")),
+    ("std/tuple", include_str!("../../../std/tuple.gel")),
+];
+
+/// Returns the sources of the embedded standard library.
+pub(crate) fn sources() -> Vec<SourceFile> {
+    MODULES
+        .iter()
+        .map(|(path, code)| SourceFile {
+            path: ModPath::from(path.split('/').map(SmolStr::new).collect()),
+            name: format!("<embedded>/{}.gel", path),
+            code: code.as_bytes().to_vec(),
+        })
+        .collect()
+}
@@ -0,0 +1,117 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use gir_generator::CompiledGIR;
+use ir::{CompiledIR, ConstantStats, Context, IRGenerator};
+
+use crate::SourceMap;
+
+/// Options for dumping the intermediate representations
+/// of a program while compiling it, see [compile_ir_emit].
+#[derive(Debug, Clone, Default)]
+pub struct EmitOptions {
+    /// Dump the GIR of every declaration.
+    pub gir: bool,
+    /// Include the declarations of the standard library in the GIR dump.
+    pub gir_std: bool,
+    /// Dump the LLVM IR of the program.
+    pub ir: bool,
+    /// Build a source map of the program's functions, see [SourceMap].
+    pub source_map: bool,
+    /// Directory to also write the dumps to, as `program.gir`, `program.ll`
+    /// and `program.map.json`.
+    pub dir: Option<PathBuf>,
+}
+
+/// Intermediate representations dumped as requested by [EmitOptions].
+#[derive(Debug, Default)]
+pub struct Emitted {
+    /// The GIR of each declaration along with its path like `main/Foo`, sorted by path.
+    pub gir: Vec<(String, String)>,
+    /// The LLVM IR of the program.
+    pub ir: Option<String>,
+    /// The source map of the program.
+    pub source_map: Option<SourceMap>,
+    /// Statistics about the constant data of the program; always collected.
+    pub constants: ConstantStats,
+}
+
+impl Emitted {
+    /// Writes all dumps into the given directory, creating it if needed.
+    pub fn write(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        if !self.gir.is_empty() {
+            let gir = self
+                .gir
+                .iter()
+                .map(|(_, gir)| &gir[..])
+                .collect::<Vec<_>>()
+                .join("\n");
+            fs::write(dir.join("program.gir"), gir)?;
+        }
+        if let Some(ir) = &self.ir {
+            fs::write(dir.join("program.ll"), ir)?;
+        }
+        if let Some(source_map) = &self.source_map {
+            source_map.write(&dir.join("program.map.json"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the pretty-printed GIR of all declarations along with their path,
+/// sorted by path. Declarations of the standard library are only included if requested.
+pub fn emit_gir(gir: &CompiledGIR, include_std: bool) -> Vec<(String, String)> {
+    let mut declarations = gir
+        .modules
+        .iter()
+        .map(|module| module.borrow())
+        .filter(|module| include_std || module.path.index(0).map_or(true, |root| root != "std"))
+        .flat_map(|module| {
+            module
+                .declarations
+                .iter()
+                .map(|(name, decl)| (format!("{}/{}", module.path, name), decl.to_string()))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    declarations.sort_by(|(a, _), (b, _)| a.cmp(b));
+    declarations
+}
+
+/// Returns the textual LLVM IR of a compiled program.
+pub fn emit_ir(ir: &CompiledIR) -> String {
+    ir.print_to_string().to_string()
+}
+
+/// Compiles GIR to LLVM IR like [crate::compile_ir], dumping both as requested.
+/// Dumps are written to the directory given in the options, if any.
+pub fn compile_ir_emit(
+    context: Context,
+    gir: CompiledGIR,
+    options: &EmitOptions,
+) -> io::Result<(CompiledIR, Emitted)> {
+    let mut emitted = Emitted::default();
+    if options.gir {
+        emitted.gir = emit_gir(&gir, options.gir_std);
+    }
+
+    let (ir, symbols, constants) = IRGenerator::new(context, gir).generate_with_stats();
+    emitted.constants = constants;
+    if options.ir || options.source_map {
+        let text = emit_ir(&ir);
+        if options.source_map {
+            emitted.source_map = Some(SourceMap::new(&symbols, &text));
+        }
+        if options.ir {
+            emitted.ir = Some(text);
+        }
+    }
+
+    if let Some(dir) = &options.dir {
+        emitted.write(dir)?;
+    }
+    Ok((ir, emitted))
+}
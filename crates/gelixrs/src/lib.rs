@@ -1,18 +1,79 @@
+mod emit;
+#[cfg(feature = "embedded-std")]
+mod embedded_std;
+mod manifest;
 mod parse_stage;
+mod source_map;
 
 use ir::IRGenerator;
 
-pub use common::{Benches, BENCH};
-pub use error::Errors;
-pub use gir_generator::{CompiledGIR, GIRFlags};
-pub use ir::{ir_context, jit::JIT, produce_binary, CompiledIR, Context};
-pub use parse_stage::{find_std_module, parse_source, stem_to_smol};
+pub use common::{
+    log, set_log_level, Benches, CountingAllocator, Level, ModPath, ModulePath, PassStats, Profile,
+    BENCH,
+};
+pub use emit::{compile_ir_emit, emit_gir, emit_ir, EmitOptions, Emitted};
+pub use error::{explain, Errors};
+pub use gir_generator::{
+    Allocator, CompiledGIR, GIRFlags, Limits, LiteralTypes, Optimizations, Tracer,
+};
+pub use gir_nodes::mangle::demangle;
+pub use ir::{
+    ir_context, jit::JIT, produce_binary, produce_output, CompiledIR, ConstantStats, Context,
+    OutputKind,
+};
+pub use manifest::{content_hash, Artifact, Manifest, ModuleInfo};
+pub use parse_stage::{
+    find_std, find_std_module, parse_modules, parse_source, stem_to_smol, FileSources, SourceFile,
+    SourceProvider, StdLibrary,
+};
+pub use source_map::{FunctionSource, Lines, Position, SourceMap, Span};
 
 use crate::parse_stage::ParsedModules;
+use error::{Error, ErrorSpan, GErr};
 use gir_generator::GIRGenerator;
+use gir_nodes::gir_err;
+use std::rc::Rc;
 
 pub fn compile_gir(ast: ParsedModules, flags: GIRFlags) -> Result<CompiledGIR, Vec<Errors>> {
-    GIRGenerator::new(ast, flags).consume()
+    GIRGenerator::new(ast, flags)
+        .consume()
+        .and_then(check_inline_ir)
+}
+
+/// Parses and compiles the modules of the given provider without
+/// touching the file system, like a `HashMap<ModulePath, String>` of
+/// sources by module path. The standard library is not included
+/// unless the provider contains it.
+pub fn compile_gir_from_sources(
+    sources: &impl SourceProvider,
+    flags: GIRFlags,
+) -> Result<CompiledGIR, Vec<Errors>> {
+    compile_gir(parse_modules(sources)?, flags)
+}
+
+/// Same as [compile_gir], but writes every type checking decision
+/// made in the program to the given tracer.
+pub fn compile_gir_traced(
+    ast: ParsedModules,
+    flags: GIRFlags,
+    tracer: Tracer,
+) -> Result<CompiledGIR, Vec<Errors>> {
+    GIRGenerator::new(ast, flags)
+        .with_tracer(tracer)
+        .consume()
+        .and_then(check_inline_ir)
+}
+
+/// Same as [compile_gir], but records the statistics of
+/// every pass in [CompiledGIR::profile].
+pub fn compile_gir_profiled(
+    ast: ParsedModules,
+    flags: GIRFlags,
+) -> Result<CompiledGIR, Vec<Errors>> {
+    GIRGenerator::new(ast, flags)
+        .with_profiling()
+        .consume()
+        .and_then(check_inline_ir)
 }
 
 pub fn compile_gir_cached_std(
@@ -20,7 +81,57 @@ pub fn compile_gir_cached_std(
     std: &CompiledGIR,
     flags: GIRFlags,
 ) -> Result<CompiledGIR, Vec<Errors>> {
-    GIRGenerator::with_cached_std(ast, std, flags).consume()
+    GIRGenerator::with_cached_std(ast, std, flags)
+        .consume()
+        .and_then(check_inline_ir)
+}
+
+/// Checks the LLVM IR of all functions given with `@inline_ir`.
+/// It can only be parsed with LLVM, which the GIR generator does not use.
+fn check_inline_ir(gir: CompiledGIR) -> Result<CompiledGIR, Vec<Errors>> {
+    let context = ir_context();
+    let errors = gir
+        .modules
+        .iter()
+        .filter_map(|module| {
+            let module = module.borrow();
+            let errors = module
+                .functions
+                .iter()
+                .filter_map(|func| {
+                    let func = func.borrow();
+                    let inline_ir = func.inline_ir.as_ref()?;
+                    let message = ir::check_inline_ir(&context, &func.name, inline_ir).err()?;
+                    let kind = GErr::E358 {
+                        name: func.name.clone(),
+                        message,
+                    };
+                    Some(match &func.ast {
+                        Some(ast) => gir_err(ast.sig().cst, kind),
+                        None => Error {
+                            index: ErrorSpan::None,
+                            kind,
+                        },
+                    })
+                })
+                .collect::<Vec<_>>();
+            if errors.is_empty() {
+                None
+            } else {
+                Some(Errors {
+                    errors,
+                    src: Some(Rc::clone(&module.src)),
+                    origin: module.path.to_string(),
+                })
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if errors.is_empty() {
+        Ok(gir)
+    } else {
+        Err(errors)
+    }
 }
 
 pub fn compile_ir(context: Context, gir: CompiledGIR) -> CompiledIR {
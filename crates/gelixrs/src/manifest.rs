@@ -0,0 +1,141 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use common::ModPath;
+use gir_generator::CompiledGIR;
+use serde::Serialize;
+
+/// A description of a compilation for build systems,
+/// listing the files it produced and the modules it read along with
+/// their dependencies and content hashes, so that a build system can tell
+/// when compilation and linking need to be redone.
+#[derive(Debug, Default, Serialize)]
+pub struct Manifest {
+    /// All files produced, in the order they were produced.
+    pub artifacts: Vec<Artifact>,
+    /// All modules of the program, sorted by path.
+    pub modules: Vec<ModuleInfo>,
+}
+
+/// A file produced by the compiler.
+#[derive(Debug, Serialize)]
+pub struct Artifact {
+    /// What the file contains, like `executable` or `llvm-ir`.
+    pub kind: &'static str,
+    pub path: PathBuf,
+    /// The hash of the file's contents, see [content_hash].
+    pub hash: String,
+}
+
+/// A module read by the compiler.
+#[derive(Debug, Serialize)]
+pub struct ModuleInfo {
+    /// The path of the module, like `std/collections/array`.
+    pub path: String,
+    /// The source file of the module, if it could be found.
+    pub file: Option<PathBuf>,
+    /// The hash of the module's source code, see [content_hash].
+    pub hash: String,
+    /// The paths of all modules this module imports from, sorted.
+    pub dependencies: Vec<String>,
+}
+
+impl Manifest {
+    /// Creates a manifest describing the modules of the given program.
+    /// `inputs` are the paths the program was parsed from with [crate::parse_source],
+    /// used to find the source file of each module.
+    pub fn new(gir: &CompiledGIR, inputs: &[PathBuf]) -> Self {
+        let mut modules = gir
+            .modules
+            .iter()
+            .map(|module| {
+                let module = module.borrow();
+                let mut dependencies = module
+                    .dependencies
+                    .iter()
+                    .map(|dep| dep.borrow().path.to_string())
+                    .collect::<Vec<_>>();
+                dependencies.sort();
+                dependencies.dedup();
+
+                ModuleInfo {
+                    path: module.path.to_string(),
+                    file: source_file(inputs, &module.path),
+                    hash: content_hash(module.src.as_bytes()),
+                    dependencies,
+                }
+            })
+            .collect::<Vec<_>>();
+        modules.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Self {
+            artifacts: vec![],
+            modules,
+        }
+    }
+
+    /// Adds a file the compiler produced.
+    /// Must be called after the file was written, as its contents are hashed.
+    pub fn add_artifact(&mut self, kind: &'static str, path: &Path) -> io::Result<()> {
+        self.artifacts.push(Artifact {
+            kind,
+            path: path.to_path_buf(),
+            hash: content_hash(&fs::read(path)?),
+        });
+        Ok(())
+    }
+
+    /// Returns the manifest as JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Writes the manifest as JSON to the given file.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_json())
+    }
+}
+
+/// Returns the source file of a module, the same way [crate::parse_source] looks for them.
+/// Modules of a directory are either `<dir>/<name>.gel` or `<dir>/<name>/module.gel`.
+fn source_file(inputs: &[PathBuf], path: &ModPath) -> Option<PathBuf> {
+    let (root, rest) = path.parts().split_first()?;
+    let input = inputs.iter().find(|input| {
+        input
+            .file_stem()
+            .map_or(false, |stem| stem == root.as_str())
+    })?;
+    if input.is_file() {
+        return if rest.is_empty() {
+            Some(input.clone())
+        } else {
+            None
+        };
+    }
+
+    let mut dir = input.clone();
+    dir.extend(rest.iter().map(|part| part.as_str()));
+    let module_file = dir.join("module.gel");
+    let file = dir.with_extension("gel");
+    if file.is_file() {
+        Some(file)
+    } else if module_file.is_file() {
+        Some(module_file)
+    } else {
+        None
+    }
+}
+
+/// Hashes the given contents with 64-bit FNV-1a, returned as hex.
+/// Not cryptographic, but stable across compiler versions and platforms,
+/// which is all a build system needs to detect changes.
+pub fn content_hash(contents: &[u8]) -> String {
+    let hash = contents
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+        });
+    format!("{:016x}", hash)
+}
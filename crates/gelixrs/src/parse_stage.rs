@@ -1,77 +1,226 @@
 use ast::Module;
-use common::ModPath;
-use error::Errors;
+use common::{ModPath, ModulePath};
+use error::{Error, ErrorSpan, Errors, GErr};
 use smol_str::SmolStr;
-use std::{env, fs, path::PathBuf, rc::Rc};
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+    str,
+};
 
 pub type ParsedModules = Vec<Module>;
 
+/// The source of a single module to parse.
+pub struct SourceFile {
+    /// The path of the module, like `std/collections/array`.
+    pub path: ModPath,
+    /// The name of the source shown in diagnostics, usually the file path.
+    pub name: String,
+    /// The source code, which should be UTF-8.
+    pub code: Vec<u8>,
+}
+
+/// Provides the modules of a program to parse, see [parse_modules].
+/// This allows compiling programs that are not on the file system,
+/// like ones of tests or opened in an editor.
+pub trait SourceProvider {
+    /// Returns the sources of all modules.
+    fn sources(&self) -> Vec<SourceFile>;
+}
+
+/// Sources read from files and directories, like the ones given to the compiler.
+/// The name of each file or directory is the first part of its module paths;
+/// directories contain a module for each `.gel` file and subdirectory,
+/// with the module of the directory itself being in `module.gel`.
+/// Files of a directory are read in the order of their names.
+pub struct FileSources(pub Vec<PathBuf>);
+
+impl SourceProvider for FileSources {
+    fn sources(&self) -> Vec<SourceFile> {
+        let mut sources = Vec::new();
+        for input in &self.0 {
+            find_files(input, &mut ModPath::new(), &mut sources);
+        }
+        sources
+    }
+}
+
+/// Sources in memory by module path, in the order of their paths.
+impl SourceProvider for HashMap<ModulePath, String> {
+    fn sources(&self) -> Vec<SourceFile> {
+        let mut sources = self
+            .iter()
+            .map(|(path, code)| SourceFile {
+                path: (**path).clone(),
+                name: path.to_string(),
+                code: code.clone().into_bytes(),
+            })
+            .collect::<Vec<_>>();
+        sources.sort_by_key(|source| source.name.clone());
+        sources
+    }
+}
+
+/// The standard library, see [find_std].
+pub enum StdLibrary {
+    /// The directory the standard library is installed in.
+    Dir(PathBuf),
+    /// The standard library embedded into the compiler.
+    #[cfg(feature = "embedded-std")]
+    Embedded,
+}
+
+impl SourceProvider for StdLibrary {
+    fn sources(&self) -> Vec<SourceFile> {
+        match self {
+            StdLibrary::Dir(dir) => FileSources(vec![dir.clone()]).sources(),
+            #[cfg(feature = "embedded-std")]
+            StdLibrary::Embedded => crate::embedded_std::sources(),
+        }
+    }
+}
+
+/// No sources if missing, like the standard library with `--no-std`.
+impl<T: SourceProvider> SourceProvider for Option<T> {
+    fn sources(&self) -> Vec<SourceFile> {
+        self.as_ref().map_or_else(Vec::new, T::sources)
+    }
+}
+
+/// The sources of both providers, like a program and the standard library.
+impl<A: SourceProvider, B: SourceProvider> SourceProvider for (A, B) {
+    fn sources(&self) -> Vec<SourceFile> {
+        let mut sources = self.0.sources();
+        sources.append(&mut self.1.sources());
+        sources
+    }
+}
+
+/// Parses all modules in the given files and directories, see [FileSources].
 pub fn parse_source(input: Vec<PathBuf>) -> Result<ParsedModules, Vec<Errors>> {
+    parse_modules(&FileSources(input))
+}
+
+/// Parses all modules of the given provider, returning the errors of all modules
+/// that failed to parse.
+pub fn parse_modules(provider: &impl SourceProvider) -> Result<ParsedModules, Vec<Errors>> {
     let mut modules = Vec::new();
-    for path in input {
-        make_modules(path, &mut ModPath::new(), &mut modules)?;
+    let mut errors = Vec::new();
+    for source in provider.sources() {
+        match parse_module(source) {
+            Ok(module) => modules.push(module),
+            Err(err) => errors.push(err),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(modules)
+    } else {
+        Err(errors)
     }
-    Ok(modules)
 }
 
-fn make_modules(
-    input: PathBuf,
-    path: &mut ModPath,
-    modules: &mut ParsedModules,
-) -> Result<(), Vec<Errors>> {
-    path.push(stem_to_smol(&input));
+fn find_files(input: &Path, path: &mut ModPath, sources: &mut Vec<SourceFile>) {
+    path.push(stem_to_smol(input));
 
     if let Ok(dir) = input.read_dir() {
-        let mut errors = Vec::new();
-        for file in dir {
-            let file = file.expect("Failed to read file").path();
-
+        // Sorted, as the order of directory entries depends on the file system
+        let mut files = dir
+            .map(|file| file.expect("Failed to read file").path())
+            .collect::<Vec<_>>();
+        files.sort();
+        for file in files {
             // If the file is named 'module.gel', it should have the
             // containing directory as its module path.
-            let result = if file.file_name().unwrap() == "module.gel" {
-                parse_module(file, path)
-                    .map(|m| modules.push(m))
-                    .map_err(|e| vec![e])
+            if file.file_name().unwrap() == "module.gel" {
+                sources.push(read_file(&file, path));
             } else {
-                make_modules(file, path, modules)
-            };
-
-            if let Err(mut errs) = result {
-                errors.append(&mut errs);
+                find_files(&file, path, sources);
             }
         }
-
-        if !errors.is_empty() {
-            return Err(errors);
-        }
     } else if *input
         .extension()
         .map(|ext| ext == "gel")
         .get_or_insert(false)
     {
         // If 'input' is a .gel file; parse it if true
-        modules.push(parse_module(input, path).map_err(|e| vec![e])?);
+        sources.push(read_file(input, path));
     }
 
     path.pop();
-    Ok(())
 }
 
-fn parse_module(input: PathBuf, path: &mut ModPath) -> Result<Module, Errors> {
-    let code = Rc::new(fs::read_to_string(&input).expect("Failed to read file."));
-    let parse = parser::parse(&code);
+fn read_file(input: &Path, path: &ModPath) -> SourceFile {
+    SourceFile {
+        path: path.clone(),
+        name: input.display().to_string(),
+        code: fs::read(input).expect("Failed to read file."),
+    }
+}
+
+fn parse_module(source: SourceFile) -> Result<Module, Errors> {
+    let (code, error) = decode_source(&source.code, source.name);
+    let code = Rc::new(code);
+    let parse = match error {
+        Some(error) => Err(vec![error]),
+        None => parser::parse(&code),
+    };
     let cst = parse.map_err(|errors| Errors {
         errors,
         src: Some(Rc::clone(&code)),
-        origin: format!("{}", path),
+        origin: format!("{}", source.path),
     })?;
-    Ok(Module::new(&path, &code, cst))
+    Ok(Module::new(&source.path, &code, cst))
 }
 
-pub fn stem_to_smol(path: &PathBuf) -> SmolStr {
+pub fn stem_to_smol(path: &Path) -> SmolStr {
     SmolStr::new(path.file_stem().unwrap().to_str().unwrap())
 }
 
+/// The byte order mark of UTF-8, which some editors put at the start of files.
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Decodes the source of a module, skipping a leading byte order mark.
+/// Sources that are not valid UTF-8 are reported instead of being parsed;
+/// invalid UTF-8 is replaced in the returned source.
+/// `file` is the name of the source used in the error.
+fn decode_source(bytes: &[u8], file: String) -> (String, Option<Error>) {
+    let bytes = if bytes.starts_with(UTF8_BOM) {
+        &bytes[UTF8_BOM.len()..]
+    } else {
+        bytes
+    };
+    match str::from_utf8(bytes) {
+        Ok(code) => (code.to_string(), None),
+
+        Err(err) => {
+            // Bytes before the invalid ones are kept as-is, so their index is
+            // the same in the replaced source, followed by the replacement character
+            let start = err.valid_up_to();
+            let end = start + '\u{FFFD}'.len_utf8();
+            let error = Error {
+                index: ErrorSpan::Span(start as u32..end as u32),
+                kind: GErr::E015(file),
+            };
+            (String::from_utf8_lossy(bytes).into_owned(), Some(error))
+        }
+    }
+}
+
+/// Returns the standard library, preferring one installed in
+/// a directory over the one embedded into the compiler.
+pub fn find_std() -> Result<StdLibrary, &'static str> {
+    match find_std_module() {
+        Ok(dir) => Ok(StdLibrary::Dir(dir)),
+        #[cfg(feature = "embedded-std")]
+        Err(_) => Ok(StdLibrary::Embedded),
+        #[cfg(not(feature = "embedded-std"))]
+        Err(err) => Err(err),
+    }
+}
+
 pub fn find_std_module() -> Result<PathBuf, &'static str> {
     let mut local_std = env::current_dir().expect("Failed to get current directory!");
     local_std.push("std");
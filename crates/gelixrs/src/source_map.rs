@@ -0,0 +1,155 @@
+use std::{collections::HashMap, fs, io, path::Path};
+
+use gir_nodes::mangle::demangle;
+use ir::FunctionSymbol;
+use serde::Serialize;
+
+/// A table mapping the functions of a compiled program back to
+/// the gelix source they were compiled from, for tools like profilers
+/// that only see symbols or LLVM IR. Unlike debug info, it is not
+/// part of the compiled program and meant to be read by external scripts.
+/// It only maps whole functions, not the individual instructions inside of them.
+#[derive(Debug, Default, Serialize)]
+pub struct SourceMap {
+    /// All functions of the program, sorted by symbol.
+    pub functions: Vec<FunctionSource>,
+}
+
+/// A compiled function and where it came from.
+#[derive(Debug, Serialize)]
+pub struct FunctionSource {
+    /// The symbol of the function, like `_G4main_4main`.
+    pub symbol: String,
+    /// The readable name of the symbol, like `std/string::String::len`.
+    pub name: String,
+    /// The module the function was declared in, like `std/string`.
+    pub module: String,
+    /// Where the function is in the source of its module.
+    /// Missing for functions generated by the compiler, like derived methods.
+    pub source: Option<Span>,
+    /// The lines of the function in the textual LLVM IR of the program,
+    /// as written by `--emit` and `--ir`.
+    pub ir: Option<Lines>,
+}
+
+/// A range of text between two positions, including the character at both.
+#[derive(Debug, Serialize)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+/// A range of lines, starting at 1 and including both.
+#[derive(Debug, Serialize)]
+pub struct Lines {
+    pub first: usize,
+    pub last: usize,
+}
+
+/// A position in text. Both line and column start at 1;
+/// columns count characters, not bytes.
+#[derive(Debug, Serialize)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl SourceMap {
+    /// Creates a source map of the given functions, see [ir::IRGenerator::generate_with_symbols].
+    /// `ir` is the textual LLVM IR of the program, used to find the lines of each function.
+    pub fn new(symbols: &[FunctionSymbol], ir: &str) -> Self {
+        let ir_lines = function_lines(ir);
+        let mut functions = symbols
+            .iter()
+            .map(|function| {
+                let module = function.module.borrow();
+                let source = function.span.as_ref().map(|span| {
+                    // Leading trivia like doc comments belongs to the node, but not the function
+                    let start = span.start as usize;
+                    let text = &module.src[start..span.end as usize];
+                    let leading = text.len() - skip_comments(text).len();
+                    let last = text
+                        .trim_end()
+                        .char_indices()
+                        .last()
+                        .map_or(leading, |(index, _)| index);
+                    Span {
+                        start: position(&module.src, start + leading),
+                        end: position(&module.src, start + last.max(leading)),
+                    }
+                });
+
+                FunctionSource {
+                    name: demangle(&function.symbol).unwrap_or_else(|| function.symbol.clone()),
+                    module: module.path.to_string(),
+                    source,
+                    ir: ir_lines
+                        .get(&function.symbol[..])
+                        .map(|&(first, last)| Lines { first, last }),
+                    symbol: function.symbol.clone(),
+                }
+            })
+            .collect::<Vec<_>>();
+        functions.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        Self { functions }
+    }
+
+    /// Returns the source map as JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Writes the source map as JSON to the given file.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_json())
+    }
+}
+
+/// Returns the position of the given byte index in the text.
+fn position(text: &str, index: usize) -> Position {
+    let before = &text[..index];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    Position {
+        line: before.matches('\n').count() + 1,
+        column: before[line_start..].chars().count() + 1,
+    }
+}
+
+/// Returns the text after its leading whitespace and line comments.
+fn skip_comments(mut text: &str) -> &str {
+    loop {
+        text = text.trim_start();
+        if !text.starts_with("//") {
+            return text;
+        }
+        text = text.find('\n').map_or("", |newline| &text[newline..]);
+    }
+}
+
+/// Returns the first and last line of every function defined in
+/// textual LLVM IR by symbol, from `define` until the closing brace.
+fn function_lines(ir: &str) -> HashMap<&str, (usize, usize)> {
+    let mut lines = HashMap::new();
+    let mut current = None;
+    for (index, line) in ir.lines().enumerate() {
+        if line.starts_with("define ") {
+            current = defined_symbol(line).map(|symbol| (symbol, index + 1));
+        } else if line == "}" {
+            if let Some((symbol, start)) = current.take() {
+                lines.insert(symbol, (start, index + 1));
+            }
+        }
+    }
+    lines
+}
+
+/// Returns the symbol of a `define` line, which is quoted if it
+/// contains characters like `:`, as in `define i64 @"_G4main_15Point:from_json"(...)`.
+fn defined_symbol(line: &str) -> Option<&str> {
+    let name = &line[line.find('@')? + 1..];
+    if let Some(quoted) = name.strip_prefix('"') {
+        quoted.split('"').next()
+    } else {
+        name.split('(').next()
+    }
+}
@@ -0,0 +1,56 @@
+use error::GErr;
+use gir_nodes::declaration::Annotations;
+
+use crate::GIRGenerator;
+
+/// The kinds of declarations an annotation can be applied to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum AnnotationTarget {
+    Adt,
+    Function,
+    Closure,
+}
+
+/// All annotations known to the compiler and the declarations they apply to.
+/// The passes using them read them from the `annotations` of the declaration.
+const KNOWN: &[(&str, AnnotationTarget)] = &[
+    ("derive", AnnotationTarget::Adt),
+    ("send", AnnotationTarget::Adt),
+    ("flags", AnnotationTarget::Adt),
+    ("inline_ir", AnnotationTarget::Function),
+    ("inline", AnnotationTarget::Function),
+    ("noinline", AnnotationTarget::Function),
+    ("pure", AnnotationTarget::Function),
+    ("cold", AnnotationTarget::Function),
+    ("link", AnnotationTarget::Function),
+    ("weak_this", AnnotationTarget::Closure),
+];
+
+impl GIRGenerator {
+    /// Collects the annotations of a declaration.
+    /// Unknown annotations are ignored with a warning, while known annotations
+    /// applied to a different kind of declaration are an error.
+    pub(crate) fn annotations(
+        &self,
+        ast: impl Iterator<Item = ast::Annotation>,
+        target: AnnotationTarget,
+    ) -> Annotations {
+        let annotations = ast
+            .filter(|annotation| {
+                let name = annotation.name();
+                match KNOWN.iter().find(|(known, _)| *known == name) {
+                    Some((_, known_target)) if *known_target == target => true,
+                    Some(_) => {
+                        self.err(annotation.cst(), GErr::E322(name));
+                        false
+                    }
+                    None => {
+                        self.warn(annotation.cst(), GErr::W002(name));
+                        false
+                    }
+                }
+            })
+            .collect();
+        Annotations::new(annotations)
+    }
+}
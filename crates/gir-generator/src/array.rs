@@ -0,0 +1,292 @@
+use std::{convert::TryFrom, rc::Rc};
+
+use ast::{Array, ArrayRepeat, CSTNode, Get};
+use error::{GErr, Res};
+use gir_nodes::{expression::CastType, gir_err, types::ArrayType, Expr, Literal, Type};
+use smol_str::SmolStr;
+use syntax::kind::SyntaxKind;
+
+use crate::{result::EmitGIRError, GIRGenerator};
+
+/// Fixed-size arrays like `[f32; 4]`, which are stored inline
+/// without a heap allocation and indexed directly.
+impl GIRGenerator {
+    /// Parses the length of an array type or repeat literal.
+    pub(crate) fn array_length(len: &str, cst: &CSTNode) -> Res<u64> {
+        len.parse::<u64>()
+            .ok()
+            .filter(|len| *len > 0)
+            .or_err(cst, GErr::E335)
+    }
+
+    /// Generates an array literal like `[1, 2, 3]`.
+    /// See `array_element_type` for the type of its elements.
+    pub(crate) fn array_literal(&mut self, array: &Array) -> Res<Expr> {
+        let mut values = array
+            .elements()
+            .map(|element| self.expression(&element))
+            .collect::<Vec<_>>();
+        let ty = self.array_element_type(&values, &array.cst())?;
+
+        for value in values.iter_mut() {
+            if !self.try_cast_in_place(value, &ty) {
+                return Err(gir_err(array.cst(), GErr::E251));
+            }
+        }
+        Ok(Expr::ArrayLiteral {
+            ty: Type::Array(Rc::new(ArrayType {
+                ty,
+                len: values.len() as u64,
+            })),
+            values,
+        })
+    }
+
+    /// Returns the element type of an array literal: the type of the first element
+    /// all other elements can be cast to, ignoring integer literals without
+    /// a type suffix to allow `[0, 1, 2u8]`.
+    /// Classes of different types are stored as the interface they all implement,
+    /// like `[Circle(), Square()]` becoming `[Shape; 2]`.
+    fn array_element_type(&mut self, values: &[Expr], cst: &CSTNode) -> Res<Type> {
+        let types = values
+            .iter()
+            .filter(|value| Self::untyped_int(value).is_none())
+            .map(Expr::get_type)
+            .filter(|ty| !ty.is_error())
+            .collect::<Vec<_>>();
+        let first = match types.first() {
+            Some(first) => first.clone(),
+            None => return Ok(values[0].get_type()),
+        };
+
+        for ty in &types {
+            let fits_all = types
+                .iter()
+                .all(|other| other.equal(ty, false) || self.can_cast_type(other, ty).is_some());
+            if fits_all {
+                return Ok(ty.clone());
+            }
+        }
+        if !types.iter().all(|ty| matches!(ty, Type::Adt(_))) {
+            // Let the caller report the element that does not fit
+            return Ok(first);
+        }
+
+        let impls = self.get_iface_impls(&first);
+        let mut common = impls
+            .borrow()
+            .interfaces
+            .keys()
+            .filter(|iface| {
+                types.iter().all(|ty| {
+                    self.get_iface_impls(ty)
+                        .borrow()
+                        .interfaces
+                        .contains_key(iface)
+                })
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        match common.len() {
+            0 => Ok(first),
+            1 => Ok(common.pop().unwrap()),
+            _ => {
+                let mut names = common
+                    .iter()
+                    .map(|iface| SmolStr::new(iface.to_string()))
+                    .collect::<Vec<_>>();
+                names.sort();
+                Err(gir_err(cst.clone(), GErr::E253(names)))
+            }
+        }
+    }
+
+    /// Generates an array literal repeating a value, like `[0; 16]`.
+    /// The value is only evaluated once.
+    pub(crate) fn array_repeat(&mut self, array: &ArrayRepeat) -> Res<Expr> {
+        let value = self.expression(&array.value());
+        let length = array.length();
+        let len = Self::array_length(&length.get()?.0, &length.cst)?;
+        Ok(Expr::ArrayRepeat {
+            ty: Type::Array(Rc::new(ArrayType {
+                ty: value.get_type(),
+                len,
+            })),
+            value: box value,
+        })
+    }
+
+    /// Casts all elements of an array literal to the element type of the given array type,
+    /// allowing for example `val a: [f32; 2] = [1, 2]`.
+    /// Returns None if the value is not an array literal of the same length
+    /// or any element cannot be cast.
+    /// Array literals are also cast to SIMD vectors of the same length,
+    /// like `val v: vec4f32 = [1, 2, 3, 4]`.
+    pub(crate) fn cast_array_literal(&mut self, value: &Expr, ty: &Type) -> Option<Expr> {
+        let goal = match ty {
+            Type::Array(goal) => goal,
+            Type::Vector(vec) => {
+                let array = self.cast_array_literal(value, &Type::Array(Rc::clone(vec)))?;
+                return Some(Expr::cast(array, ty.clone(), CastType::Vector));
+            }
+            _ => return None,
+        };
+        match value {
+            Expr::ArrayLiteral {
+                ty: Type::Array(arr),
+                values,
+            } if arr.len == goal.len => {
+                let values = values
+                    .iter()
+                    .map(|value| self.cast_or_none(value.clone(), &goal.ty))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(Expr::ArrayLiteral {
+                    ty: ty.clone(),
+                    values,
+                })
+            }
+
+            Expr::ArrayRepeat {
+                ty: Type::Array(arr),
+                value,
+            } if arr.len == goal.len => {
+                let value = self.cast_or_none((**value).clone(), &goal.ty)?;
+                Some(Expr::ArrayRepeat {
+                    ty: ty.clone(),
+                    value: box value,
+                })
+            }
+
+            _ => None,
+        }
+    }
+
+    /// Gets a field of an array or SIMD vector. `len` is the only field available.
+    pub(crate) fn array_get(arr: &ArrayType, get: &Get) -> Res<Expr> {
+        if get.property().name() == "len" {
+            Ok(Expr::literal(Literal::U64(arr.len)))
+        } else {
+            Err(gir_err(get.property().cst, GErr::E210(None)))
+        }
+    }
+
+    /// Calls a method on an array. Available are `get(index)`, returning
+    /// the element at the index, and `set(index, value)`, which
+    /// requires the array to be assignable.
+    pub(crate) fn array_call(
+        &mut self,
+        object: Expr,
+        arr: &ArrayType,
+        args: Vec<Expr>,
+        get: &Get,
+    ) -> Res<Expr> {
+        let expected = match &get.property().name()[..] {
+            "get" => 1,
+            "set" => 2,
+            _ => return Err(gir_err(get.cst(), GErr::E210(None))),
+        };
+        if args.len() != expected {
+            return Err(gir_err(
+                get.cst(),
+                GErr::E216 {
+                    expected,
+                    was: args.len(),
+                },
+            ));
+        }
+
+        let mut args = args.into_iter();
+        let index = args.next().unwrap();
+        let value = args.next();
+        if value.is_some() && !object.assignable() {
+            return Err(gir_err(get.cst(), GErr::E200(object.human_name())));
+        }
+
+        let (mut block, element) = self.array_element(object, arr, index, &get.cst)?;
+        match value {
+            Some(value) => {
+                let value = self
+                    .cast_or_none(value, &arr.ty)
+                    .or_err(&get.cst, GErr::E201)?;
+                block.push(Expr::store(element, value, false));
+            }
+            None => block.push(element),
+        }
+
+        if block.len() == 1 {
+            Ok(block.pop().unwrap())
+        } else {
+            Ok(Expr::Block(block))
+        }
+    }
+
+    /// Returns the element of the array at the given index, along with
+    /// any expressions that need to run before it.
+    fn array_element(
+        &mut self,
+        object: Expr,
+        arr: &ArrayType,
+        index: Expr,
+        cst: &CSTNode,
+    ) -> Res<(Vec<Expr>, Expr)> {
+        let (block, index) = self.checked_index(index, arr.len, cst)?;
+        Ok((block, Expr::index(object, index)))
+    }
+
+    /// Turns the given index into a `u64` that is checked to be less than `len`,
+    /// along with any expressions that need to run before it.
+    /// Indices that are integer literals without a type suffix are checked
+    /// at compile time; all others are checked at runtime, panicking
+    /// when out of bounds.
+    ///
+    /// `arr.get(i)` into `{ val tmp = i; if (tmp >= len) panic(...); arr[tmp] }`
+    pub(crate) fn checked_index(
+        &mut self,
+        index: Expr,
+        len: u64,
+        cst: &CSTNode,
+    ) -> Res<(Vec<Expr>, Expr)> {
+        if let Some(int) = Self::untyped_int(&index) {
+            return match u64::try_from(int) {
+                Ok(int) if int < len => Ok((vec![], Expr::literal(Literal::U64(int)))),
+                _ => Err(gir_err(cst.clone(), GErr::E249 { index: int, len })),
+            };
+        }
+
+        let index_ty = index.get_type();
+        if !index_ty.is_int() || index_ty.is_bool() {
+            return Err(gir_err(cst.clone(), GErr::E250));
+        }
+        let index = if index_ty == Type::U64 {
+            index
+        } else {
+            Expr::cast(index, Type::U64, CastType::Number)
+        };
+
+        let panic_fn = match &self.intrinsics.panic_fn {
+            Some(func) => Rc::clone(func),
+            None => return Ok((vec![], index)),
+        };
+        let (store, var) = self.temp_variable(index, "index".into());
+        let out_of_bounds = Expr::binary(
+            SyntaxKind::GreaterEqual,
+            Expr::lvar(&var),
+            Expr::literal(Literal::U64(len)),
+        );
+        let panic = Expr::call(
+            Expr::fvar(&panic_fn),
+            vec![Expr::literal(Literal::String {
+                text: "Array index out of bounds.".into(),
+                ty: self.intrinsics.string_type.clone().unwrap(),
+            })],
+        );
+
+        Ok((
+            vec![
+                store,
+                Expr::if_(out_of_bounds, panic, Expr::none_const(), None),
+            ],
+            Expr::lvar(&var),
+        ))
+    }
+}
@@ -0,0 +1,218 @@
+use std::{collections::HashMap, iter, rc::Rc};
+
+use common::MutRc;
+use error::Res;
+use gir_nodes::{
+    declaration::{LocalVariable, Variable, Visibility},
+    expression::CastType,
+    types::ClosureType,
+    Expr, Function, FunctionBody, IFaceImpl, Instance, Type, ADT,
+};
+use smol_str::SmolStr;
+
+use crate::{passes::declare::FnSig, GIRGenerator};
+
+impl GIRGenerator {
+    /// Wraps a closure into a value implementing the given interface,
+    /// should the interface have exactly one method with the same signature as the closure.
+    /// The wrapper is an instance of `ClosureImpl` from `std/intrinsics`,
+    /// with a compiler-generated implementation of the interface that calls the closure.
+    /// Returns the value unchanged if it cannot be wrapped.
+    pub(crate) fn maybe_wrap_closure(&mut self, value: Expr, goal: &Type) -> Res<Expr> {
+        let (closure, iface) = match (value.get_type(), goal) {
+            (Type::Closure(closure), Type::Adt(iface)) if iface.ty.borrow().ty.is_interface() => {
+                (closure, iface.clone())
+            }
+            _ => return Ok(value),
+        };
+        let wrapper = match &self.intrinsics.closure_impl {
+            Some(wrapper) => Rc::clone(wrapper),
+            None => return Ok(value),
+        };
+        let method = match Self::single_method(&iface) {
+            Some(method) if Self::signature_matches(&method, &iface, &closure) => method,
+            _ => return Ok(value),
+        };
+
+        let wrapper_ty = Type::Adt(Instance::new(
+            Rc::clone(&wrapper),
+            Rc::new(vec![Type::Closure(Rc::clone(&closure))]),
+        ));
+        let impls = self.get_iface_impls(&wrapper_ty);
+        let implemented = impls.borrow().interfaces.contains_key(goal);
+        if !implemented {
+            let gir_impl = self.closure_iface_impl(&wrapper, &wrapper_ty, &iface, &method)?;
+            impls.borrow_mut().interfaces.insert(goal.clone(), gir_impl);
+        }
+
+        let constructor = Rc::clone(&wrapper.borrow().constructors[0]);
+        Ok(Expr::Allocate {
+            ty: wrapper_ty,
+            constructor,
+            args: vec![value],
+        })
+    }
+
+    /// Turns a reference to a function into a closure of the given type,
+    /// should the function take the same parameters and return the same type.
+    /// The closure captures nothing; it is a compiler-generated function
+    /// that simply forwards its arguments to the referenced function.
+    /// Returns the value unchanged if it cannot be converted.
+    pub(crate) fn maybe_wrap_function(&mut self, value: Expr, goal: &Type) -> Res<Expr> {
+        let (func, closure) = match (&value, goal) {
+            (Expr::Variable(Variable::Function(func)), Type::Closure(closure))
+                if Self::function_matches(func, closure) =>
+            {
+                (func.clone(), Rc::clone(closure))
+            }
+            _ => return Ok(value),
+        };
+
+        let sig = {
+            let params = func
+                .ty
+                .borrow()
+                .parameters
+                .iter()
+                .zip(closure.parameters.iter())
+                .map(|(p, ty)| Ok((p.name.clone(), ty.clone())))
+                .collect::<Vec<_>>();
+            FnSig {
+                name: SmolStr::new(format!("closure-fn-{}", func.ty.borrow().name)),
+                visibility: Visibility::Private,
+                params: box params.into_iter(),
+                type_parameters: Rc::new(vec![]),
+                ret_type: Some(closure.ret_type.clone()),
+                ast: None,
+            }
+        };
+        let function = self.create_function(sig)?;
+        let body = {
+            let params = &function.borrow().parameters;
+            Expr::call(
+                Expr::Variable(Variable::Function(func)),
+                params.iter().map(Expr::lvar).collect(),
+            )
+        };
+        function.push_return(body);
+        Ok(self.closure_value(function, vec![]))
+    }
+
+    /// Turns a generated function into a closure capturing the given variables,
+    /// which the function receives as its first parameter.
+    /// Returns the closure, stored in a temporary variable.
+    pub(crate) fn closure_value(
+        &mut self,
+        function: MutRc<Function>,
+        captured: Vec<Rc<LocalVariable>>,
+    ) -> Expr {
+        let captured = Rc::new(captured);
+        function.borrow_mut().parameters.insert(
+            0,
+            Rc::new(LocalVariable {
+                name: SmolStr::new_inline("CLOSURE-CAPTURED"),
+                ty: Type::ClosureCaptured(Rc::clone(&captured)),
+                mutable: false,
+            }),
+        );
+        let expr = Expr::Closure { function, captured };
+        let (store, _) = self.temp_variable(expr, "closure-store".into());
+        store
+    }
+
+    /// Creates the implementation of the interface on the closure wrapper,
+    /// with its single method simply calling the closure.
+    fn closure_iface_impl(
+        &mut self,
+        wrapper: &MutRc<ADT>,
+        wrapper_ty: &Type,
+        iface: &Instance<ADT>,
+        iface_method: &MutRc<Function>,
+    ) -> Res<IFaceImpl> {
+        let closure_ty = wrapper_ty.type_args().unwrap()[0].clone();
+        let sig = {
+            let iface_method = iface_method.borrow();
+            let params = iface_method
+                .parameters
+                .iter()
+                .skip(1)
+                .map(|p| Ok((p.name.clone(), p.ty.resolve(iface.args()))))
+                .collect::<Vec<_>>();
+            FnSig {
+                name: iface_method.name.clone(),
+                visibility: Visibility::Public,
+                params: box iter::once(Ok((SmolStr::new_inline("this"), wrapper_ty.clone())))
+                    .chain(params),
+                type_parameters: Rc::new(vec![]),
+                ret_type: Some(iface_method.ret_type.resolve(iface.args())),
+                ast: None,
+            }
+        };
+        let method = self.create_function(sig)?;
+
+        let body = {
+            let params = &method.borrow().parameters;
+            let field = wrapper.borrow().fields.values().next().cloned().unwrap();
+            let closure = Expr::cast(
+                Expr::load(Expr::lvar(&params[0]), &field),
+                closure_ty,
+                CastType::Bitcast,
+            );
+            Expr::call(closure, params.iter().skip(1).map(Expr::lvar).collect())
+        };
+        method.push_return(body);
+
+        let mut methods = HashMap::with_capacity(1);
+        methods.insert(method.borrow().name.clone(), Rc::clone(&method));
+        Ok(IFaceImpl {
+            implementor: wrapper_ty.clone(),
+            iface: iface.clone(),
+            methods,
+            module: Rc::clone(&self.module),
+            ast: None,
+        })
+    }
+
+    /// Returns the method of the interface, should it only have one.
+    fn single_method(iface: &Instance<ADT>) -> Option<MutRc<Function>> {
+        let iface = iface.ty.borrow();
+        let mut methods = iface.methods.values().filter(|m| m.borrow().ast.is_some());
+        match (methods.next(), methods.next()) {
+            (Some(method), None) => Some(Rc::clone(method)),
+            _ => None,
+        }
+    }
+
+    /// Returns if the closure can be called in place of the interface method.
+    fn signature_matches(
+        method: &MutRc<Function>,
+        iface: &Instance<ADT>,
+        closure: &ClosureType,
+    ) -> bool {
+        let method = method.borrow();
+        let params = method
+            .parameters
+            .iter()
+            .skip(1)
+            .map(|p| p.ty.resolve(iface.args()));
+        method.parameters.len() - 1 == closure.parameters.len()
+            && params.zip(closure.parameters.iter()).all(|(p, c)| p == *c)
+            && method.ret_type.resolve(iface.args()) == closure.ret_type
+    }
+
+    /// Returns if the function can be called in place of the closure,
+    /// which requires all its type arguments to be known.
+    fn function_matches(func: &Instance<Function>, closure: &ClosureType) -> bool {
+        let function = func.ty.borrow();
+        if function.type_parameters.len() != func.args().len() || function.variadic {
+            return false;
+        }
+        let params = function
+            .parameters
+            .iter()
+            .map(|p| p.ty.resolve(func.args()));
+        function.parameters.len() == closure.parameters.len()
+            && params.zip(closure.parameters.iter()).all(|(p, c)| p == *c)
+            && function.ret_type.resolve(func.args()) == closure.ret_type
+    }
+}
@@ -1,7 +1,11 @@
-use crate::{passes::FnSig, result::EmitGIRError, FieldOrMethod, GIRGenerator};
+use crate::{
+    annotations::AnnotationTarget, passes::FnSig, result::EmitGIRError, Capture, FieldOrMethod,
+    GIRGenerator, LiteralTypes,
+};
 use ast::{
-    Binary, Block, Break, CSTNode, Call, Expression as AExpr, ForIterCond, GenericIdent, Get,
-    GetStatic, LiteralType, Return, When, WhenBranch,
+    Array, ArrayRepeat, Binary, Block, Break, CSTNode, Call, Defer, Destructure,
+    Expression as AExpr, ForIterCond, GenericIdent, Get, GetStatic, IfExpr, LiteralType, Return,
+    Try, Tuple, When, WhenBranch,
 };
 use common::MutRc;
 use error::{GErr, Res};
@@ -10,20 +14,42 @@ use gir_nodes::{
     expression::{CastType, Intrinsic},
     gir_err,
     types::{TypeArguments, TypeParameter},
-    Expr, Function, IFaceImpls, Instance, Literal, Type, ADT,
+    Declaration, Expr, Function, FunctionBody, IFaceImpls, Instance, Literal, Type, ADT,
 };
 use num_traits::Num;
 use smol_str::SmolStr;
-use std::{convert::TryInto, iter::FromIterator, rc::Rc};
+use std::{
+    convert::{TryFrom, TryInto},
+    iter::{self, FromIterator},
+    mem,
+    rc::Rc,
+};
 use syntax::kind::SyntaxKind;
 
+/// The smallest amount of branches of a switch compiled to a jump table.
+const MIN_JUMP_TABLE_CASES: usize = 4;
+
 /// This impl contains all code of the generator that directly
 /// produces expressions.
 /// This is split into its own file for readability reasons;
 /// a 1500-line file containing everything is difficult to navigate.
 impl GIRGenerator {
     pub(crate) fn expression(&mut self, expression: &AExpr) -> Expr {
+        if !self.count_expression() {
+            return Expr::poisoned();
+        }
+
+        let outer_node = if self.tracing() {
+            mem::replace(&mut self.trace_node, Some(expression.cst()))
+        } else {
+            None
+        };
+
         let expr = match expression {
+            AExpr::Array(array) => self.array_literal(array),
+
+            AExpr::ArrayRepeat(array) => self.array_repeat(array),
+
             AExpr::Binary(binary) => self.binary(binary),
 
             AExpr::Block(block) => Ok(self.block(block)),
@@ -32,6 +58,10 @@ impl GIRGenerator {
 
             AExpr::Call(call) => self.call(call),
 
+            AExpr::Defer(defer) => Ok(self.defer(defer)),
+
+            AExpr::Destructure(destructure) => self.destructure(destructure).map(Expr::Block),
+
             AExpr::For(expr) if expr.iter_cond().is_some() => {
                 self.for_iter(expr.iter_cond().unwrap(), expr.body(), expr.else_branch())
             }
@@ -39,6 +69,8 @@ impl GIRGenerator {
                 Ok(self.for_cond(expr.condition().unwrap(), expr.body(), expr.else_branch()))
             }
 
+            AExpr::FuncDef(func) => self.func_def(func).map(|_| Expr::none_const()),
+
             AExpr::Get(get) => {
                 if get.property().type_args().next().is_some() {
                     Err(gir_err(get.property().cst, GErr::E211))
@@ -59,9 +91,7 @@ impl GIRGenerator {
 
             AExpr::Grouping(inner) => Ok(self.expression(&inner.inner())),
 
-            AExpr::If(expr) => {
-                Ok(self.if_(expr.condition(), expr.then_branch(), expr.else_branch()))
-            }
+            AExpr::If(expr) => Ok(self.if_(expr)),
 
             AExpr::Literal(literal) => self.literal(literal),
 
@@ -71,15 +101,23 @@ impl GIRGenerator {
 
             AExpr::Return(ret) => self.return_(ret),
 
+            AExpr::Try(try_) => self.try_(try_),
+
+            AExpr::Tuple(tuple) => self.tuple(tuple),
+
             AExpr::Variable(var) => self.var(var),
 
             AExpr::VarDef(var) => self.var_def(var),
 
             AExpr::When(when) => self.when(when),
+
+            AExpr::Error(cst) => Err(gir_err(cst.clone(), GErr::E011)),
         };
 
-        // TODO: a proper 'poisoned' expression
-        self.eat(expr).unwrap_or(Expr::Literal(Literal::Any))
+        if self.tracing() {
+            self.trace_node = outer_node;
+        }
+        self.eat(expr).unwrap_or_else(Expr::poisoned)
     }
 
     fn binary(&mut self, expr: &Binary) -> Res<Expr> {
@@ -95,12 +133,36 @@ impl GIRGenerator {
         // turned into a regular value instead of a type get.
         let right = match ast_right {
             AExpr::GetStatic(get) if op == SyntaxKind::Is => self.get_static(&get, false)?,
+            _ if op == SyntaxKind::And || op == SyntaxKind::Or => {
+                self.logic_operand(&left, op, &ast_right)
+            }
             _ => self.expression(&ast_right),
         };
 
         self.binary_gir(&expr.cst, left, op, right)
     }
 
+    /// Generates the right side of a logic operator.
+    /// Since it only runs depending on the value of the left side,
+    /// smart casts of the left side apply to it (`a != null and a.len() > 0`).
+    fn logic_operand(&mut self, left: &Expr, operator: SyntaxKind, right: &AExpr) -> Expr {
+        self.begin_scope();
+        let mut casts = if operator == SyntaxKind::And {
+            self.smart_casts(left)
+        } else {
+            self.inverse_smart_casts(left)
+        };
+        let right = self.expression(right);
+        self.end_scope();
+
+        if casts.is_empty() {
+            right
+        } else {
+            casts.push(right);
+            Expr::Block(casts)
+        }
+    }
+
     fn assignment(&mut self, to: AExpr, value: AExpr) -> Res<Expr> {
         // Account for edge case where it is illegal to get an
         // uninitialized ADT member; it's fine here since it's being written
@@ -111,27 +173,66 @@ impl GIRGenerator {
             (self.expression(&to), false)
         };
         let rvalue = self.expression(&value);
-        let (rvalue, matching_types) = self.try_cast(rvalue, &lvalue.get_type());
+        let lvalue_ty = lvalue.get_type();
+        if lvalue_ty.is_error() {
+            return Ok(Expr::poisoned());
+        }
+        let (rvalue, matching_types) = self.try_cast(rvalue, &lvalue_ty);
 
         if !was_uninit && !lvalue.assignable() {
             Err(gir_err(to.cst(), GErr::E200(lvalue.human_name())))
         } else if !matching_types {
-            Err(gir_err(value.cst(), GErr::E201))
+            let err = self
+                .variance_error(&rvalue.get_type(), &lvalue_ty)
+                .unwrap_or(GErr::E201);
+            Err(gir_err(value.cst(), err))
         } else {
             Ok(Expr::store(lvalue, rvalue, was_uninit))
         }
     }
 
-    fn binary_gir(
+    pub(crate) fn binary_gir(
         &mut self,
         cst: &CSTNode,
-        mut left: Expr,
+        left: Expr,
         operator: SyntaxKind,
         mut right: Expr,
     ) -> Res<Expr> {
         let left_ty = left.get_type();
+        // Operators on values of erroneous types would only cause more errors
+        if left_ty.is_error() || right.get_type().is_error() {
+            return Ok(Expr::poisoned());
+        }
+        if let Type::Nullable(inner) = &left_ty {
+            // An integer literal on the right of `??` takes the type of the left side
+            if operator == SyntaxKind::QuestionQuestion && Self::untyped_int(&right).is_some() {
+                right = self.try_cast(right, inner).0;
+            }
+        }
         let right_ty = right.get_type();
 
+        if (operator == SyntaxKind::And || operator == SyntaxKind::Or)
+            && !(left_ty.equal(&Type::Bool, false) && right_ty.equal(&Type::Bool, false))
+        {
+            return Err(gir_err(cst.clone(), GErr::E242));
+        }
+
+        let bitwise = operator == SyntaxKind::Pipe || operator == SyntaxKind::Ampersand;
+        let (left, right) = if bitwise {
+            (Self::flag_bits(left), Self::flag_bits(right))
+        } else {
+            (left, right)
+        };
+
+        let (mut left, mut right) = self.widen_numbers(cst, left, right)?;
+        let (left_ty, right_ty) = (left.get_type(), right.get_type());
+        if left_ty.is_vector() || right_ty.is_vector() {
+            return self.vector_binary(cst, left, operator, right);
+        }
+        if bitwise && !(left_ty.is_int() && right_ty.is_int()) {
+            return Err(gir_err(cst.clone(), GErr::E202));
+        }
+
         if (left_ty == right_ty && left_ty.is_number()) // general numeric
             || (left_ty.is_int() && right_ty.is_int()) // integers with cast
             || left_ty.is_float() && right_ty.is_float() // floats with cast
@@ -147,12 +248,30 @@ impl GIRGenerator {
                 .get_operator_overloading_method(operator, &mut left, &mut right)
                 .or_err(cst, GErr::E202)?;
 
-            let mut expr = Expr::call(Expr::var(Variable::Function(method_var)), vec![left, right]);
-            if operator == SyntaxKind::BangEqual {
-                // Invert the result if this is `!=`, as the method is from `Eq`
-                expr = Expr::unary(SyntaxKind::Bang, expr);
-            }
-            Ok(expr)
+            let expr = Expr::call(Expr::var(Variable::Function(method_var)), vec![left, right]);
+            Ok(match operator {
+                // Invert the result if this is `!=`, as the method is from `Equal`
+                SyntaxKind::BangEqual => Expr::unary(SyntaxKind::Bang, expr),
+
+                // Comparisons compare the result of `Ordered.compare` to 0
+                SyntaxKind::Less
+                | SyntaxKind::LessEqual
+                | SyntaxKind::Greater
+                | SyntaxKind::GreaterEqual => {
+                    Expr::binary(operator, expr, Expr::literal(Literal::I64(0)))
+                }
+
+                _ => expr,
+            })
+        }
+    }
+
+    /// Turns a value of a flags enum into its bit, to allow combining
+    /// flags into an integer with `|` and `&`.
+    fn flag_bits(value: Expr) -> Expr {
+        match value.get_type().try_adt() {
+            Some(adt) if adt.ty.borrow().is_flags() => Expr::enum_tag(value),
+            _ => value,
         }
     }
 
@@ -180,8 +299,14 @@ impl GIRGenerator {
             }
 
             SyntaxKind::QuestionQuestion => {
+                // If the right side exits early (a ?? return b),
+                // the value is always the non-null left side
+                let ty = if right.diverges() {
+                    *left.get_type().into_nullable()
+                } else {
+                    right.get_type()
+                };
                 let (store, var) = self.temp_variable(left, "null-tmp".into());
-                let ty = right.get_type();
                 Expr::Block(vec![
                     store,
                     Expr::if_(
@@ -197,6 +322,11 @@ impl GIRGenerator {
                 ])
             }
 
+            SyntaxKind::Slash | SyntaxKind::Percent if right.get_type().is_int() => {
+                let (_, left, right) = self.try_unify_type(left, right);
+                self.checked_division(left, operator, right)
+            }
+
             _ => {
                 let (_, left, right) = self.try_unify_type(left, right);
                 Expr::binary(operator, left, right)
@@ -204,9 +334,107 @@ impl GIRGenerator {
         }
     }
 
+    /// Lowers an integer division or remainder, inserting checks that
+    /// panic if the divisor is 0, or if a signed division overflows
+    /// (`MIN / -1`, `MIN % -1`). Checks are left out if an uncast
+    /// literal divisor makes them unnecessary.
+    ///
+    /// `a / b` into `{ val tmp_a = a; val tmp_b = b; if (tmp_b == 0) panic(...); tmp_a / tmp_b }`
+    fn checked_division(&mut self, left: Expr, operator: SyntaxKind, right: Expr) -> Expr {
+        let literal = match &right {
+            Expr::Literal(literal) => Some(literal),
+            _ => None,
+        };
+        let ty = right.get_type();
+        let check_zero = literal.map_or(true, Literal::is_zero);
+        let check_overflow = ty.is_signed_int() && literal.map_or(true, Literal::is_minus_one);
+        let panic_fn = match &self.intrinsics.panic_fn {
+            Some(func) if check_zero || check_overflow => Rc::clone(func),
+            _ => return Expr::binary(operator, left, right),
+        };
+
+        let (left_store, left_var) = self.temp_variable(left, "dividend".into());
+        let (right_store, right_var) = self.temp_variable(right, "divisor".into());
+        let number = |num: u64| {
+            Expr::cast(
+                Expr::literal(Literal::I64(num)),
+                ty.clone(),
+                CastType::Number,
+            )
+        };
+        let panic = |message: &str| {
+            Expr::call(
+                Expr::fvar(&panic_fn),
+                vec![Expr::literal(Literal::String {
+                    text: message.into(),
+                    ty: self.intrinsics.string_type.clone().unwrap(),
+                })],
+            )
+        };
+
+        let mut exprs = vec![left_store, right_store];
+        if check_zero {
+            let is_zero = Expr::binary(SyntaxKind::EqualEqual, Expr::lvar(&right_var), number(0));
+            exprs.push(Expr::if_(
+                is_zero,
+                panic("Division by zero."),
+                Expr::none_const(),
+                None,
+            ));
+        }
+        if check_overflow {
+            // The minimum is the only value other than 0 that is its own negation,
+            // which works for every width of integer
+            let is_minus_one = Expr::binary(
+                SyntaxKind::EqualEqual,
+                Expr::lvar(&right_var),
+                Expr::unary(SyntaxKind::Minus, number(1)),
+            );
+            let is_min = Expr::binary(
+                SyntaxKind::And,
+                Expr::binary(SyntaxKind::BangEqual, Expr::lvar(&left_var), number(0)),
+                Expr::binary(
+                    SyntaxKind::EqualEqual,
+                    Expr::lvar(&left_var),
+                    Expr::unary(SyntaxKind::Minus, Expr::lvar(&left_var)),
+                ),
+            );
+            let overflows = Expr::binary(SyntaxKind::And, is_minus_one, is_min);
+            exprs.push(Expr::if_(
+                overflows,
+                panic("Division overflow."),
+                Expr::none_const(),
+                None,
+            ));
+        }
+        exprs.push(Expr::binary(
+            operator,
+            Expr::lvar(&left_var),
+            Expr::lvar(&right_var),
+        ));
+        Expr::Block(exprs)
+    }
+
     fn block(&mut self, block: &Block) -> Expr {
         self.begin_scope();
-        let exprs: Vec<_> = block.expressions().map(|e| self.expression(&e)).collect();
+        let mut exprs = Vec::new();
+        let mut iter = block.expressions().peekable();
+        while let Some(ast) = iter.next() {
+            let expr = self.expression(&ast);
+            // Casts are only needed if there is code following them
+            let casts = if iter.peek().is_some() {
+                self.early_exit_casts(&expr)
+            } else {
+                vec![]
+            };
+            match (ast, expr) {
+                // Destructured variables are defined in this block, not a nested one
+                (AExpr::Destructure(_), Expr::Block(stores)) => exprs.extend(stores),
+                (_, expr) => exprs.push(expr),
+            }
+            exprs.extend(casts);
+        }
+        self.append_deferred(&mut exprs);
         self.end_scope();
 
         if exprs.is_empty() {
@@ -216,6 +444,83 @@ impl GIRGenerator {
         }
     }
 
+    /// Defers the expression to the end of the current scope.
+    /// Only blocks run deferred expressions, so the scope must be one.
+    fn defer(&mut self, defer: &Defer) -> Expr {
+        let expr = self.expression(&defer.value());
+        if defer.cst().parent().map(|p| p.kind()) != Some(SyntaxKind::Block) {
+            self.err(defer.cst(), GErr::E255);
+        } else if Self::exits_early(&defer.cst(), false) {
+            self.err(defer.cst(), GErr::E243);
+        } else {
+            self.deferred.last_mut().unwrap().push(expr);
+        }
+        Expr::none_const()
+    }
+
+    /// Returns if the given node contains a `return`, `?` or a `break` that
+    /// would exit its scope. Closures are separate functions and loops
+    /// are the target of the breaks inside them, so these are ignored.
+    fn exits_early(node: &CSTNode, in_loop: bool) -> bool {
+        node.children().any(|child| match child.kind() {
+            SyntaxKind::ReturnExpr | SyntaxKind::TryExpr => true,
+            SyntaxKind::BreakExpr if !in_loop => true,
+            SyntaxKind::ClosureLiteral => false,
+            SyntaxKind::ForExpr => Self::exits_early(&child, true),
+            _ => Self::exits_early(&child, in_loop),
+        })
+    }
+
+    /// Appends the deferred expressions of the current scope to the
+    /// end of a block, keeping the value of the block intact.
+    /// Blocks ending with an early exit already ran them.
+    fn append_deferred(&mut self, exprs: &mut Vec<Expr>) {
+        let deferred = mem::take(self.deferred.last_mut().unwrap());
+        if deferred.is_empty() || exprs.last().map_or(false, Expr::diverges) {
+            return;
+        }
+
+        let last = exprs.pop().unwrap();
+        match last.get_type() {
+            Type::None | Type::Any | Type::Never => {
+                exprs.push(last);
+                exprs.extend(deferred.into_iter().rev());
+            }
+            _ => {
+                let (store, var) = self.temp_variable(last, "deferred-value".into());
+                exprs.push(store);
+                exprs.extend(deferred.into_iter().rev());
+                exprs.push(Expr::lvar(&var));
+            }
+        }
+    }
+
+    /// Runs the deferred expressions of all scopes above `depth` before
+    /// the given early exit, innermost scope first.
+    /// The value of the exit is evaluated before any deferred expressions.
+    fn run_deferred(&mut self, value: Expr, depth: usize, exit: fn(Expr) -> Expr) -> Expr {
+        let deferred = self.deferred[depth.min(self.deferred.len())..]
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .cloned()
+            .collect::<Vec<_>>();
+        if deferred.is_empty() {
+            return exit(value);
+        }
+
+        let (mut exprs, value) = match value.get_type() {
+            Type::None | Type::Any | Type::Never => (vec![value], Expr::none_const()),
+            _ => {
+                let (store, var) = self.temp_variable(value, "deferred-value".into());
+                (vec![store], Expr::lvar(&var))
+            }
+        };
+        exprs.extend(deferred);
+        exprs.push(exit(value));
+        Expr::Block(exprs)
+    }
+
     fn break_(&mut self, expr: &Break) -> Expr {
         if self.current_loop_ty.is_none() {
             self.err(expr.cst(), GErr::E207);
@@ -230,10 +535,13 @@ impl GIRGenerator {
             })
             .unwrap_or_else(Expr::none_const);
 
-        Expr::break_(expr)
+        self.run_deferred(expr, self.loop_deferred_depth, Expr::break_)
     }
 
     fn call(&mut self, call: &Call) -> Res<Expr> {
+        if call.args().any(|arg| Self::is_placeholder(&arg)) {
+            return self.partial_call(call);
+        }
         let mut args = call.args().map(|a| self.expression(&a)).collect::<Vec<_>>();
 
         let ast_callee = call.callee();
@@ -246,6 +554,7 @@ impl GIRGenerator {
             // Method call
             AExpr::Get(get) => {
                 let object = self.expression(&get.callee());
+                let object = self.literal_receiver(object, &get.property().name());
                 let ty = object.get_type();
                 self.get_call(object, &ty, args, get, call.args())
             }
@@ -265,6 +574,9 @@ impl GIRGenerator {
             _ => {
                 let mut callee = self.expression(&call.callee());
                 let mut callee_type = callee.get_type();
+                if callee_type.is_error() {
+                    return Ok(Expr::poisoned());
+                }
 
                 if let Type::Type(box Type::Adt(ty)) = &callee_type {
                     let mut ty_vars = callee_type.type_args().unwrap();
@@ -302,7 +614,9 @@ impl GIRGenerator {
                                         |(param, arg)| {
                                             let ty1 = arg.get_type();
                                             let ty2 = param.ty.resolve(&ty_vars);
-                                            ty1.equal(&ty2, false)
+                                            ty1.is_error()
+                                                || ty2.is_error()
+                                                || ty1.equal(&ty2, false)
                                                 || self.can_cast_type(&ty1, &ty2).is_some()
                                         },
                                     );
@@ -364,12 +678,44 @@ impl GIRGenerator {
                         &call.cst,
                         false,
                     )?;
-                    Ok(Expr::call(callee, args))
+                    self.check_thread_spawn(&callee, &args, &call.cst);
+                    Ok(self.branch_hint(callee, args))
                 }
             }
         }
     }
 
+    /// Gives an integer literal without a type suffix the first integer type
+    /// that has the given method, if its default type does not have it.
+    /// This allows calls like `5.method()` with `method` only implemented for `u8`.
+    fn literal_receiver(&mut self, object: Expr, method: &SmolStr) -> Expr {
+        let int = match Self::untyped_int(&object) {
+            Some(int) => int,
+            None => return object,
+        };
+        if self
+            .find_associated_method(&object.get_type(), method)
+            .is_some()
+        {
+            return object;
+        }
+        let int_types = [
+            Type::I64,
+            Type::I32,
+            Type::I16,
+            Type::I8,
+            Type::U64,
+            Type::U32,
+            Type::U16,
+            Type::U8,
+        ];
+        int_types
+            .iter()
+            .filter(|ty| self.find_associated_method(ty, method).is_some())
+            .find_map(|ty| Self::cast_int_literal(int, ty))
+            .unwrap_or(object)
+    }
+
     fn get_call(
         &mut self,
         object: Expr,
@@ -378,12 +724,32 @@ impl GIRGenerator {
         get: &Get,
         ast_args: impl Iterator<Item = ast::Expression>,
     ) -> Res<Expr> {
+        if ty.is_error() {
+            return Ok(Expr::poisoned());
+        }
+        if ty.is_weak() {
+            return Self::weak_call(object, args, get);
+        }
+        if let Type::Array(arr) = ty {
+            return self.array_call(object, arr, args, get);
+        }
+        if let Type::Vector(vec) = ty {
+            return self.vector_call(object, vec, args, get);
+        }
+
         let field = self.get_field(ty, &get)?;
         let func = match &field {
             FieldOrMethod::Field(_) => return Err(gir_err(get.cst(), GErr::E204)),
             FieldOrMethod::Method(method) => method,
             FieldOrMethod::VirtMethod(method) => &method.iface_method,
         };
+        // The implementor that `Self` stands for is unknown on interface values
+        let on_interface = ty
+            .try_adt()
+            .map_or(false, |a| a.ty.borrow().ty.is_interface());
+        if on_interface && Self::uses_self(func) {
+            return Err(gir_err(get.cst(), GErr::E353(get.property().name())));
+        }
 
         let obj_ty = object.get_type();
         let parent_ty_args = obj_ty.type_args().unwrap_or_else(|| Rc::new(vec![]));
@@ -415,13 +781,33 @@ impl GIRGenerator {
             .collect::<Vec<_>>();
         let func = Instance::new(Rc::clone(&func), Rc::new(ty_args));
 
-        self.check_func_args_(
-            &Type::Function(func.clone()),
-            &mut args,
-            ast_args,
-            &get.cst,
-            true,
-        )?;
+        if let FieldOrMethod::VirtMethod(_) = field {
+            // `Self` is the type parameter the interface method is called on
+            let params = func
+                .ty
+                .borrow()
+                .parameters
+                .iter()
+                .map(|p| p.ty.resolve(func.args()).replace_self(&obj_ty))
+                .collect::<Vec<_>>();
+            self.check_func_args(
+                params.into_iter(),
+                Some(func.args()),
+                &mut args,
+                ast_args,
+                false,
+                &get.cst,
+                true,
+            )?;
+        } else {
+            self.check_func_args_(
+                &Type::Function(func.clone()),
+                &mut args,
+                ast_args,
+                &get.cst,
+                true,
+            )?;
+        }
 
         let callee = match field {
             FieldOrMethod::Method(_) => Expr::var(Variable::Function(func)),
@@ -431,6 +817,169 @@ impl GIRGenerator {
         Ok(Expr::call(callee, args))
     }
 
+    /// If the signature of the given interface method refers to `Self`.
+    fn uses_self(method: &MutRc<Function>) -> bool {
+        let method = method.borrow();
+        method.ret_type.contains_self() || method.parameters.iter().any(|p| p.ty.contains_self())
+    }
+
+    /// Replaces calls to `likely` and `unlikely` with a branch hint,
+    /// leaving all other calls unchanged.
+    fn branch_hint(&self, callee: Expr, mut args: Vec<Expr>) -> Expr {
+        let is = |intrinsic: &Option<MutRc<Function>>| match (&callee, intrinsic) {
+            (Expr::Variable(Variable::Function(func)), Some(intrinsic)) => {
+                Rc::ptr_eq(&func.ty, intrinsic)
+            }
+            _ => false,
+        };
+        let expected = if is(&self.intrinsics.likely_fn) {
+            true
+        } else if is(&self.intrinsics.unlikely_fn) {
+            false
+        } else {
+            return Expr::call(callee, args);
+        };
+        Expr::Intrinsic(Intrinsic::Expect {
+            value: box args.pop().unwrap(),
+            expected,
+        })
+    }
+
+    /// Calls a method on a weak reference. `upgrade` is the only method
+    /// available, returning the strong reference or null if the value was freed.
+    fn weak_call(object: Expr, args: Vec<Expr>, get: &Get) -> Res<Expr> {
+        if get.property().name() != "upgrade" {
+            return Err(gir_err(get.cst(), GErr::E210(None)));
+        }
+        if !args.is_empty() {
+            return Err(gir_err(
+                get.cst(),
+                GErr::E216 {
+                    expected: 0,
+                    was: args.len(),
+                },
+            ));
+        }
+        Ok(Expr::upgrade_weak(object))
+    }
+
+    /// Returns if the call argument is the placeholder `_` of a partial application.
+    fn is_placeholder(arg: &AExpr) -> bool {
+        matches!(arg, AExpr::Variable(var) if var.name() == "_" && var.type_args().next().is_none())
+    }
+
+    /// Generates a partial application like `add(_, 5)`, which is a closure
+    /// taking a parameter for every placeholder `_` that calls the callee with them
+    /// in place of the placeholders. All other arguments and a closure callee
+    /// are evaluated when creating the closure and captured by it.
+    fn partial_call(&mut self, call: &Call) -> Res<Expr> {
+        let ast_callee = call.callee();
+        if let AExpr::Get(get) | AExpr::GetNullable(get) = &ast_callee {
+            let name = get.property().name().to_string();
+            return Err(gir_err(ast_callee.cst(), GErr::E356(name)));
+        }
+        let callee = self.expression(&ast_callee);
+        let callee_ty = callee.get_type();
+        let (params, ret_type) = match &callee_ty {
+            _ if callee_ty.is_error() => return Ok(Expr::poisoned()),
+
+            Type::Function(func) => {
+                let function = func.ty.borrow();
+                if function.type_parameters.len() != func.args().len() || function.variadic {
+                    return Err(gir_err(
+                        ast_callee.cst(),
+                        GErr::E356(function.name.to_string()),
+                    ));
+                }
+                let params = function
+                    .parameters
+                    .iter()
+                    .map(|p| p.ty.resolve(func.args()))
+                    .collect::<Vec<_>>();
+                (params, function.ret_type.resolve(func.args()))
+            }
+
+            Type::Closure(closure) => (closure.parameters.clone(), closure.ret_type.clone()),
+
+            _ => return Err(gir_err(ast_callee.cst(), GErr::E215(callee_ty.to_string()))),
+        };
+
+        let ast_args = call.args().collect::<Vec<_>>();
+        if ast_args.len() != params.len() {
+            return Err(gir_err(
+                call.cst(),
+                GErr::E216 {
+                    expected: params.len(),
+                    was: ast_args.len(),
+                },
+            ));
+        }
+
+        let mut stores = Vec::with_capacity(ast_args.len() + 1);
+        let mut captured = Vec::with_capacity(ast_args.len());
+        let callee = if callee_ty.is_closure() {
+            let (store, var) = self.temp_variable(callee, "partial-callee".into());
+            stores.push(store);
+            captured.push(Rc::clone(&var));
+            Expr::lvar(&var)
+        } else {
+            callee
+        };
+
+        // The captured variable of every bound argument, None for placeholders
+        let mut bound = Vec::with_capacity(ast_args.len());
+        let mut placeholders = Vec::new();
+        for (ast, param) in ast_args.iter().zip(params.into_iter()) {
+            if Self::is_placeholder(ast) {
+                let name = SmolStr::new(format!("placeholder-{}", placeholders.len()));
+                placeholders.push(Ok((name, param)));
+                bound.push(None);
+                continue;
+            }
+
+            let value = self.expression(ast);
+            let arg_type = value.get_type();
+            let (value, success) = self.try_cast(value, &param);
+            if !success {
+                let err = self
+                    .variance_error(&arg_type, &param)
+                    .unwrap_or_else(|| GErr::E218 {
+                        expected: param.to_string(),
+                        was: arg_type.to_string(),
+                    });
+                self.error(gir_err(ast.cst(), err))
+            }
+            let (store, var) = self.temp_variable(value, "partial-arg".into());
+            stores.push(store);
+            captured.push(Rc::clone(&var));
+            bound.push(Some(var));
+        }
+
+        let function = self
+            .create_function(FnSig {
+                name: SmolStr::new(format!("closure-{}", call.cst.text_range().start)),
+                visibility: Visibility::Private,
+                params: box placeholders.into_iter(),
+                type_parameters: Rc::new(vec![]),
+                ret_type: Some(ret_type),
+                ast: None,
+            })
+            .map_err(|err| gir_err(call.cst(), err.kind))?;
+        let body = {
+            let params = &function.borrow().parameters;
+            let mut params = params.iter();
+            let args = bound
+                .iter()
+                .map(|var| Expr::lvar(var.as_ref().or_else(|| params.next()).unwrap()))
+                .collect();
+            Expr::call(callee, args)
+        };
+        function.push_return(body);
+
+        stores.push(self.closure_value(function, captured));
+        Ok(Expr::Block(stores))
+    }
+
     /// Check a function call's arguments for correctness,
     /// possibly adding a cast if required.
     #[allow(clippy::too_many_arguments)] // Not ideal, but no real way of fixing this
@@ -482,13 +1031,13 @@ impl GIRGenerator {
             let arg_type = argument.get_type();
             let success = self.try_cast_in_place(argument, &parameter);
             if !success {
-                self.error(gir_err(
-                    ast.cst(),
-                    GErr::E218 {
+                let err = self
+                    .variance_error(&arg_type, &parameter)
+                    .unwrap_or_else(|| GErr::E218 {
                         expected: parameter.to_string(),
                         was: arg_type.to_string(),
-                    },
-                ))
+                    });
+                self.error(gir_err(ast.cst(), err))
             }
         }
 
@@ -579,13 +1128,25 @@ impl GIRGenerator {
                 self.match_param(param_inner, arg_inner, ty_param)
             }
 
+            // Type arguments of the same ADT, like the element types of tuples
+            (Type::Adt(param_inst), Type::Adt(arg_inst))
+                if Rc::ptr_eq(&param_inst.ty, &arg_inst.ty) =>
+            {
+                param_inst
+                    .args()
+                    .iter()
+                    .zip(arg_inst.args().iter())
+                    .find_map(|(param, arg)| self.match_param(param, arg, ty_param))
+            }
+
             _ => None,
         }
     }
 
     fn for_cond(&mut self, condition: AExpr, body: AExpr, else_b: Option<AExpr>) -> Expr {
         let cond = self.expression(&condition);
-        if cond.get_type() != Type::Bool {
+        let cond_ty = cond.get_type();
+        if !cond_ty.is_error() && cond_ty != Type::Bool {
             self.err(condition.cst(), GErr::E220);
         }
 
@@ -603,6 +1164,7 @@ impl GIRGenerator {
     /// - phi type
     fn for_body(&mut self, body_ast: AExpr, else_b: Option<AExpr>) -> (Expr, Expr, Option<Type>) {
         let prev_loop_ty = std::mem::replace(&mut self.current_loop_ty, Some(Type::Any));
+        let prev_deferred_depth = mem::replace(&mut self.loop_deferred_depth, self.deferred.len());
 
         let body = self.expression(&body_ast);
         let body_type = body.get_type();
@@ -614,6 +1176,7 @@ impl GIRGenerator {
         let (phi_ty, body, else_) = self.try_unify_type(body, else_val);
 
         self.current_loop_ty = prev_loop_ty;
+        self.loop_deferred_depth = prev_deferred_depth;
         (body, else_, phi_ty)
     }
 
@@ -643,6 +1206,10 @@ impl GIRGenerator {
         let iter_gir = self.expression(&cond.iterator());
         let (iter_store_expr, iter_gir) = self.temp_variable(iter_gir, "iter".into());
         let iter_gir = Expr::lvar(&iter_gir);
+        if iter_gir.get_type().is_error() {
+            self.end_scope();
+            return Ok(Expr::poisoned());
+        }
         let impls = self.get_iface_impls(&iter_gir.get_type());
         let (iter_value, next_fn, elem_ty) =
             self.get_iterator_value(iter_gir, &*impls.borrow(), &cond.cst)?;
@@ -717,6 +1284,23 @@ impl GIRGenerator {
     fn get(&mut self, get: &Get, allow_uninit: bool) -> Res<(Expr, bool)> {
         let object = self.expression(&get.callee());
         let ty = object.get_type();
+        if ty.is_error() {
+            return Ok((Expr::poisoned(), false));
+        }
+        if let Type::Array(arr) | Type::Vector(arr) = &ty {
+            return Ok((Self::array_get(arr, get)?, false));
+        }
+
+        // `a.case` gets the discriminant of an enum value's case, unless there is a field named `case`
+        if let Some(adt) = ty.try_adt() {
+            let adt = adt.ty.borrow();
+            if get.property().name() == "case"
+                && matches!(adt.ty, ADTType::Enum { .. } | ADTType::EnumCase { .. })
+                && !adt.fields.contains_key("case")
+            {
+                return Ok((Expr::enum_tag(object), false));
+            }
+        }
 
         let field = self.get_field(&ty, get)?;
         let field = field.try_field().or_err(&get.property().cst, GErr::E221)?;
@@ -813,9 +1397,17 @@ impl GIRGenerator {
                             Rc::clone(ty.args()),
                         )))),
                     }
+                } else if let Some(Declaration::Function(func)) =
+                    Self::generated_function(&ty.ty, &name)
+                {
+                    Ok(Expr::fvar(&func))
                 } else {
                     Err(gir_err(get.callee().cst(), GErr::E223))
                 }
+            } else if let Some(Declaration::Function(func)) =
+                Self::generated_function(&ty.ty, &name)
+            {
+                Ok(Expr::fvar(&func))
             } else {
                 Err(gir_err(get.callee().cst(), GErr::E224))
             }
@@ -824,26 +1416,158 @@ impl GIRGenerator {
         }
     }
 
-    fn if_(&mut self, condition: AExpr, then_branch: AExpr, else_branch: Option<AExpr>) -> Expr {
-        let cond = self.expression(&condition);
-        if cond.get_type() != Type::Bool {
-            self.err(condition.cst(), GErr::E220);
+    /// Returns a function generated for an ADT, like `Enum:from_ordinal`.
+    pub(crate) fn generated_function(adt: &MutRc<ADT>, name: &str) -> Option<Declaration> {
+        let adt = adt.borrow();
+        let module = adt.module.borrow();
+        module
+            .declarations
+            .get(format!("{}:{}", adt.name, name).as_str())
+            .cloned()
+    }
+
+    /// Generates an if expression. `else if` chains are flattened into a single
+    /// switch, producing one value merged from all branches.
+    fn if_(&mut self, if_: &IfExpr) -> Expr {
+        let mut branches = Vec::with_capacity(2);
+        let mut else_val = None;
+        // Inverse smart casts of all previous conditions apply to the following branches
+        let mut inverse_casts = Vec::new();
+
+        let mut next = Some(if_.clone());
+        while let Some(if_) = next.take() {
+            let condition = if_.condition();
+            let cond = self.expression(&condition);
+            let cond_ty = cond.get_type();
+            if !cond_ty.is_error() && cond_ty != Type::Bool {
+                self.err(condition.cst(), GErr::E220);
+            }
+
+            self.begin_scope(); // scope for smart casts if applicable
+            let mut then_block = self.smart_casts(&cond);
+            then_block.push(self.expression(&if_.then_branch()));
+            self.end_scope();
+
+            self.begin_scope(); // scope for inverse smart casts, ended once all branches are done
+            let inverse = self.inverse_smart_casts(&cond);
+            let cond = if inverse_casts.is_empty() {
+                cond
+            } else {
+                inverse_casts.push(cond);
+                Expr::Block(mem::take(&mut inverse_casts))
+            };
+            branches.push((cond, Expr::Block(then_block)));
+            inverse_casts = inverse;
+
+            match if_.else_branch() {
+                Some(AExpr::If(else_if)) => next = Some(else_if),
+                Some(else_branch) => {
+                    inverse_casts.push(self.expression(&else_branch));
+                    else_val = Some(Expr::Block(mem::take(&mut inverse_casts)));
+                }
+                None => (),
+            }
+        }
+        for _ in 0..branches.len() {
+            self.end_scope();
         }
 
-        self.begin_scope(); // scope for smart casts if applicable
-        let mut then_block = self.smart_casts(&cond);
-        then_block.push(self.expression(&then_branch));
-        let then_val = Expr::Block(then_block);
-        self.end_scope();
+        let else_val = match else_val {
+            Some(else_val) => else_val,
+            None => return Self::if_chain(branches, Expr::none_const(), None),
+        };
 
-        let else_val = else_branch
-            .as_ref()
-            .map_or(Expr::none_const(), |else_branch| {
-                self.expression(&else_branch)
-            });
+        let (conds, mut values): (Vec<_>, Vec<_>) = branches.into_iter().unzip();
+        values.push(else_val);
+        let (phi_type, mut values) = self.unify_branches(values);
+        let else_val = values.pop().unwrap();
+        let branches = conds.into_iter().zip(values.into_iter()).collect();
+        Self::if_chain(branches, else_val, phi_type)
+    }
 
-        let (phi_type, then_val, else_val) = self.try_unify_type(then_val, else_val);
-        Expr::if_(cond, then_val, else_val, phi_type)
+    /// Creates the expression for an if chain; a regular if
+    /// for a single branch, otherwise a switch.
+    fn if_chain(mut branches: Vec<(Expr, Expr)>, else_val: Expr, phi_type: Option<Type>) -> Expr {
+        if branches.len() == 1 {
+            let (cond, then_val) = branches.pop().unwrap();
+            Expr::if_(cond, then_val, else_val, phi_type)
+        } else {
+            Self::switch(branches, else_val, phi_type)
+        }
+    }
+
+    /// Tries to find a common type for all given branch values,
+    /// casting all of them to it. Branches that always exit early are ignored
+    /// when finding the type. Returns None as type if there is no common type.
+    fn unify_branches(&mut self, values: Vec<Expr>) -> (Option<Type>, Vec<Expr>) {
+        let mut iter = values.iter().filter(|v| !v.diverges());
+        let mut goal = match iter.next() {
+            Some(first) => first.clone(),
+            None => return (Some(Type::Any), values),
+        };
+        for value in iter {
+            match self.try_unify_type(goal, value.clone()) {
+                (Some(_), new_goal, _) => goal = new_goal,
+                (None, ..) => return (None, values),
+            }
+        }
+
+        let goal = goal.get_type();
+        let mut success = true;
+        let values = values
+            .into_iter()
+            .map(|value| {
+                let (value, cast) = self.try_cast(value, &goal);
+                success &= cast;
+                value
+            })
+            .collect();
+        (if success { Some(goal) } else { None }, values)
+    }
+
+    /// Errors if the given value is an if expression with branches that
+    /// produce values of different types, which would otherwise silently evaluate
+    /// to None. Used where the value of the expression is required.
+    /// Branches without a value or that always exit early are ignored.
+    fn check_branch_types(ast: &AExpr, value: &Expr) -> Res<()> {
+        if !matches!(ast, AExpr::If(_)) {
+            return Ok(());
+        }
+        let mut types = match value {
+            Expr::If {
+                then_branch,
+                else_branch,
+                phi_type: None,
+                ..
+            } => vec![then_branch.get_type(), else_branch.get_type()],
+            Expr::Switch {
+                branches,
+                else_branch,
+                phi_type: None,
+                ..
+            } => branches
+                .iter()
+                .map(|(_, br)| br)
+                .chain(iter::once(&**else_branch))
+                .map(Expr::get_type)
+                .collect(),
+            _ => return Ok(()),
+        };
+        if types.contains(&Type::None) {
+            return Ok(());
+        }
+        types.retain(|ty| *ty != Type::Any && *ty != Type::Never && !ty.is_error());
+
+        match types.iter().find(|ty| **ty != types[0]) {
+            Some(second) => Err(gir_err(
+                ast.cst(),
+                GErr::E244 {
+                    first: types[0].to_string(),
+                    second: second.to_string(),
+                },
+            )),
+            None => Ok(()),
+        }
     }
 
     /// Tries finding smart casts, where a type can be downcasted
@@ -851,11 +1575,81 @@ impl GIRGenerator {
     /// Will insert variables for downcasts into current scope/function.
     fn smart_casts(&mut self, condition: &Expr) -> Vec<Expr> {
         let mut casts = Vec::new();
-        self.find_casts(&mut casts, condition);
+        self.find_casts(&mut casts, condition, false);
         casts
     }
 
-    fn find_casts(&mut self, list: &mut Vec<Expr>, expr: &Expr) {
+    /// Same as `smart_casts`, but for code that only runs
+    /// when the condition is false, like else branches.
+    fn inverse_smart_casts(&mut self, condition: &Expr) -> Vec<Expr> {
+        let mut casts = Vec::new();
+        self.find_casts(&mut casts, condition, true);
+        casts
+    }
+
+    /// Finds smart casts for the code following an if expression
+    /// where one branch always exits early, like `if (a == null) return 0`.
+    /// The casts are inserted into the current scope.
+    fn early_exit_casts(&mut self, expr: &Expr) -> Vec<Expr> {
+        match expr {
+            Expr::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } if then_branch.diverges() && !else_branch.diverges() => {
+                self.inverse_smart_casts(condition)
+            }
+
+            Expr::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } if else_branch.diverges() && !then_branch.diverges() => self.smart_casts(condition),
+
+            _ => vec![],
+        }
+    }
+
+    /// Finds all smart casts in the given condition.
+    /// `inverse` specifies that the casts are for when the condition is false.
+    fn find_casts(&mut self, list: &mut Vec<Expr>, expr: &Expr, inverse: bool) {
+        // Blocks are produced by logic operators that contain smart casts themselves
+        if let Expr::Block(exprs) = expr {
+            if let Some(last) = exprs.last() {
+                self.find_casts(list, last, inverse);
+            }
+            return;
+        }
+        // Logic operators are lowered to if expressions, see `binary_expr`
+        if let Expr::If {
+            condition,
+            then_branch,
+            else_branch,
+            ..
+        } = expr
+        {
+            match (&**then_branch, &**else_branch) {
+                // Both sides are true (a and b --> if (a) b else false)
+                (_, Expr::Literal(Literal::Bool(false))) if !inverse => {
+                    self.find_casts(list, condition, inverse);
+                    self.find_casts(list, then_branch, inverse);
+                }
+                // Both sides are false !(a or b --> if (a) true else b)
+                (Expr::Literal(Literal::Bool(true)), _) if inverse => {
+                    self.find_casts(list, condition, inverse);
+                    self.find_casts(list, else_branch, inverse);
+                }
+                _ => (),
+            }
+            return;
+        }
+        // Branch hints do not change the condition
+        if let Expr::Intrinsic(Intrinsic::Expect { value, .. }) = expr {
+            return self.find_casts(list, value, inverse);
+        }
+
         if let Expr::Binary {
             left,
             operator,
@@ -878,18 +1672,13 @@ impl GIRGenerator {
             };
 
             match (operator, &**left, &**right) {
-                (SyntaxKind::And, _, _) => {
-                    self.find_casts(list, &left);
-                    self.find_casts(list, &right);
-                }
-
                 // Enum parent to case (parent is Enum:Case)
-                (SyntaxKind::Is, Expr::Variable(Variable::Local(var)), _) => {
+                (SyntaxKind::Is, Expr::Variable(Variable::Local(var)), _) if !inverse => {
                     let ty = *right.get_type().into_type();
                     clone(var, ty, CastType::Bitcast);
                 }
 
-                // Nullable to non-null (a != null)
+                // Nullable to non-null (a != null, or a == null when inverse)
                 (
                     SyntaxKind::BangEqual,
                     Expr::Variable(Variable::Local(var)),
@@ -897,7 +1686,18 @@ impl GIRGenerator {
                         inner: box Expr::Literal(Literal::Null),
                         ..
                     },
-                ) => {
+                ) if !inverse => {
+                    let ty = *left.get_type().into_nullable();
+                    clone(var, ty, CastType::FromNullable);
+                }
+                (
+                    SyntaxKind::EqualEqual,
+                    Expr::Variable(Variable::Local(var)),
+                    Expr::Cast {
+                        inner: box Expr::Literal(Literal::Null),
+                        ..
+                    },
+                ) if inverse => {
                     let ty = *left.get_type().into_nullable();
                     clone(var, ty, CastType::FromNullable);
                 }
@@ -908,7 +1708,7 @@ impl GIRGenerator {
     }
 
     fn literal(&mut self, literal: &ast::Literal) -> Res<Expr> {
-        let (text, ty) = literal.get();
+        let (text, ty) = literal.get()?;
         Ok(match ty {
             LiteralType::Null => Expr::Literal(Literal::Null),
             LiteralType::True => Expr::Literal(Literal::Bool(true)),
@@ -957,11 +1757,37 @@ impl GIRGenerator {
 
             Some(('f', "32")) => Literal::F32(self.parse_numeric_literal(value, cst)?),
 
+            _ if float && self.literal_types().float32 => {
+                Literal::F32(self.parse_numeric_literal(value, cst)?)
+            }
             _ if float => Literal::F64(self.parse_numeric_literal(value, cst)?),
-            _ => Literal::I64(self.parse_numeric_literal(value, cst)?),
+
+            _ => {
+                // Parsed as the widest type, as context typing only narrows the literal later.
+                // Without context, it takes the first default type that can hold it.
+                let value: u64 = self.parse_numeric_literal(value, cst)?;
+                let fits_i32 = i32::try_from(value).is_ok();
+                Literal::Int {
+                    value,
+                    ty: if self.literal_types().int32 && fits_i32 {
+                        Type::I32
+                    } else {
+                        Type::I64
+                    },
+                }
+            }
         })
     }
 
+    /// The types of number literals without a type suffix in the current module.
+    fn literal_types(&self) -> LiteralTypes {
+        if self.path.is(&["std"]) {
+            LiteralTypes::default()
+        } else {
+            self.flags.literal_types
+        }
+    }
+
     fn parse_numeric_literal<T: Num>(&self, text: &str, cst: &CSTNode) -> Res<T> {
         T::from_str_radix(text.trim(), 10)
             .ok()
@@ -970,7 +1796,7 @@ impl GIRGenerator {
 
     /// Replace all escape sequences inside a string literal with their proper char
     /// and return either an error or the finished string literal
-    fn string_literal(&mut self, text: SmolStr, cst: &ast::Literal) -> Res<SmolStr> {
+    pub(crate) fn string_literal(&mut self, text: SmolStr, cst: &ast::Literal) -> Res<SmolStr> {
         let mut chars = text.chars().skip(1).collect::<Vec<_>>();
         chars.pop(); // Final '"'
 
@@ -1015,7 +1841,13 @@ impl GIRGenerator {
             .ret_type()
             .map(|ty| self.find_type(&ty))
             .transpose()?;
-        let mut gen = Self::for_closure(self);
+        let annotations = self.annotations(func.annotations(), AnnotationTarget::Closure);
+        let capture = if annotations.has("weak_this") {
+            Capture::WeakThis
+        } else {
+            Capture::All
+        };
+        let mut gen = Self::for_closure(self, capture);
 
         let function = gen.create_function(FnSig {
             name: SmolStr::new_inline(&format!("closure-{}", signature.cst.text_range().start)),
@@ -1029,25 +1861,43 @@ impl GIRGenerator {
         })?;
         gen.generate_function(&function);
         let closure_data = gen.end_closure(self);
+        Ok(self.closure_value(function, closure_data.captured))
+    }
 
-        let captured = Rc::new(closure_data.captured);
-        function.borrow_mut().parameters.insert(
-            0,
-            Rc::new(LocalVariable {
-                name: SmolStr::new_inline("CLOSURE-CAPTURED"),
-                ty: Type::ClosureCaptured(Rc::clone(&captured)),
-                mutable: false,
-            }),
-        );
+    /// Declares and generates a function declared inside a function body.
+    /// It is only visible in the current scope and cannot capture variables;
+    /// in the module, it is registered with the enclosing function's name as prefix.
+    fn func_def(&mut self, ast: &ast::Function) -> Res<()> {
+        let name = ast.sig().name();
+        let outer_type_params = self.type_params.clone();
+        let function = self.function_from_ast(ast.clone(), None, None);
+        self.type_params = outer_type_params;
+        let function = function?;
+
+        let mangled = format!("{}:{}", self.cur_fn().borrow().name, name.name());
+        function.borrow_mut().name = SmolStr::new(mangled);
+        let already_defined = self
+            .local_functions
+            .last_mut()
+            .unwrap()
+            .insert(name.name(), Rc::clone(&function))
+            .is_some();
+        if already_defined {
+            self.err(name.cst(), GErr::E100(name.name()));
+        }
 
-        let expr = Expr::Closure { function, captured };
-        let (store, _) = self.temp_variable(expr, "closure-store".into());
-        Ok(store)
+        let mut gen = Self::for_closure(self, Capture::Nothing);
+        gen.generate_function(&function);
+        gen.end_closure(self);
+        Ok(())
     }
 
     fn prefix(&mut self, operator: SyntaxKind, ast_right: AExpr, cst: &CSTNode) -> Expr {
         let right = self.expression(&ast_right);
         let ty = right.get_type();
+        if ty.is_error() {
+            return Expr::poisoned();
+        }
 
         match operator {
             SyntaxKind::Bang if ty != Type::Bool => self.err(cst.clone(), GErr::E227),
@@ -1068,16 +1918,94 @@ impl GIRGenerator {
             .map(|v| self.expression(&v))
             .unwrap_or_else(Expr::none_const);
 
+        if let Some(ast) = ret.value() {
+            Self::check_branch_types(&ast, &value)?;
+        }
+
         let value_type = value.get_type();
         let ret_type = self.cur_fn().borrow().ret_type.clone();
-        let value = self
-            .cast_or_none(value, &ret_type)
-            .or_error(&ret.cst, || GErr::E212 {
-                expected: ret_type.to_string(),
-                was: value_type.to_string(),
-            })?;
+        let value = self.cast_or_none(value, &ret_type).or_error(&ret.cst, || {
+            self.variance_error(&value_type, &ret_type)
+                .unwrap_or_else(|| GErr::E212 {
+                    expected: ret_type.to_string(),
+                    was: value_type.to_string(),
+                })
+        })?;
+
+        Ok(self.run_deferred(value, 0, Expr::ret))
+    }
+
+    /// Generates `value?` on a `Res`, which is the value of `Ok`
+    /// and returns `Err` from the current function otherwise:
+    /// `var tmp = value; if (tmp is Res:Err) return Res[R, E]:Err(tmp.err); tmp.ok`
+    fn try_(&mut self, try_: &Try) -> Res<Expr> {
+        let value = self.expression(&try_.value());
+        let ty = value.get_type();
+        if ty.is_error() {
+            return Ok(Expr::poisoned());
+        }
+        let res = match (ty.try_adt(), &self.intrinsics.result_adt) {
+            (Some(inst), Some(res)) if Rc::ptr_eq(&inst.ty, res) => inst.clone(),
+            _ => return Err(gir_err(try_.cst(), GErr::E354(ty.to_string()))),
+        };
+
+        let ret_type = self.cur_fn().borrow().ret_type.clone();
+        let mismatch = || {
+            gir_err(
+                try_.cst(),
+                GErr::E355 {
+                    was: ty.to_string(),
+                    ret_type: ret_type.to_string(),
+                },
+            )
+        };
+        let ret_args = match ret_type.try_adt() {
+            Some(ret) if Rc::ptr_eq(&ret.ty, &res.ty) => Rc::clone(ret.args()),
+            _ => return Err(mismatch()),
+        };
+
+        let (ok_case, err_case) = match &res.ty.borrow().ty {
+            ADTType::Enum { cases, .. } => (Rc::clone(&cases["Ok"]), Rc::clone(&cases["Err"])),
+            _ => unreachable!(),
+        };
+        let case_ty = |case: &MutRc<ADT>, args: &Rc<TypeArguments>| {
+            Type::Adt(Instance::new(Rc::clone(case), Rc::clone(args)))
+        };
+        let case_field = |var: &Rc<LocalVariable>, case: &MutRc<ADT>, name: &str| {
+            let ty = case_ty(case, res.args());
+            let field = Rc::clone(&case.borrow().fields[name]);
+            Expr::load(Expr::cast(Expr::lvar(var), ty, CastType::Bitcast), &field)
+        };
+
+        let (store, var) = self.temp_variable(value, "try".into());
+        let constructor = Rc::clone(&err_case.borrow().constructors[0]);
+        let err_param = constructor.borrow().parameters[1].ty.resolve(&ret_args);
+        let err = self
+            .cast_or_none(case_field(&var, &err_case, "err"), &err_param)
+            .ok_or_else(mismatch)?;
+        let ret_err = Expr::Allocate {
+            ty: case_ty(&err_case, &ret_args),
+            constructor,
+            args: vec![err],
+        };
+        let ret_err = self.cast_or_none(ret_err, &ret_type).ok_or_else(mismatch)?;
 
-        Ok(Expr::ret(value))
+        let is_err = Expr::binary(
+            SyntaxKind::Is,
+            Expr::lvar(&var),
+            Expr::TypeGet(case_ty(&err_case, res.args())),
+        );
+        let early_return = Expr::if_(
+            is_err,
+            self.run_deferred(ret_err, 0, Expr::ret),
+            Expr::none_const(),
+            None,
+        );
+        Ok(Expr::Block(vec![
+            store,
+            early_return,
+            case_field(&var, &ok_case, "ok"),
+        ]))
     }
 
     fn var(&mut self, var: &GenericIdent) -> Res<Expr> {
@@ -1087,6 +2015,13 @@ impl GIRGenerator {
         match (has_ty_args, variable) {
             (true, Ok(Variable::Local(_))) => Err(gir_err(var.cst(), GErr::E213)),
 
+            (_, Ok(Variable::Function(func))) if func.ty.borrow().lazy => {
+                if has_ty_args {
+                    return Err(gir_err(var.cst(), GErr::E213));
+                }
+                Self::lazy_global(func, &var.cst)
+            }
+
             (true, Ok(Variable::Function(mut func))) => {
                 let args = var
                     .type_args()
@@ -1105,6 +2040,15 @@ impl GIRGenerator {
         }
     }
 
+    /// Reads a lazy global by calling its function.
+    fn lazy_global(func: Instance<Function>, cst: &CSTNode) -> Res<Expr> {
+        if func.ty.borrow().ret_type == Type::Any {
+            let name = func.ty.borrow().name.clone();
+            return Err(gir_err(cst.clone(), GErr::E343(name)));
+        }
+        Ok(Expr::call(Expr::var(Variable::Function(func)), vec![]))
+    }
+
     fn when(&mut self, when: &When) -> Res<Expr> {
         let value = self.expression(&when.condition());
         let cond_type = value.get_type();
@@ -1152,16 +2096,101 @@ impl GIRGenerator {
         }
 
         cases.insert(0, (first_cond, first_val));
-        if else_br.is_none() && !self.can_omit_else(&cond_type, &cases) {
+        if else_br.is_none() && !self.can_omit_else(&value, &cases) {
             first_ty = Type::None
         }
-        Ok(Expr::switch(
+        Ok(Self::switch(
             cases,
             else_br.unwrap_or_else(Expr::none_const),
             first_ty.type_or_none(),
         ))
     }
 
+    /// Creates a switch, which is compiled to a jump table
+    /// if its conditions allow it, see `is_dense_switch`.
+    pub(crate) fn switch(branches: Vec<(Expr, Expr)>, else_: Expr, phi_type: Option<Type>) -> Expr {
+        Expr::Switch {
+            jump_table: Self::is_dense_switch(&branches),
+            branches,
+            else_branch: Box::new(else_),
+            phi_type,
+        }
+    }
+
+    /// If all conditions of a switch compare the same integer variable
+    /// against distinct literals, which cover at least half of the range
+    /// between the smallest and largest of them. Small switches are not,
+    /// since a chain of comparisons is just as fast for them.
+    fn is_dense_switch(branches: &[(Expr, Expr)]) -> bool {
+        if branches.len() < MIN_JUMP_TABLE_CASES {
+            return false;
+        }
+
+        let mut literals = Vec::with_capacity(branches.len());
+        for (condition, _) in branches {
+            let literal = match (condition, &branches[0].0) {
+                (
+                    Expr::Binary {
+                        left,
+                        operator: SyntaxKind::EqualEqual,
+                        right: box Expr::Literal(literal),
+                    },
+                    Expr::Binary { left: first, .. },
+                ) if left.get_type().is_int() && Self::same_value(left, first) => {
+                    Self::int_literal(literal)
+                }
+                _ => None,
+            };
+            match literal {
+                Some(literal) => literals.push(literal),
+                None => return false,
+            }
+        }
+
+        literals.sort_unstable();
+        literals.dedup();
+        let range = literals[literals.len() - 1] - literals[0] + 1;
+        literals.len() == branches.len() && range <= 2 * literals.len() as i128
+    }
+
+    /// If both expressions are certain to give the same value, as is
+    /// the case for the same variable or field of a variable.
+    fn same_value(a: &Expr, b: &Expr) -> bool {
+        match (a, b) {
+            (Expr::Variable(a), Expr::Variable(b)) => a == b,
+            (
+                Expr::Load {
+                    object: a,
+                    field: a_field,
+                },
+                Expr::Load {
+                    object: b,
+                    field: b_field,
+                },
+            ) => Rc::ptr_eq(a_field, b_field) && Self::same_value(a, b),
+            (Expr::Intrinsic(Intrinsic::EnumTag(a)), Expr::Intrinsic(Intrinsic::EnumTag(b))) => {
+                Self::same_value(a, b)
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the value of an integer literal.
+    fn int_literal(literal: &Literal) -> Option<i128> {
+        Some(match *literal {
+            Literal::Int { value, .. } => value as i128,
+            Literal::I8(value) => value as i8 as i128,
+            Literal::I16(value) => value as i16 as i128,
+            Literal::I32(value) => value as i32 as i128,
+            Literal::I64(value) => value as i64 as i128,
+            Literal::U8(value) => value as i128,
+            Literal::U16(value) => value as i128,
+            Literal::U32(value) => value as i128,
+            Literal::U64(value) => value as i128,
+            _ => return None,
+        })
+    }
+
     fn when_branch(
         &mut self,
         value: Expr,
@@ -1174,6 +2203,14 @@ impl GIRGenerator {
             AExpr::GetStatic(get) => self.get_static(&get, false)?,
             _ => self.expression(&cond),
         };
+        // `when (a.case)` compares against the discriminants of cases
+        let br_cond = match &value {
+            Expr::Intrinsic(Intrinsic::EnumTag(inner)) if br_cond.get_type().is_type() => {
+                Self::case_discriminant(&inner.get_type(), &br_cond.get_type_get_type())
+                    .or_err(&cond.cst(), GErr::E229)?
+            }
+            _ => br_cond,
+        };
 
         let br_type = br_cond.get_type();
         if &br_type != cond_type && !br_type.is_type() {
@@ -1196,16 +2233,37 @@ impl GIRGenerator {
         Ok((cond, branch_val))
     }
 
+    /// Returns the discriminant of the given case of an enum as a literal,
+    /// or None if it is not a case of the enum.
+    fn case_discriminant(enum_ty: &Type, case: &Type) -> Option<Expr> {
+        let (enum_ty, case) = (enum_ty.try_adt()?, case.try_adt()?);
+        match &case.ty.borrow().ty {
+            ADTType::EnumCase {
+                parent,
+                discriminant,
+                ..
+            } if Rc::ptr_eq(parent, &enum_ty.ty) => {
+                Some(Expr::literal(Literal::I64(*discriminant)))
+            }
+            _ => None,
+        }
+    }
+
     /// If a when expression can safely give a value even when an else branch is missing.
-    /// Only true when switching on enum type with every case present.
-    fn can_omit_else(&self, value_ty: &Type, when_cases: &[(Expr, Expr)]) -> bool {
+    /// Only true when switching on enum type or its case (`when (a.case)`)
+    /// with every case present.
+    fn can_omit_else(&self, value: &Expr, when_cases: &[(Expr, Expr)]) -> bool {
+        let (value_ty, by_case) = match value {
+            Expr::Intrinsic(Intrinsic::EnumTag(inner)) => (inner.get_type(), true),
+            _ => (value.get_type(), false),
+        };
         let adt = if let Some(adt) = value_ty.try_adt() {
             adt
         } else {
             return false;
         };
         let adt = adt.ty.borrow();
-        let cases = if let ADTType::Enum { cases } = &adt.ty {
+        let cases = if let ADTType::Enum { cases, .. } = &adt.ty {
             cases
         } else {
             return false;
@@ -1222,12 +2280,20 @@ impl GIRGenerator {
                 panic!("Invalid when condition")
             };
 
-            if *op != SyntaxKind::Is {
-                return false;
+            let i = match (op, &**right) {
+                (SyntaxKind::Is, _) if !by_case => {
+                    let ty = right.get_type();
+                    let adt = ty.as_type().as_adt();
+                    cases.iter().position(|c| Rc::ptr_eq(c, &adt.ty))
+                }
+                (SyntaxKind::EqualEqual, Expr::Literal(Literal::I64(value))) if by_case => {
+                    cases.iter().position(|c| match c.borrow().ty {
+                        ADTType::EnumCase { discriminant, .. } => discriminant == *value,
+                        _ => false,
+                    })
+                }
+                _ => return false,
             };
-            let ty = right.get_type();
-            let adt = ty.as_type().as_adt();
-            let i = cases.iter().position(|c| Rc::ptr_eq(c, &adt.ty));
             if let Some(i) = i {
                 cases.remove(i);
             }
@@ -1235,8 +2301,82 @@ impl GIRGenerator {
         cases.is_empty()
     }
 
+    fn tuple(&mut self, tuple: &Tuple) -> Res<Expr> {
+        let args = tuple
+            .elements()
+            .map(|element| self.expression(&element))
+            .collect::<Vec<_>>();
+        let ty = self.tuple_type(args.iter().map(Expr::get_type).collect(), &tuple.cst)?;
+        let constructor = Rc::clone(&ty.as_adt().ty.borrow().constructors[0]);
+        Ok(Expr::Allocate {
+            ty,
+            constructor,
+            args,
+        })
+    }
+
+    /// Destructures a tuple into a new variable for each element.
+    /// Returns the stores of the new variables, to be placed in the current block.
+    fn destructure(&mut self, destructure: &Destructure) -> Res<Vec<Expr>> {
+        let init = self.expression(&destructure.initializer());
+        let ty = init.get_type();
+        let fields = match ty.try_adt() {
+            Some(adt) if self.intrinsics.is_tuple(&adt.ty) => {
+                adt.ty.borrow().fields.values().cloned().collect::<Vec<_>>()
+            }
+            _ => {
+                return Err(gir_err(
+                    destructure.initializer().cst(),
+                    GErr::E245(ty.to_string()),
+                ))
+            }
+        };
+
+        let names = destructure.names().collect::<Vec<_>>();
+        if names.len() != fields.len() {
+            return Err(gir_err(
+                destructure.cst(),
+                GErr::E246 {
+                    expected: fields.len(),
+                    was: names.len(),
+                },
+            ));
+        }
+
+        let (store, tuple) = self.temp_variable(init, SmolStr::new_inline("destructured"));
+        let mut stores = vec![store];
+        for (name, field) in names.into_iter().zip(fields.iter()) {
+            let value = Expr::load(Expr::lvar(&tuple), field);
+            let var = self.define_variable_(
+                LocalVariable {
+                    name,
+                    mutable: destructure.mutable(),
+                    ty: value.get_type(),
+                },
+                Some(&destructure.cst),
+            );
+            stores.push(Expr::store(Expr::lvar(&var), value, true));
+        }
+        Ok(stores)
+    }
+
     fn var_def(&mut self, var: &ast::Variable) -> Res<Expr> {
         let init = self.expression(&var.initializer());
+        Self::check_branch_types(&var.initializer(), &init)?;
+        let init = match var._type() {
+            Some(ast_ty) => {
+                let ty = self.find_type(&ast_ty)?;
+                let (init, success) = self.try_cast(init, &ty);
+                if !success {
+                    let err = self
+                        .variance_error(&init.get_type(), &ty)
+                        .unwrap_or(GErr::E201);
+                    return Err(gir_err(var.initializer().cst(), err));
+                }
+                init
+            }
+            None => init,
+        };
         let type_ = init.get_type();
         if type_.is_assignable() {
             let var = self.define_variable(var.clone(), type_);
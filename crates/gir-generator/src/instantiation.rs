@@ -0,0 +1,329 @@
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use common::MutRc;
+use error::GErr;
+use gir_nodes::{
+    declaration::Variable, types::TypeArguments, Expr, Function, IFaceImpls, Type, ADT,
+};
+
+use crate::GIRGenerator;
+
+/// The maximum nesting depth of the type arguments of an instantiated declaration.
+/// Programs exceeding it instantiate a declaration with endlessly growing
+/// type arguments, like a `Wrap[T]` using a `Wrap[Wrap[T]]`, which would
+/// otherwise hang the compiler or overflow its stack during monomorphisation.
+const DEPTH_LIMIT: usize = 32;
+
+#[derive(Clone)]
+enum Decl {
+    Function(MutRc<Function>),
+    Adt(MutRc<ADT>),
+}
+
+impl Decl {
+    fn id(&self) -> usize {
+        match self {
+            Decl::Function(func) => Rc::as_ptr(func) as *const () as usize,
+            Decl::Adt(adt) => Rc::as_ptr(adt) as *const () as usize,
+        }
+    }
+}
+
+/// A declaration instantiated with type arguments.
+struct Instantiation {
+    decl: Decl,
+    args: Rc<TypeArguments>,
+    /// The instantiation that required this one, if any.
+    parent: Option<usize>,
+}
+
+impl Instantiation {
+    fn describe(&self) -> String {
+        let name = match &self.decl {
+            Decl::Function(func) => func.borrow().name.clone(),
+            Decl::Adt(adt) => adt.borrow().name.clone(),
+        };
+        if self.args.is_empty() {
+            name.to_string()
+        } else {
+            let args = self.args.iter().map(Type::to_string).collect::<Vec<_>>();
+            format!("{}[{}]", name, args.join(", "))
+        }
+    }
+}
+
+impl GIRGenerator {
+    /// Instantiates all generic declarations used by the program the same way
+    /// IR generation will, to report declarations instantiating themselves
+    /// with growing type arguments before IR generation gets stuck on them.
+    pub(crate) fn check_instantiations(&mut self) {
+        let mut checker = Checker {
+            limit: self.flags.limits.max_instantiations,
+            ..Checker::default()
+        };
+        // Interface methods are instantiated along with the vtables of
+        // their implementor, which IR generation creates for every instance
+        for (ty, impls) in &self.iface_impls {
+            let key = match ty {
+                Type::Adt(inst) => ImplsKey::Adt(Rc::as_ptr(&inst.ty) as *const () as usize),
+                _ => ImplsKey::Type(ty.clone()),
+            };
+            checker
+                .iface_impls
+                .entry(key)
+                .or_insert_with(Vec::new)
+                .push(Rc::clone(impls));
+        }
+
+        for module in &self.modules_uncompiled {
+            for func in &module.borrow().functions {
+                if func.borrow().type_parameters.is_empty() {
+                    checker.add(Decl::Function(Rc::clone(func)), Rc::new(vec![]), None);
+                }
+            }
+        }
+
+        if let Some(index) = checker.run() {
+            let chain = checker.chain(index);
+            self.instantiation_error(&chain);
+        } else if checker.exceeds_limit() {
+            self.limit_error("instantiations", checker.limit.unwrap().to_string());
+        }
+    }
+
+    /// Reports the error at the last declaration of the chain that has a location.
+    fn instantiation_error(&mut self, chain: &[&Instantiation]) {
+        let described = chain.iter().map(|inst| inst.describe()).collect::<Vec<_>>();
+        let chain_str = if described.len() > 6 {
+            format!(
+                "{} -> ... -> {}",
+                described[..3].join(" -> "),
+                described[described.len() - 2..].join(" -> ")
+            )
+        } else {
+            described.join(" -> ")
+        };
+
+        let location = chain.iter().rev().find_map(|inst| match &inst.decl {
+            Decl::Function(func) => {
+                let func = func.borrow();
+                let cst = func.ast.as_ref()?.sig().name().cst;
+                Some((Rc::clone(&func.module), cst))
+            }
+            Decl::Adt(adt) => {
+                let adt = adt.borrow();
+                let cst = adt.ast.name().cst;
+                Some((Rc::clone(&adt.module), cst))
+            }
+        });
+        if let Some((module, cst)) = location {
+            self.switch_module(module);
+            self.err(
+                cst,
+                GErr::E341 {
+                    limit: DEPTH_LIMIT,
+                    chain: chain_str,
+                },
+            );
+        }
+    }
+}
+
+/// The key of the interface implementations of a type.
+/// Implementations on generic ADTs apply to all their instances,
+/// so these are found by the ADT alone.
+#[derive(PartialEq, Eq, Hash)]
+enum ImplsKey {
+    Adt(usize),
+    Type(Type),
+}
+
+#[derive(Default)]
+struct Checker {
+    /// All instantiations found, in the order they were found.
+    instantiations: Vec<Instantiation>,
+    /// Declarations and type arguments of all instantiations.
+    seen: HashSet<(usize, Rc<TypeArguments>)>,
+    /// The most instantiations to find before giving up, see `Limits::max_instantiations`.
+    limit: Option<usize>,
+    /// The interface implementations of all types.
+    iface_impls: HashMap<ImplsKey, Vec<MutRc<IFaceImpls>>>,
+}
+
+impl Checker {
+    /// Instantiates everything required by the instantiations found so far,
+    /// until either nothing new is found or the depth limit is exceeded.
+    /// Returns the index of the instantiation that exceeded the limit.
+    /// Also stops once more instantiations than `limit` were found.
+    fn run(&mut self) -> Option<usize> {
+        let mut next = 0;
+        while next < self.instantiations.len() {
+            if self.exceeds_limit() {
+                return None;
+            }
+
+            let inst = &self.instantiations[next];
+            let (decl, args) = (inst.decl.clone(), Rc::clone(&inst.args));
+            if args.iter().map(depth).max().unwrap_or(0) > DEPTH_LIMIT {
+                return Some(next);
+            }
+
+            match decl {
+                Decl::Function(func) => {
+                    let func = func.borrow();
+                    for var in func.parameters.iter().chain(func.variables.values()) {
+                        self.add_type(&var.ty.resolve(&args), next);
+                    }
+                    self.add_type(&func.ret_type.resolve(&args), next);
+                    for expr in &func.exprs {
+                        self.expr(expr, &args, next);
+                    }
+                }
+
+                Decl::Adt(adt) => {
+                    for field in adt.borrow().fields.values() {
+                        self.add_type(&field.ty.resolve(&args), next);
+                    }
+                }
+            }
+            next += 1;
+        }
+        None
+    }
+
+    fn expr(&mut self, expr: &Expr, args: &Rc<TypeArguments>, parent: usize) {
+        match expr {
+            Expr::Variable(Variable::Function(func)) => {
+                let func_args = resolve_args(func.args(), &func.ty.borrow(), args);
+                self.add(Decl::Function(Rc::clone(&func.ty)), func_args, Some(parent));
+            }
+
+            Expr::Allocate {
+                ty, constructor, ..
+            } => {
+                let ty = ty.resolve(args);
+                if let Type::Adt(inst) = &ty {
+                    let decl = Decl::Function(Rc::clone(constructor));
+                    self.add(decl, Rc::clone(inst.args()), Some(parent));
+                }
+                self.add_type(&ty, parent);
+            }
+
+            Expr::Closure { function, .. } => {
+                self.add(
+                    Decl::Function(Rc::clone(function)),
+                    Rc::clone(args),
+                    Some(parent),
+                );
+            }
+
+            _ => (),
+        }
+        expr.for_each_child(&mut |child| self.expr(child, args, parent));
+    }
+
+    /// Adds the ADTs required to build the given type,
+    /// together with the methods of its interface implementations.
+    fn add_type(&mut self, ty: &Type, parent: usize) {
+        self.add_impl_methods(ty, parent);
+        match ty {
+            Type::Adt(inst) => self.add(
+                Decl::Adt(Rc::clone(&inst.ty)),
+                Rc::clone(inst.args()),
+                Some(parent),
+            ),
+            Type::Nullable(inner) | Type::Weak(inner) | Type::RawPtr(inner) => {
+                self.add_type(inner, parent)
+            }
+            Type::Array(arr) | Type::Vector(arr) => self.add_type(&arr.ty, parent),
+            _ => (),
+        }
+    }
+
+    fn add_impl_methods(&mut self, ty: &Type, parent: usize) {
+        let (key, args) = match ty {
+            Type::Adt(inst) => (
+                ImplsKey::Adt(Rc::as_ptr(&inst.ty) as *const () as usize),
+                Rc::clone(inst.args()),
+            ),
+            _ => (ImplsKey::Type(ty.clone()), Rc::new(vec![])),
+        };
+        let methods = self
+            .iface_impls
+            .get(&key)
+            .into_iter()
+            .flatten()
+            .flat_map(|impls| {
+                impls
+                    .borrow()
+                    .interfaces
+                    .values()
+                    .flat_map(|imp| imp.methods.values().cloned())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        for method in methods {
+            self.add(Decl::Function(method), Rc::clone(&args), Some(parent));
+        }
+    }
+
+    fn add(&mut self, decl: Decl, args: Rc<TypeArguments>, parent: Option<usize>) {
+        if self.seen.insert((decl.id(), Rc::clone(&args))) {
+            self.instantiations
+                .push(Instantiation { decl, args, parent });
+        }
+    }
+
+    /// If more instantiations than the limit were found.
+    fn exceeds_limit(&self) -> bool {
+        self.limit
+            .map_or(false, |max| self.instantiations.len() > max)
+    }
+
+    /// Returns the chain of instantiations leading to the given one,
+    /// starting at the non-generic function that required it.
+    fn chain(&self, index: usize) -> Vec<&Instantiation> {
+        let mut chain = vec![];
+        let mut next = Some(index);
+        while let Some(index) = next {
+            let inst = &self.instantiations[index];
+            chain.push(inst);
+            next = inst.parent;
+        }
+        chain.reverse();
+        chain
+    }
+}
+
+/// Resolves the type arguments of a function instance in the context of the given arguments.
+/// Instances without arguments of generic functions use the arguments of the context,
+/// like IR generation does.
+fn resolve_args(
+    inst_args: &Rc<TypeArguments>,
+    func: &Function,
+    args: &Rc<TypeArguments>,
+) -> Rc<TypeArguments> {
+    if inst_args.is_empty() && !func.type_parameters.is_empty() {
+        Rc::clone(args)
+    } else {
+        Rc::new(inst_args.iter().map(|arg| arg.resolve(args)).collect())
+    }
+}
+
+/// Returns how deeply the given type is nested.
+fn depth(ty: &Type) -> usize {
+    match ty {
+        Type::Adt(inst) => 1 + inst.args().iter().map(depth).max().unwrap_or(0),
+        Type::Function(inst) => 1 + inst.args().iter().map(depth).max().unwrap_or(0),
+        Type::Closure(closure) => {
+            let ret = depth(&closure.ret_type);
+            1 + closure.parameters.iter().map(depth).fold(ret, usize::max)
+        }
+        Type::Nullable(inner) | Type::Weak(inner) | Type::RawPtr(inner) => 1 + depth(inner),
+        Type::Array(arr) | Type::Vector(arr) => 1 + depth(&arr.ty),
+        _ => 0,
+    }
+}
@@ -26,21 +26,48 @@ pub struct Intrinsics {
     ops: HashMap<SyntaxKind, MutRc<ADT>>,
     /// String type, used for string literals.
     pub string_type: Option<Type>,
+    /// `std/string/ToString`, which can be derived on classes.
+    pub(crate) to_string_iface: Option<MutRc<ADT>>,
     /// `std/iter/Iter` prototype
     pub(crate) iter_proto: Option<MutRc<ADT>>,
     /// `std/iter/ToIter` prototype
     pub(crate) to_iter_proto: Option<MutRc<ADT>>,
     /// The Free interface, used while compiling a class destructor.
     pub(crate) free_iface: Option<MutRc<ADT>>,
+    /// `std/hash/Hash`, which can be derived on classes.
+    pub(crate) hash_iface: Option<MutRc<ADT>>,
+    /// `std/json/Json`, which can be derived on classes.
+    pub(crate) json_iface: Option<MutRc<ADT>>,
+    /// `std/json/JsonReader`, used by the functions reading classes deriving Json.
+    pub(crate) json_reader: Option<MutRc<ADT>>,
+    /// `std/intrinsics/ClosureImpl`, used to pass closures as single-method interfaces.
+    pub(crate) closure_impl: Option<MutRc<ADT>>,
+    /// `std/tuple/TupleN` classes used to represent tuples,
+    /// starting with the one for tuples with 2 elements.
+    pub(crate) tuples: Vec<MutRc<ADT>>,
+    /// `std/prelude/Res`, whose errors can be propagated with `?`.
+    pub(crate) result_adt: Option<MutRc<ADT>>,
     /// libc free.
     pub(crate) libc_free: Option<MutRc<Function>>,
+    /// `std/prelude/panic`, called on runtime errors like division by zero.
+    pub(crate) panic_fn: Option<MutRc<Function>>,
+    /// `std/prelude/likely` and `unlikely`, whose calls are
+    /// replaced with branch hints.
+    pub(crate) likely_fn: Option<MutRc<Function>>,
+    pub(crate) unlikely_fn: Option<MutRc<Function>>,
+    /// `std/io/print` and `println`, which are available
+    /// in every module without importing them.
+    pub(crate) print_fn: Option<MutRc<Function>>,
+    pub(crate) println_fn: Option<MutRc<Function>>,
+    /// `std/thread/spawn`, whose closure argument is checked to be safe to send.
+    pub(crate) thread_spawn: Option<MutRc<Function>>,
     /// The entry point of the program - more than one function
     /// named main is a compile error
     pub(crate) main_fn: Option<MutRc<Function>>,
     /// std/prelude module for auto-import
     pub(crate) std_prelude: Option<MutRc<Module>>,
     /// A list of functions required for compilation.
-    /// Currently main_fn and a few intrinsics.
+    /// Currently main_fn, a few intrinsics and exported functions.
     pub required_compile_fns: Vec<MutRc<Function>>,
 }
 
@@ -60,10 +87,17 @@ impl Intrinsics {
                 "Sub" => self.ops.insert(SyntaxKind::Minus, iface),
                 "Mul" => self.ops.insert(SyntaxKind::Star, iface),
                 "Div" => self.ops.insert(SyntaxKind::Slash, iface),
+                "Rem" => self.ops.insert(SyntaxKind::Percent, iface),
                 "Equal" => {
                     self.ops.insert(SyntaxKind::EqualEqual, Rc::clone(&iface));
                     self.ops.insert(SyntaxKind::BangEqual, iface)
                 }
+                "Ordered" => {
+                    self.ops.insert(SyntaxKind::Less, Rc::clone(&iface));
+                    self.ops.insert(SyntaxKind::LessEqual, Rc::clone(&iface));
+                    self.ops.insert(SyntaxKind::Greater, Rc::clone(&iface));
+                    self.ops.insert(SyntaxKind::GreaterEqual, iface)
+                }
                 "IndexGet" => self.ops.insert(SyntaxKind::LeftBracket, iface),
                 "IndexSet" => self.ops.insert(SyntaxKind::RightBracket, iface),
                 _ => None,
@@ -71,6 +105,26 @@ impl Intrinsics {
         }
     }
 
+    /// Returns the intrinsic function with the given name that is
+    /// available in every module, unless shadowed by a declaration.
+    pub(crate) fn global_fn(&self, name: &str) -> Option<&MutRc<Function>> {
+        match name {
+            "print" => self.print_fn.as_ref(),
+            "println" => self.println_fn.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Returns the class used to represent tuples with the given amount of elements.
+    pub(crate) fn tuple_adt(&self, len: usize) -> Option<&MutRc<ADT>> {
+        self.tuples.get(len.checked_sub(2)?)
+    }
+
+    /// Returns if the given ADT is used to represent tuples.
+    pub(crate) fn is_tuple(&self, adt: &MutRc<ADT>) -> bool {
+        self.tuples.iter().any(|tuple| Rc::ptr_eq(tuple, adt))
+    }
+
     /// Sets the main fn. Returns success, None indicates that
     /// a main function already existed
     pub(crate) fn set_main_fn(&mut self, func: &MutRc<Function>) -> Option<()> {
@@ -4,43 +4,86 @@
 // Often required due to clones; also false positives from type aliases
 #![allow(clippy::ptr_arg)]
 
-use crate::intrinsics::Intrinsics;
-use common::{bench, mutrc_new, ModulePath, MutRc};
+use crate::{intrinsics::Intrinsics, limits::Usage};
+use common::{bench, mutrc_new, ModulePath, MutRc, Profile};
 use gir_nodes::{
     declaration::Visibility,
     expression::ConcreteMethodGet,
     gir_err,
     types::{ToInstance, TypeParameterBound, TypeVariable},
-    Declaration, Expr, Function, IFaceImpls, Instance, Module, Type, ADT,
+    Declaration, Expr, Function, FunctionBody, IFaceImpls, Instance, Module, Type, ADT,
 };
 use result::EmitGIRError;
 use std::{
     collections::{HashMap, HashSet},
     mem,
     rc::Rc,
+    str::FromStr,
 };
 use syntax::kind::SyntaxKind;
 
 use ast::{CSTNode, Get};
-use error::{Error, Errors, GErr, Res};
+use error::{Error, ErrorSpan, Errors, GErr, Res};
 use gir_nodes::{
     declaration::{Field, LocalVariable, Variable},
     types::TypeParameters,
 };
 use smol_str::SmolStr;
 
+mod annotations;
+mod array;
+mod closure_impl;
 mod expr;
+mod instantiation;
 mod intrinsics;
+mod limits;
+mod optimize;
 mod passes;
+mod pure;
 mod resolver;
 mod result;
+mod send;
+mod simd;
+mod suggest;
+mod trace;
 mod types;
 
+pub use limits::Limits;
+pub use trace::Tracer;
+
 /// A struct containing all data produced by GIR compilation.
 pub struct CompiledGIR {
     pub modules: Vec<MutRc<Module>>,
     pub intrinsics: Intrinsics,
     pub iface_impls: HashMap<Type, MutRc<IFaceImpls>>,
+    /// Warnings produced during compilation.
+    pub warnings: Vec<Errors>,
+    /// The flags used during compilation.
+    pub flags: GIRFlags,
+    /// The statistics of every pass, if profiling was enabled.
+    pub profile: Option<Profile>,
+}
+
+impl CompiledGIR {
+    /// Returns all system libraries the program needs to be linked with,
+    /// given with `@link` on extern functions. Sorted and without duplicates.
+    pub fn link_libraries(&self) -> Vec<SmolStr> {
+        let mut libraries = self
+            .modules
+            .iter()
+            .flat_map(|module| {
+                module
+                    .borrow()
+                    .functions
+                    .iter()
+                    .filter_map(|func| func.borrow().link.clone())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        libraries.sort();
+        libraries.dedup();
+        libraries
+    }
 }
 
 /// A struct containing various compiler flags
@@ -56,6 +99,11 @@ pub struct GIRFlags {
     /// If true, a main function will not be required.
     pub library: bool,
 
+    /// If this compilation run produces an executable, as opposed to
+    /// an object file or library linked by another build process.
+    /// Only executables have their functions internalized.
+    pub executable: bool,
+
     /// This compilation run does not use the standard library.
     /// This will disable quite a few features and is quite buggy.
     /// Mainly intended for debugging, WIP.
@@ -63,6 +111,143 @@ pub struct GIRFlags {
 
     /// Do not import the prelude into every module. no_std requires this.
     pub no_prelude: bool,
+
+    /// Emit a warning when a local variable shadows
+    /// a variable of an outer scope.
+    pub warn_shadowing: bool,
+
+    /// Free class instances once they are no longer referenced, which
+    /// `destroy` methods and weak references rely on. Experimental and
+    /// off by default, since refcounts are not yet correct everywhere.
+    pub refcounting: bool,
+
+    /// Use atomic instructions when changing refcounts, allowing
+    /// values to be shared between threads at a slight performance cost.
+    pub atomic_refcounts: bool,
+
+    /// Count the live instances of every class and print the ones
+    /// still alive when `main` returns. Helps finding reference cycles.
+    pub leak_report: bool,
+
+    /// The allocator used for class instances.
+    pub allocator: Allocator,
+
+    /// The optimizations to run on GIR.
+    pub optimizations: Optimizations,
+
+    /// The types of number literals without a type suffix.
+    pub literal_types: LiteralTypes,
+
+    /// Limits on the resources compilation may use.
+    pub limits: Limits,
+}
+
+/// The strategy used to allocate class instances on the heap.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Allocator {
+    /// Use libc malloc and free.
+    Malloc,
+    /// Allocate from large chunks of memory that are never freed.
+    /// Faster than malloc, but only suited for short-lived programs.
+    /// Not thread-safe.
+    Arena,
+}
+
+impl Default for Allocator {
+    fn default() -> Self {
+        Allocator::Malloc
+    }
+}
+
+impl FromStr for Allocator {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "malloc" => Ok(Allocator::Malloc),
+            "arena" => Ok(Allocator::Arena),
+            _ => Err("Unknown allocator, expected 'malloc' or 'arena'."),
+        }
+    }
+}
+
+/// Optimizations performed on GIR, independent of the ones done by LLVM.
+/// They are simple, but help speeding up builds without LLVM optimizations.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Optimizations {
+    /// Loop-invariant code motion, computing pure expressions
+    /// that do not change inside a loop once before it.
+    pub licm: bool,
+    /// Common subexpression elimination, reusing the result of
+    /// pure expressions that were already computed.
+    pub cse: bool,
+    /// Replace calls to methods that only get or set a field
+    /// with the field access itself.
+    pub inline: bool,
+}
+
+impl Optimizations {
+    pub fn all() -> Self {
+        Self {
+            licm: true,
+            cse: true,
+            inline: true,
+        }
+    }
+}
+
+impl FromStr for Optimizations {
+    type Err = &'static str;
+
+    /// Parses either 'all', 'none' or a comma-separated list of optimizations.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => return Ok(Self::all()),
+            "none" => return Ok(Self::default()),
+            _ => (),
+        }
+
+        let mut opts = Self::default();
+        for opt in s.split(',') {
+            match opt.trim() {
+                "licm" => opts.licm = true,
+                "cse" => opts.cse = true,
+                "inline" => opts.inline = true,
+                _ => return Err("Unknown optimization, expected 'licm', 'cse' or 'inline'."),
+            }
+        }
+        Ok(opts)
+    }
+}
+
+/// The types given to number literals without a type suffix when their
+/// context does not require a specific type. The standard library
+/// always uses the default of i64 and f64, as it is written for them.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct LiteralTypes {
+    /// Integer literals are i32 instead of i64.
+    pub int32: bool,
+    /// Float literals are f32 instead of f64.
+    pub float32: bool,
+}
+
+impl FromStr for LiteralTypes {
+    type Err = &'static str;
+
+    /// Parses a comma-separated list of one integer and/or one float type.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut types = Self::default();
+        for ty in s.split(',') {
+            match ty.trim() {
+                "i32" => types.int32 = true,
+                "i64" => types.int32 = false,
+                "f32" => types.float32 = true,
+                "f64" => types.float32 = false,
+                _ => return Err("Unknown literal type, expected 'i32', 'i64', 'f32' or 'f64'."),
+            }
+        }
+        Ok(types)
+    }
 }
 
 type Environment = HashMap<SmolStr, Rc<LocalVariable>>;
@@ -73,17 +258,29 @@ pub struct GIRGenerator {
     position: Option<MutRc<Function>>,
     /// Current impl type, if inside a method
     ty_position: Option<Type>,
+    /// The type `Self` refers to, if inside an interface or impl block
+    self_type: Option<Type>,
 
     /// An environment is a scope that variables live in.
     /// This field is used like a stack.
     /// See the begin_scope and end_scope functions for more info.
     environments: Vec<Environment>,
+    /// Expressions deferred with `defer` for each scope,
+    /// run in reverse order whenever the scope is exited.
+    /// Used like a stack in parallel to [environments].
+    deferred: Vec<Vec<Expr>>,
+    /// Functions declared inside function bodies for each scope.
+    /// Used like a stack in parallel to [environments].
+    local_functions: Vec<HashMap<SmolStr, MutRc<Function>>>,
 
     /// Type parameters of currently compiling declaration
     type_params: Option<Rc<TypeParameters>>,
 
     /// The current loop's type, if in one.
     current_loop_ty: Option<Type>,
+    /// The amount of deferred scopes outside the current loop;
+    /// `break` runs all deferred expressions of scopes after them.
+    loop_deferred_depth: usize,
 
     /// All class members that are not initialized yet.
     /// This is only used when generating constructors to check
@@ -111,9 +308,25 @@ pub struct GIRGenerator {
     intrinsics: Intrinsics,
     /// Interface implementations.
     iface_impls: HashMap<Type, MutRc<IFaceImpls>>,
+    /// The modules declaring a type of each name, in any module.
+    /// Used to suggest imports for unknown types, see `index_types`.
+    type_index: Rc<HashMap<SmolStr, Vec<ModulePath>>>,
 
     /// Errors produced
     errors: MutRc<HashMap<ModulePath, Errors>>,
+    /// Warnings produced
+    warnings: MutRc<HashMap<ModulePath, Errors>>,
+
+    /// Receives all type checking decisions, if tracing is enabled.
+    tracer: Option<Tracer>,
+    /// The expression currently compiling, used as location when tracing.
+    trace_node: Option<CSTNode>,
+    /// Receives the statistics of every pass, if profiling is enabled.
+    profile: Option<Profile>,
+    /// The number of nodes processed by the current pass, see `PassStats::nodes`.
+    nodes_processed: usize,
+    /// The resources used so far, checked against `GIRFlags::limits`.
+    usage: Rc<Usage>,
 
     flags: GIRFlags,
 }
@@ -139,6 +352,9 @@ impl GIRGenerator {
                 modules: self.modules,
                 intrinsics: self.intrinsics,
                 iface_impls: self.iface_impls,
+                warnings: sorted_warnings(self.warnings.take()),
+                flags: self.flags,
+                profile: self.profile,
             })
         } else {
             Err(errs)
@@ -167,10 +383,11 @@ impl GIRGenerator {
     /// Defines a new variable. It is put into the variable list in the current function
     /// and placed in the topmost scope.
     /// This function additionally allows overriding mutability.
+    /// Variables without a CST node are compiler-generated and may be redefined.
     fn define_variable_(&mut self, var: LocalVariable, cst: Option<&CSTNode>) -> Rc<LocalVariable> {
         let def = Rc::new(var);
         self.add_function_variable(Rc::clone(&def));
-        self.insert_variable(&def, true, cst);
+        self.insert_variable(&def, cst.is_none(), cst);
         def
     }
 
@@ -189,6 +406,9 @@ impl GIRGenerator {
     }
 
     /// Inserts a variable into the topmost scope.
+    /// Redefining a variable in the same scope is an error unless `allow_redefine` is set.
+    /// Shadowing a variable of an outer scope is allowed, but warned about
+    /// if `warn_shadowing` is enabled.
     /// Note that the variable does NOT get added to the function!
     fn insert_variable(
         &mut self,
@@ -196,13 +416,42 @@ impl GIRGenerator {
         allow_redefine: bool,
         err: Option<&CSTNode>,
     ) {
+        let shadows = !allow_redefine && self.flags.warn_shadowing && self.is_shadowing(&var.name);
         let cur_env = self.environments.last_mut().unwrap();
         let was_defined = cur_env.insert(var.name.clone(), Rc::clone(&var)).is_some();
+        // Compiler-generated variables have no node to point at
+        let index = err.map_or(ErrorSpan::None, |cst| ErrorSpan::Span(cst.text_range()));
         if was_defined && !allow_redefine {
-            self.err(err.unwrap().clone(), GErr::E208(var.name.clone()));
+            self.error(Error {
+                index,
+                kind: GErr::E208(var.name.clone()),
+            });
+        } else if shadows {
+            let warning = Error {
+                index,
+                kind: GErr::W001(var.name.clone()),
+            };
+            self.push_error(&self.warnings, warning, &self.module.borrow());
         }
     }
 
+    /// If a variable with the given name exists in any scope
+    /// other than the topmost one.
+    fn is_shadowing(&self, name: &SmolStr) -> bool {
+        let outer_env = self
+            .closure_data
+            .as_ref()
+            .map(|c| c.outer_env.iter())
+            .into_iter()
+            .flatten();
+        self.environments
+            .iter()
+            .rev()
+            .skip(1)
+            .chain(outer_env)
+            .any(|env| env.contains_key(name))
+    }
+
     /// Will insert the variable into the current function.
     fn add_function_variable(&mut self, variable: Rc<LocalVariable>) {
         self.cur_fn()
@@ -253,8 +502,15 @@ impl GIRGenerator {
     fn find_var(&mut self, name: &SmolStr, cst: &CSTNode) -> Res<Variable> {
         self.find_local_var(name, cst)
             .map(Variable::Local)
-            .or_else(|| self.find_global_var(name))
-            .or_err(cst, GErr::E206(name.clone()))
+            .or_else(|| self.find_local_function(name))
+            .map(Ok)
+            .unwrap_or_else(|| {
+                self.check_ambiguous(name, cst)?;
+                self.find_global_var(name).or_error(cst, || GErr::E206 {
+                    name: name.clone(),
+                    suggestion: self.suggest_variable(name),
+                })
+            })
     }
 
     /// Searches for a local variable.
@@ -265,33 +521,85 @@ impl GIRGenerator {
             }
         }
 
-        if let Some(closure_data) = &mut self.closure_data {
-            for env in closure_data.outer_env.iter().rev() {
-                if let Some(var) = env.get(name) {
-                    if !var.ty.is_assignable() {
-                        gir_err(cst.clone(), GErr::E205);
-                    }
-                    closure_data.captured.push(Rc::clone(var));
-                    return Some(Rc::clone(var));
+        let closure_data = self.closure_data.as_mut()?;
+        let mut var = closure_data
+            .outer_env
+            .iter()
+            .rev()
+            .find_map(|env| env.get(name))
+            .cloned()?;
+
+        if closure_data.capture == Capture::Nothing {
+            self.err(cst.clone(), GErr::E247(name.clone()));
+            return Some(var);
+        }
+
+        if closure_data.capture == Capture::WeakThis && name == "this" {
+            // Captured as a weak reference, which is stored in the closure's
+            // environment to ensure it is only captured once
+            if !var.ty.is_weak() {
+                if !var.ty.is_ref_adt() {
+                    self.err(cst.clone(), GErr::E333);
                 }
+                var = Rc::new(LocalVariable {
+                    name: name.clone(),
+                    ty: Type::Weak(box var.ty.clone()),
+                    mutable: false,
+                });
             }
+            let closure_data = self.closure_data.as_mut().unwrap();
+            closure_data.captured.push(Rc::clone(&var));
+            self.environments[0].insert(name.clone(), Rc::clone(&var));
+            return Some(var);
         }
-        None
+        closure_data.captured.push(Rc::clone(&var));
+
+        // Captured variables are kept alive by the closure, which weak references cannot do
+        if var.ty.is_weak() {
+            self.err(cst.clone(), GErr::E205);
+        }
+        Some(var)
     }
 
-    fn find_global_var(&self, name: &SmolStr) -> Option<Variable> {
-        let decl = self.module.borrow().find_decl(name)?;
-        match decl {
-            Declaration::Function(func) => Some(Variable::Function(Instance::new_(func))),
-            _ => None,
+    /// Searches all scopes for a function declared inside a function body.
+    fn find_local_function(&self, name: &SmolStr) -> Option<Variable> {
+        self.local_functions
+            .iter()
+            .rev()
+            .find_map(|fns| fns.get(name))
+            .map(|func| Variable::Function(Instance::new_(Rc::clone(func))))
+    }
+
+    /// Errors if the given name refers to different declarations
+    /// imported from multiple modules with `+`.
+    fn check_ambiguous(&self, name: &SmolStr, cst: &CSTNode) -> Res<()> {
+        match self.module.borrow().find_ambiguous(name) {
+            Some((first, second)) => Err(gir_err(
+                cst.clone(),
+                GErr::E105 {
+                    name: name.clone(),
+                    first: first.to_string(),
+                    second: second.to_string(),
+                },
+            )),
+            None => Ok(()),
         }
     }
 
+    fn find_global_var(&self, name: &SmolStr) -> Option<Variable> {
+        let func = match self.module.borrow().find_decl(name) {
+            Some(Declaration::Function(func)) => func,
+            Some(_) => return None,
+            None => Rc::clone(self.intrinsics.global_fn(name)?),
+        };
+        Some(Variable::Function(Instance::new_(func)))
+    }
+
     /// Returns the variable of the current loop or creates it if it does not exist yet.
     /// This variable stores the value of the last loop iteration.
     fn set_loop_type(&mut self, type_: &Type, err: &CSTNode) {
         match &self.current_loop_ty {
-            Some(ty) if !ty.equal(type_, false) => self.err(
+            Some(ty) if !ty.is_error() && !type_.is_error() && !ty.equal(type_, false) => self.err(
                 err.clone(),
                 GErr::E209 {
                     expected: ty.to_string(),
@@ -343,15 +651,27 @@ impl GIRGenerator {
 
     fn get_field_(&mut self, ty: &Type, get: &Get) -> Res<FieldOrMethod> {
         if let Some(adt) = ty.try_adt() {
+            let name = get.property().name();
+            let is_tuple = self.intrinsics.is_tuple(&adt.ty);
             let adt = adt.ty.borrow();
-            let field = adt.fields.get(&get.property().name());
+            let field = match name.parse::<usize>() {
+                // Tuple elements are accessed by index, like `tuple.0`
+                Ok(index) if is_tuple => adt.fields.get_index(index).map(|(_, field)| field),
+                _ => adt.fields.get(&name),
+            };
             if let Some(field) = field {
                 return Ok(FieldOrMethod::Field(Rc::clone(field)));
             }
         }
 
-        self.find_associated_method(&ty, &get.property().name())
-            .or_err(&get.cst, GErr::E210)
+        let name = get.property().name();
+        match self.find_associated_method(&ty, &name) {
+            Some(method) => Ok(method),
+            None => Err(gir_err(
+                get.cst(),
+                GErr::E210(self.suggest_member(ty, &name)),
+            )),
+        }
     }
 
     /// Searches for an associated method on a type. Can be either an interface
@@ -380,15 +700,32 @@ impl GIRGenerator {
 
             _ => None,
         };
+        if let Some(method) = method {
+            let kind = match &method {
+                FieldOrMethod::VirtMethod(_) => "interface method of type parameter",
+                _ => "method",
+            };
+            self.trace(format_args!("resolved {}.{} to {}", ty, name, kind));
+            return Some(method);
+        }
 
-        method.or_else(|| {
-            self.get_iface_impls(ty)
-                .borrow()
-                .methods
-                .get(name)
-                .cloned()
-                .map(FieldOrMethod::Method)
-        })
+        let impls = self.get_iface_impls(ty);
+        let impls = impls.borrow();
+        let method = impls.methods.get(name).cloned();
+        if let (Some(method), true) = (&method, self.tracing()) {
+            let iface = impls
+                .interfaces
+                .iter()
+                .find(|(_, im)| im.methods.values().any(|m| Rc::ptr_eq(m, method)))
+                .map(|(iface, _)| iface);
+            if let Some(iface) = iface {
+                self.trace(format_args!(
+                    "resolved {}.{} to implementation of {}",
+                    ty, name, iface
+                ));
+            }
+        }
+        method.map(FieldOrMethod::Method)
     }
 
     /// Creates a new scope. A new scope is created for every function and block,
@@ -404,18 +741,20 @@ impl GIRGenerator {
     /// }                   <- scope #2 gets removed, along with a
     fn begin_scope(&mut self) {
         self.environments.push(HashMap::new());
+        self.deferred.push(vec![]);
+        self.local_functions.push(HashMap::new());
     }
 
     /// Removes the topmost scope.
     fn end_scope(&mut self) {
         self.environments.pop();
+        self.deferred.pop();
+        self.local_functions.pop();
     }
 
     /// Inserts the given expression at the current insertion pointer.
     fn insert_at_ptr(&mut self, expr: Expr) {
-        let func = self.cur_fn();
-        let mut func = func.borrow_mut();
-        func.exprs.push(expr)
+        self.cur_fn().push_expr(expr)
     }
 
     /// Sets the insertion pointer.
@@ -442,7 +781,16 @@ impl GIRGenerator {
     }
 
     fn error_(&self, error: Error, module: &Module) {
-        let mut errs = self.errors.borrow_mut();
+        self.push_error(&self.errors, error, module)
+    }
+
+    /// Create new warning and add it to the list of warnings.
+    fn warn(&self, cst: CSTNode, warning: GErr) {
+        self.push_error(&self.warnings, gir_err(cst, warning), &self.module.borrow())
+    }
+
+    fn push_error(&self, list: &MutRc<HashMap<ModulePath, Errors>>, error: Error, module: &Module) {
+        let mut errs = list.borrow_mut();
         if let Some(errs) = errs.get_mut(&self.path) {
             errs.errors.push(error);
         } else {
@@ -463,6 +811,8 @@ impl GIRGenerator {
         self.path = Rc::clone(&self.module.borrow().path);
         self.type_params = None;
         self.environments.clear();
+        self.deferred.clear();
+        self.local_functions.clear();
         self.current_loop_ty = None;
         self.position = None;
         self.uninitialized_this_fields.clear();
@@ -501,19 +851,47 @@ impl GIRGenerator {
         })
     }
 
-    /// Produces a [GIRGenerator] usable for generating a closure literal,
-    /// temporarily making the outer generator unusable.
+    /// Enables profiling, recording the statistics of every pass in [CompiledGIR::profile].
+    /// This also enables counting allocations, see [common::CountingAllocator].
+    pub fn with_profiling(mut self) -> Self {
+        common::count_allocations(true);
+        self.profile = Some(Profile::default());
+        self
+    }
+
+    /// Enables tracing of type checking decisions, writing them to the given tracer.
+    pub fn with_tracer(mut self, tracer: Tracer) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    /// Produces a [GIRGenerator] usable for generating a closure literal
+    /// or nested function, temporarily making the outer generator unusable.
     /// It takes the outer environments to allow for capturing variables,
     /// and also records some other required closure data.
     /// This data is then retried with `self.end_closure`.
-    fn for_closure(outer: &mut GIRGenerator) -> Self {
+    fn for_closure(outer: &mut GIRGenerator, capture: Capture) -> Self {
         let modules = mem::replace(&mut outer.modules, vec![]);
         let modules_uncompiled = mem::replace(&mut outer.modules_uncompiled, vec![]);
         GIRGenerator {
             closure_data: Some(ClosureData {
                 outer_env: mem::replace(&mut outer.environments, vec![]),
                 captured: Vec::with_capacity(3),
+                capture,
             }),
+            // Closures inside methods may access private members of `this`
+            ty_position: outer.ty_position.clone(),
+            self_type: outer.self_type.clone(),
+            local_functions: outer.local_functions.clone(),
+            module: Rc::clone(&outer.module),
+            path: Rc::clone(&outer.path),
+            intrinsics: mem::take(&mut outer.intrinsics),
+            iface_impls: mem::take(&mut outer.iface_impls),
+            type_index: Rc::clone(&outer.type_index),
+            errors: Rc::clone(&outer.errors),
+            warnings: Rc::clone(&outer.warnings),
+            tracer: outer.tracer.clone(),
+            usage: Rc::clone(&outer.usage),
             ..Self::from_modules_(modules, modules_uncompiled, outer.flags)
         }
     }
@@ -525,6 +903,8 @@ impl GIRGenerator {
         outer.environments = mem::replace(&mut closure_data.outer_env, vec![]);
         outer.modules = self.modules;
         outer.modules_uncompiled = self.modules_uncompiled;
+        outer.intrinsics = self.intrinsics;
+        outer.iface_impls = self.iface_impls;
         closure_data
     }
 
@@ -548,18 +928,29 @@ impl GIRGenerator {
         Self {
             position: None,
             ty_position: None,
+            self_type: None,
             path,
             module: Rc::clone(&uncompiled[0]),
             modules,
             modules_uncompiled: uncompiled,
             intrinsics: Intrinsics::default(),
             iface_impls: HashMap::with_capacity(100),
+            type_index: Rc::default(),
             environments: vec![HashMap::with_capacity(3)],
+            deferred: vec![],
+            local_functions: vec![HashMap::new()],
             type_params: None,
             current_loop_ty: None,
+            loop_deferred_depth: 0,
             uninitialized_this_fields: HashSet::with_capacity(5),
             closure_data: None,
             errors: mutrc_new(HashMap::new()),
+            warnings: mutrc_new(HashMap::new()),
+            tracer: None,
+            trace_node: None,
+            profile: None,
+            nodes_processed: 0,
+            usage: Rc::default(),
             flags,
         }
     }
@@ -574,18 +965,29 @@ impl GIRGenerator {
         Self {
             position: None,
             ty_position: None,
+            self_type: None,
             path,
             module: Rc::clone(&modules[0]),
             modules,
             modules_uncompiled,
             intrinsics: Intrinsics::default(),
             iface_impls: HashMap::with_capacity(100),
+            type_index: Rc::default(),
             environments: vec![HashMap::with_capacity(3)],
+            deferred: vec![],
+            local_functions: vec![HashMap::new()],
             type_params: None,
             current_loop_ty: None,
+            loop_deferred_depth: 0,
             uninitialized_this_fields: HashSet::with_capacity(5),
             closure_data: None,
             errors: mutrc_new(HashMap::new()),
+            warnings: mutrc_new(HashMap::new()),
+            tracer: None,
+            trace_node: None,
+            profile: None,
+            nodes_processed: 0,
+            usage: Rc::default(),
             flags,
         }
     }
@@ -614,6 +1016,35 @@ struct ClosureData {
     /// All variables inside the outer environments that are used
     /// inside the closure and therefore 'captured'
     pub captured: Vec<Rc<LocalVariable>>,
+    /// How variables of the outer environments are captured.
+    pub capture: Capture,
+}
+
+/// Orders warnings by module and position inside it, since
+/// they are collected in a map without a stable order.
+fn sorted_warnings(warnings: HashMap<ModulePath, Errors>) -> Vec<Errors> {
+    let mut warnings = warnings.into_iter().map(|(_, v)| v).collect::<Vec<_>>();
+    warnings.sort_by(|a, b| a.origin.cmp(&b.origin));
+    for module in &mut warnings {
+        // GIR warnings only ever point at spans, or nowhere at all
+        module.errors.sort_by_key(|warning| match &warning.index {
+            ErrorSpan::Span(span) => span.start,
+            _ => 0,
+        });
+    }
+    warnings
+}
+
+/// The ways a closure can capture variables.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Capture {
+    /// All variables are captured strongly.
+    All,
+    /// Like `All`, but `this` is captured as a weak reference;
+    /// requested with the `@weak_this` annotation.
+    WeakThis,
+    /// Nothing may be captured, used for nested functions.
+    Nothing,
 }
 
 #[derive(Debug)]
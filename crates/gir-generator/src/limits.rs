@@ -0,0 +1,99 @@
+use std::{cell::Cell, rc::Rc, time::Duration};
+
+use common::ModPath;
+use error::{Error, ErrorSpan, Errors, GErr};
+
+use crate::GIRGenerator;
+
+/// Limits on the resources a compilation may use, meant for embedding
+/// the compiler in services that compile untrusted programs.
+/// Exceeding any of them aborts compilation with E357 instead of
+/// letting it run out of memory or hang. All are unlimited by default.
+/// They only bound GIR generation; IR generation and LLVM are not limited.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Limits {
+    /// The most modules that may be compiled, not counting the standard library.
+    pub max_modules: Option<usize>,
+    /// The most instances of generic declarations the program may require.
+    pub max_instantiations: Option<usize>,
+    /// The most expressions that may be generated, including
+    /// the ones of the standard library if it is not cached.
+    pub max_expressions: Option<usize>,
+    /// The longest the generator may run for.
+    /// Only checked between passes, so it may overrun by the length of one.
+    pub timeout: Option<Duration>,
+}
+
+/// The resources used so far, shared with the generators of closures.
+#[derive(Default)]
+pub(crate) struct Usage {
+    /// The amount of expressions generated.
+    expressions: Cell<usize>,
+    /// If a limit was exceeded, which aborts all remaining passes.
+    exceeded: Cell<bool>,
+}
+
+impl GIRGenerator {
+    /// Counts a generated expression, returning false
+    /// if this exceeded the limit, which is reported once.
+    pub(crate) fn count_expression(&self) -> bool {
+        let count = self.usage.expressions.get() + 1;
+        self.usage.expressions.set(count);
+        match self.flags.limits.max_expressions {
+            Some(max) if count > max => {
+                if count == max + 1 {
+                    self.limit_error("expressions", max.to_string());
+                }
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Checks the limits on modules and compile time, returning
+    /// false if compilation should be aborted due to any exceeded limit.
+    pub(crate) fn check_limits(&self, elapsed: Duration) -> bool {
+        let limits = self.flags.limits;
+        if let Some(max) = limits.max_modules {
+            let modules = self
+                .modules_uncompiled
+                .iter()
+                .filter(|module| !module.borrow().path.is(&["std"]))
+                .count();
+            if modules > max {
+                self.limit_error("modules", max.to_string());
+            }
+        }
+        if let Some(timeout) = limits.timeout {
+            if elapsed > timeout {
+                self.limit_error("compile time", format!("{}ms", timeout.as_millis()));
+            }
+        }
+        !self.limit_exceeded()
+    }
+
+    /// If a limit was exceeded, after which compilation is aborted.
+    pub(crate) fn limit_exceeded(&self) -> bool {
+        self.usage.exceeded.get()
+    }
+
+    /// Reports a limit being exceeded. The error has no location,
+    /// as the program as a whole is too large.
+    pub(crate) fn limit_error(&self, resource: &'static str, limit: String) {
+        self.usage.exceeded.set(true);
+        let error = Error {
+            index: ErrorSpan::None,
+            kind: GErr::E357 { resource, limit },
+        };
+        self.errors
+            .borrow_mut()
+            .entry(Rc::new(ModPath::new()))
+            .or_insert_with(|| Errors {
+                errors: vec![],
+                src: None,
+                origin: "Compiler".to_string(),
+            })
+            .errors
+            .push(error);
+    }
+}
@@ -0,0 +1,567 @@
+use std::{
+    collections::{HashMap, HashSet},
+    mem,
+    rc::Rc,
+};
+
+use common::MutRc;
+use gir_nodes::{
+    declaration::{Field, InlineHint, LocalVariable, Variable},
+    expression::CastType,
+    Expr, Function, FunctionBody, Literal, Type,
+};
+use smol_str::SmolStr;
+use syntax::kind::SyntaxKind;
+
+use crate::{GIRGenerator, Optimizations};
+
+type VarSet = HashSet<*const LocalVariable>;
+
+/// A small optimizer running on the GIR of functions after they were generated.
+/// Other than inlining accessors, it only touches pure expressions, which are
+/// arithmetic, comparisons and casts on numbers and local variables containing them,
+/// as well as calls to functions marked `@pure` with such arguments.
+/// LLVM does all of this (and much more) on its own; these optimizations
+/// exist to make code compiled without LLVM optimizations bearable.
+impl GIRGenerator {
+    /// Runs the optimizations enabled in the compiler flags on the given function.
+    pub(crate) fn optimize_function(&self, function: &MutRc<Function>) {
+        let opts = self.flags.optimizations;
+        if opts == Optimizations::default() {
+            return;
+        }
+
+        let mut func = function.borrow_mut();
+        let mut exprs = mem::take(&mut func.exprs);
+        let mut optimizer = Optimizer::new(&mut *func, &exprs);
+        if opts.licm {
+            for expr in exprs.iter_mut() {
+                optimizer.licm(expr);
+            }
+        }
+        if opts.cse {
+            optimizer.cse(&mut exprs);
+        }
+        func.exprs = exprs;
+    }
+
+    /// Replaces calls to trivial accessor methods, which only get or set
+    /// a field of `this`, with the field access itself.
+    /// Runs once all functions are generated, as it requires the body of the callee.
+    pub(crate) fn inline_accessors(&self) {
+        if !self.flags.optimizations.inline {
+            return;
+        }
+
+        for module in &self.modules_uncompiled {
+            let functions = module.borrow().functions.clone();
+            for function in functions {
+                let mut exprs = mem::take(&mut function.borrow_mut().exprs);
+                for expr in exprs.iter_mut() {
+                    inline_accessor_calls(expr);
+                }
+                function.set_exprs(exprs);
+            }
+        }
+    }
+}
+
+struct Optimizer<'f> {
+    /// The function optimized, used for inserting temporary variables.
+    function: &'f mut Function,
+    /// All local variables of the function that can only change by
+    /// assigning to them in the function itself.
+    /// Variables captured by closures are not included.
+    locals: VarSet,
+}
+
+impl<'f> Optimizer<'f> {
+    fn new(function: &'f mut Function, exprs: &[Expr]) -> Self {
+        let mut locals = function
+            .variables
+            .values()
+            .chain(function.parameters.iter())
+            .map(Rc::as_ptr)
+            .collect();
+        for expr in exprs {
+            remove_captured(expr, &mut locals);
+        }
+        Self { function, locals }
+    }
+
+    /// Loop-invariant code motion: Pure expressions inside a loop that only use
+    /// variables not assigned to in the loop are computed once before the loop.
+    ///
+    /// `for (i < len * 2) ...` into `{ val tmp = len * 2; for (i < tmp) ... }`
+    fn licm(&mut self, expr: &mut Expr) {
+        let mut hoisted = Vec::new();
+        if let Expr::Loop {
+            condition, body, ..
+        } = expr
+        {
+            let mut written = VarSet::new();
+            assignments(condition, &mut written);
+            assignments(body, &mut written);
+            self.hoist(condition, &written, &mut hoisted);
+            self.hoist(body, &written, &mut hoisted);
+        }
+
+        // Outer loops are done first, so that inner loops
+        // only hoist what varies in the outer loop
+        expr.for_each_child_mut(&mut |child| self.licm(child));
+
+        if !hoisted.is_empty() {
+            let mut block = hoisted
+                .into_iter()
+                .map(|(value, var)| Expr::store(Expr::lvar(&var), value, true))
+                .collect::<Vec<_>>();
+            block.push(mem::replace(expr, Expr::none_const()));
+            *expr = Expr::Block(block);
+        }
+    }
+
+    /// Replaces all invariant expressions with a temporary variable,
+    /// adding them to the hoisted list.
+    fn hoist(
+        &mut self,
+        expr: &mut Expr,
+        written: &VarSet,
+        hoisted: &mut Vec<(Expr, Rc<LocalVariable>)>,
+    ) {
+        if self.candidate(expr) && speculatable(expr) && !reads_any(expr, written) {
+            let var = match hoisted.iter().find(|(value, _)| same(value, expr)) {
+                Some((_, var)) => Rc::clone(var),
+                None => {
+                    let var = self.temp(expr.get_type(), "licm");
+                    hoisted.push((expr.clone(), Rc::clone(&var)));
+                    var
+                }
+            };
+            *expr = Expr::lvar(&var);
+        } else {
+            expr.for_each_child_mut(&mut |child| self.hoist(child, written, hoisted));
+        }
+    }
+
+    /// Common subexpression elimination: Pure expressions that were already computed
+    /// before, with none of their variables changing since, reuse the previous result.
+    ///
+    /// This is done in 2 walks over the function: The first finds repeated expressions,
+    /// the second stores the first occurrence of each in a temporary variable
+    /// and replaces the others with it. Both walks number all candidate
+    /// expressions in evaluation order to match them up.
+    fn cse(&mut self, exprs: &mut [Expr]) {
+        let mut scan = CseScan::default();
+        for expr in exprs.iter() {
+            self.cse_scan(expr, &mut scan);
+        }
+        if scan.repeated.is_empty() {
+            return;
+        }
+
+        let first = scan.repeated.values().copied().collect::<HashSet<_>>();
+        let mut rewrite = CseRewrite {
+            count: 0,
+            repeated: scan.repeated,
+            first,
+            temps: HashMap::new(),
+        };
+        for expr in exprs.iter_mut() {
+            self.cse_rewrite(expr, &mut rewrite);
+        }
+    }
+
+    fn cse_scan<'e>(&self, expr: &'e Expr, scan: &mut CseScan<'e>) {
+        if self.candidate(expr) {
+            let index = scan.count;
+            scan.count += 1;
+            if let Some((first, _)) = scan.available.iter().find(|(_, prev)| same(prev, expr)) {
+                scan.repeated.insert(index, *first);
+                return;
+            }
+            scan.available.push((index, expr));
+        }
+
+        match expr {
+            Expr::If {
+                condition,
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                self.cse_scan(condition, scan);
+                self.cse_branch(then_branch, scan);
+                self.cse_branch(else_branch, scan);
+            }
+
+            Expr::Switch { .. } => expr.for_each_child(&mut |child| self.cse_branch(child, scan)),
+
+            Expr::Loop { .. } => {
+                // Anything changing in the loop is not available in the next iteration
+                let mut written = VarSet::new();
+                assignments(expr, &mut written);
+                scan.invalidate(&written);
+                expr.for_each_child(&mut |child| self.cse_branch(child, scan))
+            }
+
+            Expr::Store {
+                location, value, ..
+            } => {
+                self.cse_scan(location, scan);
+                self.cse_scan(value, scan);
+                let mut written = VarSet::new();
+                assigned_variables(location, &mut written);
+                scan.invalidate(&written);
+            }
+
+            _ => expr.for_each_child(&mut |child| self.cse_scan(child, scan)),
+        }
+    }
+
+    /// Scans an expression that is only evaluated conditionally.
+    /// Expressions in it are not available after it.
+    fn cse_branch<'e>(&self, expr: &'e Expr, scan: &mut CseScan<'e>) {
+        let available = scan.available.clone();
+        self.cse_scan(expr, scan);
+        scan.available = available;
+
+        let mut written = VarSet::new();
+        assignments(expr, &mut written);
+        scan.invalidate(&written);
+    }
+
+    fn cse_rewrite(&mut self, expr: &mut Expr, rewrite: &mut CseRewrite) {
+        let index = if self.candidate(expr) {
+            rewrite.count += 1;
+            Some(rewrite.count - 1)
+        } else {
+            None
+        };
+        if let Some(first) = index.and_then(|index| rewrite.repeated.get(&index)) {
+            *expr = Expr::lvar(&rewrite.temps[first]);
+            return;
+        }
+
+        expr.for_each_child_mut(&mut |child| self.cse_rewrite(child, rewrite));
+
+        if let Some(index) = index.filter(|index| rewrite.first.contains(index)) {
+            let var = self.temp(expr.get_type(), "cse");
+            let value = mem::replace(expr, Expr::none_const());
+            *expr = Expr::store(Expr::lvar(&var), value, true);
+            rewrite.temps.insert(index, var);
+        }
+    }
+
+    /// Returns if the expression is worth storing in a temporary variable
+    /// to avoid computing it multiple times, which is true for all
+    /// pure expressions that are not just a literal or variable.
+    fn candidate(&self, expr: &Expr) -> bool {
+        matches!(
+            expr,
+            Expr::Binary { .. } | Expr::Unary { .. } | Expr::Cast { .. } | Expr::Call { .. }
+        ) && self.pure(expr)
+    }
+
+    /// Returns if the expression has no side effects and always produces
+    /// the same number when none of its variables changed.
+    fn pure(&self, expr: &Expr) -> bool {
+        let pure = match expr {
+            Expr::Literal(_) => true,
+            Expr::Variable(Variable::Local(var)) => self.locals.contains(&Rc::as_ptr(var)),
+            Expr::Binary {
+                left,
+                operator,
+                right,
+            } => *operator != SyntaxKind::Is && self.pure(left) && self.pure(right),
+            Expr::Unary { right, .. } => self.pure(right),
+            Expr::Cast {
+                inner,
+                method: CastType::Number,
+                ..
+            } => self.pure(inner),
+            Expr::Call {
+                callee: box Expr::Variable(Variable::Function(func)),
+                arguments,
+            } => {
+                // The function being optimized is borrowed already,
+                // recursive calls are therefore never deduplicated
+                func.ty.try_borrow().map_or(false, |func| func.pure)
+                    && arguments.iter().all(|arg| self.pure(arg))
+            }
+            _ => false,
+        };
+        let ty = expr.get_type();
+        pure && ty.is_number() && !ty.is_variable()
+    }
+
+    fn temp(&mut self, ty: Type, name: &str) -> Rc<LocalVariable> {
+        let var = Rc::new(LocalVariable {
+            name: SmolStr::new_inline(name),
+            ty,
+            mutable: false,
+        });
+        self.function.insert_var(var.name.clone(), Rc::clone(&var));
+        self.locals.insert(Rc::as_ptr(&var));
+        var
+    }
+}
+
+#[derive(Default)]
+struct CseScan<'e> {
+    /// The amount of candidate expressions seen.
+    count: usize,
+    /// All expressions whose value is available at the current position.
+    available: Vec<(usize, &'e Expr)>,
+    /// Maps repeated occurrences to the number of their first occurrence.
+    repeated: HashMap<usize, usize>,
+}
+
+impl CseScan<'_> {
+    fn invalidate(&mut self, written: &VarSet) {
+        if !written.is_empty() {
+            self.available.retain(|(_, expr)| !reads_any(expr, written));
+        }
+    }
+}
+
+struct CseRewrite {
+    count: usize,
+    repeated: HashMap<usize, usize>,
+    /// All first occurrences that are repeated later.
+    first: HashSet<usize>,
+    /// Temporary variables of first occurrences.
+    temps: HashMap<usize, Rc<LocalVariable>>,
+}
+
+fn inline_accessor_calls(expr: &mut Expr) {
+    expr.for_each_child_mut(&mut inline_accessor_calls);
+
+    if let Expr::Call { callee, arguments } = expr {
+        let field = match &**callee {
+            Expr::Variable(Variable::Function(func)) => accessor(&func.ty.borrow()),
+            _ => None,
+        };
+        let field = match field {
+            Some(field) => field,
+            None => return,
+        };
+
+        let mut arguments = mem::take(arguments).into_iter();
+        let object = arguments.next().unwrap();
+        *expr = match arguments.next() {
+            Some(value) => Expr::Block(vec![
+                Expr::store(Expr::load(object, &field), value, false),
+                Expr::none_const(),
+            ]),
+            None => Expr::load(object, &field),
+        };
+    }
+}
+
+/// Returns the field of a method that only returns a field of `this`,
+/// like `func x() -> i64 = this.x`, or only sets one to its parameter,
+/// like `func set_x(x: i64) = this.x = x`.
+/// Setters are only inlined on reference types, since value types
+/// only set the field on their copy of `this`.
+fn accessor(function: &Function) -> Option<Rc<Field>> {
+    if function.inline == InlineHint::Never || function.exprs.len() != 1 {
+        return None;
+    }
+
+    match (&function.exprs[0], &function.parameters[..]) {
+        (Expr::Return(value), [this]) => match single(value) {
+            Expr::Load { object, field } if is_var(object, this) => Some(Rc::clone(field)),
+            _ => None,
+        },
+
+        (body, [this, param]) if function.ret_type == Type::None && this.ty.is_ref_adt() => {
+            match single(body) {
+                Expr::Store {
+                    location: box Expr::Load { object, field },
+                    value,
+                    ..
+                } if is_var(object, this) && is_var(value, param) => Some(Rc::clone(field)),
+                _ => None,
+            }
+        }
+
+        _ => None,
+    }
+}
+
+/// Returns the only expression inside of blocks containing a single expression.
+fn single(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Block(exprs) if exprs.len() == 1 => single(&exprs[0]),
+        _ => expr,
+    }
+}
+
+fn is_var(expr: &Expr, var: &Rc<LocalVariable>) -> bool {
+    matches!(expr, Expr::Variable(Variable::Local(local)) if Rc::ptr_eq(local, var))
+}
+
+/// Returns if evaluating the expression can never trap, making it safe
+/// to evaluate even when it otherwise would not have been.
+/// Integer division by zero traps, and calls might panic or never return.
+fn speculatable(expr: &Expr) -> bool {
+    let traps = matches!(
+        expr,
+        Expr::Binary {
+            operator: SyntaxKind::Slash | SyntaxKind::Percent,
+            right,
+            ..
+        } if right.get_type().is_int()
+    ) || matches!(expr, Expr::Call { .. });
+    let mut children = true;
+    expr.for_each_child(&mut |child| children &= speculatable(child));
+    !traps && children
+}
+
+/// Returns if both expressions compute the same value.
+/// Only supports the expressions that can be pure.
+fn same(left: &Expr, right: &Expr) -> bool {
+    match (left, right) {
+        (Expr::Literal(left), Expr::Literal(right)) => {
+            left.get_type() == right.get_type()
+                && literal_bits(left).is_some()
+                && literal_bits(left) == literal_bits(right)
+        }
+
+        (Expr::Variable(Variable::Local(left)), Expr::Variable(Variable::Local(right))) => {
+            Rc::ptr_eq(left, right)
+        }
+
+        (
+            Expr::Binary {
+                left: l_left,
+                operator: l_op,
+                right: l_right,
+            },
+            Expr::Binary {
+                left: r_left,
+                operator: r_op,
+                right: r_right,
+            },
+        ) => l_op == r_op && same(l_left, r_left) && same(l_right, r_right),
+
+        (
+            Expr::Unary {
+                operator: l_op,
+                right: l_right,
+            },
+            Expr::Unary {
+                operator: r_op,
+                right: r_right,
+            },
+        ) => l_op == r_op && same(l_right, r_right),
+
+        (
+            Expr::Cast {
+                inner: l_inner,
+                to: l_to,
+                ..
+            },
+            Expr::Cast {
+                inner: r_inner,
+                to: r_to,
+                ..
+            },
+        ) => l_to == r_to && same(l_inner, r_inner),
+
+        (
+            Expr::Call {
+                callee: box Expr::Variable(Variable::Function(l_func)),
+                arguments: l_args,
+            },
+            Expr::Call {
+                callee: box Expr::Variable(Variable::Function(r_func)),
+                arguments: r_args,
+            },
+        ) => {
+            l_func == r_func
+                && l_args.len() == r_args.len()
+                && l_args.iter().zip(r_args).all(|(l, r)| same(l, r))
+        }
+
+        _ => false,
+    }
+}
+
+/// Returns the bits of a number literal.
+fn literal_bits(literal: &Literal) -> Option<u64> {
+    Some(match literal {
+        Literal::Bool(b) => *b as u64,
+        Literal::I64(num) | Literal::U64(num) | Literal::Int { value: num, .. } => *num,
+        Literal::I8(num) | Literal::U8(num) => *num as u64,
+        Literal::I16(num) | Literal::U16(num) => *num as u64,
+        Literal::I32(num) | Literal::U32(num) => *num as u64,
+        Literal::F32(num) => num.to_bits() as u64,
+        Literal::F64(num) => num.to_bits(),
+        _ => return None,
+    })
+}
+
+/// Returns if the expression reads any of the given variables.
+fn reads_any(expr: &Expr, vars: &VarSet) -> bool {
+    match expr {
+        Expr::Variable(Variable::Local(var)) => vars.contains(&Rc::as_ptr(var)),
+        _ => {
+            let mut reads = false;
+            expr.for_each_child(&mut |child| reads |= reads_any(child, vars));
+            reads
+        }
+    }
+}
+
+/// Collects all local variables assigned to anywhere in the expression.
+fn assignments(expr: &Expr, out: &mut VarSet) {
+    if let Expr::Store { location, .. } = expr {
+        assigned_variables(location, out);
+    }
+    expr.for_each_child(&mut |child| assignments(child, out));
+}
+
+/// Collects the local variables changed by storing into the given location.
+fn assigned_variables(location: &Expr, out: &mut VarSet) {
+    match location {
+        Expr::Variable(Variable::Local(var)) => {
+            out.insert(Rc::as_ptr(var));
+        }
+        Expr::Index { object, .. } => assigned_variables(object, out),
+        Expr::Block(exprs) => {
+            if let Some(last) = exprs.last() {
+                assigned_variables(last, out)
+            }
+        }
+        Expr::If {
+            then_branch,
+            else_branch,
+            ..
+        } => {
+            assigned_variables(then_branch, out);
+            assigned_variables(else_branch, out);
+        }
+        Expr::Switch {
+            branches,
+            else_branch,
+            ..
+        } => {
+            for (_, branch) in branches {
+                assigned_variables(branch, out);
+            }
+            assigned_variables(else_branch, out);
+        }
+        _ => (),
+    }
+}
+
+/// Removes all variables captured by closures in the expression.
+fn remove_captured(expr: &Expr, locals: &mut VarSet) {
+    if let Expr::Closure { captured, .. } = expr {
+        for var in captured.iter() {
+            locals.remove(&Rc::as_ptr(var));
+        }
+    }
+    expr.for_each_child(&mut |child| remove_captured(child, locals));
+}
@@ -1,11 +1,17 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{hash_map::Entry, HashMap, HashSet},
+    rc::Rc,
+};
 
-use crate::{eat, eatc, result::EmitGIRError, GIRGenerator};
+use crate::{annotations::AnnotationTarget, eat, eatc, result::EmitGIRError, GIRGenerator};
 use ast::CSTNode;
-use common::{mutrc_new, MutRc};
-use error::{GErr, Res};
+use common::{mutrc_new, ModulePath, MutRc};
+use error::{Error, ErrorSpan, GErr, Res};
 use gir_nodes::{
-    declaration::{ADTType, CaseType, IRAdt, IRFunction, LocalVariable, Visibility},
+    declaration::{
+        ADTType, Annotations, CaseType, IRAdt, IRFunction, InlineHint, LocalVariable, Visibility,
+    },
     gir_err,
     types::{TypeKind, TypeParameter, TypeParameterBound, TypeParameters},
     Declaration, Function, IFaceImpl, Type, ADT,
@@ -24,6 +30,33 @@ impl GIRGenerator {
         }
     }
 
+    /// Indexes the types of all modules by name, including already compiled ones.
+    /// Enum cases and private types are left out, as they cannot be imported.
+    pub(super) fn index_types(&mut self) {
+        let mut index = HashMap::<SmolStr, Vec<ModulePath>>::new();
+        for module in &self.modules {
+            let module = module.borrow();
+            for (name, decl) in &module.declarations {
+                match decl {
+                    Declaration::Adt(adt)
+                        if adt.borrow().visibility != Visibility::Private
+                            && !adt.borrow().ty.is_enum_case() =>
+                    {
+                        index
+                            .entry(name.clone())
+                            .or_default()
+                            .push(Rc::clone(&module.path))
+                    }
+                    _ => (),
+                }
+            }
+        }
+        for modules in index.values_mut() {
+            modules.sort_by_key(|path| path.to_string());
+        }
+        self.type_index = Rc::new(index);
+    }
+
     fn adt_from_ast(&mut self, ast: ast::Adt, parent: Option<MutRc<ADT>>) -> Res<MutRc<ADT>> {
         let name = ast.name();
         let mut adt_name = name.name();
@@ -32,6 +65,7 @@ impl GIRGenerator {
         } else {
             TypeKind::Reference
         };
+        let annotations = self.annotations(ast.annotations(), AnnotationTarget::Adt);
 
         let ty = match ast.kind() {
             SyntaxKind::Class => ADTType::Class {
@@ -46,6 +80,7 @@ impl GIRGenerator {
 
             SyntaxKind::Enum => ADTType::Enum {
                 cases: Rc::new(HashMap::new()),
+                flags: annotations.has("flags"),
             },
 
             // Enum cases can have multiple starting tokens, just use the catch-all
@@ -66,11 +101,19 @@ impl GIRGenerator {
                         } else {
                             return Err(gir_err(ast.name().cst, GErr::E320));
                         }
-                    } else if ast.cst.children().count() == 1 {
+                    } else if ast
+                        .cst
+                        .children()
+                        .filter(|c| c.kind() != SyntaxKind::Literal)
+                        .count()
+                        == 1
+                    {
                         CaseType::Simple
                     } else {
                         CaseType::Adt
                     },
+                    // Assigned in `maybe_enum_cases` once all cases are known
+                    discriminant: 0,
                     parent,
                 }
             }
@@ -91,6 +134,7 @@ impl GIRGenerator {
             type_parameters,
             ty,
             ast,
+            annotations,
             module: Rc::clone(&self.module),
         });
 
@@ -117,13 +161,63 @@ impl GIRGenerator {
                     })
                     .collect::<Res<_>>()?
             };
-            if let ADTType::Enum { ref mut cases } = &mut adt_rc.borrow_mut().ty {
+            if let ADTType::Enum { ref mut cases, .. } = &mut adt_rc.borrow_mut().ty {
                 *cases = Rc::new(enum_cases);
             }
+            self.enum_discriminants(adt_rc);
         }
         Ok(())
     }
 
+    /// Assigns the discriminants of all cases of an enum, in order of declaration.
+    /// Cases without an explicit one get the previous case's plus one.
+    fn enum_discriminants(&mut self, adt: &MutRc<ADT>) {
+        let adt = adt.borrow();
+        if adt.is_flags() {
+            return self.flag_discriminants(&adt);
+        }
+
+        let mut used = HashSet::new();
+        let mut next = 0;
+        for case_ast in adt.ast.cases() {
+            if let Some(literal) = case_ast.discriminant() {
+                match literal.cst.text().to_string().trim().parse::<u64>() {
+                    Ok(value) => next = value,
+                    Err(_) => self.err(literal.cst.clone(), GErr::E233),
+                }
+            }
+            if !used.insert(next) {
+                self.err(case_ast.name().cst, GErr::E345(next));
+            }
+
+            let case = &adt.ty.cases()[&case_ast.name().name()];
+            if let ADTType::EnumCase { discriminant, .. } = &mut case.borrow_mut().ty {
+                *discriminant = next;
+            }
+            next = next.wrapping_add(1);
+        }
+    }
+
+    /// Assigns the discriminants of a flags enum, where every case is its own bit.
+    /// Only simple cases without explicit discriminants are allowed.
+    fn flag_discriminants(&mut self, adt: &ADT) {
+        for (index, case_ast) in adt.ast.cases().enumerate() {
+            let case = &adt.ty.cases()[&case_ast.name().name()];
+            let mut case = case.borrow_mut();
+            match (&mut case.ty, 1u64.checked_shl(index as u32)) {
+                (
+                    ADTType::EnumCase {
+                        ty: CaseType::Simple,
+                        discriminant,
+                        ..
+                    },
+                    Some(bit),
+                ) if case_ast.discriminant().is_none() => *discriminant = bit,
+                _ => self.err(case_ast.name().cst, GErr::E346),
+            }
+        }
+    }
+
     /// Takes a list of type parameters of an AST node and
     /// returns it's GIR representation. Can log an error
     /// if type bound cannot be resolved.
@@ -133,7 +227,21 @@ impl GIRGenerator {
         parent_params: Option<Rc<TypeParameters>>,
     ) -> Rc<TypeParameters> {
         let parent_size = parent_params.as_ref().map(|g| g.len()).unwrap_or(0);
-        let param_iter = params.enumerate().map(|param| {
+        let params = params.collect::<Vec<_>>();
+
+        // Set a context of unbounded parameters first, allowing
+        // bounds to refer to other parameters (like `K: Hash[K]`)
+        let unbounded = params.iter().enumerate().map(|param| TypeParameter {
+            name: param.1.name(),
+            index: param.0 + parent_size,
+            bound: TypeParameterBound::default(),
+        });
+        self.set_context(&Rc::new(match &parent_params {
+            Some(parent) => parent.iter().cloned().chain(unbounded).collect(),
+            None => unbounded.collect(),
+        }));
+
+        let param_iter = params.into_iter().enumerate().map(|param| {
             TypeParameter {
                 name: param.1.name(),
                 index: param.0 + parent_size,
@@ -164,30 +272,91 @@ impl GIRGenerator {
         let implementor = eat!(self, self.find_type(&iface_impl.implementor()));
 
         let iface = eat!(self, self.find_type(&iface_impl.iface()));
+        if implementor.is_error() || iface.is_error() {
+            return;
+        }
         if !iface.is_adt() || !iface.as_adt().ty.borrow().ty.is_interface() {
             self.err(iface_impl.iface().cst(), GErr::E307);
             return;
         }
         let iface_adt = iface.as_adt();
+        if !self.can_implement(&iface_adt.ty) {
+            self.err(
+                iface_impl.iface().cst(),
+                GErr::E349(iface_adt.ty.borrow().name.clone()),
+            );
+        } else if !self.owns_impl(&implementor, &iface_adt.ty) {
+            self.err(
+                iface_impl.iface().cst(),
+                GErr::E350 {
+                    iface: iface_adt.ty.borrow().name.clone(),
+                    implementor: implementor.to_string(),
+                },
+            );
+        }
 
-        let impls = self.get_iface_impls(&implementor);
         let gir_impl = IFaceImpl {
             implementor,
             iface: iface.as_adt().clone(),
             methods: HashMap::with_capacity(iface_adt.ty.borrow().methods.len()),
             module: Rc::clone(&self.module),
-            ast: iface_impl.clone(),
+            ast: Some(iface_impl.clone()),
         };
-        let already_defined = impls
-            .borrow_mut()
-            .interfaces
-            .insert(iface, gir_impl)
-            .is_some();
-        if already_defined {
-            self.err(iface_impl.iface().cst, GErr::E306);
+        if let Err(err) = self.add_iface_impl(iface, gir_impl) {
+            self.err(iface_impl.iface().cst, err);
         }
     }
 
+    /// Adds the implementation to the interfaces of its implementor.
+    /// Should the implementor already implement the interface in any module,
+    /// the existing implementation is kept and an error naming its location returned.
+    pub(super) fn add_iface_impl(&mut self, iface: Type, gir_impl: IFaceImpl) -> Result<(), GErr> {
+        let impls = self.get_iface_impls(&gir_impl.implementor);
+        let mut impls = impls.borrow_mut();
+        match impls.interfaces.entry(iface) {
+            Entry::Occupied(existing) => Err(GErr::E306(Self::impl_location(existing.get()))),
+            Entry::Vacant(entry) => {
+                entry.insert(gir_impl);
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns where the given implementation is declared, like `main L12`.
+    fn impl_location(iface_impl: &IFaceImpl) -> String {
+        let module = iface_impl.module.borrow();
+        match &iface_impl.ast {
+            Some(ast) => {
+                let start = ast.iface().cst.text_range().start as usize;
+                let line = module.src[..start].matches('\n').count() + 1;
+                format!("{} L{}", module.path, line)
+            }
+            None => format!("{} (@derive)", module.path),
+        }
+    }
+
+    /// If the current module can implement the given interface,
+    /// which is only restricted to its own package if it is sealed.
+    fn can_implement(&self, iface: &MutRc<ADT>) -> bool {
+        let iface = iface.borrow();
+        let sealed = iface.ast.modifiers().any(|m| m == SyntaxKind::Sealed);
+        !sealed || iface.module.borrow().path.index(0) == self.module.borrow().path.index(0)
+    }
+
+    /// If the current package declares the implementor or the interface,
+    /// one of which is required to implement an interface.
+    /// Types other than ADTs, like primitives, belong to the standard library.
+    fn owns_impl(&self, implementor: &Type, iface: &MutRc<ADT>) -> bool {
+        let module = self.module.borrow();
+        let package = module.path.index(0);
+        let declared_in = |adt: &MutRc<ADT>| adt.borrow().module.borrow().path.index(0) == package;
+        declared_in(iface)
+            || match implementor.try_adt() {
+                Some(adt) => declared_in(&adt.ty),
+                None => module.path.is(&["std"]),
+            }
+    }
+
     pub(super) fn declare_functions(&mut self, ast: &ast::Module) {
         for ast in ast.functions() {
             eatc!(self, self.declare_function(ast));
@@ -198,15 +367,118 @@ impl GIRGenerator {
         let name = func.sig().name();
         self.try_reserve_name(&name.cst, &name.name());
 
-        let function = self.function_from_ast(func, None, None)?;
+        let function = self.function_from_ast(func.clone(), None, None)?;
         self.module
             .borrow_mut()
             .declarations
             .insert(name.name(), Declaration::Function(Rc::clone(&function)));
         self.maybe_set_main_fn(&function, &name.cst);
+        self.maybe_export(&function);
+        self.function_annotations(&function, &func);
         Ok(function)
     }
 
+    /// Applies the annotations of a function or method.
+    /// Supported are `@inline_ir`, `@inline`, `@noinline`, `@pure`, `@cold` and `@link`.
+    pub(super) fn function_annotations(&mut self, function: &MutRc<Function>, ast: &ast::Function) {
+        let annotations = self.annotations(ast.annotations(), AnnotationTarget::Function);
+        for annotation in annotations.iter() {
+            let res = match &annotation.name()[..] {
+                "inline_ir" => self.inline_ir(function, ast, &annotation),
+                "inline" | "noinline" => Self::inline_hint(function, &annotation),
+                "pure" => Self::flag(&annotation, &mut function.borrow_mut().pure),
+                "cold" => Self::flag(&annotation, &mut function.borrow_mut().cold),
+                "link" => self.link(function, ast, &annotation),
+                // Known for functions, but not handled by this pass
+                _ => Err(gir_err(annotation.cst(), GErr::E322(annotation.name()))),
+            };
+            self.eat(res);
+        }
+        function.borrow_mut().annotations = annotations;
+    }
+
+    /// Sets the inlining hint of a function, given with `@inline` or `@noinline`.
+    fn inline_hint(function: &MutRc<Function>, annotation: &ast::Annotation) -> Res<()> {
+        let hint = if annotation.name() == "inline" {
+            InlineHint::Always
+        } else {
+            InlineHint::Never
+        };
+
+        let mut function = function.borrow_mut();
+        if annotation.arguments().next().is_some()
+            || annotation.literals().next().is_some()
+            || function.inline != InlineHint::Default
+        {
+            return Err(gir_err(annotation.cst(), GErr::E337));
+        }
+        function.inline = hint;
+        Ok(())
+    }
+
+    /// Sets a flag of a function given with an annotation
+    /// without arguments, like `@pure` or `@cold`.
+    fn flag(annotation: &ast::Annotation, flag: &mut bool) -> Res<()> {
+        if annotation.arguments().next().is_some()
+            || annotation.literals().next().is_some()
+            || *flag
+        {
+            return Err(gir_err(annotation.cst(), GErr::E338(annotation.name())));
+        }
+        *flag = true;
+        Ok(())
+    }
+
+    /// Sets the LLVM IR of an extern function, given with `@inline_ir("...")`.
+    /// The IR is only parsed by LLVM during IR generation.
+    fn inline_ir(
+        &mut self,
+        function: &MutRc<Function>,
+        ast: &ast::Function,
+        annotation: &ast::Annotation,
+    ) -> Res<()> {
+        let is_extern = ast.modifiers().any(|m| m == SyntaxKind::Extern);
+        if !is_extern || !function.borrow().type_parameters.is_empty() {
+            return Err(gir_err(annotation.cst(), GErr::E329));
+        }
+
+        let mut literals = annotation.literals();
+        let literal = match (
+            literals.next(),
+            literals.next(),
+            annotation.arguments().next(),
+        ) {
+            (Some(literal), None, None) => literal,
+            _ => return Err(gir_err(annotation.cst(), GErr::E328)),
+        };
+        let ir = self.string_literal(literal.get()?.0, &literal)?;
+        function.borrow_mut().inline_ir = Some(ir);
+        Ok(())
+    }
+
+    /// Sets the library an extern function is defined in, given with `@link("name")`.
+    /// The library is passed to the linker when producing an executable or library.
+    fn link(
+        &mut self,
+        function: &MutRc<Function>,
+        ast: &ast::Function,
+        annotation: &ast::Annotation,
+    ) -> Res<()> {
+        let is_extern = ast.modifiers().any(|m| m == SyntaxKind::Extern);
+        let mut literals = annotation.literals();
+        let literal = match (
+            literals.next(),
+            literals.next(),
+            annotation.arguments().next(),
+        ) {
+            (Some(literal), None, None) if is_extern && function.borrow().link.is_none() => literal,
+            _ => return Err(gir_err(annotation.cst(), GErr::E342)),
+        };
+        let library = self.string_literal(literal.get()?.0, &literal)?;
+        function.borrow_mut().link = Some(library);
+        Ok(())
+    }
+
     /// Creates a function from AST. See create_function for post-AST verification.
     /// `this_arg` indicates that the function is a method
     /// with some kind of receiver, with the 'this' parameter
@@ -244,13 +516,20 @@ impl GIRGenerator {
     /// not reserved, making this function suitable for methods or
     /// functions not written by the user.
     /// The main use of this method is validation of the function.
+    /// Errors of functions without AST have no location, which
+    /// the caller should add if it has one.
     pub(crate) fn create_function(&self, sig: FnSig) -> Res<MutRc<Function>> {
         let ret_type = sig.ret_type.unwrap_or_default();
-        if !ret_type.is_assignable() {
-            self.err(
-                sig.ast.as_ref().unwrap().sig().ret_type().unwrap().cst,
-                GErr::E308,
-            );
+        if !ret_type.is_assignable() || ret_type.is_weak() {
+            match sig.ast.as_ref().and_then(|ast| ast.sig().ret_type()) {
+                Some(ast_ty) => self.err(ast_ty.cst, GErr::E308),
+                None => {
+                    return Err(Error {
+                        index: ErrorSpan::None,
+                        kind: GErr::E308,
+                    })
+                }
+            }
         }
 
         let parameters = sig
@@ -278,6 +557,14 @@ impl GIRGenerator {
             variables: Default::default(),
             ret_type,
             ast: sig.ast,
+            inline_ir: None,
+            inline: InlineHint::Default,
+            pure: false,
+            cold: false,
+            link: None,
+            annotations: Annotations::default(),
+            lazy: false,
+            case_overrides: vec![],
             module: Rc::clone(&self.module),
 
             ir: RefCell::new(IRFunction::new(!sig.type_parameters.is_empty())),
@@ -290,6 +577,24 @@ impl GIRGenerator {
         Ok(function)
     }
 
+    /// Libraries export all public functions of their own modules
+    /// that have a body and no type parameters.
+    /// They need to be compiled even if nothing in the library calls them.
+    fn maybe_export(&mut self, func: &MutRc<Function>) {
+        let exported = {
+            let func = func.borrow();
+            self.flags.library
+                && !self.path.is(&["std"])
+                && func.visibility == Visibility::Public
+                && func.type_parameters.is_empty()
+                && func.name != "main"
+                && func.ast.as_ref().and_then(|ast| ast.body()).is_some()
+        };
+        if exported {
+            self.intrinsics.required_compile_fns.push(Rc::clone(func));
+        }
+    }
+
     fn maybe_set_main_fn(&mut self, func: &MutRc<Function>, err_cst: &CSTNode) {
         if func.borrow().name == "main" {
             let res = self
@@ -297,6 +602,11 @@ impl GIRGenerator {
                 .set_main_fn(func)
                 .or_err(err_cst, GErr::E305);
             self.eat(res);
+
+            // The return value of main is the process exit code
+            if !matches!(func.borrow().ret_type, Type::None | Type::I64) {
+                self.err(err_cst.clone(), GErr::E327);
+            }
         }
     }
 }
@@ -0,0 +1,388 @@
+use std::{collections::HashMap, iter, rc::Rc};
+
+use ast::TypeE;
+use smol_str::SmolStr;
+use syntax::kind::SyntaxKind;
+
+use crate::{eatc, result::EmitGIRError, GIRGenerator};
+use common::MutRc;
+use error::{GErr, Res};
+use gir_nodes::{
+    declaration::{ADTType, Field, Visibility},
+    gir_err,
+    types::ToInstance,
+    Expr, Function, IFaceImpl, IFaceImpls, Instance, Literal, Type, ADT,
+};
+
+use super::declare::FnSig;
+
+type DeriveGenerator =
+    fn(&mut GIRGenerator, &MutRc<ADT>, &MutRc<ADT>, &MutRc<Function>) -> Res<Expr>;
+
+impl GIRGenerator {
+    /// Declare interface implementations requested with `@derive` annotations.
+    /// This only registers the implementation, allowing it to be used for type bounds;
+    /// its methods are declared once all interface methods are known.
+    /// Other annotations on ADTs are validated here as well.
+    pub(super) fn declare_derives(&mut self, adt: &MutRc<ADT>) {
+        let annotations = adt.borrow().annotations.clone();
+        for annotation in annotations.iter() {
+            match &annotation.name()[..] {
+                "derive" => {
+                    for arg in annotation.arguments() {
+                        eatc!(self, self.declare_derive(adt, &arg));
+                    }
+                }
+                // Checked when capturing values in closures passed to `std/thread/spawn`
+                "send" => (),
+                // Read when declaring the enum, see `ADTType::Enum`
+                "flags" => {
+                    let has_args = annotation.arguments().next().is_some()
+                        || annotation.literals().next().is_some();
+                    if !adt.borrow().ty.is_enum() || has_args {
+                        self.err(annotation.cst(), GErr::E346)
+                    }
+                }
+                // Known for ADTs, but not handled by this pass
+                _ => self.err(annotation.cst(), GErr::E322(annotation.name())),
+            }
+        }
+    }
+
+    fn declare_derive(&mut self, adt: &MutRc<ADT>, arg: &ast::Type) -> Res<()> {
+        let name = match arg.get()? {
+            TypeE::Ident(name) => name,
+            _ => SmolStr::new(arg.cst.text().to_string()),
+        };
+        let iface = self
+            .derivable_iface(&name)
+            .or_err(&arg.cst, GErr::E323(name.clone()))?;
+
+        let is_class = matches!(adt.borrow().ty, ADTType::Class { external: false });
+        if !is_class || !adt.borrow().type_parameters.is_empty() {
+            return Err(gir_err(arg.cst(), GErr::E324));
+        }
+
+        let implementor = adt.to_type();
+        let iface_ty = Self::derived_iface_type(&iface, &implementor);
+        let gir_impl = IFaceImpl {
+            implementor,
+            iface: iface_ty.as_adt().clone(),
+            methods: HashMap::with_capacity(2),
+            module: Rc::clone(&self.module),
+            ast: None,
+        };
+
+        self.add_iface_impl(iface_ty, gir_impl)
+            .map_err(|err| gir_err(arg.cst(), err))
+    }
+
+    /// Declare the methods of derived interface implementations,
+    /// using the signatures of the interface's methods.
+    pub(super) fn declare_derived_methods(&mut self, adt: &MutRc<ADT>) {
+        let implementor = adt.to_type();
+        let impls = self.get_iface_impls(&implementor);
+        let mut impls = impls.borrow_mut();
+        let IFaceImpls {
+            interfaces,
+            methods,
+            ..
+        } = &mut *impls;
+
+        for iface_impl in interfaces.values_mut().filter(|im| im.ast.is_none()) {
+            let iface = Rc::clone(&iface_impl.iface.ty);
+            let iface_args = Rc::clone(iface_impl.iface.args());
+
+            for iface_method in iface
+                .borrow()
+                .methods
+                .values()
+                .filter(|m| m.borrow().ast.is_some())
+            {
+                let sig = {
+                    let iface_method = iface_method.borrow();
+                    let params = iface_method
+                        .parameters
+                        .iter()
+                        .skip(1)
+                        .map(|p| {
+                            let ty = p.ty.resolve(&iface_args).replace_self(&implementor);
+                            Ok((p.name.clone(), ty))
+                        })
+                        .collect::<Vec<_>>();
+                    FnSig {
+                        name: iface_method.name.clone(),
+                        visibility: Visibility::Public,
+                        params: box iter::once(Ok((
+                            SmolStr::new_inline("this"),
+                            implementor.clone(),
+                        )))
+                        .chain(params),
+                        type_parameters: Rc::clone(&adt.borrow().type_parameters),
+                        ret_type: Some(
+                            iface_method
+                                .ret_type
+                                .resolve(&iface_args)
+                                .replace_self(&implementor),
+                        ),
+                        ast: None,
+                    }
+                };
+
+                let name = sig.name.clone();
+                let method = eatc!(self, self.create_function(sig));
+                iface_impl.methods.insert(name.clone(), Rc::clone(&method));
+                if methods.contains_key(&name) {
+                    methods.remove(&name);
+                } else {
+                    methods.insert(name, method);
+                }
+            }
+        }
+    }
+
+    /// Generate the bodies of derived methods. This requires the fields of all
+    /// ADTs and all derived methods to be declared, since derived methods
+    /// call the methods of the same interface on all fields.
+    pub(super) fn generate_derived_methods(&mut self, adt: &MutRc<ADT>) {
+        let impls = self.get_iface_impls(&adt.to_type());
+        let derived = impls
+            .borrow()
+            .interfaces
+            .values()
+            .filter(|im| im.ast.is_none())
+            .map(|im| {
+                let methods = im.methods.values().cloned().collect::<Vec<_>>();
+                (Rc::clone(&im.iface.ty), methods)
+            })
+            .collect::<Vec<_>>();
+
+        for (iface, methods) in derived {
+            for method in methods {
+                let generator = Self::derived_method_generator(&method.borrow().name);
+                let body = eatc!(self, generator(self, adt, &iface, &method));
+                self.set_pointer(&method);
+                self.insert_at_ptr(Expr::ret(body));
+            }
+        }
+    }
+
+    /// Returns the interface with the given name, if it can be derived.
+    fn derivable_iface(&self, name: &str) -> Option<MutRc<ADT>> {
+        match name {
+            "Hash" => self.intrinsics.hash_iface.clone(),
+            "Equal" => self.intrinsics.get_op_iface(SyntaxKind::EqualEqual),
+            "ToString" => self.intrinsics.to_string_iface.clone(),
+            "Json" => self.intrinsics.json_iface.clone(),
+            _ => None,
+        }
+    }
+
+    /// Returns the generator of a derived method's body.
+    fn derived_method_generator(name: &str) -> DeriveGenerator {
+        match name {
+            "hash" => Self::generate_hash,
+            "hash_equal" => Self::generate_hash_equal,
+            "equal" => Self::generate_equal,
+            "to_string" => Self::generate_to_string,
+            "to_json" => Self::generate_to_json,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Returns the type of the given interface implemented on the implementor.
+    /// All type parameters of the interface are the implementor itself, like `Hash[Implementor]`.
+    fn derived_iface_type(iface: &MutRc<ADT>, implementor: &Type) -> Type {
+        let args = iface
+            .borrow()
+            .type_parameters
+            .iter()
+            .map(|_| implementor.clone())
+            .collect();
+        Type::Adt(Instance::new(Rc::clone(iface), Rc::new(args)))
+    }
+
+    /// Combines the hashes of all fields.
+    fn generate_hash(
+        &mut self,
+        adt: &MutRc<ADT>,
+        iface: &MutRc<ADT>,
+        func: &MutRc<Function>,
+    ) -> Res<Expr> {
+        let this = Rc::clone(&func.borrow().parameters[0]);
+        let mut hash = Expr::literal(Literal::U64(17));
+        for field in adt.borrow().fields.values() {
+            let method = self.derived_field_method(adt, field, iface, "hash")?;
+            let field_hash = Expr::call(
+                Expr::fvar(&method),
+                vec![Expr::load(Expr::lvar(&this), field)],
+            );
+            let multiplied = Expr::binary(SyntaxKind::Star, hash, Expr::literal(Literal::U64(31)));
+            hash = Expr::binary(SyntaxKind::Plus, multiplied, field_hash);
+        }
+        Ok(hash)
+    }
+
+    /// Compares all fields using `hash_equal` on each field.
+    fn generate_hash_equal(
+        &mut self,
+        adt: &MutRc<ADT>,
+        iface: &MutRc<ADT>,
+        func: &MutRc<Function>,
+    ) -> Res<Expr> {
+        self.fields_equal(adt, func, |gen, field, left, right| {
+            let method = gen.derived_field_method(adt, field, iface, "hash_equal")?;
+            Ok(Expr::call(Expr::fvar(&method), vec![left, right]))
+        })
+    }
+
+    /// Compares all fields using `==` on each field.
+    fn generate_equal(
+        &mut self,
+        adt: &MutRc<ADT>,
+        iface: &MutRc<ADT>,
+        func: &MutRc<Function>,
+    ) -> Res<Expr> {
+        let cst = adt.borrow().ast.name().cst;
+        self.fields_equal(adt, func, |gen, field, left, right| {
+            gen.binary_gir(&cst, left, SyntaxKind::EqualEqual, right)
+                .map_err(|_| {
+                    gir_err(
+                        cst.clone(),
+                        GErr::E325 {
+                            iface: iface.borrow().name.clone(),
+                            field: field.name.clone(),
+                        },
+                    )
+                })
+        })
+    }
+
+    /// Compares all fields of `this` and the first parameter with the given
+    /// comparison. Stops at the first field that is not equal.
+    fn fields_equal<F: FnMut(&mut Self, &Field, Expr, Expr) -> Res<Expr>>(
+        &mut self,
+        adt: &MutRc<ADT>,
+        func: &MutRc<Function>,
+        mut compare: F,
+    ) -> Res<Expr> {
+        let this = Rc::clone(&func.borrow().parameters[0]);
+        let other = Rc::clone(&func.borrow().parameters[1]);
+
+        let mut equal = Expr::literal(Literal::Bool(true));
+        for field in adt.borrow().fields.values().rev() {
+            let field_equal = compare(
+                self,
+                field,
+                Expr::load(Expr::lvar(&this), field),
+                Expr::load(Expr::lvar(&other), field),
+            )?;
+            equal = Expr::if_(
+                field_equal,
+                equal,
+                Expr::literal(Literal::Bool(false)),
+                Some(Type::Bool),
+            );
+        }
+        Ok(equal)
+    }
+
+    /// Builds a string like `ClassName(field1: ..., field2: ...)`,
+    /// using `to_string` on each field.
+    fn generate_to_string(
+        &mut self,
+        adt: &MutRc<ADT>,
+        iface: &MutRc<ADT>,
+        func: &MutRc<Function>,
+    ) -> Res<Expr> {
+        let cst = adt.borrow().ast.name().cst;
+        let this = Rc::clone(&func.borrow().parameters[0]);
+        let string_type = self.intrinsics.string_type.clone().unwrap();
+        let literal = |text: String| {
+            Expr::literal(Literal::String {
+                text: text.into(),
+                ty: string_type.clone(),
+            })
+        };
+
+        let mut string = literal(format!("{}(", adt.borrow().name));
+        for (index, field) in adt.borrow().fields.values().enumerate() {
+            let separator = if index == 0 { "" } else { ", " };
+            let name = literal(format!("{}{}: ", separator, field.name));
+            string = self.binary_gir(&cst, string, SyntaxKind::Plus, name)?;
+
+            let value = Expr::load(Expr::lvar(&this), field);
+            string = self
+                .binary_gir(&cst, string, SyntaxKind::Plus, value)
+                .map_err(|_| {
+                    gir_err(
+                        cst.clone(),
+                        GErr::E325 {
+                            iface: iface.borrow().name.clone(),
+                            field: field.name.clone(),
+                        },
+                    )
+                })?;
+        }
+        self.binary_gir(&cst, string, SyntaxKind::Plus, literal(")".to_string()))
+    }
+
+    /// Builds a JSON object like `{"field1": ..., "field2": ...}`,
+    /// using `to_json` on each field.
+    fn generate_to_json(
+        &mut self,
+        adt: &MutRc<ADT>,
+        iface: &MutRc<ADT>,
+        func: &MutRc<Function>,
+    ) -> Res<Expr> {
+        let cst = adt.borrow().ast.name().cst;
+        let this = Rc::clone(&func.borrow().parameters[0]);
+        let string_type = self.intrinsics.string_type.clone().unwrap();
+        let literal = |text: String| {
+            Expr::literal(Literal::String {
+                text: text.into(),
+                ty: string_type.clone(),
+            })
+        };
+
+        let mut json = literal("{".to_string());
+        for (index, field) in adt.borrow().fields.values().enumerate() {
+            let separator = if index == 0 { "" } else { ", " };
+            let name = literal(format!("{}\"{}\": ", separator, field.name));
+            json = self.binary_gir(&cst, json, SyntaxKind::Plus, name)?;
+
+            let method = self.derived_field_method(adt, field, iface, "to_json")?;
+            let value = Expr::call(
+                Expr::fvar(&method),
+                vec![Expr::load(Expr::lvar(&this), field)],
+            );
+            json = self.binary_gir(&cst, json, SyntaxKind::Plus, value)?;
+        }
+        self.binary_gir(&cst, json, SyntaxKind::Plus, literal("}".to_string()))
+    }
+
+    /// Returns the method of the derived interface on the type of the given field.
+    /// Errors if the field's type does not implement the interface.
+    fn derived_field_method(
+        &mut self,
+        adt: &MutRc<ADT>,
+        field: &Field,
+        iface: &MutRc<ADT>,
+        name: &str,
+    ) -> Res<MutRc<Function>> {
+        let iface_ty = Self::derived_iface_type(iface, &field.ty);
+        self.get_iface_impls(&field.ty)
+            .borrow()
+            .interfaces
+            .get(&iface_ty)
+            .map(|im| im.methods.get(name).cloned())
+            .flatten()
+            .or_err(
+                &adt.borrow().ast.name().cst,
+                GErr::E325 {
+                    iface: iface.borrow().name.clone(),
+                    field: field.name.clone(),
+                },
+            )
+    }
+}
@@ -0,0 +1,256 @@
+use std::{collections::HashMap, iter, rc::Rc};
+
+use common::MutRc;
+use error::{GErr, Res};
+use gir_nodes::{
+    declaration::{ADTType, Variable, Visibility},
+    expression::CastType,
+    types::{ToInstance, TypeVariable},
+    Declaration, Expr, Function, FunctionBody, Instance, Literal, Type, ADT,
+};
+use smol_str::SmolStr;
+use syntax::kind::SyntaxKind;
+
+use super::declare::FnSig;
+use crate::{eat, GIRGenerator};
+
+impl GIRGenerator {
+    /// Declares `ordinal` on an enum, returning the discriminant of the value's case.
+    /// Must run before methods are copied into cases, see `declare_methods`.
+    /// Enums defining their own `ordinal` keep it.
+    pub(super) fn declare_enum_ordinal(&mut self, adt: &MutRc<ADT>) {
+        if !adt.borrow().ty.is_enum() || adt.borrow().methods.contains_key("ordinal") {
+            return;
+        }
+
+        let this_type = Type::Adt(Instance::new(
+            Rc::clone(adt),
+            Rc::new(
+                adt.borrow()
+                    .type_parameters
+                    .iter()
+                    .map(TypeVariable::from_param)
+                    .map(Type::Variable)
+                    .collect(),
+            ),
+        ));
+        let method = eat!(
+            self,
+            self.create_function(FnSig {
+                name: "ordinal".into(),
+                visibility: Visibility::Public,
+                params: box iter::once(Ok((SmolStr::new_inline("this"), this_type))),
+                type_parameters: Rc::clone(&adt.borrow().type_parameters),
+                ret_type: Some(Type::I64),
+                ast: None,
+            })
+        );
+        let this = Rc::clone(&method.borrow().parameters[0]);
+        method.push_return(Expr::enum_tag(Expr::lvar(&this)));
+        adt.borrow_mut().methods.insert("ordinal".into(), method);
+    }
+
+    /// Records the methods of cases overriding a method of their enum,
+    /// see `Function::case_overrides`. Requires all methods to be declared.
+    pub(super) fn enum_overrides(&mut self, adt: &MutRc<ADT>) {
+        let adt = adt.borrow();
+        let mut cases = match &adt.ty {
+            ADTType::Enum { cases, .. } => cases.values().collect::<Vec<_>>(),
+            _ => return,
+        };
+        // Sorted to keep the generated code the same between compilations
+        cases.sort_by(|a, b| a.borrow().name.cmp(&b.borrow().name));
+
+        for (name, method) in &adt.methods {
+            for case in &cases {
+                let case_method = match case.borrow().methods.get(name) {
+                    Some(case_method) if !Rc::ptr_eq(case_method, method) => Rc::clone(case_method),
+                    _ => continue,
+                };
+
+                if Self::same_signature(&method.borrow(), &case_method.borrow()) {
+                    method.borrow_mut().case_overrides.push(case_method);
+                } else {
+                    let ast = case_method.borrow().ast.clone().unwrap();
+                    self.err(ast.sig().name().cst, GErr::E347(name.clone()));
+                }
+            }
+        }
+    }
+
+    /// If two methods take the same parameters after `this` and return the same type.
+    fn same_signature(method: &Function, other: &Function) -> bool {
+        method.ret_type == other.ret_type
+            && method.parameters.len() == other.parameters.len()
+            && method
+                .parameters
+                .iter()
+                .zip(other.parameters.iter())
+                .skip(1)
+                .all(|(a, b)| a.ty == b.ty)
+    }
+
+    /// Inserts the dispatch of an enum method to the cases overriding it,
+    /// like `if (this is Enum:Case) return Enum:Case.method(this, ...)`.
+    pub(super) fn case_dispatch(&mut self, function: &MutRc<Function>) {
+        let dispatch = {
+            let func = function.borrow();
+            let this = &func.parameters[0];
+            func.case_overrides
+                .iter()
+                .map(|case_method| {
+                    let case_method_ref = case_method.borrow();
+                    let case_ty = case_method_ref.parameters[0].ty.clone();
+                    let type_args = case_method_ref
+                        .type_parameters
+                        .iter()
+                        .map(TypeVariable::from_param)
+                        .map(Type::Variable)
+                        .collect();
+                    let args = iter::once(Expr::cast(
+                        Expr::lvar(this),
+                        case_ty.clone(),
+                        CastType::Bitcast,
+                    ))
+                    .chain(func.parameters.iter().skip(1).map(Expr::lvar))
+                    .collect();
+                    let call = Expr::call(
+                        Expr::var(Variable::Function(Instance::new(
+                            Rc::clone(case_method),
+                            Rc::new(type_args),
+                        ))),
+                        args,
+                    );
+
+                    let ret = if func.ret_type == Type::None {
+                        Expr::Block(vec![call, Expr::ret(Expr::none_const())])
+                    } else {
+                        Expr::ret(call)
+                    };
+                    let is_case =
+                        Expr::binary(SyntaxKind::Is, Expr::lvar(this), Expr::TypeGet(case_ty));
+                    Expr::if_(is_case, ret, Expr::none_const(), None)
+                })
+                .collect::<Vec<_>>()
+        };
+        function.extend_exprs(dispatch);
+    }
+
+    /// Declares and generates the functions of enums without type parameters,
+    /// which are called like `Enum:from_ordinal(1)`.
+    /// Requires the constructors of all cases.
+    pub(super) fn enum_functions(&mut self, adt: &MutRc<ADT>) {
+        let cases = match &adt.borrow().ty {
+            ADTType::Enum { cases, .. } if adt.borrow().type_parameters.is_empty() => {
+                Rc::clone(cases)
+            }
+            _ => return,
+        };
+        self.enum_from_ordinal(adt, &cases);
+        if adt.borrow().is_flags() {
+            self.flags_contains(adt);
+        }
+    }
+
+    /// Generates `from_ordinal`, returning the simple case
+    /// with the given discriminant or null if there is none.
+    fn enum_from_ordinal(&mut self, adt: &MutRc<ADT>, cases: &HashMap<SmolStr, MutRc<ADT>>) {
+        let ret_type = Type::Nullable(box adt.to_type());
+        let function = eat!(
+            self,
+            self.adt_function(
+                adt,
+                "from_ordinal",
+                vec![("ordinal", Type::I64)],
+                ret_type.clone()
+            )
+        );
+        let ordinal = Rc::clone(&function.borrow().parameters[0]);
+
+        // Sorted to keep the generated code the same between compilations
+        let mut cases = cases
+            .values()
+            .map(|case| match case.borrow().ty {
+                ADTType::EnumCase { discriminant, .. } => (discriminant, Rc::clone(case)),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        cases.sort_by_key(|(discriminant, _)| *discriminant);
+
+        let no_args = Rc::new(vec![]);
+        let branches = cases
+            .into_iter()
+            .filter_map(|(discriminant, case)| {
+                let value = ADT::get_singleton_inst(&case, &no_args)?;
+                let condition = Expr::binary(
+                    SyntaxKind::EqualEqual,
+                    Expr::lvar(&ordinal),
+                    Expr::literal(Literal::I64(discriminant)),
+                );
+                Some((condition, self.try_cast(value, &ret_type).0))
+            })
+            .collect();
+        let null = Expr::cast(
+            Expr::literal(Literal::Null),
+            ret_type.clone(),
+            CastType::ToNullable,
+        );
+        function.push_return(Self::switch(branches, null, Some(ret_type)));
+    }
+
+    /// Generates `contains` of flags enums, returning if
+    /// the flag is set in the given set of flags.
+    fn flags_contains(&mut self, adt: &MutRc<ADT>) {
+        let function = eat!(
+            self,
+            self.adt_function(
+                adt,
+                "contains",
+                vec![("set", Type::I64), ("flag", adt.to_type())],
+                Type::Bool
+            )
+        );
+        let set = Rc::clone(&function.borrow().parameters[0]);
+        let flag = Rc::clone(&function.borrow().parameters[1]);
+
+        // (set & flag) != 0
+        let bits = Expr::binary(
+            SyntaxKind::Ampersand,
+            Expr::lvar(&set),
+            Expr::enum_tag(Expr::lvar(&flag)),
+        );
+        let contains = Expr::binary(SyntaxKind::BangEqual, bits, Expr::literal(Literal::I64(0)));
+        function.push_return(contains);
+    }
+
+    /// Declares a function of an ADT, named like `Enum:name`.
+    pub(super) fn adt_function(
+        &mut self,
+        adt: &MutRc<ADT>,
+        name: &str,
+        params: Vec<(&str, Type)>,
+        ret_type: Type,
+    ) -> Res<MutRc<Function>> {
+        let (full_name, visibility) = {
+            let adt = adt.borrow();
+            (format!("{}:{}", adt.name, name), adt.visibility)
+        };
+        let function = self.create_function(FnSig {
+            name: full_name.into(),
+            visibility,
+            params: box params
+                .into_iter()
+                .map(|(name, ty)| Ok((SmolStr::new(name), ty))),
+            type_parameters: Rc::new(vec![]),
+            ret_type: Some(ret_type),
+            ast: None,
+        })?;
+
+        let name = function.borrow().name.clone();
+        self.module
+            .borrow_mut()
+            .declarations
+            .insert(name, Declaration::Function(Rc::clone(&function)));
+        Ok(function)
+    }
+}
@@ -2,7 +2,7 @@ use common::MutRc;
 use error::GErr;
 use gir_nodes::{
     declaration::{ADTType, Field},
-    types::ToInstance,
+    types::{ToInstance, TypeKind},
     ADT,
 };
 use std::{cell::RefCell, rc::Rc};
@@ -16,7 +16,7 @@ impl GIRGenerator {
         match &adt.ty {
             ADTType::Class { .. } => self.fill_adt(&mut adt),
 
-            ADTType::Enum { cases } => {
+            ADTType::Enum { cases, .. } => {
                 let cases = Rc::clone(cases);
                 self.fill_adt(&mut adt);
                 for case in cases.values() {
@@ -51,7 +51,9 @@ impl GIRGenerator {
                 )
             );
 
-            if !ty.is_assignable() {
+            // Value types are copied without their weak references being tracked
+            let weak_in_value = ty.is_weak() && adt.type_kind != TypeKind::Reference;
+            if !ty.is_assignable() || weak_in_value {
                 self.err(field.cst(), GErr::E234);
             }
 
@@ -13,6 +13,8 @@ use std::iter;
 impl GIRGenerator {
     pub(super) fn generate(&mut self, decl: Declaration) {
         match decl {
+            // Generated before all other declarations, see `generate_lazy_globals`
+            Declaration::Function(func) if func.borrow().lazy => (),
             Declaration::Function(func) => self.generate_function(&func),
 
             Declaration::Adt(adt_rc) => {
@@ -37,6 +39,7 @@ impl GIRGenerator {
     fn generate_impl(&mut self, impls: &MutRc<IFaceImpls>) {
         let impls = impls.borrow();
         self.ty_position = Some(impls.implementor.clone());
+        self.self_type = Some(impls.implementor.clone());
         for im in impls
             .interfaces
             .values()
@@ -48,6 +51,7 @@ impl GIRGenerator {
             }
         }
         self.ty_position = None;
+        self.self_type = None;
     }
 
     pub(crate) fn generate_function(&mut self, function: &MutRc<Function>) {
@@ -62,6 +66,7 @@ impl GIRGenerator {
         }
 
         self.prepare_function(&function);
+        self.case_dispatch(function);
         let ast = function.borrow().ast.clone();
 
         let body = match (ast.as_ref().map(|a| a.body()).flatten(), method_index) {
@@ -73,21 +78,31 @@ impl GIRGenerator {
         let ret_type = function.borrow().ret_type.clone();
         if ret_type == Type::None {
             self.insert_at_ptr(body)
+        } else if ret_type == Type::Never {
+            // Functions returning 'Never' must diverge on all paths,
+            // like by calling another function returning 'Never'
+            if !body.diverges() {
+                self.err(ast.unwrap().sig().name().cst, GErr::E326);
+            }
+            self.insert_at_ptr(body)
         } else {
             let (body, success) = self.try_cast(body, &ret_type);
             if !success {
-                self.err(
-                    ast.unwrap().sig().name().cst,
-                    GErr::E310 {
+                let body_ty = body.get_type();
+                let err = self
+                    .variance_error(&body_ty, &ret_type)
+                    .unwrap_or_else(|| GErr::E310 {
                         expected: ret_type.to_string(),
-                        was: body.get_type().to_string(),
-                    },
-                );
+                        was: body_ty.to_string(),
+                    });
+                self.err(ast.unwrap().sig().name().cst, err);
             }
             self.insert_at_ptr(Expr::ret(body));
         }
 
         self.end_scope();
+        self.check_pure(function);
+        self.optimize_function(function);
     }
 
     /// This method generates the method body for an iface function.
@@ -130,6 +145,7 @@ impl GIRGenerator {
             }
 
             self.end_scope();
+            self.optimize_function(constructor);
         }
 
         self.uninitialized_this_fields.clear();
@@ -3,7 +3,7 @@ use std::{mem, rc::Rc};
 use common::{ModPath, MutRc};
 use error::{GErr, Res};
 use gir_nodes::{
-    module::{Imports, UnresolvedImport},
+    module::{Imports, UnresolvedImport, WildcardImport},
     Module,
 };
 
@@ -20,44 +20,60 @@ impl GIRGenerator {
 
         for import in ast.imports() {
             let mut path = import.parts().collect::<Vec<_>>();
-            let symbol = path.pop().unwrap();
+            let selected = import.selected();
+            let symbols = if selected.is_empty() {
+                vec![path.pop().unwrap()]
+            } else {
+                selected
+            };
             let path = ModPath::from(path);
 
             let src_module_rc = eatc!(self, self.find_module(&path, &import));
+            module
+                .borrow_mut()
+                .dependencies
+                .push(Rc::clone(src_module_rc));
             let src_module = src_module_rc.borrow();
 
-            if symbol == "+" {
-                Self::get_imports(&mut module.borrow_mut(), import.is_export())
-                    .modules
-                    .push(src_module_rc.clone());
-            } else {
-                let decl = src_module.find_import(&symbol);
-                if let Some(decl) = decl {
-                    self.try_reserve_name(&import.cst, &symbol);
+            for symbol in symbols {
+                if symbol == "+" {
                     Self::get_imports(&mut module.borrow_mut(), import.is_export())
-                        .decls
-                        .insert(symbol, decl);
-                    continue;
+                        .modules
+                        .push(WildcardImport {
+                            module: Rc::clone(src_module_rc),
+                            hidden: import.hidden(),
+                        });
+                } else {
+                    let decl = src_module.find_import(&symbol);
+                    if let Some(decl) = decl {
+                        self.try_reserve_name(&import.cst, &symbol);
+                        Self::get_imports(&mut module.borrow_mut(), import.is_export())
+                            .decls
+                            .insert(symbol, decl);
+                        continue;
+                    }
                 }
-            }
 
-            module
-                .borrow_mut()
-                .imports
-                .unresolved
-                .push(UnresolvedImport {
-                    ast: import,
-                    module: Rc::clone(&src_module_rc),
-                    symbol,
-                })
+                module
+                    .borrow_mut()
+                    .imports
+                    .unresolved
+                    .push(UnresolvedImport {
+                        ast: import.clone(),
+                        module: Rc::clone(&src_module_rc),
+                        symbol,
+                    })
+            }
         }
 
         let mut module = module.borrow_mut();
         if !self.flags.no_prelude && !module.path.is(&["std", "prelude"]) {
-            module
-                .imports
-                .modules
-                .push(self.intrinsics.std_prelude.clone().unwrap());
+            let prelude = self.intrinsics.std_prelude.clone().unwrap();
+            module.dependencies.push(Rc::clone(&prelude));
+            module.imports.modules.push(WildcardImport {
+                module: prelude,
+                hidden: vec![],
+            });
         }
         module.return_ast(ast_borrow);
     }
@@ -68,8 +84,19 @@ impl GIRGenerator {
             let src_module = import.module.borrow();
 
             if import.symbol == "+" {
+                let hidden = import.ast.hidden();
                 for name in src_module.declarations.keys() {
-                    self.try_reserve_name(&import.ast.cst, name);
+                    if !hidden.contains(name)
+                        && !Self::imported_elsewhere(&module.borrow(), &import.module, name)
+                    {
+                        self.try_reserve_name(&import.ast.cst, name);
+                    }
+                }
+                if hidden
+                    .iter()
+                    .any(|name| src_module.find_import(name).is_none())
+                {
+                    self.err(import.ast.cst(), GErr::E103);
                 }
             } else {
                 let decl = src_module.find_import(&import.symbol);
@@ -86,20 +113,27 @@ impl GIRGenerator {
 
         if !self.flags.no_prelude && !module.borrow().path.is(&["std", "prelude"]) {
             let dummy = CSTNode::dummy();
-            for name in self
-                .intrinsics
-                .std_prelude
-                .as_ref()
-                .unwrap()
-                .borrow()
-                .declarations
-                .keys()
-            {
-                self.try_reserve_name(&dummy, name);
+            let prelude = Rc::clone(self.intrinsics.std_prelude.as_ref().unwrap());
+            for name in prelude.borrow().declarations.keys() {
+                if !Self::imported_elsewhere(&module.borrow(), &prelude, name) {
+                    self.try_reserve_name(&dummy, name);
+                }
             }
         }
     }
 
+    /// Returns if a declaration with the given name is also imported with `+`
+    /// from a module other than `src`. The names of such declarations are not
+    /// reserved, since using them is reported as ambiguous instead.
+    fn imported_elsewhere(module: &Module, src: &MutRc<Module>, name: &str) -> bool {
+        module
+            .imports
+            .modules
+            .iter()
+            .filter(|wildcard| !Rc::ptr_eq(&wildcard.module, src))
+            .any(|wildcard| wildcard.find(name).is_some())
+    }
+
     fn find_module(&self, path: &ModPath, import: &ast::Import) -> Res<&MutRc<Module>> {
         self.modules
             .iter()
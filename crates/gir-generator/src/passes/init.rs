@@ -0,0 +1,84 @@
+use std::{collections::HashSet, iter, rc::Rc};
+
+use common::{ModulePath, MutRc};
+use error::GErr;
+use gir_nodes::{declaration::Visibility, Expr, FunctionBody, Module};
+use smol_str::SmolStr;
+
+use super::declare::FnSig;
+use crate::{eatc, GIRGenerator};
+
+impl GIRGenerator {
+    /// Declares the function containing the module's `init` block.
+    pub(super) fn declare_init(&mut self, ast: &ast::Module) {
+        for init in ast.inits() {
+            if self.module.borrow().init.is_some() {
+                self.err(init.cst(), GErr::E104);
+                continue;
+            }
+
+            let function = eatc!(
+                self,
+                self.create_function(FnSig {
+                    name: SmolStr::new_inline("init"),
+                    visibility: Visibility::Private,
+                    params: box iter::empty(),
+                    type_parameters: Rc::new(vec![]),
+                    ret_type: None,
+                    ast: None,
+                })
+            );
+            self.module.borrow_mut().init = Some(function);
+        }
+    }
+
+    /// Generates the body of the module's `init` function.
+    pub(super) fn generate_init(&mut self, ast: &ast::Module) {
+        let function = match self.module.borrow().init.clone() {
+            Some(function) => function,
+            None => return,
+        };
+        let init = ast.inits().next().unwrap();
+
+        self.set_pointer(&function);
+        self.begin_scope();
+        let body = self.expression(&init.body());
+        self.insert_at_ptr(body);
+        self.end_scope();
+        self.optimize_function(&function);
+    }
+
+    /// Inserts calls to the `init` functions of all modules at the start of `main`.
+    /// Modules are initialized after all modules they import from.
+    pub(super) fn insert_module_inits(&mut self) {
+        let main = match &self.intrinsics.main_fn {
+            Some(main) => Rc::clone(main),
+            None => return,
+        };
+
+        let mut visited = HashSet::with_capacity(self.modules.len());
+        let mut calls = Vec::new();
+        for module in &self.modules {
+            Self::collect_inits(module, &mut visited, &mut calls);
+        }
+        main.prepend_exprs(calls);
+    }
+
+    fn collect_inits(
+        module: &MutRc<Module>,
+        visited: &mut HashSet<ModulePath>,
+        calls: &mut Vec<Expr>,
+    ) {
+        let module = module.borrow();
+        if !visited.insert(Rc::clone(&module.path)) {
+            return;
+        }
+
+        for dependency in &module.dependencies {
+            Self::collect_inits(dependency, visited, calls);
+        }
+        if let Some(init) = &module.init {
+            calls.push(Expr::call(Expr::fvar(init), vec![]));
+        }
+    }
+}
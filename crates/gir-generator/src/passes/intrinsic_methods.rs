@@ -5,6 +5,7 @@ use syntax::kind::SyntaxKind;
 
 use crate::{eat, GIRGenerator};
 use common::MutRc;
+use error::GErr;
 use gir_nodes::{
     declaration::ADTType, expression::CastType, types::ToInstance, Expr, Function, Type, ADT,
 };
@@ -107,6 +108,10 @@ impl GIRGenerator {
 
         let mut exprs = Vec::with_capacity(4);
 
+        if !is_value {
+            let destroy = self.maybe_call_destroy(adt, Expr::lvar(&adt_var));
+            exprs.push(destroy);
+        }
         let maybe_free = self.maybe_call_free_impl(Expr::lvar(&adt_var));
         exprs.push(maybe_free);
 
@@ -114,7 +119,7 @@ impl GIRGenerator {
             // Interface, TODO
             ADTType::Interface => (),
 
-            ADTType::Enum { cases } => {
+            ADTType::Enum { cases, .. } => {
                 let dest = Self::build_enum_destructor(Expr::lvar(&adt_var), cases);
                 exprs.push(dest);
             }
@@ -152,6 +157,31 @@ impl GIRGenerator {
         }
     }
 
+    /// Calls the user-defined `destroy` method of a class, if it has one.
+    /// It runs before the class's fields are released, allowing classes
+    /// wrapping raw resources to clean them up.
+    fn maybe_call_destroy(&mut self, adt: &MutRc<ADT>, this: Expr) -> Expr {
+        if !matches!(adt.borrow().ty, ADTType::Class { .. }) {
+            return Expr::none_const();
+        }
+        let method = match adt.borrow().methods.get("destroy") {
+            Some(method) => Rc::clone(method),
+            None => return Expr::none_const(),
+        };
+
+        let valid = {
+            let method = method.borrow();
+            method.parameters.len() == 1 && method.ret_type == Type::None
+        };
+        if valid {
+            Expr::call(Expr::fvar(&method), vec![this])
+        } else {
+            let ast = method.borrow().ast.clone().unwrap();
+            self.err(ast.sig().name().cst, GErr::E332);
+            Expr::none_const()
+        }
+    }
+
     fn maybe_call_free_impl(&mut self, adt: Expr) -> Expr {
         if self.flags.no_std {
             return Expr::none_const();
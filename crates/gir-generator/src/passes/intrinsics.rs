@@ -15,14 +15,30 @@ impl GIRGenerator {
             self.intrinsics.fill_ops_table(module);
         } else if module.path.is(&["std", "string"]) {
             let str_ty = module.find_decl("String").map(|d| d.into_adt()).unwrap();
-            self.intrinsics.string_type = Some(str_ty.to_type())
+            self.intrinsics.string_type = Some(str_ty.to_type());
+            self.intrinsics.to_string_iface = module.find_decl("ToString").map(|d| d.into_adt());
         } else if module.path.is(&["std", "memory"]) {
             self.intrinsics.free_iface = module.find_decl("Free").map(|d| d.into_adt());
+        } else if module.path.is(&["std", "hash"]) {
+            self.intrinsics.hash_iface = module.find_decl("Hash").map(|d| d.into_adt());
+        } else if module.path.is(&["std", "json"]) {
+            self.intrinsics.json_iface = module.find_decl("Json").map(|d| d.into_adt());
+            self.intrinsics.json_reader = module.find_decl("JsonReader").map(|d| d.into_adt());
+        } else if module.path.is(&["std", "intrinsics"]) {
+            self.intrinsics.closure_impl = module.find_decl("ClosureImpl").map(|d| d.into_adt());
         } else if module.path.is(&["std", "iter"]) {
             self.intrinsics.iter_proto = module.find_decl("Iter").map(|d| d.into_adt());
             self.intrinsics.to_iter_proto = module.find_decl("ToIter").map(|d| d.into_adt());
+        } else if module.path.is(&["std", "tuple"]) {
+            self.intrinsics.tuples = (2..)
+                .map(|len| module.find_decl(&format!("Tuple{}", len)))
+                .take_while(Option::is_some)
+                .flatten()
+                .map(|d| d.into_adt())
+                .collect();
         } else if module.path.is(&["std", "prelude"]) {
-            self.intrinsics.std_prelude = Some(Rc::clone(&module_rc))
+            self.intrinsics.std_prelude = Some(Rc::clone(&module_rc));
+            self.intrinsics.result_adt = module.find_decl("Res").map(|d| d.into_adt());
         }
     }
 
@@ -42,6 +58,15 @@ impl GIRGenerator {
             add_fn("malloc");
             add_fn("gelixrs_inc_ref_iface");
             add_fn("gelixrs_dec_ref_iface");
+        } else if module.path.is(&["std", "prelude"]) {
+            self.intrinsics.panic_fn = module.find_decl("panic").map(|d| d.into_function());
+            self.intrinsics.likely_fn = module.find_decl("likely").map(|d| d.into_function());
+            self.intrinsics.unlikely_fn = module.find_decl("unlikely").map(|d| d.into_function());
+        } else if module.path.is(&["std", "io"]) {
+            self.intrinsics.print_fn = module.find_decl("print").map(|d| d.into_function());
+            self.intrinsics.println_fn = module.find_decl("println").map(|d| d.into_function());
+        } else if module.path.is(&["std", "thread"]) {
+            self.intrinsics.thread_spawn = module.find_decl("spawn").map(|d| d.into_function());
         }
     }
 
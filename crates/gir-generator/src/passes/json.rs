@@ -0,0 +1,208 @@
+use std::rc::Rc;
+
+use ast::CSTNode;
+use common::MutRc;
+use error::{Error, GErr, Res};
+use gir_nodes::{
+    declaration::LocalVariable, expression::CastType, gir_err, types::ToInstance, Declaration,
+    Expr, Function, Literal, Type, ADT,
+};
+use smol_str::SmolStr;
+
+use crate::{eat, result::EmitGIRError, GIRGenerator};
+
+impl GIRGenerator {
+    /// Declares the functions reading classes deriving Json from JSON text:
+    /// `Class:from_json(text: String) -> Class?`, returning null on invalid text,
+    /// and `Class:read_json(reader: JsonReader) -> Class`, used for fields of the class.
+    /// Their bodies are generated by `generate_json_functions`.
+    pub(super) fn declare_json_functions(&mut self, adt: &MutRc<ADT>) {
+        if !self.derives_json(adt) {
+            return;
+        }
+        let reader_type = self.intrinsics.json_reader.as_ref().unwrap().to_type();
+        let string_type = self.intrinsics.string_type.clone().unwrap();
+        eat!(
+            self,
+            self.adt_function(
+                adt,
+                "read_json",
+                vec![("reader", reader_type)],
+                adt.to_type()
+            )
+        );
+        eat!(
+            self,
+            self.adt_function(
+                adt,
+                "from_json",
+                vec![("text", string_type)],
+                Type::Nullable(box adt.to_type())
+            )
+        );
+    }
+
+    /// Generates the functions declared by `declare_json_functions`.
+    /// This requires the fields and constructors of all ADTs, as well as
+    /// the `read_json` functions of all classes deriving Json.
+    pub(super) fn generate_json_functions(&mut self, adt: &MutRc<ADT>) {
+        if !self.derives_json(adt) {
+            return;
+        }
+        let (read_json, from_json) = match (
+            Self::generated_function(adt, "read_json"),
+            Self::generated_function(adt, "from_json"),
+        ) {
+            (Some(Declaration::Function(read)), Some(Declaration::Function(from))) => (read, from),
+            _ => return,
+        };
+
+        let reader = Rc::clone(&read_json.borrow().parameters[0]);
+        let body = eat!(self, self.read_json(adt, &reader));
+        self.set_pointer(&read_json);
+        self.insert_at_ptr(Expr::ret(body));
+
+        self.generate_from_json(adt, &read_json, &from_json);
+    }
+
+    /// If the given ADT derives Json.
+    fn derives_json(&mut self, adt: &MutRc<ADT>) -> bool {
+        let json = match &self.intrinsics.json_iface {
+            Some(json) => Rc::clone(json),
+            None => return false,
+        };
+        let impls = self.get_iface_impls(&adt.to_type());
+        let derives = impls
+            .borrow()
+            .interfaces
+            .values()
+            .any(|im| im.ast.is_none() && Rc::ptr_eq(&im.iface.ty, &json));
+        derives
+    }
+
+    /// Creates the class with the constructor taking all fields,
+    /// reading each field from the object of the reader.
+    fn read_json(&mut self, adt: &MutRc<ADT>, reader: &Rc<LocalVariable>) -> Res<Expr> {
+        let cst = adt.borrow().ast.name().cst;
+        let constructor =
+            Self::json_constructor(adt).or_err(&cst, GErr::E348(adt.borrow().name.clone()))?;
+        let string_type = self.intrinsics.string_type.clone().unwrap();
+
+        let fields = adt.borrow().fields.values().cloned().collect::<Vec<_>>();
+        let mut args = Vec::with_capacity(fields.len());
+        for field in fields {
+            let name = Expr::literal(Literal::String {
+                text: field.name.clone(),
+                ty: string_type.clone(),
+            });
+            let field_reader = self.json_reader_call("field", vec![Expr::lvar(reader), name]);
+            let value = match &field.ty {
+                Type::I64 => self.json_reader_call("read_i64", vec![field_reader]),
+                Type::F64 => self.json_reader_call("read_f64", vec![field_reader]),
+                Type::Bool => self.json_reader_call("read_bool", vec![field_reader]),
+                ty if *ty == string_type => {
+                    self.json_reader_call("read_string", vec![field_reader])
+                }
+                Type::Adt(inst) => match Self::generated_function(&inst.ty, "read_json") {
+                    Some(Declaration::Function(read)) => {
+                        Expr::call(Expr::fvar(&read), vec![field_reader])
+                    }
+                    _ => return Err(Self::json_field_error(&cst, &field.name)),
+                },
+                _ => return Err(Self::json_field_error(&cst, &field.name)),
+            };
+            args.push(value);
+        }
+
+        Ok(Expr::Allocate {
+            ty: adt.to_type(),
+            constructor,
+            args,
+        })
+    }
+
+    /// Generates `from_json`, which reads the class and returns null
+    /// if reading any of its values failed.
+    fn generate_from_json(
+        &mut self,
+        adt: &MutRc<ADT>,
+        read_json: &MutRc<Function>,
+        from_json: &MutRc<Function>,
+    ) {
+        let text = Rc::clone(&from_json.borrow().parameters[0]);
+        let reader_adt = Rc::clone(self.intrinsics.json_reader.as_ref().unwrap());
+        let constructor = reader_adt
+            .borrow()
+            .constructors
+            .iter()
+            .find(|constructor| constructor.borrow().parameters.len() == 2)
+            .cloned()
+            .unwrap();
+        let ret_type = Type::Nullable(box adt.to_type());
+
+        self.set_pointer(from_json);
+        self.begin_scope();
+        let (store_reader, reader) = self.temp_variable(
+            Expr::Allocate {
+                ty: reader_adt.to_type(),
+                constructor,
+                args: vec![Expr::lvar(&text)],
+            },
+            SmolStr::new_inline("reader"),
+        );
+        let (store_value, value) = self.temp_variable(
+            Expr::call(Expr::fvar(read_json), vec![Expr::lvar(&reader)]),
+            SmolStr::new_inline("value"),
+        );
+        let failed = self.json_reader_call("failed", vec![Expr::lvar(&reader)]);
+        let result = Expr::if_(
+            failed,
+            Expr::cast(
+                Expr::literal(Literal::Null),
+                ret_type.clone(),
+                CastType::ToNullable,
+            ),
+            Expr::cast(Expr::lvar(&value), ret_type.clone(), CastType::ToNullable),
+            Some(ret_type),
+        );
+        self.insert_at_ptr(store_reader);
+        self.insert_at_ptr(store_value);
+        self.insert_at_ptr(Expr::ret(result));
+        self.end_scope();
+    }
+
+    /// Returns the constructor taking exactly the fields of the class in order,
+    /// with parameters named like the fields.
+    fn json_constructor(adt: &MutRc<ADT>) -> Option<MutRc<Function>> {
+        let adt = adt.borrow();
+        adt.constructors
+            .iter()
+            .find(|constructor| {
+                let constructor = constructor.borrow();
+                let params = &constructor.parameters[1..];
+                params.len() == adt.fields.len()
+                    && params
+                        .iter()
+                        .zip(adt.fields.values())
+                        .all(|(param, field)| param.name == field.name && param.ty == field.ty)
+            })
+            .cloned()
+    }
+
+    /// Calls the given method of `std/json/JsonReader`.
+    fn json_reader_call(&self, name: &str, args: Vec<Expr>) -> Expr {
+        let reader = self.intrinsics.json_reader.as_ref().unwrap().borrow();
+        Expr::call(Expr::fvar(&reader.methods[name]), args)
+    }
+
+    /// Returns the error of a field whose type cannot be read from JSON.
+    fn json_field_error(cst: &CSTNode, field: &SmolStr) -> Error {
+        gir_err(
+            cst.clone(),
+            GErr::E325 {
+                iface: SmolStr::new_inline("Json"),
+                field: field.clone(),
+            },
+        )
+    }
+}
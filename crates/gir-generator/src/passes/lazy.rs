@@ -0,0 +1,93 @@
+use std::{iter, rc::Rc};
+
+use error::GErr;
+use gir_nodes::{Declaration, Expr, Type};
+
+use super::declare::FnSig;
+use crate::{eatc, GIRGenerator};
+
+impl GIRGenerator {
+    /// Declares the functions computing the module's lazy globals.
+    /// Reading a lazy global calls its function, which only runs
+    /// the initializer on the first call, see `IRGenerator::lazy_global`.
+    pub(super) fn declare_lazy_globals(&mut self, ast: &ast::Module) {
+        for global in ast.globals() {
+            let name = global.name();
+            self.try_reserve_name(&global.cst, &name);
+
+            // Globals without a type get the type of their initializer
+            // in `generate_lazy_globals`, until then it is `Any`
+            let ty = match global._type() {
+                Some(ast_ty) => {
+                    let ty = eatc!(self, self.find_type(&ast_ty));
+                    if !ty.is_assignable() {
+                        self.err(ast_ty.cst(), GErr::E230(ty.to_string()));
+                    } else if ty.is_weak() {
+                        self.err(ast_ty.cst(), GErr::E344);
+                    }
+                    ty
+                }
+                None => Type::Any,
+            };
+
+            let function = eatc!(
+                self,
+                self.create_function(FnSig {
+                    name: name.clone(),
+                    visibility: self.visibility_from_modifiers(global.modifiers(), &global.cst),
+                    params: box iter::empty(),
+                    type_parameters: Rc::new(vec![]),
+                    ret_type: Some(ty),
+                    ast: None,
+                })
+            );
+            function.borrow_mut().lazy = true;
+            self.module
+                .borrow_mut()
+                .declarations
+                .insert(name, Declaration::Function(function));
+        }
+    }
+
+    /// Generates the initializers of the module's lazy globals.
+    /// They are generated before all other functions, so that globals
+    /// without an explicit type have one when they are used.
+    pub(super) fn generate_lazy_globals(&mut self, ast: &ast::Module) {
+        for global in ast.globals() {
+            let function = match self.module.borrow().declarations.get(&global.name()) {
+                Some(Declaration::Function(func)) if func.borrow().lazy => Rc::clone(func),
+                _ => continue,
+            };
+            if !function.borrow().exprs.is_empty() {
+                // Declared twice with the same name, which is already an error
+                continue;
+            }
+
+            self.set_pointer(&function);
+            self.begin_scope();
+            let value = self.expression(&global.initializer());
+            let ty = function.borrow().ret_type.clone();
+            let value = if ty == Type::Any {
+                let value_ty = value.get_type();
+                if !value_ty.is_assignable() {
+                    self.err(global.initializer().cst(), GErr::E230(value_ty.to_string()));
+                } else if value_ty.is_weak() {
+                    self.err(global.initializer().cst(), GErr::E344);
+                }
+                function.borrow_mut().ret_type = value_ty;
+                value
+            } else {
+                let (value, success) = self.try_cast(value, &ty);
+                if !success {
+                    let err = self
+                        .variance_error(&value.get_type(), &ty)
+                        .unwrap_or(GErr::E201);
+                    self.err(global.initializer().cst(), err);
+                }
+                value
+            };
+            self.insert_at_ptr(Expr::ret(value));
+            self.end_scope();
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use std::ops::Range;
+
+use common::MutRc;
+use error::{Error, ErrorSpan, GErr};
+use gir_nodes::Module;
+
+use crate::GIRGenerator;
+
+impl GIRGenerator {
+    /// Warns about modules mixing Unix and Windows line endings.
+    /// Both are whitespace to the parser, so this does not stop compilation.
+    pub(super) fn check_line_endings(&mut self, module: MutRc<Module>) {
+        let module = module.borrow();
+        if let Some(ending) = mixed_line_ending(&module.src) {
+            let warning = Error {
+                index: ErrorSpan::Span(ending.start as u32..ending.end as u32),
+                kind: GErr::W003,
+            };
+            self.push_error(&self.warnings, warning, &module);
+        }
+    }
+}
+
+/// Returns the first line ending that is different from
+/// the first one of the code, either `\n` or `\r\n`.
+fn mixed_line_ending(code: &str) -> Option<Range<usize>> {
+    let mut endings = code.match_indices('\n').map(|(index, _)| {
+        if code[..index].ends_with('\r') {
+            (index - 1)..(index + 1)
+        } else {
+            index..(index + 1)
+        }
+    });
+    let first = endings.next()?.len();
+    endings.find(|ending| ending.len() != first)
+}
@@ -8,7 +8,7 @@ use gir_nodes::{
     declaration::{ADTType, CaseType, LocalVariable},
     gir_err,
     types::TypeArguments,
-    Expr, Function, IFaceImpls, Instance, Type, ADT,
+    Expr, Function, FunctionBody, IFaceImpls, Instance, Type, ADT,
 };
 use smol_str::SmolStr;
 
@@ -19,13 +19,22 @@ use std::collections::HashMap;
 impl GIRGenerator {
     pub(super) fn declare_methods(&mut self, adt: &MutRc<ADT>) {
         self.declare_user_methods(&adt);
+        self.declare_enum_ordinal(&adt);
 
-        if let ADTType::Enum { cases } = &adt.borrow().ty {
+        // Cases get all methods of their enum they do not override
+        if let ADTType::Enum { cases, .. } = &adt.borrow().ty {
             for case in cases.values() {
                 let mut case = case.borrow_mut();
+                let overrides = case
+                    .ast
+                    .methods()
+                    .map(|method| method.sig().name().name())
+                    .collect::<HashSet<_>>();
                 case.methods.reserve(adt.borrow().methods.len());
                 for method in &adt.borrow().methods {
-                    case.methods.insert(method.0.clone(), Rc::clone(method.1));
+                    if !overrides.contains(method.0) {
+                        case.methods.insert(method.0.clone(), Rc::clone(method.1));
+                    }
                 }
             }
         }
@@ -48,6 +57,9 @@ impl GIRGenerator {
             ),
         );
 
+        if adt.borrow().ty.is_interface() {
+            self.self_type = Some(Type::Variable(TypeVariable::this()));
+        }
         for method in ast.methods() {
             let name = method.sig().name();
             let this_type = Type::Adt(this_inst.clone());
@@ -55,17 +67,19 @@ impl GIRGenerator {
             let gir_method = eat!(
                 self,
                 self.function_from_ast(
-                    method,
+                    method.clone(),
                     Some(("this".into(), this_type)),
                     Some(Rc::clone(&adt.borrow().type_parameters))
                 )
             );
+            self.function_annotations(&gir_method, &method);
 
             let existing = adt.borrow_mut().methods.insert(name.name(), gir_method);
             if existing.is_some() {
                 self.err(name.cst, GErr::E319)
             }
         }
+        self.self_type = None;
 
         self.declare_constructors(adt, &ast, this_inst);
     }
@@ -198,10 +212,15 @@ impl GIRGenerator {
             .values_mut()
             .filter(|im| !im.module.borrow().compiled)
         {
+            let ast = match &iface_impl.ast {
+                Some(ast) => ast.clone(),
+                // Derived impls get their methods in `declare_derived_methods`
+                None => continue,
+            };
             self.switch_module(Rc::clone(&iface_impl.module));
 
-            let ast = &iface_impl.ast;
             let iface = Rc::clone(&iface_impl.iface.ty);
+            self.self_type = Some(iface_impl.implementor.clone());
 
             for ast_method in ast.methods() {
                 let iface = iface.borrow();
@@ -219,8 +238,17 @@ impl GIRGenerator {
                 // TODO: Type parameters on impls
                 let impl_method = eatc!(
                     self,
-                    self.function_from_ast(ast_method, Some(("this".into(), this_type)), None)
+                    self.function_from_ast(
+                        ast_method.clone(),
+                        Some(("this".into(), this_type)),
+                        None
+                    )
                 );
+                self.function_annotations(&impl_method, &ast_method);
+                // Private interface methods can only be called by the implementing type itself
+                if iface_method.borrow().visibility == Visibility::Private {
+                    impl_method.borrow_mut().visibility = Visibility::Private;
+                }
                 iface_impl
                     .methods
                     .insert(name.name(), Rc::clone(&impl_method));
@@ -253,6 +281,7 @@ impl GIRGenerator {
         }
 
         impls.methods = methods;
+        self.self_type = None;
     }
 
     /// Ensures that the implemented interface method matches the expected signature.
@@ -265,19 +294,23 @@ impl GIRGenerator {
         let impl_method = impl_method.borrow();
         let iface_method = iface_method.borrow();
         let ast = impl_method.ast.clone().unwrap();
+        let implementor = &impl_method.parameters[0].ty;
+        let expected = |ty: &Type| ty.resolve(iface_args).replace_self(implementor);
 
-        if impl_method.ret_type != iface_method.ret_type.resolve(iface_args) {
+        let ret_type = expected(&iface_method.ret_type);
+        if impl_method.ret_type != ret_type {
             let sig = ast.sig();
             let tok = sig.ret_type().map_or_else(|| sig.name().cst, |r| r.cst);
             self.err(
                 tok,
                 GErr::E315 {
-                    expected: iface_method.ret_type.resolve(iface_args).to_string(),
+                    expected: ret_type.to_string(),
                     was: impl_method.ret_type.to_string(),
                 },
             );
         }
 
+        let params = ast.sig().parameters().collect::<Vec<_>>();
         for (i, (method_param, iface_param)) in impl_method
             .parameters
             .iter()
@@ -285,18 +318,43 @@ impl GIRGenerator {
             .skip(1)
             .enumerate()
         {
-            let iface_ty = iface_param.ty.resolve(iface_args);
+            let iface_ty = expected(&iface_param.ty);
             if method_param.ty != iface_ty {
-                let cst = ast.sig().parameters().nth(i).unwrap().cst;
                 self.err(
-                    cst,
+                    params[i].cst.clone(),
                     GErr::E316 {
+                        name: iface_param.name.clone(),
                         expected: iface_ty.to_string(),
                         was: method_param.ty.to_string(),
                     },
                 );
             }
         }
+
+        // Report each parameter the interface does not have, or each the implementation lacks
+        let iface_len = iface_method.parameters.len();
+        for (param, cst) in impl_method
+            .parameters
+            .iter()
+            .skip(1)
+            .zip(params.iter())
+            .skip(iface_len - 1)
+        {
+            self.err(cst.cst.clone(), GErr::E351(param.name.clone()));
+        }
+        for iface_param in iface_method
+            .parameters
+            .iter()
+            .skip(impl_method.parameters.len())
+        {
+            self.err(
+                ast.sig().name().cst,
+                GErr::E352 {
+                    name: iface_param.name.clone(),
+                    ty: expected(&iface_param.ty).to_string(),
+                },
+            );
+        }
     }
 
     /// Insert all constructor 'setter' parameters into the entry
@@ -310,8 +368,7 @@ impl GIRGenerator {
                 self,
                 self.insert_constructor_setters(&adt, &constructor, &func.borrow().parameters)
             );
-            // (local variable to prevent 'already borrowed' panic)
-            func.borrow_mut().exprs = exprs;
+            func.set_exprs(exprs);
         }
 
         if let ADTType::EnumCase {
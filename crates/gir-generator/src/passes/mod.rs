@@ -1,50 +1,402 @@
 use crate::GIRGenerator;
-use common::{bench, MutRc};
+use common::{bench, log, Level, MutRc};
 use gir_nodes::{Declaration, Module, ADT};
-use std::rc::Rc;
+use std::{rc::Rc, time::Instant};
 
 pub(crate) use declare::FnSig;
 
-mod declare;
+pub(crate) mod declare;
+mod derive;
+mod enums;
 mod fields;
 mod generate;
 mod import;
+mod init;
 mod intrinsic_methods;
 mod intrinsics;
+mod json;
+mod lazy;
+mod line_endings;
 mod methods;
 
-impl GIRGenerator {
-    pub(crate) fn run_passes(&mut self) {
-        bench!("gir stage 1", {
-            self.run_ast(Self::declare_adts);
-            self.run_mod(Self::populate_intrinsics);
-            self.run_mod(Self::import_stage_1);
-            self.run_ast(Self::declare_iface_impls);
-            self.run_ast(Self::declare_functions);
-            self.run_mod(Self::populate_intrinsics_fn);
-            self.validate_intrinsics();
-            self.run_mod(Self::import_stage_2);
-        });
+/// Something a pass produces that later passes can require, see [Pass].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Product {
+    /// All ADTs are declared, without any of their members.
+    Adts,
+    /// All exported types are indexed for import suggestions.
+    TypeIndex,
+    /// The intrinsic types of the standard library are known.
+    IntrinsicTypes,
+    /// Imported types are visible in their importing module.
+    TypeImports,
+    /// Interface implementations are declared, without their methods.
+    IfaceImpls,
+    /// Derived interface implementations are declared, without their methods.
+    DerivedImpls,
+    /// All functions are declared.
+    Functions,
+    /// The `init` functions of all modules are declared.
+    InitFunctions,
+    /// The functions of lazy globals are declared.
+    LazyGlobals,
+    /// The intrinsic functions of the standard library are known.
+    IntrinsicFunctions,
+    /// All imports are resolved.
+    Imports,
+    /// All methods of ADTs are declared.
+    Methods,
+    /// Methods overridden by enum cases are known.
+    EnumOverrides,
+    /// The methods of interface implementations are known.
+    ImplMethods,
+    /// The methods of derived interface implementations are declared.
+    DerivedMethods,
+    /// The JSON functions of ADTs deriving Json are declared.
+    JsonFunctions,
+    /// All fields of ADTs are filled in.
+    Fields,
+    /// Constructors set the fields given as parameters.
+    ConstructorSetters,
+    /// The functions of enums like `from_ordinal` are generated.
+    EnumFunctions,
+    /// Lifecycle methods of ADTs are declared.
+    LifecycleMethods,
+    /// The bodies of all functions are generated.
+    Bodies,
+    /// The bodies of `init` functions are generated.
+    InitBodies,
+    /// `main` initializes all modules.
+    ModuleInits,
+}
 
-        bench!("gir stage 2", {
-            self.run_adt(Self::declare_methods);
-            self.fill_impls();
-            self.run_adt(Self::insert_adt_fields);
-            self.run_adt(Self::constructor_setters);
-            self.run_adt(Self::declare_lifecycle_methods);
-            self.run_adt(Self::generate_lifecycle_methods);
-        });
+/// A pass of the generator over all uncompiled modules.
+/// Passes declare what they require and produce, which
+/// is used to order them, see [schedule].
+struct Pass {
+    name: &'static str,
+    /// The stage the pass belongs to, used as name of its benchmark.
+    stage: &'static str,
+    requires: &'static [Product],
+    produces: &'static [Product],
+    run: fn(&mut GIRGenerator),
+}
+
+fn pass(
+    stage: &'static str,
+    name: &'static str,
+    requires: &'static [Product],
+    produces: &'static [Product],
+    run: fn(&mut GIRGenerator),
+) -> Pass {
+    Pass {
+        name,
+        stage,
+        requires,
+        produces,
+        run,
+    }
+}
+
+/// All passes, in the order they should run in if possible.
+fn passes() -> Vec<Pass> {
+    use Product::*;
+    let stage_1 = "gir stage 1";
+    let stage_2 = "gir stage 2";
+    let generation = "gir generation";
+    let optimization = "gir optimization";
 
-        bench!("gir generation", {
-            self.run_dec(Self::generate);
-            self.generate_impls();
+    vec![
+        pass(stage_1, "check_line_endings", &[], &[], |gen| {
+            gen.run_mod(GIRGenerator::check_line_endings)
+        }),
+        pass(stage_1, "declare_adts", &[], &[Adts], |gen| {
+            gen.run_ast(GIRGenerator::declare_adts)
+        }),
+        pass(stage_1, "index_types", &[Adts], &[TypeIndex], |gen| {
+            gen.index_types()
+        }),
+        pass(
+            stage_1,
+            "populate_intrinsics",
+            &[Adts],
+            &[IntrinsicTypes],
+            |gen| gen.run_mod(GIRGenerator::populate_intrinsics),
+        ),
+        pass(stage_1, "import_stage_1", &[Adts], &[TypeImports], |gen| {
+            gen.run_mod(GIRGenerator::import_stage_1)
+        }),
+        pass(
+            stage_1,
+            "declare_iface_impls",
+            &[TypeIndex, TypeImports],
+            &[IfaceImpls],
+            |gen| gen.run_ast(GIRGenerator::declare_iface_impls),
+        ),
+        pass(
+            stage_1,
+            "declare_derives",
+            &[IntrinsicTypes, IfaceImpls],
+            &[DerivedImpls],
+            |gen| gen.run_adt(GIRGenerator::declare_derives),
+        ),
+        pass(
+            stage_1,
+            "declare_functions",
+            &[TypeIndex, TypeImports],
+            &[Functions],
+            |gen| gen.run_ast(GIRGenerator::declare_functions),
+        ),
+        pass(
+            stage_1,
+            "declare_init",
+            &[Functions],
+            &[InitFunctions],
+            |gen| gen.run_ast(GIRGenerator::declare_init),
+        ),
+        pass(
+            stage_1,
+            "declare_lazy_globals",
+            &[TypeImports],
+            &[LazyGlobals],
+            |gen| gen.run_ast(GIRGenerator::declare_lazy_globals),
+        ),
+        pass(
+            stage_1,
+            "populate_intrinsics_fn",
+            &[Functions],
+            &[IntrinsicFunctions],
+            |gen| gen.run_mod(GIRGenerator::populate_intrinsics_fn),
+        ),
+        pass(
+            stage_1,
+            "validate_intrinsics",
+            &[IntrinsicTypes, IntrinsicFunctions],
+            &[],
+            |gen| gen.validate_intrinsics(),
+        ),
+        pass(
+            stage_1,
+            "import_stage_2",
+            &[Functions, LazyGlobals],
+            &[Imports],
+            |gen| gen.run_mod(GIRGenerator::import_stage_2),
+        ),
+        pass(
+            stage_2,
+            "declare_methods",
+            &[IfaceImpls, Imports],
+            &[Methods],
+            |gen| gen.run_adt(GIRGenerator::declare_methods),
+        ),
+        pass(
+            stage_2,
+            "enum_overrides",
+            &[Methods],
+            &[EnumOverrides],
+            |gen| gen.run_adt(GIRGenerator::enum_overrides),
+        ),
+        pass(
+            stage_2,
+            "fill_impls",
+            &[Methods, DerivedImpls],
+            &[ImplMethods],
+            |gen| gen.fill_impls(),
+        ),
+        pass(
+            stage_2,
+            "declare_derived_methods",
+            &[ImplMethods],
+            &[DerivedMethods],
+            |gen| gen.run_adt(GIRGenerator::declare_derived_methods),
+        ),
+        pass(
+            stage_2,
+            "declare_json_functions",
+            &[Methods, DerivedImpls],
+            &[JsonFunctions],
+            |gen| gen.run_adt(GIRGenerator::declare_json_functions),
+        ),
+        pass(stage_2, "insert_adt_fields", &[Imports], &[Fields], |gen| {
+            gen.run_adt(GIRGenerator::insert_adt_fields)
+        }),
+        pass(
+            stage_2,
+            "constructor_setters",
+            &[Methods, Fields],
+            &[ConstructorSetters],
+            |gen| gen.run_adt(GIRGenerator::constructor_setters),
+        ),
+        pass(
+            stage_2,
+            "enum_functions",
+            &[Methods, Fields],
+            &[EnumFunctions],
+            |gen| gen.run_adt(GIRGenerator::enum_functions),
+        ),
+        pass(
+            stage_2,
+            "declare_lifecycle_methods",
+            &[Fields],
+            &[LifecycleMethods],
+            |gen| gen.run_adt(GIRGenerator::declare_lifecycle_methods),
+        ),
+        pass(
+            stage_2,
+            "generate_lifecycle_methods",
+            &[LifecycleMethods, ImplMethods],
+            &[Bodies],
+            |gen| gen.run_adt(GIRGenerator::generate_lifecycle_methods),
+        ),
+        pass(
+            stage_2,
+            "generate_derived_methods",
+            &[DerivedMethods, Fields],
+            &[Bodies],
+            |gen| gen.run_adt(GIRGenerator::generate_derived_methods),
+        ),
+        pass(
+            stage_2,
+            "generate_json_functions",
+            &[JsonFunctions, Fields],
+            &[Bodies],
+            |gen| gen.run_adt(GIRGenerator::generate_json_functions),
+        ),
+        pass(
+            generation,
+            "generate_lazy_globals",
+            &[
+                LazyGlobals,
+                EnumOverrides,
+                ConstructorSetters,
+                EnumFunctions,
+            ],
+            &[Bodies],
+            |gen| gen.run_ast(GIRGenerator::generate_lazy_globals),
+        ),
+        pass(
+            generation,
+            "generate",
+            &[
+                EnumOverrides,
+                ConstructorSetters,
+                EnumFunctions,
+                DerivedMethods,
+            ],
+            &[Bodies],
+            |gen| gen.run_dec(GIRGenerator::generate),
+        ),
+        pass(
+            generation,
+            "generate_init",
+            &[InitFunctions, Fields],
+            &[InitBodies],
+            |gen| gen.run_ast(GIRGenerator::generate_init),
+        ),
+        pass(
+            generation,
+            "generate_impls",
+            &[ImplMethods],
+            &[Bodies],
+            |gen| gen.generate_impls(),
+        ),
+        pass(
+            generation,
+            "insert_module_inits",
+            &[InitBodies],
+            &[ModuleInits],
+            |gen| gen.insert_module_inits(),
+        ),
+        pass(
+            generation,
+            "check_instantiations",
+            &[Bodies, ModuleInits],
+            &[],
+            |gen| gen.check_instantiations(),
+        ),
+        pass(optimization, "inline_accessors", &[Bodies], &[], |gen| {
+            gen.inline_accessors()
+        }),
+    ]
+}
+
+/// Orders the passes so that each one runs after all passes producing
+/// something it requires, keeping the given order where possible.
+/// A requirement that is never produced or passes requiring each other
+/// are bugs in their declarations, which debug builds panic on.
+fn schedule(mut passes: Vec<Pass>) -> Vec<Pass> {
+    for pass in &passes {
+        for product in pass.requires {
+            debug_assert!(
+                passes.iter().any(|p| p.produces.contains(product)),
+                "Pass '{}' requires {:?}, which no pass produces.",
+                pass.name,
+                product
+            );
+        }
+    }
+
+    let mut ordered = Vec::with_capacity(passes.len());
+    while !passes.is_empty() {
+        let ready = passes.iter().position(|pass| {
+            pass.requires
+                .iter()
+                .all(|product| !passes.iter().any(|p| p.produces.contains(product)))
         });
+        debug_assert!(
+            ready.is_some(),
+            "Passes require each other: {}",
+            passes.iter().map(|p| p.name).collect::<Vec<_>>().join(", ")
+        );
+        ordered.push(passes.remove(ready.unwrap_or(0)));
+    }
+    ordered
+}
+
+impl GIRGenerator {
+    /// Runs all passes, stopping early if a limit
+    /// of `GIRFlags::limits` is exceeded.
+    pub(crate) fn run_passes(&mut self) {
+        let start = Instant::now();
+        if !self.check_limits(start.elapsed()) {
+            return;
+        }
+
+        let passes = schedule(passes());
+        let mut remaining = &passes[..];
+        while let Some(first) = remaining.first() {
+            let len = remaining
+                .iter()
+                .take_while(|pass| pass.stage == first.stage)
+                .count();
+            let (stage, rest) = remaining.split_at(len);
+            let mut profile = self.profile.take();
+            bench!(first.stage, {
+                for pass in stage {
+                    log!(Level::Debug, "Running pass {}", pass.name);
+                    bench!(profile, pass.name, {
+                        self.nodes_processed = 0;
+                        (pass.run)(self);
+                        self.nodes_processed
+                    });
+                    if !self.check_limits(start.elapsed()) {
+                        break;
+                    }
+                }
+            });
+            self.profile = profile;
+            if self.limit_exceeded() {
+                return;
+            }
+            remaining = rest;
+        }
     }
 
     /// Execute a given module-scope pass.
     fn run_mod<T: FnMut(&mut Self, MutRc<Module>)>(&mut self, mut runner: T) {
         for module in self.modules_uncompiled.clone().into_iter() {
             self.switch_module(Rc::clone(&module));
+            self.nodes_processed += 1;
             runner(self, module)
         }
     }
@@ -54,6 +406,7 @@ impl GIRGenerator {
     fn run_ast<T: FnMut(&mut Self, &ast::Module)>(&mut self, mut runner: T) {
         for module in self.modules_uncompiled.clone().into_iter() {
             self.switch_module(Rc::clone(&module));
+            self.nodes_processed += 1;
             let ast = module.borrow_mut().borrow_ast();
             runner(self, &ast.0);
             module.borrow_mut().return_ast(ast);
@@ -82,6 +435,7 @@ impl GIRGenerator {
             self.switch_module(module);
             for decl in declarations {
                 self.set_context(&decl.type_parameters());
+                self.nodes_processed += 1;
                 runner(self, decl)
             }
         }
@@ -95,3 +449,63 @@ impl GIRGenerator {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{pass, passes, schedule, Pass, Product, Product::*};
+
+    fn test_pass(
+        name: &'static str,
+        requires: &'static [Product],
+        produces: &'static [Product],
+    ) -> Pass {
+        pass("test", name, requires, produces, |_| ())
+    }
+
+    fn names(passes: &[Pass]) -> Vec<&'static str> {
+        passes.iter().map(|pass| pass.name).collect()
+    }
+
+    #[test]
+    fn keeps_valid_order() {
+        let ordered = schedule(vec![
+            test_pass("a", &[], &[Adts]),
+            test_pass("b", &[], &[]),
+            test_pass("c", &[Adts], &[Functions]),
+            test_pass("d", &[Functions, Adts], &[]),
+        ]);
+        assert_eq!(names(&ordered), ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn keeps_order_of_all_passes() {
+        let declared = names(&passes());
+        assert_eq!(names(&schedule(passes())), declared);
+    }
+
+    #[test]
+    fn moves_pass_after_its_requirement() {
+        let ordered = schedule(vec![
+            test_pass("a", &[Functions], &[]),
+            test_pass("b", &[], &[]),
+            test_pass("c", &[], &[Functions]),
+            test_pass("d", &[], &[]),
+        ]);
+        assert_eq!(names(&ordered), ["b", "c", "a", "d"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "which no pass produces")]
+    fn missing_requirement() {
+        schedule(vec![test_pass("a", &[Adts], &[])]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Passes require each other")]
+    fn cyclic_requirement() {
+        schedule(vec![
+            test_pass("a", &[Functions], &[Adts]),
+            test_pass("b", &[Adts], &[Functions]),
+        ]);
+    }
+}
@@ -0,0 +1,77 @@
+use std::{collections::HashSet, rc::Rc};
+
+use common::MutRc;
+use error::GErr;
+use gir_nodes::{
+    declaration::{LocalVariable, Variable},
+    expression::Intrinsic,
+    Expr, Function,
+};
+
+use crate::GIRGenerator;
+
+type VarSet = HashSet<*const LocalVariable>;
+
+impl GIRGenerator {
+    /// Checks that a function marked `@pure` has no side effects.
+    /// This is only a best-effort check: The function may only assign to
+    /// its own local variables and only call other pure functions,
+    /// which includes extern functions marked `@pure`.
+    /// Panicking, like on a failed bounds check, is allowed.
+    pub(crate) fn check_pure(&self, function: &MutRc<Function>) {
+        let func = function.borrow();
+        if !func.pure {
+            return;
+        }
+
+        let locals = func.variables.values().map(Rc::as_ptr).collect();
+        let mut error = None;
+        for expr in &func.exprs {
+            self.find_side_effect(expr, &locals, &mut error);
+        }
+        if let Some(error) = error {
+            self.err(func.ast.as_ref().unwrap().sig().name().cst, error);
+        }
+    }
+
+    fn find_side_effect(&self, expr: &Expr, locals: &VarSet, error: &mut Option<GErr>) {
+        match expr {
+            Expr::Store { location, .. } if !is_local(location, locals) => {
+                error.get_or_insert(GErr::E339);
+            }
+            Expr::Call { callee, .. } if !self.is_pure_callee(callee) => {
+                error.get_or_insert(GErr::E340);
+            }
+            Expr::Intrinsic(Intrinsic::IfaceCall { .. }) => {
+                error.get_or_insert(GErr::E340);
+            }
+            _ => (),
+        }
+        expr.for_each_child(&mut |child| self.find_side_effect(child, locals, error));
+    }
+
+    fn is_pure_callee(&self, callee: &Expr) -> bool {
+        match callee {
+            Expr::Variable(Variable::Function(func)) => {
+                func.ty.borrow().pure
+                    || self
+                        .intrinsics
+                        .panic_fn
+                        .as_ref()
+                        .map_or(false, |panic| Rc::ptr_eq(panic, &func.ty))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Returns if the location of a store is a local variable of the function,
+/// or an element of a local array.
+/// Variables captured by closures are not local variables of the closure.
+fn is_local(location: &Expr, locals: &VarSet) -> bool {
+    match location {
+        Expr::Variable(Variable::Local(var)) => locals.contains(&Rc::as_ptr(var)),
+        Expr::Index { object, .. } => is_local(object, locals),
+        _ => false,
+    }
+}
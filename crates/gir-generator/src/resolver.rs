@@ -1,6 +1,6 @@
 use std::rc::Rc;
 
-use crate::{result::EmitGIRError, GIRGenerator};
+use crate::{result::EmitGIRError, trace::cast_name, GIRGenerator};
 use ast::CSTNode;
 use common::{mutrc_new, MutRc};
 use error::{GErr, Res};
@@ -8,11 +8,12 @@ use gir_nodes::{
     declaration::ADTType,
     expression::{CastType, CastType::Bitcast},
     gir_err,
-    types::{ClosureType, TypeParameters, TypeVariable},
-    Expr, IFaceImpls, Instance, Type,
+    types::{ArrayType, ClosureType, TypeParameters, TypeVariable},
+    Expr, IFaceImpls, Instance, Literal, Type,
 };
 use smol_str::SmolStr;
-use std::{collections::HashMap, mem};
+use std::{collections::HashMap, convert::TryFrom, mem};
+use syntax::kind::SyntaxKind;
 
 /// Resolver part of the GIR generator.
 /// Responsible for resolving all types and casting them,
@@ -23,12 +24,35 @@ impl GIRGenerator {
         self.find_type_(ast, false)
     }
 
+    /// Resolves the given AST type, allowing functions if `allow_fn` is set.
+    /// Unknown types are reported right away and resolve to `Type::Error`,
+    /// so that declarations using them still exist and do not cause further errors.
     pub(crate) fn find_type_(&self, ast: &ast::Type, allow_fn: bool) -> Res<Type> {
-        match ast.get() {
+        match self.resolve_type(ast, allow_fn) {
+            Err(err) if matches!(err.kind, GErr::E300 { .. }) => {
+                self.error(err);
+                Ok(Type::Error)
+            }
+            result => result,
+        }
+    }
+
+    fn resolve_type(&self, ast: &ast::Type, allow_fn: bool) -> Res<Type> {
+        match ast.get()? {
+            ast::TypeE::Ident(tok) if tok == "Self" && self.self_type.is_some() => {
+                Ok(self.self_type.clone().unwrap())
+            }
+
             ast::TypeE::Ident(tok) => {
                 let ty = self.search_type_param(&tok);
+                if ty.is_none() {
+                    self.check_ambiguous(&tok, &ast.cst)?;
+                }
                 let ty = ty.or_else(|| self.symbol(&tok));
-                let ty = ty.or_err(&ast.cst, GErr::E300(tok.to_string()))?;
+                if ty.is_none() && Self::vector_type(&tok).is_some() {
+                    return Err(gir_err(ast.cst(), GErr::E336));
+                }
+                let ty = ty.or_error(&ast.cst, || self.unknown_type(&tok))?;
                 Self::check_args_count(&ty, &ast.cst)?;
 
                 if ty.is_function() && !allow_fn {
@@ -48,6 +72,35 @@ impl GIRGenerator {
 
             ast::TypeE::RawPtr(inner) => Ok(Type::RawPtr(Box::new(self.find_type(&inner)?))),
 
+            ast::TypeE::Weak(inner) => {
+                let inner = self.find_type(&inner)?;
+                let is_class = match &inner {
+                    Type::Adt(inst) => {
+                        let adt = inst.ty.borrow();
+                        adt.refcounted() && matches!(adt.ty, ADTType::Class { external: false })
+                    }
+                    _ => false,
+                };
+                if is_class {
+                    Ok(Type::Weak(box inner))
+                } else {
+                    Err(gir_err(ast.cst(), GErr::E333))
+                }
+            }
+
+            ast::TypeE::Array { ty, len } => Ok(Type::Array(Rc::new(ArrayType {
+                ty: self.find_type(&ty)?,
+                len: Self::array_length(&len, &ast.cst)?,
+            }))),
+
+            ast::TypeE::Tuple(types) => {
+                let types = types
+                    .iter()
+                    .map(|t| self.find_type(t))
+                    .collect::<Res<Vec<_>>>()?;
+                self.tuple_type(types, &ast.cst)
+            }
+
             ast::TypeE::Closure {
                 params, ret_type, ..
             } => {
@@ -71,9 +124,19 @@ impl GIRGenerator {
         }
     }
 
+    /// Returns the type of a tuple with the given element types.
+    pub(crate) fn tuple_type(&self, types: Vec<Type>, cst: &CSTNode) -> Res<Type> {
+        let tuple = self
+            .intrinsics
+            .tuple_adt(types.len())
+            .or_err(cst, GErr::E334(self.intrinsics.tuples.len() + 1))?;
+        Ok(Type::Adt(Instance::new(Rc::clone(tuple), Rc::new(types))))
+    }
+
     pub(crate) fn symbol(&self, name: &SmolStr) -> Option<Type> {
         Some(match &name[..] {
             "None" => Type::None,
+            "Never" => Type::Never,
             "bool" => Type::Bool,
 
             "i8" => Type::I8,
@@ -97,7 +160,10 @@ impl GIRGenerator {
             "f32" => Type::F32,
             "f64" => Type::F64,
 
-            _ => self.module.borrow().find_decl(name).map(|d| d.to_type())?,
+            _ => match Self::vector_type(name) {
+                Some(vector) if self.simd_enabled() => vector,
+                _ => self.module.borrow().find_decl(name).map(|d| d.to_type())?,
+            },
         })
     }
 
@@ -107,9 +173,10 @@ impl GIRGenerator {
         args: T,
         cst: &CSTNode,
     ) -> Res<Type> {
+        self.check_ambiguous(ident, cst)?;
         let mut ty = self
             .symbol(ident)
-            .or_err(cst, GErr::E300(ident.to_string()))?;
+            .or_error(cst, || self.unknown_type(ident))?;
         let args = args.map(|p| self.find_type(&p)).collect::<Res<Vec<_>>>()?;
         if !args.is_empty() {
             let args = Rc::new(args);
@@ -160,19 +227,106 @@ impl GIRGenerator {
     /// Boolean indicates if the cast was successful.
     pub(crate) fn try_cast(&mut self, value: Expr, ty: &Type) -> (Expr, bool) {
         let val_ty = value.get_type();
-        if val_ty.equal(ty, false) {
+        // Erroneous types were already reported where they came from
+        if val_ty.is_error() || ty.is_error() || val_ty.equal(ty, false) {
             return (value, true);
         }
+        if let Some(int) = Self::untyped_int(&value) {
+            match Self::cast_int_literal(int, ty) {
+                Some(literal) => {
+                    self.trace(format_args!("typed integer literal {} as {}", int, ty));
+                    return (literal, true);
+                }
+                // Out of range; a regular cast would silently truncate the value
+                None if Self::is_sized_int(ty) => return (value, false),
+                None => (),
+            }
+        }
+        if let Some(tuple) = self.cast_tuple_literal(&value, ty) {
+            self.trace(format_args!("cast tuple literal {} to {}", val_ty, ty));
+            return (tuple, true);
+        }
+        if let Some(array) = self.cast_array_literal(&value, ty) {
+            self.trace(format_args!("cast array literal {} to {}", val_ty, ty));
+            return (array, true);
+        }
+        let value = match self
+            .maybe_wrap_function(value, ty)
+            .and_then(|value| self.maybe_wrap_closure(value, ty))
+        {
+            Ok(value) => value,
+            Err(err) => {
+                // Reported here, so the caller must not report a type mismatch as well
+                self.error(err);
+                return (Expr::poisoned(), true);
+            }
+        };
+        let val_ty = value.get_type();
 
         (
             match self.can_cast_type(&val_ty, ty) {
-                Some(cast) => Expr::cast(value, ty.clone(), cast),
-                None => return (value, false),
+                Some(cast) => {
+                    self.trace(format_args!(
+                        "inserted cast from {} to {} ({})",
+                        val_ty,
+                        ty,
+                        cast_name(&cast)
+                    ));
+                    Expr::cast(value, ty.clone(), cast)
+                }
+                None => return self.wrap_nullable(value, ty),
             },
             true,
         )
     }
 
+    /// Casts a value to the inner type of a nullable type and then wraps it,
+    /// allowing values to be used as `T?` whenever they can be used as `T`,
+    /// like an implementor of an interface as the nullable interface.
+    fn wrap_nullable(&mut self, value: Expr, ty: &Type) -> (Expr, bool) {
+        match ty {
+            Type::Nullable(inner) if !matches!(value.get_type(), Type::Nullable(_)) => {
+                let (value, success) = self.try_cast(value, inner);
+                if success {
+                    (Expr::cast(value, ty.clone(), CastType::ToNullable), true)
+                } else {
+                    (value, false)
+                }
+            }
+            _ => (value, false),
+        }
+    }
+
+    /// Casts all elements of a tuple literal to the element types of the given tuple type,
+    /// allowing for example `return (5, null)` in a function returning `(u8, String?)`.
+    /// Returns None if the value is not a tuple literal of the same length
+    /// or any element cannot be cast.
+    fn cast_tuple_literal(&mut self, value: &Expr, ty: &Type) -> Option<Expr> {
+        let (inst, constructor, args) = match value {
+            Expr::Allocate {
+                ty: Type::Adt(inst),
+                constructor,
+                args,
+            } if self.intrinsics.is_tuple(&inst.ty) => (inst, constructor, args),
+            _ => return None,
+        };
+        match ty {
+            Type::Adt(goal) if Rc::ptr_eq(&goal.ty, &inst.ty) => (),
+            _ => return None,
+        }
+
+        let args = args
+            .iter()
+            .zip(ty.type_args().unwrap().iter())
+            .map(|(arg, goal)| self.cast_or_none(arg.clone(), goal))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Expr::Allocate {
+            ty: ty.clone(),
+            constructor: Rc::clone(constructor),
+            args,
+        })
+    }
+
     /// Same as above but utilizing `std::mem::replace` to only
     /// require a mutable reference at the cost of a slight performance penalty.
     /// Returns success.
@@ -183,11 +337,78 @@ impl GIRGenerator {
         success
     }
 
+    /// Returns the value of an integer literal without a type suffix,
+    /// which may be negated.
+    pub(crate) fn untyped_int(value: &Expr) -> Option<i128> {
+        match value {
+            Expr::Literal(Literal::Int { value, .. }) => Some(*value as i128),
+            Expr::Unary {
+                operator: SyntaxKind::Minus,
+                right: box Expr::Literal(Literal::Int { value, .. }),
+            } => Some(-(*value as i128)),
+            _ => None,
+        }
+    }
+
+    /// Turns an integer literal without a type suffix into a literal of the given type.
+    /// Returns None if the type is not an integer or the value is out of its range.
+    pub(crate) fn cast_int_literal(int: i128, ty: &Type) -> Option<Expr> {
+        let literal = match ty {
+            Type::I8 => Literal::I8(i8::try_from(int).ok()? as u8),
+            Type::I16 => Literal::I16(i16::try_from(int).ok()? as u16),
+            Type::I32 => Literal::I32(i32::try_from(int).ok()? as u32),
+            Type::I64 => Literal::I64(i64::try_from(int).ok()? as u64),
+            Type::U8 => Literal::U8(u8::try_from(int).ok()?),
+            Type::U16 => Literal::U16(u16::try_from(int).ok()?),
+            Type::U32 => Literal::U32(u32::try_from(int).ok()?),
+            Type::U64 => Literal::U64(u64::try_from(int).ok()?),
+            Type::Nullable(inner) => {
+                let literal = Self::cast_int_literal(int, inner)?;
+                return Some(Expr::cast(literal, ty.clone(), CastType::ToNullable));
+            }
+            _ => return None,
+        };
+        Some(Expr::literal(literal))
+    }
+
+    /// Returns if the type is a concrete integer type.
+    fn is_sized_int(ty: &Type) -> bool {
+        matches!(
+            ty,
+            Type::I8
+                | Type::I16
+                | Type::I32
+                | Type::I64
+                | Type::U8
+                | Type::U16
+                | Type::U32
+                | Type::U64
+        )
+    }
+
     /// Will try to make left and right be of the same type.
     /// Return value is `(NewType, left, right)`.
     /// If both are already the same type, this will just return the original type.
     /// If they cannot be made to match, it returns None as type.
     pub(crate) fn try_unify_type(&mut self, left: Expr, right: Expr) -> (Option<Type>, Expr, Expr) {
+        if !self.tracing() {
+            return self.try_unify_type_(left, right);
+        }
+
+        let (left_ty, right_ty) = (left.get_type(), right.get_type());
+        let result = self.try_unify_type_(left, right);
+        match &result.0 {
+            _ if left_ty == right_ty => (),
+            Some(ty) => self.trace(format_args!(
+                "unified {} and {} to {}",
+                left_ty, right_ty, ty
+            )),
+            None => self.trace(format_args!("could not unify {} and {}", left_ty, right_ty)),
+        }
+        result
+    }
+
+    fn try_unify_type_(&mut self, left: Expr, right: Expr) -> (Option<Type>, Expr, Expr) {
         let left_ty = left.get_type();
         let right_ty = right.get_type();
 
@@ -213,7 +434,7 @@ impl GIRGenerator {
 
                 // Run this function a second time to convert any
                 // value/nullable mismatches
-                return self.try_unify_type(
+                return self.try_unify_type_(
                     Expr::cast(left, ty.clone(), Bitcast),
                     Expr::cast(right, ty, Bitcast),
                 );
@@ -235,6 +456,15 @@ impl GIRGenerator {
             _ => (),
         };
 
+        // Integer literals without a type suffix should take the type of the other side
+        let (right, success) = match Self::untyped_int(&right) {
+            Some(_) => self.try_cast(right, &left_ty),
+            None => (right, false),
+        };
+        if success {
+            return (Some(left_ty), left, right);
+        }
+
         // Simply trying to cast one into the other is enough for all other cases
         let (left, success) = self.try_cast(left, &right_ty);
         if success {
@@ -0,0 +1,76 @@
+use std::rc::Rc;
+
+use ast::CSTNode;
+use error::GErr;
+use gir_nodes::{
+    declaration::{ADTType, Variable},
+    Expr, Instance, Type, ADT,
+};
+
+use crate::GIRGenerator;
+
+impl GIRGenerator {
+    /// Checks that the closure passed to `std/thread/spawn` can be safely
+    /// sent to another thread. Refcounts are not atomic by default,
+    /// so the closure may not capture any refcounted values unless
+    /// their type is marked with `@send`.
+    /// Does nothing if the callee is not `spawn` or refcounts are atomic.
+    pub(crate) fn check_thread_spawn(&self, callee: &Expr, args: &[Expr], cst: &CSTNode) {
+        let spawn = match &self.intrinsics.thread_spawn {
+            Some(spawn) if !self.flags.atomic_refcounts => spawn,
+            _ => return,
+        };
+        match callee {
+            Expr::Variable(Variable::Function(func)) if Rc::ptr_eq(&func.ty, spawn) => (),
+            _ => return,
+        }
+
+        match args.first() {
+            Some(Expr::Store {
+                value: box Expr::Closure { captured, .. },
+                ..
+            }) => {
+                for var in captured.iter().filter(|v| !Self::is_sendable(&v.ty)) {
+                    self.err(cst.clone(), GErr::E330(var.name.clone()))
+                }
+            }
+            Some(arg) if !Self::is_sendable(&arg.get_type()) => self.err(cst.clone(), GErr::E331),
+            _ => (),
+        }
+    }
+
+    /// Returns if values of the given type can be used from another thread.
+    /// Closures and type variables might contain anything, and are therefore never sendable.
+    /// Weak references are not either, since upgrading them is not atomic.
+    fn is_sendable(ty: &Type) -> bool {
+        match ty {
+            Type::Adt(inst) | Type::Nullable(box Type::Adt(inst)) if ty.is_ref_adt() => {
+                inst.ty.borrow().annotations.has("send")
+            }
+            // Value types are copied, but might still contain refcounted values
+            Type::Adt(inst) | Type::Nullable(box Type::Adt(inst)) => Self::fields_sendable(inst),
+            Type::Array(arr) => Self::is_sendable(&arr.ty),
+            Type::Closure(_) | Type::ClosureCaptured(_) | Type::Variable(_) | Type::Weak(_) => {
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Returns if all fields of the given value ADT, including the ones
+    /// of all cases if it is an enum, are sendable.
+    fn fields_sendable(inst: &Instance<ADT>) -> bool {
+        let adt = inst.ty.borrow();
+        let cases_sendable = match &adt.ty {
+            ADTType::Enum { cases, .. } => cases.values().all(|case| {
+                Self::fields_sendable(&Instance::new(Rc::clone(case), Rc::clone(inst.args())))
+            }),
+            _ => true,
+        };
+        cases_sendable
+            && adt
+                .fields
+                .values()
+                .all(|field| Self::is_sendable(&field.ty.resolve(inst.args())))
+    }
+}
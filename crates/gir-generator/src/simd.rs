@@ -0,0 +1,215 @@
+use std::{convert::TryFrom, rc::Rc};
+
+use ast::{CSTNode, Get};
+use error::{GErr, Res};
+use gir_nodes::{expression::Intrinsic, gir_err, types::ArrayType, Expr, Type};
+use syntax::kind::SyntaxKind;
+
+use crate::{result::EmitGIRError, GIRGenerator};
+
+/// SIMD vector types like `vec4f32`, which are lowered to LLVM vectors.
+/// They are only available to modules importing `std/simd`.
+impl GIRGenerator {
+    /// Returns the SIMD vector type with the given name, should it be one.
+    /// Vectors can have 2, 4, 8 or 16 elements of any sized number type.
+    pub(crate) fn vector_type(name: &str) -> Option<Type> {
+        let name = name.strip_prefix("vec")?;
+        let (len, elem) = name.split_at(name.find(|c: char| !c.is_ascii_digit())?);
+        let len = match len {
+            "2" | "4" | "8" | "16" => len.parse().unwrap(),
+            _ => return None,
+        };
+        let ty = match elem {
+            "i8" => Type::I8,
+            "i16" => Type::I16,
+            "i32" => Type::I32,
+            "i64" => Type::I64,
+            "u8" => Type::U8,
+            "u16" => Type::U16,
+            "u32" => Type::U32,
+            "u64" => Type::U64,
+            "f32" => Type::F32,
+            "f64" => Type::F64,
+            _ => return None,
+        };
+        Some(Type::Vector(Rc::new(ArrayType { ty, len })))
+    }
+
+    /// Returns if the current module can use SIMD vector types.
+    pub(crate) fn simd_enabled(&self) -> bool {
+        let module = self.module.borrow();
+        module.path.is(&["std", "simd"])
+            || module
+                .dependencies
+                .iter()
+                .any(|dep| dep.borrow().path.is(&["std", "simd"]))
+    }
+
+    /// Generates an elementwise arithmetic operation on vectors.
+    /// A number on either side is used for all elements, like `vec * 2.0`.
+    /// Integer vectors cannot be divided, as division by 0 is not checked for every element.
+    pub(crate) fn vector_binary(
+        &mut self,
+        cst: &CSTNode,
+        left: Expr,
+        operator: SyntaxKind,
+        right: Expr,
+    ) -> Res<Expr> {
+        let vec = match (left.get_type(), right.get_type()) {
+            (Type::Vector(vec), _) | (_, Type::Vector(vec)) => vec,
+            _ => unreachable!(),
+        };
+        match operator {
+            SyntaxKind::Plus
+            | SyntaxKind::Minus
+            | SyntaxKind::Star
+            | SyntaxKind::Slash
+            | SyntaxKind::Percent => (),
+            _ => return Err(gir_err(cst.clone(), GErr::E202)),
+        }
+        if matches!(operator, SyntaxKind::Slash | SyntaxKind::Percent) && vec.ty.is_int() {
+            return Err(gir_err(cst.clone(), GErr::E256));
+        }
+
+        let left = self.splat(left, &vec).or_err(cst, GErr::E202)?;
+        let right = self.splat(right, &vec).or_err(cst, GErr::E202)?;
+        Ok(Expr::binary(operator, left, right))
+    }
+
+    /// Turns a number into a vector containing it in all elements.
+    /// Returns vectors of the given type unchanged, and None for anything else.
+    fn splat(&mut self, value: Expr, vec: &Rc<ArrayType>) -> Option<Expr> {
+        let ty = Type::Vector(Rc::clone(vec));
+        match value.get_type() {
+            Type::Vector(_) if value.get_type() == ty => Some(value),
+            Type::Vector(_) => None,
+            _ => {
+                let value = self.cast_or_none(value, &vec.ty)?;
+                Some(Expr::Intrinsic(Intrinsic::VectorSplat {
+                    value: box value,
+                    ty,
+                }))
+            }
+        }
+    }
+
+    /// Calls a method on a vector. Available are `get(index)` and `set(index, value)`
+    /// like on fixed-size arrays, as well as `shuffle(mask)` and `shuffle(other, mask)`,
+    /// which create a new vector out of the elements at the indices in the mask.
+    /// Indices in the mask refer to the elements of `this`, followed by those of `other`.
+    pub(crate) fn vector_call(
+        &mut self,
+        object: Expr,
+        vec: &Rc<ArrayType>,
+        args: Vec<Expr>,
+        get: &Get,
+    ) -> Res<Expr> {
+        let expected: &[usize] = match &get.property().name()[..] {
+            "get" => &[1],
+            "set" => &[2],
+            "shuffle" => &[1, 2],
+            _ => return Err(gir_err(get.cst(), GErr::E210(None))),
+        };
+        if !expected.contains(&args.len()) {
+            return Err(gir_err(
+                get.cst(),
+                GErr::E216 {
+                    expected: *expected.last().unwrap(),
+                    was: args.len(),
+                },
+            ));
+        }
+
+        let mut args = args.into_iter();
+        let mut block = match &get.property().name()[..] {
+            "get" => {
+                let (mut block, index) =
+                    self.checked_index(args.next().unwrap(), vec.len, &get.cst)?;
+                block.push(Expr::Intrinsic(Intrinsic::VectorExtract {
+                    vector: box object,
+                    index: box index,
+                }));
+                block
+            }
+
+            "set" => {
+                if !object.assignable() {
+                    return Err(gir_err(get.cst(), GErr::E200(object.human_name())));
+                }
+                let (mut block, index) =
+                    self.checked_index(args.next().unwrap(), vec.len, &get.cst)?;
+                let value = self
+                    .cast_or_none(args.next().unwrap(), &vec.ty)
+                    .or_err(&get.cst, GErr::E201)?;
+                let insert = Expr::Intrinsic(Intrinsic::VectorInsert {
+                    vector: box object.clone(),
+                    index: box index,
+                    value: box value,
+                });
+                block.push(Expr::store(object, insert, false));
+                block
+            }
+
+            _ => {
+                let mask = args.next_back().unwrap();
+                let right = args.next();
+                if let Some(right) = &right {
+                    if right.get_type() != object.get_type() {
+                        return Err(gir_err(
+                            get.cst(),
+                            GErr::E218 {
+                                expected: object.get_type().to_string(),
+                                was: right.get_type().to_string(),
+                            },
+                        ));
+                    }
+                }
+                let max = if right.is_some() {
+                    vec.len * 2
+                } else {
+                    vec.len
+                };
+                let mask = Self::shuffle_mask(&mask, max).or_err(&get.cst, GErr::E252)?;
+                let ty = Type::Vector(Rc::new(ArrayType {
+                    ty: vec.ty.clone(),
+                    len: mask.len() as u64,
+                }));
+                vec![Expr::Intrinsic(Intrinsic::VectorShuffle {
+                    left: box object,
+                    right: right.map(Box::new),
+                    mask,
+                    ty,
+                })]
+            }
+        };
+
+        if block.len() == 1 {
+            Ok(block.pop().unwrap())
+        } else {
+            Ok(Expr::Block(block))
+        }
+    }
+
+    /// Returns the indices of a shuffle mask, which must be an array literal of
+    /// integer literals less than `max`. The mask must have a valid vector length.
+    fn shuffle_mask(mask: &Expr, max: u64) -> Option<Vec<u32>> {
+        let values = match mask {
+            Expr::ArrayLiteral { values, .. } => values,
+            _ => return None,
+        };
+        if !matches!(values.len(), 2 | 4 | 8 | 16) {
+            return None;
+        }
+        values
+            .iter()
+            .map(|value| {
+                let index = u64::try_from(Self::untyped_int(value)?).ok()?;
+                if index < max {
+                    Some(index as u32)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,117 @@
+use error::GErr;
+use gir_nodes::{Declaration, Type};
+use smol_str::SmolStr;
+
+use crate::GIRGenerator;
+
+/// Names of types built into the compiler, see `GIRGenerator::symbol`.
+const BUILTIN_TYPES: [&str; 15] = [
+    "None", "Never", "bool", "i8", "i16", "i32", "i64", "isize", "u8", "u16", "u32", "u64",
+    "usize", "f32", "f64",
+];
+
+/// Suggestions for names that could not be found,
+/// used to help with typos in error messages.
+impl GIRGenerator {
+    /// Returns the variable or function in scope with the name closest to the given one.
+    pub(crate) fn suggest_variable(&self, name: &str) -> Option<SmolStr> {
+        let mut names = self
+            .environments
+            .iter()
+            .chain(self.closure_data.iter().flat_map(|c| c.outer_env.iter()))
+            .flat_map(|env| env.values().map(|var| var.name.clone()))
+            .collect::<Vec<_>>();
+        names.extend(
+            self.local_functions
+                .iter()
+                .flat_map(|fns| fns.values().map(|func| func.borrow().name.clone())),
+        );
+        names.extend(
+            self.module
+                .borrow()
+                .visible_declarations()
+                .into_iter()
+                .map(|(name, _)| name),
+        );
+        closest_name(name, names.iter())
+    }
+
+    /// Returns the error for an unknown type, suggesting to import it
+    /// if another module declares a type of the name.
+    pub(crate) fn unknown_type(&self, name: &SmolStr) -> GErr {
+        GErr::E300 {
+            name: name.to_string(),
+            suggestion: self.suggest_type(name),
+            module: self
+                .type_index
+                .get(name)
+                .and_then(|modules| modules.first())
+                .map(|module| module.to_string()),
+        }
+    }
+
+    /// Returns the type in scope with the name closest to the given one.
+    fn suggest_type(&self, name: &str) -> Option<SmolStr> {
+        let mut names = BUILTIN_TYPES
+            .iter()
+            .map(|name| SmolStr::new(name))
+            .collect::<Vec<_>>();
+        if let Some(params) = &self.type_params {
+            names.extend(params.iter().map(|param| param.name.clone()));
+        }
+        names.extend(
+            self.module
+                .borrow()
+                .visible_declarations()
+                .into_iter()
+                .filter(|(_, decl)| matches!(decl, Declaration::Adt(_)))
+                .map(|(name, _)| name),
+        );
+        closest_name(name, names.iter())
+    }
+
+    /// Returns the field or method of the given type with the name closest to the given one.
+    pub(crate) fn suggest_member(&self, ty: &Type, name: &str) -> Option<SmolStr> {
+        let mut names = Vec::new();
+        if let Some(adt) = ty.try_adt() {
+            let adt = adt.ty.borrow();
+            names.extend(adt.fields.keys().cloned());
+            names.extend(adt.methods.keys().cloned());
+        }
+        if let Some(impls) = self.maybe_get_iface_impls(ty) {
+            names.extend(impls.borrow().methods.keys().cloned());
+        }
+        closest_name(name, names.iter())
+    }
+}
+
+/// Returns the candidate closest to the given name,
+/// if it is close enough to likely be a misspelling of it.
+fn closest_name<'a>(name: &str, candidates: impl Iterator<Item = &'a SmolStr>) -> Option<SmolStr> {
+    // One edit for every 3 characters, so that short names do not match everything
+    let max_distance = (name.chars().count() / 3).max(1);
+    candidates
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by(|(a, a_name), (b, b_name)| a.cmp(b).then_with(|| a_name.cmp(b_name)))
+        .map(|(_, candidate)| candidate.clone())
+}
+
+/// The Levenshtein distance between two strings: How many characters need
+/// to be inserted, removed or replaced to turn one into the other.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    // The distances of the prefix of `a` seen so far to each prefix of `b`
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let replaced = diagonal + (a_char != *b_char) as usize;
+            diagonal = row[j + 1];
+            row[j + 1] = replaced.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
@@ -0,0 +1,63 @@
+use std::{cell::RefCell, fmt::Arguments, io::Write, rc::Rc};
+
+use gir_nodes::expression::CastType;
+
+use crate::GIRGenerator;
+
+/// A writer receiving a line for every type checking decision
+/// of the generator, like inserted casts, unified types and resolved methods.
+/// Meant for understanding why an expression got the type it has,
+/// and for debugging the compiler itself.
+#[derive(Clone)]
+pub struct Tracer(Rc<RefCell<dyn Write>>);
+
+impl Tracer {
+    pub fn new<W: Write + 'static>(writer: W) -> Self {
+        Self(Rc::new(RefCell::new(writer)))
+    }
+}
+
+impl GIRGenerator {
+    /// Writes a decision to the tracer, if any.
+    /// It is prefixed with the location of the expression currently compiling.
+    /// Decisions made inside the standard library are not traced,
+    /// as they would drown out the ones in the user's code.
+    pub(crate) fn trace(&self, decision: Arguments) {
+        let tracer = match &self.tracer {
+            Some(tracer) if !self.path.is(&["std"]) => tracer,
+            _ => return,
+        };
+
+        let module = self.module.borrow();
+        let location = match &self.trace_node {
+            Some(node) => {
+                let start = node.text_range().start as usize;
+                let before = &module.src[..start];
+                let line = before.matches('\n').count() + 1;
+                let column = start - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+                format!("{} L{}:{}", module.path, line, column)
+            }
+            None => format!("{}", module.path),
+        };
+        writeln!(tracer.0.borrow_mut(), "{}: {}", location, decision).ok();
+    }
+
+    /// If tracing is enabled.
+    /// Used to avoid work only required for tracing.
+    pub(crate) fn tracing(&self) -> bool {
+        self.tracer.is_some()
+    }
+}
+
+/// Returns a short description of a cast for tracing.
+pub(crate) fn cast_name(cast: &CastType) -> &'static str {
+    match cast {
+        CastType::Number => "number cast",
+        CastType::ToValue => "reference to value",
+        CastType::Bitcast => "bitcast",
+        CastType::ToNullable => "to nullable",
+        CastType::FromNullable => "from nullable",
+        CastType::ToInterface(_) => "to interface",
+        CastType::Vector => "array/vector cast",
+    }
+}
@@ -8,11 +8,12 @@ use gir_nodes::{
     Type,
 };
 
-use crate::GIRGenerator;
+use crate::{result::EmitGIRError, GIRGenerator};
 use ast::CSTNode;
 use gir_nodes::{
     declaration::Visibility,
     types::{TypeArguments, TypeParameter},
+    Expr,
 };
 use syntax::kind::SyntaxKind;
 
@@ -23,6 +24,8 @@ impl GIRGenerator {
         match (ty, goal) {
             // Any, just return a no-op cast
             (Type::Any, _) | (_, Type::Any) => Some(CastType::Bitcast),
+            // Never, which does not produce a value to cast
+            (Type::Never, _) => Some(CastType::Bitcast),
 
             // Interface cast
             _ if self
@@ -41,6 +44,16 @@ impl GIRGenerator {
             // `null` to nullable cast
             (Type::Null, Type::Nullable(_)) => Some(CastType::ToNullable),
 
+            // Strong to weak reference cast
+            (_, Type::Weak(inner)) if ty.equal(inner, true) => Some(CastType::Bitcast),
+
+            // Fixed-size array to SIMD vector cast and back
+            (Type::Array(arr), Type::Vector(vec)) | (Type::Vector(vec), Type::Array(arr))
+                if arr == vec =>
+            {
+                Some(CastType::Vector)
+            }
+
             // Enum case to enum cast
             (Type::Adt(adt), Type::Adt(other)) => match &adt.ty.borrow().ty {
                 ADTType::EnumCase { parent, .. }
@@ -64,6 +77,19 @@ impl GIRGenerator {
                 _ => None,
             },
 
+            // Nullable enum case to nullable parent cast
+            (Type::Nullable(box Type::Adt(adt)), Type::Nullable(box Type::Adt(other))) => {
+                match &adt.ty.borrow().ty {
+                    ADTType::EnumCase { parent, .. }
+                        if Rc::ptr_eq(parent, &other.ty) && other.args() == adt.args() =>
+                    {
+                        Some(CastType::Bitcast)
+                    }
+
+                    _ => None,
+                }
+            }
+
             // Number cast
             _ if ty.is_int() && goal.is_int() => Some(CastType::Number),
             _ if ty.is_float() && goal.is_float() => Some(CastType::Number),
@@ -72,16 +98,90 @@ impl GIRGenerator {
         }
     }
 
+    /// Returns an error explaining that type arguments must match exactly,
+    /// if [ty] and [goal] are the same generic type with type arguments that
+    /// could otherwise be cast to each other, like `Array[Enum:Case]` to `Array[Enum]`.
+    /// Such casts are not allowed, since the value could then be used to store
+    /// a different case in the array, which would break its original type.
+    pub(crate) fn variance_error(&mut self, ty: &Type, goal: &Type) -> Option<GErr> {
+        let (adt, other) = match (ty, goal) {
+            (Type::Nullable(inner), Type::Nullable(goal)) => {
+                return self.variance_error(inner, goal)
+            }
+            (_, Type::Nullable(goal)) => return self.variance_error(ty, goal),
+            (Type::Adt(adt), Type::Adt(other)) if Rc::ptr_eq(&adt.ty, &other.ty) => (adt, other),
+            _ => return None,
+        };
+
+        let args = adt.args().iter().zip(other.args().iter());
+        let mut differ = false;
+        for (arg, goal_arg) in args {
+            if arg.equal(goal_arg, false) {
+                continue;
+            }
+            if self.can_cast_type(arg, goal_arg).is_none() {
+                return None;
+            }
+            differ = true;
+        }
+        if differ {
+            Some(GErr::E254 {
+                expected: goal.to_string(),
+                was: ty.to_string(),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Widens both operands of a binary operator on numbers of different types
+    /// to a type able to hold all values of both, like `i32 + i64` to `i64`
+    /// or `u8 + f32` to `f32`. Errors if there is no such type, as is the case
+    /// for integers of different signedness or integers too large for a float;
+    /// these require an explicit cast.
+    /// Integer literals without a type suffix are left alone, since they
+    /// take the type of the other side instead.
+    pub(crate) fn widen_numbers(
+        &mut self,
+        cst: &CSTNode,
+        left: Expr,
+        right: Expr,
+    ) -> Res<(Expr, Expr)> {
+        let (left_ty, right_ty) = (left.get_type(), right.get_type());
+        let untyped = Self::untyped_int(&left).is_some() || Self::untyped_int(&right).is_some();
+        let (left_num, right_num) = match (NumberType::of(&left_ty), NumberType::of(&right_ty)) {
+            (Some(l), Some(r)) if left_ty != right_ty && !untyped => (l, r),
+            _ => return Ok((left, right)),
+        };
+
+        let ty = left_num
+            .widen(right_num)
+            .or_error(cst, || GErr::E248 {
+                left: left_ty.to_string(),
+                right: right_ty.to_string(),
+            })?
+            .to_type();
+        let widen = |value: Expr, value_ty: &Type| {
+            if *value_ty == ty {
+                value
+            } else {
+                Expr::cast(value, ty.clone(), CastType::Number)
+            }
+        };
+        Ok((widen(left, &left_ty), widen(right, &right_ty)))
+    }
+
     pub(crate) fn validate_type_args(
         &self,
         args: &TypeArguments,
         params: &[TypeParameter],
         cst: &CSTNode,
     ) {
-        for (index, (arg, param)) in args
+        let resolve_args = Rc::new(args.to_vec());
+        for (index, (arg, bound)) in args
             .iter()
-            .zip(params.iter())
-            .filter(|(a, p)| !self.matches_bound(a, &p.bound))
+            .zip(params.iter().map(|p| p.bound.resolve(&resolve_args)))
+            .filter(|(a, b)| !self.matches_bound(a, b))
             .enumerate()
         {
             self.err(
@@ -89,7 +189,7 @@ impl GIRGenerator {
                 GErr::E239 {
                     index,
                     argument: arg.to_string(),
-                    bound: param.bound.to_string(),
+                    bound: bound.to_string(),
                 },
             )
         }
@@ -98,6 +198,8 @@ impl GIRGenerator {
     /// Returns if the type matches this bound and can be used.
     pub(crate) fn matches_bound(&self, ty: &Type, bound: &TypeParameterBound) -> bool {
         match bound {
+            _ if ty.is_error() => true,
+            TypeParameterBound::Interface(i) if i.is_error() => true,
             TypeParameterBound::Interface(i) => {
                 let impls = self.maybe_get_iface_impls(ty);
                 if let Some(impls) = impls {
@@ -126,7 +228,7 @@ impl GIRGenerator {
     /// Can error if bound cannot be resolved.
     pub(crate) fn bound_from_ast(&mut self, ast: Option<&ast::Type>) -> Res<TypeParameterBound> {
         Ok(if let Some(ast) = ast {
-            match ast.get() {
+            match ast.get()? {
                 ast::TypeE::Ident(tok) => match &tok[..] {
                     "Primitive" => TypeParameterBound::Bound(Bound::Primitive),
                     "Number" => TypeParameterBound::Bound(Bound::Number),
@@ -167,3 +269,75 @@ impl GIRGenerator {
         vis.unwrap_or(Visibility::Public)
     }
 }
+
+/// A concrete number type and its size in bits,
+/// used for widening operands of binary operators.
+#[derive(Copy, Clone)]
+enum NumberType {
+    Signed(u32),
+    Unsigned(u32),
+    Float(u32),
+}
+
+impl NumberType {
+    fn of(ty: &Type) -> Option<NumberType> {
+        Some(match ty {
+            Type::I8 => NumberType::Signed(8),
+            Type::I16 => NumberType::Signed(16),
+            Type::I32 => NumberType::Signed(32),
+            Type::I64 => NumberType::Signed(64),
+            Type::U8 => NumberType::Unsigned(8),
+            Type::U16 => NumberType::Unsigned(16),
+            Type::U32 => NumberType::Unsigned(32),
+            Type::U64 => NumberType::Unsigned(64),
+            Type::F32 => NumberType::Float(32),
+            Type::F64 => NumberType::Float(64),
+            _ => return None,
+        })
+    }
+
+    /// Returns the smallest number type able to represent
+    /// all values of both types, if there is one.
+    fn widen(self, other: NumberType) -> Option<NumberType> {
+        use NumberType::*;
+        match (self, other) {
+            (Signed(a), Signed(b)) => Some(Signed(a.max(b))),
+            (Unsigned(a), Unsigned(b)) => Some(Unsigned(a.max(b))),
+            (Float(a), Float(b)) => Some(Float(a.max(b))),
+
+            // Floats only represent integers exactly up to the size of their mantissa,
+            // which is 24 bits for f32 and 53 bits for f64
+            (Float(float), Signed(int))
+            | (Signed(int), Float(float))
+            | (Float(float), Unsigned(int))
+            | (Unsigned(int), Float(float)) => match int {
+                0..=16 => Some(Float(float)),
+                17..=32 => Some(Float(64)),
+                _ => None,
+            },
+
+            // Signed types can only represent all values of smaller unsigned types
+            (Signed(signed), Unsigned(unsigned)) | (Unsigned(unsigned), Signed(signed))
+                if signed > unsigned =>
+            {
+                Some(Signed(signed))
+            }
+            (Signed(_), Unsigned(_)) | (Unsigned(_), Signed(_)) => None,
+        }
+    }
+
+    fn to_type(self) -> Type {
+        match self {
+            NumberType::Signed(8) => Type::I8,
+            NumberType::Signed(16) => Type::I16,
+            NumberType::Signed(32) => Type::I32,
+            NumberType::Signed(_) => Type::I64,
+            NumberType::Unsigned(8) => Type::U8,
+            NumberType::Unsigned(16) => Type::U16,
+            NumberType::Unsigned(32) => Type::U32,
+            NumberType::Unsigned(_) => Type::U64,
+            NumberType::Float(32) => Type::F32,
+            NumberType::Float(_) => Type::F64,
+        }
+    }
+}
@@ -10,6 +10,9 @@ use std::{
     rc::Rc,
 };
 
+/// The IR of a GIR declaration. Declarations with type parameters keep one
+/// instance per set of type arguments. As the whole program is compiled into
+/// a single LLVM module, all modules using an instance share it.
 pub enum IRAdapter<T: Copy, A: Hash + Eq> {
     NoTypeArgs(Option<T>),
     TypeArgs(IndexMap<Rc<A>, T>),
@@ -43,6 +43,15 @@ impl Declaration {
         }
     }
 
+    /// Returns if both are the same declaration.
+    pub fn is(&self, other: &Declaration) -> bool {
+        match (self, other) {
+            (Self::Function(a), Self::Function(b)) => Rc::ptr_eq(a, b),
+            (Self::Adt(a), Self::Adt(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
     pub fn visible(&self, from: &ModPath) -> bool {
         match self {
             Self::Function(f) => f.borrow().visible(from),
@@ -98,6 +107,8 @@ pub struct ADT {
     pub ty: ADTType,
     /// The AST of this ADT
     pub ast: ast::Adt,
+    /// The annotations of this ADT, like `@derive(Hash)`.
+    pub annotations: Annotations,
     /// The module this ADT was declared in
     pub module: MutRc<Module>,
     /// IR-level information of this ADT
@@ -117,6 +128,15 @@ impl ADT {
         self.visibility.from(&self.module.borrow().path, from)
     }
 
+    /// Is this a flags enum or one of its cases?
+    pub fn is_flags(&self) -> bool {
+        match &self.ty {
+            ADTType::Enum { flags, .. } => *flags,
+            ADTType::EnumCase { parent, .. } => parent.borrow().is_flags(),
+            _ => false,
+        }
+    }
+
     pub fn get_singleton_inst(inst: &MutRc<ADT>, args: &Rc<TypeArguments>) -> Option<Expr> {
         if let ADTType::EnumCase { ty, .. } = &inst.borrow().ty {
             if *ty == CaseType::Simple {
@@ -151,17 +171,25 @@ pub enum ADTType {
     Enum {
         /// All cases.
         cases: Rc<HashMap<SmolStr, MutRc<ADT>>>,
+        /// If this enum is a set of flags, see `@flags` in the gelix docs.
+        flags: bool,
     },
 
     /// An enum with known case.
-    EnumCase { parent: MutRc<ADT>, ty: CaseType },
+    EnumCase {
+        parent: MutRc<ADT>,
+        ty: CaseType,
+        /// The value identifying this case at runtime,
+        /// either given explicitly or one more than the previous case's.
+        discriminant: u64,
+    },
 }
 
 impl ADTType {
     /// Returns the cases of an enum type.
     /// Use on any other type will result in a panic.
     pub fn cases(&self) -> &HashMap<SmolStr, MutRc<ADT>> {
-        if let ADTType::Enum { cases } = self {
+        if let ADTType::Enum { cases, .. } = self {
             cases
         } else {
             unreachable!();
@@ -241,13 +269,34 @@ pub struct Function {
     pub type_parameters: Rc<TypeParameters>,
     /// A list of expressions that make up the func, executed in order.
     pub exprs: Vec<Expr>,
-    /// All variables declared inside the function.
-    pub variables: HashMap<SmolStr, Rc<LocalVariable>>,
+    /// All variables declared inside the function, in the order they were declared.
+    pub variables: IndexMap<SmolStr, Rc<LocalVariable>>,
     /// The return type of the function; Type::None if omitted.
     pub ret_type: Type,
     /// The AST for this function, if it is a user function
     /// and not compiler-generated.
     pub ast: Option<ast::Function>,
+    /// LLVM IR defining this function, given with `@inline_ir`.
+    /// Only present on extern functions.
+    pub inline_ir: Option<SmolStr>,
+    /// If LLVM should inline this function, given with `@inline` or `@noinline`.
+    pub inline: InlineHint,
+    /// If this function has no side effects, given with `@pure`.
+    /// Calls to pure functions with the same arguments can be deduplicated.
+    pub pure: bool,
+    /// If this function is rarely called, given with `@cold`.
+    pub cold: bool,
+    /// The system library defining this extern function, given with `@link`.
+    pub link: Option<SmolStr>,
+    /// The annotations of this function, like `@cold`.
+    pub annotations: Annotations,
+    /// If this function computes a lazy global like `lazy val a = 5`.
+    /// Its body is the initializer, which only runs on the first call;
+    /// later calls return the stored value.
+    pub lazy: bool,
+    /// Methods of enum cases overriding this enum method.
+    /// Calls to it run the override of the case `this` is, if any.
+    pub case_overrides: Vec<MutRc<Function>>,
     /// The module this was declared in.
     pub module: MutRc<Module>,
     /// IR data for this function, used by IR generator
@@ -261,9 +310,16 @@ impl Function {
 
     /// Inserts a variable into the functions allocation table.
     /// Returns the name of it (should be used since a change can be needed due to colliding names).
+    /// Colliding names are numbered by how often the name was declared before,
+    /// like `name-2` for the third variable called `name`, so that they
+    /// do not change with unrelated variables.
     pub fn insert_var(&mut self, mut name: SmolStr, var: Rc<LocalVariable>) -> SmolStr {
         if self.variables.contains_key(&name) {
-            name = SmolStr::new(format!("{}-{}", name, self.variables.len()));
+            let base = name;
+            name = (1..)
+                .map(|count| SmolStr::new(format!("{}-{}", base, count)))
+                .find(|name| !self.variables.contains_key(name))
+                .unwrap();
         }
         self.variables.insert(name.clone(), var);
         name
@@ -286,6 +342,83 @@ impl Function {
     }
 }
 
+/// Building the body of a shared function. Every method only borrows
+/// the function for the duration of the call, after its arguments are computed;
+/// with `func.borrow_mut().exprs.push(expr)`, the function is already borrowed
+/// while `expr` is evaluated, which panics if that borrows the function again.
+pub trait FunctionBody {
+    /// Appends an expression to the end of the body.
+    fn push_expr(&self, expr: Expr);
+
+    /// Appends a return of the given value to the end of the body.
+    fn push_return(&self, value: Expr) {
+        self.push_expr(Expr::ret(value))
+    }
+
+    /// Appends all given expressions to the end of the body.
+    fn extend_exprs(&self, exprs: Vec<Expr>);
+
+    /// Inserts all given expressions at the start of the body.
+    fn prepend_exprs(&self, exprs: Vec<Expr>);
+
+    /// Replaces the body with the given expressions.
+    fn set_exprs(&self, exprs: Vec<Expr>);
+}
+
+impl FunctionBody for MutRc<Function> {
+    fn push_expr(&self, expr: Expr) {
+        self.borrow_mut().exprs.push(expr)
+    }
+
+    fn extend_exprs(&self, exprs: Vec<Expr>) {
+        self.borrow_mut().exprs.extend(exprs)
+    }
+
+    fn prepend_exprs(&self, exprs: Vec<Expr>) {
+        self.borrow_mut().exprs.splice(0..0, exprs);
+    }
+
+    fn set_exprs(&self, exprs: Vec<Expr>) {
+        self.borrow_mut().exprs = exprs
+    }
+}
+
+/// The annotations of a declaration, like `@derive(Hash)` or `@cold`.
+/// Only contains annotations known to the compiler that apply to the declaration.
+#[derive(Debug, Clone, Default)]
+pub struct Annotations(Vec<ast::Annotation>);
+
+impl Annotations {
+    pub fn new(annotations: Vec<ast::Annotation>) -> Self {
+        Self(annotations)
+    }
+
+    /// Returns the first annotation with the given name.
+    pub fn get(&self, name: &str) -> Option<&ast::Annotation> {
+        self.0.iter().find(|annotation| annotation.name() == name)
+    }
+
+    /// Returns if there is an annotation with the given name.
+    pub fn has(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ast::Annotation> {
+        self.0.iter()
+    }
+}
+
+/// An inlining hint given on a function.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum InlineHint {
+    /// No hint given; left to LLVM.
+    Default,
+    /// Always inline, given with `@inline`.
+    Always,
+    /// Never inline, given with `@noinline`.
+    Never,
+}
+
 /// A variable that can be loaded to produce a value by user code.
 /// Can be either a global or local variable.
 #[derive(Debug, Clone)]
@@ -6,10 +6,11 @@
 
 use crate::{
     declaration::{Field, LocalVariable, Variable},
-    types::ToInstance,
+    types::{ToInstance, TypeParameterBound, TypeVariable},
     Function, Literal, Type,
 };
 use common::MutRc;
+use smol_str::SmolStr;
 use std::rc::Rc;
 use syntax::kind::SyntaxKind;
 
@@ -58,7 +59,27 @@ pub enum Expr {
         field: Rc<Field>,
     },
 
-    /// Store into an ADT or variable.
+    /// A fixed-size array literal with one value per element.
+    ArrayLiteral {
+        ty: Type,
+        values: Vec<Expr>,
+    },
+
+    /// A fixed-size array literal repeating a single value
+    /// for all elements, like `[0; 16]`.
+    ArrayRepeat {
+        ty: Type,
+        value: Box<Expr>,
+    },
+
+    /// An element of a fixed-size array. The index is a `u64`;
+    /// it is not checked to be within the bounds of the array.
+    Index {
+        object: Box<Expr>,
+        index: Box<Expr>,
+    },
+
+    /// Store into an ADT, variable or array element.
     Store {
         location: Box<Expr>,
         value: Box<Expr>,
@@ -103,6 +124,10 @@ pub enum Expr {
         else_branch: Box<Expr>,
         /// Returned type, if returning a value
         phi_type: Option<Type>,
+        /// If all conditions compare the same integer against distinct literals,
+        /// which are dense enough to be compiled to a jump table by LLVM.
+        /// The value is then only evaluated once.
+        jump_table: bool,
     },
 
     /// A conditional (for) loop.
@@ -151,6 +176,12 @@ impl Expr {
         Expr::Literal(Literal::None)
     }
 
+    /// The value of an expression that failed to generate, or that
+    /// uses such a value and would only produce further errors.
+    pub fn poisoned() -> Expr {
+        Expr::Literal(Literal::Error)
+    }
+
     pub fn type_get(inner: Type) -> Expr {
         match inner {
             Type::None => Expr::none_const(),
@@ -181,6 +212,13 @@ impl Expr {
         }
     }
 
+    pub fn index(obj: Expr, index: Expr) -> Expr {
+        Expr::Index {
+            object: Box::new(obj),
+            index: Box::new(index),
+        }
+    }
+
     pub fn store(loc: Expr, value: Expr, first_store: bool) -> Expr {
         Expr::Store {
             location: Box::new(loc),
@@ -225,6 +263,7 @@ impl Expr {
             branches,
             else_branch: Box::new(else_),
             phi_type,
+            jump_table: false,
         }
     }
 
@@ -265,6 +304,14 @@ impl Expr {
         Expr::Intrinsic(Intrinsic::Free(box val))
     }
 
+    pub fn upgrade_weak(weak: Expr) -> Expr {
+        Expr::Intrinsic(Intrinsic::UpgradeWeak(box weak))
+    }
+
+    pub fn enum_tag(value: Expr) -> Expr {
+        Expr::Intrinsic(Intrinsic::EnumTag(box value))
+    }
+
     pub fn iface_call(callee: Expr, index: usize, arguments: Vec<Expr>, ret_type: Type) -> Expr {
         Expr::Intrinsic(Intrinsic::IfaceCall {
             iface: box callee,
@@ -276,7 +323,7 @@ impl Expr {
 
     pub fn get_type(&self) -> Type {
         match self {
-            Expr::Block(exprs) => exprs.last().unwrap().get_type(),
+            Expr::Block(exprs) => exprs.last().map_or(Type::None, Expr::get_type),
 
             Expr::Literal(literal) => literal.get_type(),
 
@@ -286,6 +333,11 @@ impl Expr {
                 field.ty.resolve(&object.get_type().type_args().unwrap())
             }
 
+            Expr::Index { object, .. } => match object.get_type() {
+                Type::Array(arr) => arr.ty.clone(),
+                _ => panic!("Invalid array"),
+            },
+
             Expr::Store { value, .. } => value.get_type(),
 
             Expr::Binary {
@@ -301,6 +353,11 @@ impl Expr {
                 }
             }
 
+            Expr::Call {
+                callee: box Expr::Intrinsic(Intrinsic::ConcreteMethodGet(get)),
+                ..
+            } => get.ret_type(),
+
             Expr::Call { callee, .. } => match callee.get_type() {
                 Type::Function(func) => func.ty.borrow().ret_type.resolve(func.args()),
                 Type::Closure(closure) => closure.ret_type.clone(),
@@ -319,7 +376,10 @@ impl Expr {
 
             Expr::Break(_) | Expr::Return(_) => Type::Any,
 
-            Expr::Cast { to, .. } | Expr::Allocate { ty: to, .. } => to.clone(),
+            Expr::Cast { to, .. }
+            | Expr::Allocate { ty: to, .. }
+            | Expr::ArrayLiteral { ty: to, .. }
+            | Expr::ArrayRepeat { ty: to, .. } => to.clone(),
 
             Expr::Closure { function, .. } => function.borrow().to_closure_type(),
 
@@ -327,6 +387,20 @@ impl Expr {
 
             Expr::Intrinsic(intrin) => match intrin {
                 Intrinsic::ConcreteMethodGet(get) => get.iface_method.to_type(),
+                Intrinsic::UpgradeWeak(weak) => match weak.get_type() {
+                    Type::Weak(inner) => Type::Nullable(inner),
+                    _ => Type::Any,
+                },
+                Intrinsic::VectorExtract { vector, .. } => match vector.get_type() {
+                    Type::Vector(vec) => vec.ty.clone(),
+                    _ => Type::Any,
+                },
+                Intrinsic::VectorInsert { vector, .. } => vector.get_type(),
+                Intrinsic::Expect { .. } => Type::Bool,
+                Intrinsic::EnumTag(_) => Type::I64,
+                Intrinsic::VectorSplat { ty, .. } | Intrinsic::VectorShuffle { ty, .. } => {
+                    ty.clone()
+                }
                 _ => Type::Any,
             },
         }
@@ -356,6 +430,8 @@ impl Expr {
 
             Expr::Load { field, .. } => field.mutable,
 
+            Expr::Index { object, .. } => object.assignable(),
+
             Expr::If {
                 then_branch,
                 else_branch,
@@ -367,6 +443,7 @@ impl Expr {
                 branches,
                 else_branch,
                 phi_type,
+                ..
             } => {
                 phi_type.is_some()
                     && else_branch.assignable()
@@ -377,6 +454,27 @@ impl Expr {
         }
     }
 
+    /// If this expression always leaves the current block,
+    /// either by returning from the function or breaking out of a loop.
+    pub fn diverges(&self) -> bool {
+        match self {
+            Expr::Block(exprs) => exprs.iter().any(Expr::diverges),
+            Expr::Return(_) | Expr::Break(_) => true,
+            Expr::Call { .. } => self.get_type() == Type::Never,
+            Expr::If {
+                then_branch,
+                else_branch,
+                ..
+            } => then_branch.diverges() && else_branch.diverges(),
+            Expr::Switch {
+                branches,
+                else_branch,
+                ..
+            } => branches.iter().all(|(_, br)| br.diverges()) && else_branch.diverges(),
+            _ => false,
+        }
+    }
+
     /// A 'human readable' name used for error reporting.
     /// For example, when the user tries assigning to a non-assignable value,
     /// the error message would be "Cannot assign to {{ expr.human_name() }}."
@@ -397,6 +495,9 @@ impl Expr {
             Expr::Allocate { .. } => "allocation",
             Expr::Load { field, .. } if field.mutable => "mutable field",
             Expr::Load { .. } => "immutable field",
+            Expr::ArrayLiteral { .. } | Expr::ArrayRepeat { .. } => "array literal",
+            Expr::Index { object, .. } if object.assignable() => "mutable array element",
+            Expr::Index { .. } => "immutable array element",
             Expr::Store { .. } => "assignment",
             Expr::Binary { .. } => "infix operation",
             Expr::Unary { .. } => "prefix operation",
@@ -412,6 +513,216 @@ impl Expr {
             Expr::Intrinsic(_) => "<intrinsic>",
         }
     }
+
+    /// Calls the function on all direct children of the expression, in evaluation order.
+    pub fn for_each_child<'e>(&'e self, f: &mut impl FnMut(&'e Expr)) {
+        match self {
+            Expr::Block(exprs)
+            | Expr::Allocate { args: exprs, .. }
+            | Expr::ArrayLiteral { values: exprs, .. } => exprs.iter().for_each(f),
+
+            Expr::Load { object: inner, .. }
+            | Expr::ArrayRepeat { value: inner, .. }
+            | Expr::Unary { right: inner, .. }
+            | Expr::Break(inner)
+            | Expr::Return(inner)
+            | Expr::Cast { inner, .. }
+            | Expr::Intrinsic(Intrinsic::IncRc(inner))
+            | Expr::Intrinsic(Intrinsic::DecRc(inner))
+            | Expr::Intrinsic(Intrinsic::Free(inner))
+            | Expr::Intrinsic(Intrinsic::UpgradeWeak(inner))
+            | Expr::Intrinsic(Intrinsic::EnumTag(inner))
+            | Expr::Intrinsic(Intrinsic::Expect { value: inner, .. })
+            | Expr::Intrinsic(Intrinsic::VectorSplat { value: inner, .. }) => f(inner),
+
+            Expr::Index {
+                object: first,
+                index: second,
+            }
+            | Expr::Store {
+                location: first,
+                value: second,
+                ..
+            }
+            | Expr::Binary {
+                left: first,
+                right: second,
+                ..
+            }
+            | Expr::Intrinsic(Intrinsic::VectorExtract {
+                vector: first,
+                index: second,
+            }) => {
+                f(first);
+                f(second);
+            }
+
+            Expr::Call {
+                callee: first,
+                arguments,
+            }
+            | Expr::Intrinsic(Intrinsic::IfaceCall {
+                iface: first,
+                arguments,
+                ..
+            }) => {
+                f(first);
+                arguments.iter().for_each(f);
+            }
+
+            Expr::If {
+                condition: first,
+                then_branch: second,
+                else_branch: third,
+                ..
+            }
+            | Expr::Loop {
+                condition: first,
+                body: second,
+                else_branch: third,
+                ..
+            }
+            | Expr::Intrinsic(Intrinsic::VectorInsert {
+                vector: first,
+                index: second,
+                value: third,
+            }) => {
+                f(first);
+                f(second);
+                f(third);
+            }
+
+            Expr::Switch {
+                branches,
+                else_branch,
+                ..
+            } => {
+                for (condition, branch) in branches {
+                    f(condition);
+                    f(branch);
+                }
+                f(else_branch);
+            }
+
+            Expr::Intrinsic(Intrinsic::VectorShuffle { left, right, .. }) => {
+                f(left);
+                if let Some(right) = right {
+                    f(right);
+                }
+            }
+
+            Expr::Literal(_)
+            | Expr::Variable(_)
+            | Expr::Closure { .. }
+            | Expr::TypeGet(_)
+            | Expr::Intrinsic(Intrinsic::ConcreteMethodGet(_)) => (),
+        }
+    }
+
+    /// Mutable version of [Expr::for_each_child], visiting children in the same order.
+    pub fn for_each_child_mut(&mut self, f: &mut impl FnMut(&mut Expr)) {
+        match self {
+            Expr::Block(exprs)
+            | Expr::Allocate { args: exprs, .. }
+            | Expr::ArrayLiteral { values: exprs, .. } => exprs.iter_mut().for_each(f),
+
+            Expr::Load { object: inner, .. }
+            | Expr::ArrayRepeat { value: inner, .. }
+            | Expr::Unary { right: inner, .. }
+            | Expr::Break(inner)
+            | Expr::Return(inner)
+            | Expr::Cast { inner, .. }
+            | Expr::Intrinsic(Intrinsic::IncRc(inner))
+            | Expr::Intrinsic(Intrinsic::DecRc(inner))
+            | Expr::Intrinsic(Intrinsic::Free(inner))
+            | Expr::Intrinsic(Intrinsic::UpgradeWeak(inner))
+            | Expr::Intrinsic(Intrinsic::EnumTag(inner))
+            | Expr::Intrinsic(Intrinsic::Expect { value: inner, .. })
+            | Expr::Intrinsic(Intrinsic::VectorSplat { value: inner, .. }) => f(inner),
+
+            Expr::Index {
+                object: first,
+                index: second,
+            }
+            | Expr::Store {
+                location: first,
+                value: second,
+                ..
+            }
+            | Expr::Binary {
+                left: first,
+                right: second,
+                ..
+            }
+            | Expr::Intrinsic(Intrinsic::VectorExtract {
+                vector: first,
+                index: second,
+            }) => {
+                f(first);
+                f(second);
+            }
+
+            Expr::Call {
+                callee: first,
+                arguments,
+            }
+            | Expr::Intrinsic(Intrinsic::IfaceCall {
+                iface: first,
+                arguments,
+                ..
+            }) => {
+                f(first);
+                arguments.iter_mut().for_each(f);
+            }
+
+            Expr::If {
+                condition: first,
+                then_branch: second,
+                else_branch: third,
+                ..
+            }
+            | Expr::Loop {
+                condition: first,
+                body: second,
+                else_branch: third,
+                ..
+            }
+            | Expr::Intrinsic(Intrinsic::VectorInsert {
+                vector: first,
+                index: second,
+                value: third,
+            }) => {
+                f(first);
+                f(second);
+                f(third);
+            }
+
+            Expr::Switch {
+                branches,
+                else_branch,
+                ..
+            } => {
+                for (condition, branch) in branches {
+                    f(condition);
+                    f(branch);
+                }
+                f(else_branch);
+            }
+
+            Expr::Intrinsic(Intrinsic::VectorShuffle { left, right, .. }) => {
+                f(left);
+                if let Some(right) = right {
+                    f(right);
+                }
+            }
+
+            Expr::Literal(_)
+            | Expr::Variable(_)
+            | Expr::Closure { .. }
+            | Expr::TypeGet(_)
+            | Expr::Intrinsic(Intrinsic::ConcreteMethodGet(_)) => (),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -423,6 +734,9 @@ pub enum Intrinsic {
     /// Manually free a value. Only calls free,
     /// does not do anything else.
     Free(Box<Expr>),
+    /// Upgrade a weak reference to a strong one, producing
+    /// null if the referenced value was already freed.
+    UpgradeWeak(Box<Expr>),
     /// Perform a virtual call. callee must
     /// be a type with an embedded vtable
     /// (currently only interfaces).
@@ -433,6 +747,40 @@ pub enum Intrinsic {
         ret_type: Type,
     },
     ConcreteMethodGet(ConcreteMethodGet),
+    /// Create a SIMD vector with the value in all elements.
+    VectorSplat {
+        value: Box<Expr>,
+        ty: Type,
+    },
+    /// Get the element of a SIMD vector at the given index.
+    VectorExtract {
+        vector: Box<Expr>,
+        index: Box<Expr>,
+    },
+    /// Produce a copy of a SIMD vector with the element
+    /// at the given index replaced.
+    VectorInsert {
+        vector: Box<Expr>,
+        index: Box<Expr>,
+        value: Box<Expr>,
+    },
+    /// Create a new SIMD vector out of the elements of one or two vectors,
+    /// where the elements of `right` follow those of `left`.
+    /// The mask contains the index of the element to use for each element of the result.
+    VectorShuffle {
+        left: Box<Expr>,
+        right: Option<Box<Expr>>,
+        mask: Vec<u32>,
+        ty: Type,
+    },
+    /// A boolean that is expected to usually have the given value,
+    /// created by `likely` and `unlikely`.
+    Expect {
+        value: Box<Expr>,
+        expected: bool,
+    },
+    /// Get the discriminant of an enum value's case.
+    EnumTag(Box<Expr>),
 }
 
 /// Gets the concrete method of an iface implementor.
@@ -444,6 +792,18 @@ pub struct ConcreteMethodGet {
     pub iface_method: MutRc<Function>,
 }
 
+impl ConcreteMethodGet {
+    /// The return type of the method, with `Self` being the type parameter it is called on.
+    pub fn ret_type(&self) -> Type {
+        let this = Type::Variable(TypeVariable {
+            index: self.index,
+            name: SmolStr::new_inline("Self"),
+            bound: TypeParameterBound::Interface(box self.interface.clone()),
+        });
+        self.iface_method.borrow().ret_type.replace_self(&this)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum CastType {
     /// A numeric cast between any number type
@@ -462,4 +822,7 @@ pub enum CastType {
     FromNullable,
     // Type is the implementor type
     ToInterface(Type),
+    /// Cast between a fixed-size array and a SIMD vector
+    /// with the same element type and length.
+    Vector,
 }
@@ -11,7 +11,8 @@ pub struct IFaceImpl {
     pub methods: HashMap<SmolStr, MutRc<Function>>,
     /// Module that the impl block is in.
     pub module: MutRc<Module>,
-    pub ast: ast::IfaceImpl,
+    /// The impl block, missing on impls generated with `@derive`.
+    pub ast: Option<ast::IfaceImpl>,
 }
 
 /// A struct representing all interfaces implemented by a type.
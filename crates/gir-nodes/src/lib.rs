@@ -5,12 +5,15 @@ pub mod declaration;
 pub mod expression;
 mod iface_impls;
 pub mod literal;
+pub mod mangle;
 pub mod module;
 mod printer;
+#[cfg(feature = "serde")]
+mod serialize;
 pub mod types;
 
 use ast::CSTNode;
-pub use declaration::{Declaration, Function, ADT};
+pub use declaration::{Declaration, Function, FunctionBody, ADT};
 use error::{Error, ErrorSpan, GErr};
 pub use expression::Expr;
 pub use iface_impls::{IFaceImpl, IFaceImpls};
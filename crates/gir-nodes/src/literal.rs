@@ -4,10 +4,17 @@ use smol_str::SmolStr;
 #[derive(Clone, Debug)]
 pub enum Literal {
     Any,
+    /// The value of an expression that failed to generate, see [Type::Error].
+    Error,
     None,
     Null,
     Bool(bool),
 
+    /// An integer literal without a type suffix.
+    /// Takes the integer type required by its context, or `ty` if there is none,
+    /// which is the default integer type of the compilation.
+    Int { value: u64, ty: Type },
+
     I8(u8),
     I16(u16),
     I32(u32),
@@ -28,6 +35,7 @@ impl Literal {
     pub fn get_type(&self) -> Type {
         match self {
             Literal::Any => Type::Any,
+            Literal::Error => Type::Error,
             Literal::None => Type::None,
             Literal::Null => Type::Null,
             Literal::Bool(_) => Type::Bool,
@@ -41,7 +49,32 @@ impl Literal {
             Literal::U64(_) => Type::U64,
             Literal::F32(_) => Type::F32,
             Literal::F64(_) => Type::F64,
-            Literal::String { ty, .. } => ty.clone(),
+            Literal::Int { ty, .. } | Literal::String { ty, .. } => ty.clone(),
+        }
+    }
+
+    /// If this literal is a number equal to 0.
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Literal::I8(num) | Literal::U8(num) => *num == 0,
+            Literal::I16(num) | Literal::U16(num) => *num == 0,
+            Literal::I32(num) | Literal::U32(num) => *num == 0,
+            Literal::I64(num) | Literal::U64(num) | Literal::Int { value: num, .. } => *num == 0,
+            Literal::F32(num) => *num == 0.0,
+            Literal::F64(num) => *num == 0.0,
+            _ => false,
+        }
+    }
+
+    /// If this literal is a signed integer equal to -1, which has every bit set.
+    pub fn is_minus_one(&self) -> bool {
+        match self {
+            Literal::I8(num) => *num == u8::MAX,
+            Literal::I16(num) => *num == u16::MAX,
+            Literal::I32(num) => *num == u32::MAX,
+            Literal::I64(num) => *num == u64::MAX,
+            Literal::Int { value, ty } => ty.is_signed_int() && *value == u64::MAX,
+            _ => false,
         }
     }
 }
@@ -0,0 +1,205 @@
+//! The scheme used for naming functions in compiled code.
+//! Names are made of length-prefixed identifiers, so that they are unique
+//! for every module, declaration and type arguments, and can be turned back
+//! into a readable name with [demangle], like for backtraces.
+//!
+//! ```text
+//! symbol := "_G" ident* "_" ident+ ["I" type* "E"]   module path, declaration, type arguments
+//! type   := "N" ident* "_" ident ["I" type* "E"] "E" ADT with module path and type arguments
+//!         | "Q" type                                 nullable
+//!         | "W" type                                 weak reference
+//!         | "R" type                                 raw pointer
+//!         | "P" ident                                any other type by name
+//! ident  := <length in bytes> <bytes>
+//! ```
+//!
+//! For example, `toString` on `String` in `std/string` is `_G3std6string_6String8toString`.
+
+use std::fmt::Write;
+
+use common::ModPath;
+
+use crate::{Function, Type};
+
+/// Returns the symbol name of a function instance with the given type arguments.
+/// Methods are named after the type of their receiver.
+pub fn mangle(func: &Function, args: &[Type]) -> String {
+    let mut out = String::from("_G");
+    path(&mut out, &func.module.borrow().path);
+
+    if let Some(this) = func.parameters.first().filter(|param| param.name == "this") {
+        match &this.ty {
+            Type::Adt(adt) => ident(&mut out, &adt.ty.borrow().name),
+            ty => ident(&mut out, &type_name(ty)),
+        }
+    }
+    ident(&mut out, &func.name);
+    type_args(&mut out, args);
+    out
+}
+
+/// Turns a symbol produced by [mangle] back into a readable name,
+/// like `std/string::String::toString` or `main::identity[i64]`.
+/// Returns `None` if the symbol was not produced by [mangle].
+pub fn demangle(symbol: &str) -> Option<String> {
+    let mut demangler = Demangler {
+        rest: symbol.strip_prefix("_G")?,
+    };
+    let mut out = demangler.path()?.join("/");
+    while !demangler.rest.is_empty() && !demangler.rest.starts_with('I') {
+        out.push_str("::");
+        out.push_str(demangler.ident()?);
+    }
+    demangler.type_args(&mut out)?;
+    if demangler.rest.is_empty() {
+        Some(out)
+    } else {
+        None
+    }
+}
+
+fn path(out: &mut String, path: &ModPath) {
+    for part in path.parts() {
+        ident(out, part);
+    }
+    out.push('_');
+}
+
+fn ident(out: &mut String, ident: &str) {
+    write!(out, "{}{}", ident.len(), ident).unwrap();
+}
+
+fn type_args(out: &mut String, args: &[Type]) {
+    if !args.is_empty() {
+        out.push('I');
+        for arg in args {
+            ty(out, arg);
+        }
+        out.push('E');
+    }
+}
+
+fn ty(out: &mut String, ty: &Type) {
+    match ty {
+        Type::Adt(adt) => {
+            let adt_ref = adt.ty.borrow();
+            out.push('N');
+            path(out, &adt_ref.module.borrow().path);
+            ident(out, &adt_ref.name);
+            type_args(out, adt.args());
+            out.push('E');
+        }
+        Type::Nullable(inner) => {
+            out.push('Q');
+            self::ty(out, inner);
+        }
+        Type::Weak(inner) => {
+            out.push('W');
+            self::ty(out, inner);
+        }
+        Type::RawPtr(inner) => {
+            out.push('R');
+            self::ty(out, inner);
+        }
+        _ => {
+            out.push('P');
+            ident(out, &type_name(ty));
+        }
+    }
+}
+
+/// The name of a type without any structure known to the mangler.
+/// Primitive types only have debug names like `I64`, which are lowercased.
+fn type_name(ty: &Type) -> String {
+    let name = ty.to_string();
+    if ty.is_number()
+        || matches!(
+            ty,
+            Type::Any | Type::Never | Type::None | Type::Null | Type::Bool
+        )
+    {
+        name.to_lowercase()
+    } else {
+        name
+    }
+}
+
+struct Demangler<'s> {
+    rest: &'s str,
+}
+
+impl<'s> Demangler<'s> {
+    /// Parses identifiers up to and including the `_` ending a module path.
+    fn path(&mut self) -> Option<Vec<&'s str>> {
+        let mut parts = vec![];
+        while !self.eat('_') {
+            parts.push(self.ident()?);
+        }
+        Some(parts)
+    }
+
+    fn ident(&mut self) -> Option<&'s str> {
+        let digits = self.rest.find(|c: char| !c.is_ascii_digit())?;
+        let len = self.rest[..digits].parse::<usize>().ok()?;
+        let end = digits.checked_add(len)?;
+        let ident = self.rest.get(digits..end)?;
+        self.rest = &self.rest[end..];
+        Some(ident)
+    }
+
+    /// Parses type arguments if there are any, appending them like `[A, B]`.
+    fn type_args(&mut self, out: &mut String) -> Option<()> {
+        if self.eat('I') {
+            out.push('[');
+            let mut first = true;
+            while !self.eat('E') {
+                if !first {
+                    out.push_str(", ");
+                }
+                first = false;
+                self.ty(out)?;
+            }
+            out.push(']');
+        }
+        Some(())
+    }
+
+    fn ty(&mut self, out: &mut String) -> Option<()> {
+        let kind = self.rest.chars().next()?;
+        self.rest = &self.rest[kind.len_utf8()..];
+        match kind {
+            'N' => {
+                self.path()?;
+                out.push_str(self.ident()?);
+                self.type_args(out)?;
+                if !self.eat('E') {
+                    return None;
+                }
+            }
+            'Q' => {
+                self.ty(out)?;
+                out.push('?');
+            }
+            'W' => {
+                out.push_str("weak ");
+                self.ty(out)?;
+            }
+            'R' => {
+                out.push('*');
+                self.ty(out)?;
+            }
+            'P' => out.push_str(self.ident()?),
+            _ => return None,
+        }
+        Some(())
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.rest.starts_with(c) {
+            self.rest = &self.rest[1..];
+            true
+        } else {
+            false
+        }
+    }
+}
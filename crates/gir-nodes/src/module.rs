@@ -19,6 +19,13 @@ pub struct Module {
     /// Defined here additionally allow easily compiling all in IR.
     pub functions: Vec<MutRc<Function>>,
 
+    /// The function containing the module's `init` block, if any.
+    /// It is called before `main`, see `GIRGenerator::insert_module_inits`.
+    pub init: Option<MutRc<Function>>,
+    /// All modules this module imports from, used to
+    /// order `init` blocks by import dependency.
+    pub dependencies: Vec<MutRc<Module>>,
+
     /// All imports from other modules.
     pub imports: Imports,
     /// All exports from other modules.
@@ -41,6 +48,23 @@ impl Module {
         self.find_import(name).or_else(|| self.imports.get(name))
     }
 
+    /// Returns the modules of two different declarations with the given name
+    /// imported with `+`, should the name not be declared or imported otherwise.
+    /// Using such a name is ambiguous and therefore an error.
+    pub fn find_ambiguous(&self, name: &str) -> Option<(ModulePath, ModulePath)> {
+        if self.find_import(name).is_some() || self.imports.decls.contains_key(name) {
+            return None;
+        }
+
+        let mut found = self.imports.modules.iter().filter_map(|wildcard| {
+            let decl = wildcard.find(name)?;
+            Some((decl, Rc::clone(&wildcard.module.borrow().path)))
+        });
+        let (first, first_path) = found.next()?;
+        let (_, second_path) = found.find(|(decl, _)| !decl.is(&first))?;
+        Some((first_path, second_path))
+    }
+
     /// Find a declaration on name, only checking local or exported declarations.
     pub fn find_import(&self, name: &str) -> Option<Declaration> {
         self.declarations
@@ -49,6 +73,37 @@ impl Module {
             .or_else(|| self.exports.get(name))
     }
 
+    /// Returns all declarations that can be used by name in this module,
+    /// including imported ones. Used to suggest names when one was not found.
+    pub fn visible_declarations(&self) -> Vec<(SmolStr, Declaration)> {
+        let mut decls = self.exported_declarations();
+        decls.extend(
+            self.imports
+                .decls
+                .iter()
+                .map(|(name, decl)| (name.clone(), decl.clone())),
+        );
+        for wildcard in &self.imports.modules {
+            decls.extend(wildcard.declarations());
+        }
+        decls
+    }
+
+    /// Returns all declarations of this module and the ones it exports,
+    /// which are the ones other modules can import from it.
+    fn exported_declarations(&self) -> Vec<(SmolStr, Declaration)> {
+        let mut decls = self
+            .declarations
+            .iter()
+            .chain(&self.exports.decls)
+            .map(|(name, decl)| (name.clone(), decl.clone()))
+            .collect::<Vec<_>>();
+        for wildcard in &self.exports.modules {
+            decls.extend(wildcard.declarations());
+        }
+        decls
+    }
+
     /// "Borrow" ownership of the AST for temporary use. Return with [return_ast]
     pub fn borrow_ast(&mut self) -> BorrowedAST {
         BorrowedAST(
@@ -77,6 +132,8 @@ impl Module {
         mutrc_new(Self {
             declarations: HashMap::with_capacity(10),
             functions: Vec::with_capacity(10),
+            init: None,
+            dependencies: Vec::with_capacity(5),
             imports: Imports::default(),
             exports: Imports::default(),
             used_names: HashSet::with_capacity(10),
@@ -92,17 +149,41 @@ impl Module {
 #[derive(Default, Debug)]
 pub struct Imports {
     pub decls: HashMap<SmolStr, Declaration>,
-    pub modules: Vec<MutRc<Module>>,
+    pub modules: Vec<WildcardImport>,
     pub unresolved: Vec<UnresolvedImport>,
 }
 
 impl Imports {
     fn get(&self, name: &str) -> Option<Declaration> {
-        self.decls.get(name).cloned().or_else(|| {
-            self.modules
-                .iter()
-                .find_map(|m| m.borrow().find_import(name))
-        })
+        self.decls
+            .get(name)
+            .cloned()
+            .or_else(|| self.modules.iter().find_map(|m| m.find(name)))
+    }
+}
+
+/// An import of all declarations in a module, like `import foo/+`.
+#[derive(Debug)]
+pub struct WildcardImport {
+    pub module: MutRc<Module>,
+    /// Names excluded from the import with `hiding (a, b)`.
+    pub hidden: Vec<SmolStr>,
+}
+
+impl WildcardImport {
+    pub fn find(&self, name: &str) -> Option<Declaration> {
+        if self.hidden.iter().any(|hidden| hidden == name) {
+            None
+        } else {
+            self.module.borrow().find_import(name)
+        }
+    }
+
+    /// Returns all declarations imported, see [Module::exported_declarations].
+    pub fn declarations(&self) -> Vec<(SmolStr, Declaration)> {
+        let mut decls = self.module.borrow().exported_declarations();
+        decls.retain(|(name, _)| !self.hidden.contains(name));
+        decls
     }
 }
 
@@ -18,18 +18,29 @@ impl Display for Module {
     fn fmt(&self, f: &mut Formatter) -> R {
         writeln!(f, "--> {}:", self.path)?;
         writeln!(f, "Used names: ")?;
-        for name in &self.used_names {
+        let mut names = self.used_names.iter().collect::<Vec<_>>();
+        names.sort();
+        for name in names {
             writeln!(f, "{} ", name)?;
         }
         writeln!(f, "\n\n")?;
-        for ty in self.declarations.values() {
-            ty.display(f)?;
+        // Sorted to keep the output the same between compilations
+        let mut declarations = self.declarations.iter().collect::<Vec<_>>();
+        declarations.sort_by_key(|(name, _)| *name);
+        for (_, decl) in declarations {
+            decl.display(f)?;
             writeln!(f)?;
         }
         Ok(())
     }
 }
 
+impl Display for Declaration {
+    fn fmt(&self, f: &mut Formatter) -> R {
+        self.display(f)
+    }
+}
+
 impl Declaration {
     fn display(&self, f: &mut Formatter) -> R {
         match self {
@@ -56,7 +67,9 @@ impl Function {
         for typ in self.type_parameters.iter() {
             writeln!(f, "    {}tyvar {}: {:?}", indent, typ.name, typ.bound)?;
         }
-        for (name, var) in &self.variables {
+        let mut variables = self.variables.iter().collect::<Vec<_>>();
+        variables.sort_by_key(|(name, _)| *name);
+        for (name, var) in variables {
             writeln!(
                 f,
                 "{}    {} {}: {}",
@@ -163,13 +176,46 @@ impl Expr {
                 Ok(())
             }
 
-            Expr::Allocate { ty, .. } => write!(f, "allocate({})", ty),
+            Expr::Allocate { ty, args, .. } => {
+                write!(f, "allocate({}", ty)?;
+                for arg in args {
+                    write!(f, ", ")?;
+                    arg.display(f, indent_size + INDENT)?;
+                }
+                write!(f, ")")
+            }
 
             Expr::Load { object, field } => {
                 object.display(f, indent_size + INDENT)?;
                 write!(f, ".{}", field.name)
             }
 
+            Expr::ArrayLiteral { values, .. } => {
+                write!(f, "[")?;
+                let mut values = values.iter();
+                values
+                    .next()
+                    .map(|value| value.display(f, indent_size + INDENT));
+                for value in values {
+                    write!(f, ", ")?;
+                    value.display(f, indent_size + INDENT)?;
+                }
+                write!(f, "]")
+            }
+
+            Expr::ArrayRepeat { ty, value } => {
+                write!(f, "[")?;
+                value.display(f, indent_size + INDENT)?;
+                write!(f, "; {}]", ty.as_array().len)
+            }
+
+            Expr::Index { object, index } => {
+                object.display(f, indent_size + INDENT)?;
+                write!(f, "[")?;
+                index.display(f, indent_size + INDENT)?;
+                write!(f, "]")
+            }
+
             Expr::Store {
                 location, value, ..
             } => {
@@ -203,7 +249,7 @@ impl Expr {
                     write!(f, ", ")?;
                     arg.display(f, indent_size + INDENT)?;
                 }
-                writeln!(f, ")")
+                write!(f, ")")
             }
 
             Expr::If {
@@ -223,12 +269,17 @@ impl Expr {
             Expr::Switch {
                 branches,
                 else_branch,
+                jump_table,
                 ..
             } => {
                 let indent = repeat(' ').take(indent_size).collect::<String>();
                 let indent_inner = repeat(' ').take(indent_size + INDENT).collect::<String>();
 
-                writeln!(f, "switch {{")?;
+                if *jump_table {
+                    writeln!(f, "switch table {{")?;
+                } else {
+                    writeln!(f, "switch {{")?;
+                }
                 for branch in branches {
                     write!(f, "{}", indent_inner)?;
                     branch.0.display(f, indent_size + INDENT)?;
@@ -283,13 +334,21 @@ impl Expr {
     }
 }
 
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> R {
+        self.display(f, 0)
+    }
+}
+
 impl Display for Literal {
     fn fmt(&self, f: &mut Formatter<'_>) -> R {
         match self {
             Literal::Any => write!(f, "Any"),
+            Literal::Error => write!(f, "<error>"),
             Literal::None => write!(f, "None"),
             Literal::Null => write!(f, "null"),
             Literal::Bool(b) => write!(f, "{}", b),
+            Literal::Int { value, .. } => write!(f, "{}", value),
             Literal::I8(num) => write!(f, "{}i8", num),
             Literal::I16(num) => write!(f, "{}i16", num),
             Literal::I32(num) => write!(f, "{}i32", num),
@@ -309,16 +368,28 @@ impl Display for Intrinsic {
     fn fmt(&self, f: &mut Formatter<'_>) -> R {
         match self {
             Intrinsic::DecRc(_) => write!(f, "dec_rc("),
-            Intrinsic::IncRc(_) => write!(f, "dec_rc("),
+            Intrinsic::IncRc(_) => write!(f, "inc_rc("),
             Intrinsic::Free(_) => write!(f, "free("),
+            Intrinsic::UpgradeWeak(_) => write!(f, "upgrade("),
             Intrinsic::IfaceCall { .. } => write!(f, "vcall("),
             Intrinsic::ConcreteMethodGet { .. } => write!(f, "method_of("),
+            Intrinsic::VectorSplat { .. } => write!(f, "splat("),
+            Intrinsic::VectorExtract { .. } => write!(f, "extract("),
+            Intrinsic::VectorInsert { .. } => write!(f, "insert("),
+            Intrinsic::VectorShuffle { .. } => write!(f, "shuffle("),
+            Intrinsic::Expect { expected: true, .. } => write!(f, "likely("),
+            Intrinsic::Expect { .. } => write!(f, "unlikely("),
+            Intrinsic::EnumTag(_) => write!(f, "enum_tag("),
         }?;
         match self {
             Intrinsic::IfaceCall { iface: e, .. }
             | Intrinsic::Free(e)
+            | Intrinsic::UpgradeWeak(e)
+            | Intrinsic::EnumTag(e)
             | Intrinsic::IncRc(e)
-            | Intrinsic::DecRc(e) => e.display(f, 0),
+            | Intrinsic::DecRc(e)
+            | Intrinsic::VectorSplat { value: e, .. }
+            | Intrinsic::Expect { value: e, .. } => e.display(f, 0),
             Intrinsic::ConcreteMethodGet(ConcreteMethodGet {
                 index,
                 interface,
@@ -330,6 +401,32 @@ impl Display for Intrinsic {
                 interface,
                 iface_method.borrow().name
             ),
+            Intrinsic::VectorExtract { vector, index } => {
+                vector.display(f, 0)?;
+                write!(f, ", ")?;
+                index.display(f, 0)
+            }
+            Intrinsic::VectorInsert {
+                vector,
+                index,
+                value,
+            } => {
+                vector.display(f, 0)?;
+                write!(f, ", ")?;
+                index.display(f, 0)?;
+                write!(f, ", ")?;
+                value.display(f, 0)
+            }
+            Intrinsic::VectorShuffle {
+                left, right, mask, ..
+            } => {
+                left.display(f, 0)?;
+                if let Some(right) = right {
+                    write!(f, ", ")?;
+                    right.display(f, 0)?;
+                }
+                write!(f, ", {:?}", mask)
+            }
         }?;
         write!(f, ")")
     }
@@ -0,0 +1,414 @@
+//! Serialization of GIR using serde, enabled with the `serde` feature.
+//! Allows other tools to consume the output of the GIR generator.
+//! References to other declarations are serialized as their name,
+//! types as their display representation.
+//! Serialization is one-way, as GIR cannot be reconstructed without its references.
+
+use crate::{
+    declaration::{ADTType, Field, LocalVariable, Variable},
+    expression::{CastType, ConcreteMethodGet, Intrinsic},
+    Declaration, Expr, Function, Literal, Module, ADT,
+};
+use common::MutRc;
+use serde::ser::{Serialize, SerializeStruct, SerializeStructVariant, Serializer};
+use std::fmt::Display;
+
+/// Serializes a value as its display representation.
+struct Str<'a, T: Display>(&'a T);
+
+impl<T: Display> Serialize for Str<'_, T> {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(self.0)
+    }
+}
+
+/// Serializes a function as its name.
+struct Name<'a>(&'a MutRc<Function>);
+
+impl Serialize for Name<'_> {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&self.0.borrow().name)
+    }
+}
+
+/// Serializes a struct variant of the given enum with the given fields.
+macro_rules! variant {
+    ($s:expr, $enum_name:expr, $index:expr, $name:expr, { $($field:ident: $value:expr),* }) => {{
+        let len = [$(stringify!($field)),*].len();
+        let mut variant = $s.serialize_struct_variant($enum_name, $index, $name, len)?;
+        $(variant.serialize_field(stringify!($field), $value)?;)*
+        variant.end()
+    }};
+}
+
+impl Serialize for Module {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut declarations = self.declarations.iter().collect::<Vec<_>>();
+        declarations.sort_by_key(|(name, _)| *name);
+        let declarations = declarations
+            .into_iter()
+            .map(|(_, decl)| decl)
+            .collect::<Vec<_>>();
+
+        let mut module = s.serialize_struct("Module", 2)?;
+        module.serialize_field("path", &Str(&self.path))?;
+        module.serialize_field("declarations", &declarations)?;
+        module.end()
+    }
+}
+
+impl Serialize for Declaration {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Declaration::Function(func) => {
+                s.serialize_newtype_variant("Declaration", 0, "Function", &*func.borrow())
+            }
+            Declaration::Adt(adt) => {
+                s.serialize_newtype_variant("Declaration", 1, "Adt", &*adt.borrow())
+            }
+        }
+    }
+}
+
+impl Serialize for Function {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let type_parameters = self
+            .type_parameters
+            .iter()
+            .map(|param| &param.name[..])
+            .collect::<Vec<_>>();
+        let mut variables = self.variables.values().collect::<Vec<_>>();
+        variables.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut func = s.serialize_struct("Function", 7)?;
+        func.serialize_field("name", &self.name[..])?;
+        func.serialize_field("visibility", &Str(&self.visibility))?;
+        func.serialize_field("type_parameters", &type_parameters)?;
+        func.serialize_field("parameters", &self.parameters)?;
+        func.serialize_field("ret_type", &Str(&self.ret_type))?;
+        func.serialize_field("variables", &variables)?;
+        func.serialize_field("exprs", &self.exprs)?;
+        func.end()
+    }
+}
+
+impl Serialize for ADT {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let kind = match self.ty {
+            ADTType::Class { .. } => "class",
+            ADTType::Interface => "interface",
+            ADTType::Enum { .. } => "enum",
+            ADTType::EnumCase { .. } => "case",
+        };
+        let type_parameters = self
+            .type_parameters
+            .iter()
+            .map(|param| &param.name[..])
+            .collect::<Vec<_>>();
+        let fields = self.fields.values().collect::<Vec<_>>();
+        let constructors = self
+            .constructors
+            .iter()
+            .map(|func| func.borrow())
+            .collect::<Vec<_>>();
+        let constructors = constructors.iter().map(|f| &**f).collect::<Vec<_>>();
+        let methods = self
+            .methods
+            .values()
+            .map(|func| func.borrow())
+            .collect::<Vec<_>>();
+        let methods = methods.iter().map(|f| &**f).collect::<Vec<_>>();
+
+        let mut adt = s.serialize_struct("ADT", 7)?;
+        adt.serialize_field("name", &self.name[..])?;
+        adt.serialize_field("visibility", &Str(&self.visibility))?;
+        adt.serialize_field("kind", kind)?;
+        adt.serialize_field("type_parameters", &type_parameters)?;
+        adt.serialize_field("fields", &fields)?;
+        adt.serialize_field("constructors", &constructors)?;
+        adt.serialize_field("methods", &methods)?;
+        adt.end()
+    }
+}
+
+impl Serialize for Field {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut field = s.serialize_struct("Field", 4)?;
+        field.serialize_field("name", &self.name[..])?;
+        field.serialize_field("visibility", &Str(&self.visibility))?;
+        field.serialize_field("mutable", &self.mutable)?;
+        field.serialize_field("ty", &Str(&self.ty))?;
+        field.end()
+    }
+}
+
+impl Serialize for LocalVariable {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut var = s.serialize_struct("LocalVariable", 3)?;
+        var.serialize_field("name", &self.name[..])?;
+        var.serialize_field("ty", &Str(&self.ty))?;
+        var.serialize_field("mutable", &self.mutable)?;
+        var.end()
+    }
+}
+
+impl Serialize for Variable {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Variable::Local(var) => s.serialize_newtype_variant("Variable", 0, "Local", &**var),
+            Variable::Function(func) => {
+                let type_args = func.args().iter().map(Str).collect::<Vec<_>>();
+                variant!(s, "Variable", 1, "Function", {
+                    name: &func.ty.borrow().name[..],
+                    type_args: &type_args
+                })
+            }
+        }
+    }
+}
+
+impl Serialize for Expr {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Expr::Block(exprs) => s.serialize_newtype_variant("Expr", 0, "Block", exprs),
+
+            Expr::Literal(literal) => s.serialize_newtype_variant("Expr", 1, "Literal", literal),
+
+            Expr::Variable(var) => s.serialize_newtype_variant("Expr", 2, "Variable", var),
+
+            Expr::Allocate {
+                ty,
+                constructor,
+                args,
+            } => variant!(s, "Expr", 3, "Allocate", {
+                ty: &Str(ty),
+                constructor: &Name(constructor),
+                args: args
+            }),
+
+            Expr::Load { object, field } => variant!(s, "Expr", 4, "Load", {
+                object: object,
+                field: &field.name[..]
+            }),
+
+            Expr::ArrayLiteral { ty, values } => variant!(s, "Expr", 5, "ArrayLiteral", {
+                ty: &Str(ty),
+                values: values
+            }),
+
+            Expr::ArrayRepeat { ty, value } => variant!(s, "Expr", 6, "ArrayRepeat", {
+                ty: &Str(ty),
+                value: value
+            }),
+
+            Expr::Index { object, index } => variant!(s, "Expr", 7, "Index", {
+                object: object,
+                index: index
+            }),
+
+            Expr::Store {
+                location,
+                value,
+                first_store,
+            } => variant!(s, "Expr", 8, "Store", {
+                location: location,
+                value: value,
+                first_store: first_store
+            }),
+
+            Expr::Binary {
+                left,
+                operator,
+                right,
+            } => variant!(s, "Expr", 9, "Binary", {
+                left: left,
+                operator: &format!("{:?}", operator),
+                right: right
+            }),
+
+            Expr::Unary { operator, right } => variant!(s, "Expr", 10, "Unary", {
+                operator: &format!("{:?}", operator),
+                right: right
+            }),
+
+            Expr::Call { callee, arguments } => variant!(s, "Expr", 11, "Call", {
+                callee: callee,
+                arguments: arguments
+            }),
+
+            Expr::If {
+                condition,
+                then_branch,
+                else_branch,
+                phi_type,
+            } => variant!(s, "Expr", 12, "If", {
+                condition: condition,
+                then_branch: then_branch,
+                else_branch: else_branch,
+                phi_type: &phi_type.as_ref().map(Str)
+            }),
+
+            Expr::Switch {
+                branches,
+                else_branch,
+                phi_type,
+                jump_table,
+            } => variant!(s, "Expr", 13, "Switch", {
+                branches: branches,
+                else_branch: else_branch,
+                phi_type: &phi_type.as_ref().map(Str),
+                jump_table: jump_table
+            }),
+
+            Expr::Loop {
+                condition,
+                body,
+                else_branch,
+                phi_type,
+            } => variant!(s, "Expr", 14, "Loop", {
+                condition: condition,
+                body: body,
+                else_branch: else_branch,
+                phi_type: &phi_type.as_ref().map(Str)
+            }),
+
+            Expr::Break(value) => s.serialize_newtype_variant("Expr", 15, "Break", value),
+
+            Expr::Return(value) => s.serialize_newtype_variant("Expr", 16, "Return", value),
+
+            Expr::Cast { inner, to, method } => variant!(s, "Expr", 17, "Cast", {
+                inner: inner,
+                to: &Str(to),
+                method: &Str(method)
+            }),
+
+            Expr::Closure { function, captured } => variant!(s, "Expr", 18, "Closure", {
+                function: &Name(function),
+                captured: captured
+            }),
+
+            Expr::TypeGet(ty) => s.serialize_newtype_variant("Expr", 19, "TypeGet", &Str(ty)),
+
+            Expr::Intrinsic(intrinsic) => {
+                s.serialize_newtype_variant("Expr", 20, "Intrinsic", intrinsic)
+            }
+        }
+    }
+}
+
+impl Serialize for Intrinsic {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Intrinsic::IncRc(value) => s.serialize_newtype_variant("Intrinsic", 0, "IncRc", value),
+
+            Intrinsic::DecRc(value) => s.serialize_newtype_variant("Intrinsic", 1, "DecRc", value),
+
+            Intrinsic::Free(value) => s.serialize_newtype_variant("Intrinsic", 2, "Free", value),
+
+            Intrinsic::UpgradeWeak(value) => {
+                s.serialize_newtype_variant("Intrinsic", 3, "UpgradeWeak", value)
+            }
+
+            Intrinsic::IfaceCall {
+                iface,
+                index,
+                arguments,
+                ret_type,
+            } => variant!(s, "Intrinsic", 4, "IfaceCall", {
+                iface: iface,
+                index: index,
+                arguments: arguments,
+                ret_type: &Str(ret_type)
+            }),
+
+            Intrinsic::ConcreteMethodGet(ConcreteMethodGet {
+                index,
+                interface,
+                iface_method,
+            }) => variant!(s, "Intrinsic", 5, "ConcreteMethodGet", {
+                index: index,
+                interface: &Str(interface),
+                iface_method: &Name(iface_method)
+            }),
+
+            Intrinsic::VectorSplat { value, ty } => variant!(s, "Intrinsic", 6, "VectorSplat", {
+                value: value,
+                ty: &Str(ty)
+            }),
+
+            Intrinsic::VectorExtract { vector, index } => {
+                variant!(s, "Intrinsic", 7, "VectorExtract", {
+                    vector: vector,
+                    index: index
+                })
+            }
+
+            Intrinsic::VectorInsert {
+                vector,
+                index,
+                value,
+            } => variant!(s, "Intrinsic", 8, "VectorInsert", {
+                vector: vector,
+                index: index,
+                value: value
+            }),
+
+            Intrinsic::VectorShuffle {
+                left,
+                right,
+                mask,
+                ty,
+            } => variant!(s, "Intrinsic", 9, "VectorShuffle", {
+                left: left,
+                right: right,
+                mask: mask,
+                ty: &Str(ty)
+            }),
+
+            Intrinsic::Expect { value, expected } => variant!(s, "Intrinsic", 10, "Expect", {
+                value: value,
+                expected: expected
+            }),
+
+            Intrinsic::EnumTag(value) => {
+                s.serialize_newtype_variant("Intrinsic", 11, "EnumTag", value)
+            }
+        }
+    }
+}
+
+impl Serialize for Literal {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Literal::Any => s.serialize_unit_variant("Literal", 0, "Any"),
+            Literal::None => s.serialize_unit_variant("Literal", 1, "None"),
+            Literal::Null => s.serialize_unit_variant("Literal", 2, "Null"),
+            Literal::Bool(b) => s.serialize_newtype_variant("Literal", 3, "Bool", b),
+            Literal::Int { value, ty } => variant!(s, "Literal", 4, "Int", {
+                value: value,
+                ty: &Str(ty)
+            }),
+            Literal::I8(num) => s.serialize_newtype_variant("Literal", 5, "I8", num),
+            Literal::I16(num) => s.serialize_newtype_variant("Literal", 6, "I16", num),
+            Literal::I32(num) => s.serialize_newtype_variant("Literal", 7, "I32", num),
+            Literal::I64(num) => s.serialize_newtype_variant("Literal", 8, "I64", num),
+            Literal::U8(num) => s.serialize_newtype_variant("Literal", 9, "U8", num),
+            Literal::U16(num) => s.serialize_newtype_variant("Literal", 10, "U16", num),
+            Literal::U32(num) => s.serialize_newtype_variant("Literal", 11, "U32", num),
+            Literal::U64(num) => s.serialize_newtype_variant("Literal", 12, "U64", num),
+            Literal::F32(num) => s.serialize_newtype_variant("Literal", 13, "F32", num),
+            Literal::F64(num) => s.serialize_newtype_variant("Literal", 14, "F64", num),
+            Literal::String { text, ty } => variant!(s, "Literal", 15, "String", {
+                text: &text[..],
+                ty: &Str(ty)
+            }),
+            Literal::Error => s.serialize_unit_variant("Literal", 16, "Error"),
+        }
+    }
+}
+
+impl Serialize for CastType {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(self)
+    }
+}
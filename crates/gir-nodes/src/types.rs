@@ -5,7 +5,10 @@ use std::{
     rc::Rc,
 };
 
-use crate::{declaration::LocalVariable, Function, ADT};
+use crate::{
+    declaration::{ADTType, LocalVariable},
+    Function, ADT,
+};
 use common::MutRc;
 use enum_methods::{EnumAsGetters, EnumIntoGetters, EnumIsA};
 use gir_ir_adapter::IRClosure;
@@ -24,6 +27,15 @@ pub enum Type {
     /// Any type that can cast to anything; used by
     /// control flow branching away to allow phi usage with them
     Any,
+    /// The return type of functions that never return, like `std/process/exit`.
+    /// Calls to them diverge, so it can be cast to anything like `Any`.
+    Never,
+    /// The type of a type that could not be resolved or an expression
+    /// that failed to generate, which was already reported as an error.
+    /// Type checks accept it in place of any type, so that anything using it
+    /// does not produce further errors that are only caused by the first one.
+    /// It never makes it to IR, as compilation fails with the first error.
+    Error,
     /// None singleton type used for expressions that do not produce a value
     None,
     /// Type of the `null` literal, cast to Nullable as appropriate
@@ -66,6 +78,17 @@ pub enum Type {
     /// memory operations
     RawPtr(Box<Type>),
 
+    /// A weak reference to a class, which does not keep
+    /// the class alive. Must be upgraded to a strong reference before use.
+    Weak(Box<Type>),
+
+    /// A fixed-size array, stored inline without a heap allocation.
+    Array(Rc<ArrayType>),
+
+    /// A SIMD vector of numbers like `vec4f32`, operated on elementwise.
+    /// Only available to modules importing `std/simd`.
+    Vector(Rc<ArrayType>),
+
     /// An unresolved type parameter, resolved at IR.
     Variable(TypeVariable),
     /// A type itself. This is used for static fields,
@@ -80,6 +103,7 @@ impl Type {
     pub fn equal(&self, other: &Self, strict: bool) -> bool {
         match (self, other) {
             (Self::Any, _) | (_, Self::Any) => !strict,
+            (Self::Never, _) | (_, Self::Never) if !strict => true,
 
             (Self::Function(f), Self::Function(o)) => f == o,
             (Self::Closure(f), Self::Closure(o)) => f == o,
@@ -88,6 +112,9 @@ impl Type {
             (Self::Type(v), Self::Type(o)) => v == o,
             (Self::Variable(i), Self::Variable(o)) => i.index == o.index,
             (Self::RawPtr(p), Self::RawPtr(o)) => p == o,
+            (Self::Weak(w), Self::Weak(o)) => w == o,
+            (Self::Array(a), Self::Array(o)) => a == o,
+            (Self::Vector(v), Self::Vector(o)) => v == o,
 
             _ => std::mem::discriminant(self) == std::mem::discriminant(other),
         }
@@ -98,7 +125,9 @@ impl Type {
         match self {
             Self::Function(inst) => Some(&inst.args).cloned(),
             Self::Adt(inst) => Some(&inst.args).cloned(),
-            Self::Type(ty) | Self::RawPtr(ty) | Self::Nullable(ty) => ty.type_args(),
+            Self::Type(ty) | Self::RawPtr(ty) | Self::Nullable(ty) | Self::Weak(ty) => {
+                ty.type_args()
+            }
             Self::Variable(TypeVariable {
                 bound: TypeParameterBound::Interface(iface),
                 ..
@@ -115,6 +144,7 @@ impl Type {
             Self::Type(ty)
             | Self::RawPtr(ty)
             | Self::Nullable(ty)
+            | Self::Weak(ty)
             | Self::Variable(TypeVariable {
                 bound: TypeParameterBound::Interface(ty),
                 ..
@@ -135,7 +165,9 @@ impl Type {
                 inst.args = args;
                 true
             }
-            Self::Type(ty) | Self::RawPtr(ty) | Self::Nullable(ty) => ty.set_type_args(args),
+            Self::Type(ty) | Self::RawPtr(ty) | Self::Nullable(ty) | Self::Weak(ty) => {
+                ty.set_type_args(args)
+            }
             _ => false,
         }
     }
@@ -223,6 +255,29 @@ impl Type {
         }
     }
 
+    /// Can values of this type be copied byte-by-byte, without adjusting
+    /// any reference counts? True for primitives, raw pointers, functions
+    /// and value classes, arrays and vectors only made up of them.
+    /// Unresolved type parameters are not, since they could be anything.
+    pub fn is_trivially_copyable(&self) -> bool {
+        match self {
+            Type::Function(_) | Type::RawPtr(_) => true,
+            Type::Nullable(inner) if inner.is_primitive() => true,
+            Type::Array(arr) | Type::Vector(arr) => arr.ty.is_trivially_copyable(),
+            Type::Adt(inst) => {
+                let adt = inst.ty.borrow();
+                adt.type_kind == TypeKind::Value
+                    && matches!(adt.ty, ADTType::Class { .. })
+                    && adt
+                        .fields
+                        .values()
+                        .all(|field| field.ty.resolve(inst.args()).is_trivially_copyable())
+            }
+            Type::Variable(_) => false,
+            _ => self.is_primitive(),
+        }
+    }
+
     pub fn is_var_with_marker(&self, marker: Bound) -> bool {
         if let Type::Variable(var) = self {
             if let TypeParameterBound::Bound(bound) = &var.bound {
@@ -265,7 +320,7 @@ impl Type {
 
     pub fn type_or_none(self) -> Option<Type> {
         match self {
-            Type::None | Type::Any => None,
+            Type::None | Type::Any | Type::Never => None,
             _ => Some(self),
         }
     }
@@ -280,6 +335,10 @@ impl Type {
             Type::Nullable(box Type::Variable(var)) if var.index < args.len() => {
                 Type::Nullable(box args[var.index].clone())
             }
+            Type::Array(arr) => Type::Array(Rc::new(ArrayType {
+                ty: arr.ty.resolve(args),
+                len: arr.len,
+            })),
             _ => self.clone(),
         };
 
@@ -300,6 +359,42 @@ impl Type {
 
         ty
     }
+
+    /// Replaces `Self` of an interface method signature with the given type,
+    /// which is the implementor or a type parameter bound on the interface.
+    pub fn replace_self(&self, with: &Type) -> Type {
+        let mut ty = match self {
+            Type::Variable(var) if var.is_self() => return with.clone(),
+            Type::Nullable(inner) => Type::Nullable(box inner.replace_self(with)),
+            Type::RawPtr(inner) => Type::RawPtr(box inner.replace_self(with)),
+            Type::Weak(inner) => Type::Weak(box inner.replace_self(with)),
+            Type::Array(arr) => Type::Array(Rc::new(ArrayType {
+                ty: arr.ty.replace_self(with),
+                len: arr.len,
+            })),
+            _ => self.clone(),
+        };
+
+        if let Some(a) = ty.type_args() {
+            let new = Rc::new(a.iter().map(|a| a.replace_self(with)).collect::<Vec<_>>());
+            ty.set_type_args(new);
+        }
+        ty
+    }
+
+    /// If this type refers to `Self` of an interface method signature.
+    pub fn contains_self(&self) -> bool {
+        match self {
+            Type::Variable(var) => var.is_self(),
+            Type::Nullable(inner) | Type::RawPtr(inner) | Type::Weak(inner) => {
+                inner.contains_self()
+            }
+            Type::Array(arr) => arr.ty.contains_self(),
+            _ => self
+                .type_args()
+                .map_or(false, |args| args.iter().any(Type::contains_self)),
+        }
+    }
 }
 
 impl PartialEq for Type {
@@ -317,7 +412,7 @@ impl Hash for Type {
 
             Self::Adt(v) => v.ty.borrow().name.hash(state),
 
-            Self::Type(v) | Self::RawPtr(v) | Self::Nullable(v) => v.hash(state),
+            Self::Type(v) | Self::RawPtr(v) | Self::Nullable(v) | Self::Weak(v) => v.hash(state),
 
             Self::Closure(cls) => {
                 for param in &cls.parameters {
@@ -328,6 +423,11 @@ impl Hash for Type {
 
             Self::ClosureCaptured(cap) => cap.iter().for_each(|i| i.ty.hash(state)),
 
+            Self::Array(arr) | Self::Vector(arr) => {
+                arr.ty.hash(state);
+                arr.len.hash(state)
+            }
+
             Self::Variable(var) => var.index.hash(state),
 
             _ => std::mem::discriminant(self).hash(state),
@@ -349,6 +449,9 @@ impl Display for Type {
             Type::Adt(adt) => write!(f, "{}", adt),
             Type::Nullable(adt) => write!(f, "{}?", adt),
             Type::RawPtr(inner) => write!(f, "*{}", inner),
+            Type::Weak(inner) => write!(f, "weak {}", inner),
+            Type::Array(arr) => write!(f, "[{}; {}]", arr.ty, arr.len),
+            Type::Vector(vec) => write!(f, "vec{}{}", vec.len, vec.ty),
             Type::Variable(var) => write!(f, "{}: {}", var.name, var.bound),
             Type::Type(ty) => match **ty {
                 Type::Function(_) => write!(f, "<function>"),
@@ -490,6 +593,10 @@ pub struct TypeVariable {
 }
 
 impl TypeVariable {
+    /// The index of `Self`, which is replaced with [Type::replace_self]
+    /// instead of type arguments.
+    const SELF_INDEX: usize = usize::MAX;
+
     pub fn from_param(param: &TypeParameter) -> TypeVariable {
         TypeVariable {
             index: param.index,
@@ -497,6 +604,20 @@ impl TypeVariable {
             bound: param.bound.clone(),
         }
     }
+
+    /// `Self` inside the method signatures of an interface,
+    /// standing for the type implementing the interface.
+    pub fn this() -> TypeVariable {
+        TypeVariable {
+            index: Self::SELF_INDEX,
+            name: SmolStr::new_inline("Self"),
+            bound: TypeParameterBound::default(),
+        }
+    }
+
+    pub fn is_self(&self) -> bool {
+        self.index == Self::SELF_INDEX
+    }
 }
 
 /// A closure signature.
@@ -521,6 +642,13 @@ impl Display for ClosureType {
     }
 }
 
+/// The element type and length of a fixed-size array or SIMD vector.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ArrayType {
+    pub ty: Type,
+    pub len: u64,
+}
+
 /// A single type parameter on a declaration.
 #[derive(Debug, Clone)]
 pub struct TypeParameter {
@@ -541,6 +669,19 @@ pub enum TypeParameterBound {
     Bound(Bound),
 }
 
+impl TypeParameterBound {
+    /// Resolves the interface of this bound with the given type arguments,
+    /// used for bounds that refer to other type parameters (like `K: Hash[K]`).
+    pub fn resolve(&self, args: &Rc<TypeArguments>) -> TypeParameterBound {
+        match self {
+            TypeParameterBound::Interface(iface) => {
+                TypeParameterBound::Interface(Box::new(iface.resolve(args)))
+            }
+            TypeParameterBound::Bound(_) => self.clone(),
+        }
+    }
+}
+
 impl Default for TypeParameterBound {
     fn default() -> Self {
         TypeParameterBound::Bound(Bound::Unbounded)
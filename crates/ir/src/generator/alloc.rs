@@ -0,0 +1,130 @@
+use gir_generator::Allocator;
+use inkwell::{
+    types::{BasicType, BasicTypeEnum},
+    values::{FunctionValue, PointerValue},
+    AddressSpace::Generic,
+    IntPredicate,
+};
+
+use super::IRGenerator;
+
+/// Name of the function allocating from the arena.
+const ARENA_ALLOC_FN: &str = "gelixrs_arena_alloc";
+/// Size of the chunks allocated by the arena allocator, in bytes.
+/// Allocations larger than this get their own chunk.
+const ARENA_CHUNK_SIZE: u64 = 1 << 20;
+
+impl IRGenerator {
+    /// Allocates memory for a value of the given type on the heap,
+    /// using the allocator the program is compiled with.
+    pub(crate) fn build_heap_alloc(&mut self, ty: BasicTypeEnum) -> PointerValue {
+        let i = self.context.i64_type();
+        let ty_size = unsafe {
+            self.builder.build_gep(
+                ty.ptr_type(Generic).const_null(),
+                &[self.context.i32_type().const_int(1, false)],
+                "size",
+            )
+        };
+        let ty_size = self.builder.build_ptr_to_int(ty_size, i, "sizeint").into();
+
+        let alloc_fn = self.alloc_fn().as_global_value().as_pointer_value();
+        let alloc_ty = ty.ptr_type(Generic).fn_type(&[i.into()], false);
+        let alloc_fn = self
+            .builder
+            .build_bitcast(alloc_fn, alloc_ty.ptr_type(Generic), "alloccast")
+            .into_pointer_value();
+
+        self.builder
+            .build_call(alloc_fn, &[ty_size], "alloc")
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_pointer_value()
+    }
+
+    /// Returns the function allocating memory with the allocator
+    /// the program is compiled with. It takes the size in bytes
+    /// and returns the allocated pointer as an integer.
+    pub(crate) fn alloc_fn(&self) -> FunctionValue {
+        match self.gir_data.flags.allocator {
+            Allocator::Malloc => self.module.get_function("malloc").unwrap(),
+            Allocator::Arena => self.arena_alloc_fn(),
+        }
+    }
+
+    /// Frees memory allocated with `build_heap_alloc` or `alloc_fn`.
+    /// Does nothing when using the arena allocator.
+    pub(crate) fn build_heap_free(&self, ptr: PointerValue) {
+        match self.gir_data.flags.allocator {
+            Allocator::Malloc => {
+                self.builder.build_free(ptr);
+            }
+            Allocator::Arena => (),
+        }
+    }
+
+    /// Returns the function allocating from the arena, generating it if needed.
+    /// The arena is a chunk of memory allocated with malloc, which
+    /// allocations are taken from by simply bumping a pointer.
+    /// Once the chunk is full, a new one is allocated; chunks are never freed.
+    fn arena_alloc_fn(&self) -> FunctionValue {
+        if let Some(func) = self.module.get_function(ARENA_ALLOC_FN) {
+            return func;
+        }
+
+        let i = self.context.i64_type();
+        let zero = i.const_int(0, false);
+        let func_ty = i.fn_type(&[i.into()], false);
+        let func = self.module.add_function(ARENA_ALLOC_FN, func_ty, None);
+
+        let next = self.module.add_global(i, None, "arena-next");
+        next.set_initializer(&zero);
+        let next = next.as_pointer_value();
+        let end = self.module.add_global(i, None, "arena-end");
+        end.set_initializer(&zero);
+        let end = end.as_pointer_value();
+
+        let builder = self.context.create_builder();
+        let entry_bb = self.context.append_basic_block(&func, "entry");
+        let bump_bb = self.context.append_basic_block(&func, "bump");
+        let grow_bb = self.context.append_basic_block(&func, "grow");
+
+        // Round the size up to a multiple of 8 to keep allocations aligned
+        builder.position_at_end(&entry_bb);
+        let size = func.get_first_param().unwrap().into_int_value();
+        let size = builder.build_int_add(size, i.const_int(7, false), "sizeadd");
+        let size = builder.build_and(size, i.const_int(!7, false), "sizealign");
+        let ptr = builder.build_load(next, "nextload").into_int_value();
+        let new_next = builder.build_int_add(ptr, size, "newnext");
+        let end_val = builder.build_load(end, "endload").into_int_value();
+        let fits = builder.build_int_compare(IntPredicate::ULE, new_next, end_val, "fits");
+        builder.build_conditional_branch(fits, &bump_bb, &grow_bb);
+
+        builder.position_at_end(&bump_bb);
+        builder.build_store(next, new_next);
+        builder.build_return(Some(&ptr));
+
+        builder.position_at_end(&grow_bb);
+        let chunk_size = i.const_int(ARENA_CHUNK_SIZE, false);
+        let is_large = builder.build_int_compare(IntPredicate::UGT, size, chunk_size, "large");
+        let chunk_size = builder
+            .build_select(is_large, size, chunk_size, "chunksize")
+            .into_int_value();
+
+        let malloc = self.module.get_function("malloc").unwrap();
+        let chunk = builder
+            .build_call(malloc, &[chunk_size.into()], "chunk")
+            .try_as_basic_value()
+            .left()
+            .unwrap()
+            .into_int_value();
+        let chunk_next = builder.build_int_add(chunk, size, "chunknext");
+        let chunk_end = builder.build_int_add(chunk, chunk_size, "chunkend");
+        builder.build_store(next, chunk_next);
+        builder.build_store(end, chunk_end);
+        builder.build_return(Some(&chunk));
+
+        func
+    }
+}
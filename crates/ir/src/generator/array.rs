@@ -0,0 +1,101 @@
+use gir_nodes::{Expr, Type};
+use inkwell::{
+    values::{BasicValueEnum, IntValue, PointerValue},
+    IntPredicate,
+};
+
+use super::{IRGenerator, LLPtr, LLValue};
+
+impl IRGenerator {
+    /// Builds an array literal with one value per element.
+    /// Literals of only constants are loaded from a global instead.
+    pub(crate) fn array_literal(&mut self, ty: &Type, values: &[Expr]) -> LLValue {
+        if let Some(constant) = self.constant_array(ty, values) {
+            return constant;
+        }
+        let elem_ty = &ty.as_array().ty;
+        let ptr = self.array_alloc(ty);
+        for (index, value) in values.iter().enumerate() {
+            let value = self.expression(value);
+            let index = self.context.i64_type().const_int(index as u64, false);
+            let elem = self.array_gep(ptr, index);
+            self.build_store(&LLPtr::from(elem, elem_ty), &value, true);
+        }
+        self.load_ptr(&LLPtr::from(ptr, ty))
+    }
+
+    /// Builds an array literal repeating a single value, which is only
+    /// evaluated once and then stored into all elements using a loop.
+    pub(crate) fn array_repeat(&mut self, ty: &Type, value: &Expr) -> LLValue {
+        let arr = ty.as_array();
+        let ptr = self.array_alloc(ty);
+        let value = self.expression(value);
+
+        let i64_ty = self.context.i64_type();
+        let counter = self.create_alloc(Type::U64, i64_ty.into(), false);
+        self.builder
+            .build_store(counter, i64_ty.const_int(0, false));
+        let loop_bb = self.append_block("arr-loop");
+        let cont_bb = self.append_block("arr-cont");
+        self.builder.build_unconditional_branch(&loop_bb);
+
+        // Arrays are never empty, so the condition is only checked after the first store
+        self.position_at_block(loop_bb);
+        let index = self
+            .builder
+            .build_load(counter, "arrindex")
+            .into_int_value();
+        let elem = self.array_gep(ptr, index);
+        self.build_store(&LLPtr::from(elem, &arr.ty), &value, true);
+        let next = self
+            .builder
+            .build_int_add(index, i64_ty.const_int(1, false), "arrnext");
+        self.builder.build_store(counter, next);
+        let done = self.builder.build_int_compare(
+            IntPredicate::EQ,
+            next,
+            i64_ty.const_int(arr.len, false),
+            "arrdone",
+        );
+        self.builder
+            .build_conditional_branch(done, &cont_bb, &loop_bb);
+
+        self.position_at_block(cont_bb);
+        self.load_ptr(&LLPtr::from(ptr, ty))
+    }
+
+    /// Returns the element of an array at the given index,
+    /// or a pointer to it if `no_load` is set.
+    /// The array is indexed in place if it is a variable or field;
+    /// other values are first stored in a temporary allocation.
+    pub(crate) fn array_index(&mut self, object: &Expr, index: &Expr, no_load: bool) -> LLValue {
+        let arr_ty = object.get_type();
+        let elem_ty = arr_ty.as_array().ty.clone();
+        let ptr = match *self.expression_(object, true) {
+            BasicValueEnum::PointerValue(ptr) => ptr,
+            value => {
+                let ptr = self.array_alloc(&arr_ty);
+                self.builder.build_store(ptr, value);
+                ptr
+            }
+        };
+
+        let index = self.expression(index).into_int_value();
+        let elem = self.array_gep(ptr, index);
+        if no_load {
+            LLValue::from(elem.into(), &elem_ty)
+        } else {
+            self.load_ptr(&LLPtr::from(elem, &elem_ty))
+        }
+    }
+
+    fn array_alloc(&mut self, ty: &Type) -> PointerValue {
+        let ir_ty = self.ir_ty_allocs(ty);
+        self.create_alloc(ty.clone(), ir_ty, false)
+    }
+
+    fn array_gep(&self, ptr: PointerValue, index: IntValue) -> PointerValue {
+        let zero = self.context.i64_type().const_int(0, false);
+        unsafe { self.builder.build_gep(ptr, &[zero, index], "arrgep") }
+    }
+}
@@ -0,0 +1,115 @@
+use std::rc::Rc;
+
+use gir_nodes::{
+    declaration::{InlineHint, Variable},
+    Expr, Function, Type,
+};
+use inkwell::{
+    attributes::{Attribute, AttributeLoc},
+    values::FunctionValue,
+};
+
+use super::IRGenerator;
+
+impl IRGenerator {
+    /// Adds all LLVM function attributes requested with annotations.
+    pub(crate) fn add_attributes(&self, func: &Function, func_val: FunctionValue) {
+        let inline = match func.inline {
+            InlineHint::Default => None,
+            InlineHint::Always => Some("alwaysinline"),
+            InlineHint::Never => Some("noinline"),
+        };
+        let memory = match memory_access(func, &mut vec![]) {
+            MemoryAccess::None => Some("readnone"),
+            MemoryAccess::Read => Some("readonly"),
+            MemoryAccess::Unknown => None,
+        };
+        let cold = if func.cold { Some("cold") } else { None };
+
+        for name in inline.into_iter().chain(memory).chain(cold) {
+            let kind = Attribute::get_named_enum_kind_id(name);
+            let attribute = self.context.create_enum_attribute(kind, 0);
+            func_val.add_attribute(AttributeLoc::Function, attribute);
+        }
+    }
+}
+
+/// How a function accesses memory, from least to most access.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum MemoryAccess {
+    None,
+    Read,
+    Unknown,
+}
+
+/// Returns how a function marked `@pure` accesses memory.
+/// Functions only working on numbers do not access it at all,
+/// while ones also reading number fields of their parameters only read it.
+/// Anything else is unknown, since even pure functions write
+/// to memory when modifying refcounts.
+/// `visited` contains all functions currently looked at, to stop on recursion.
+fn memory_access(func: &Function, visited: &mut Vec<*const Function>) -> MemoryAccess {
+    let returns_scalar = is_scalar(&func.ret_type) || func.ret_type == Type::None;
+    if !func.pure || !returns_scalar || func.variadic {
+        return MemoryAccess::Unknown;
+    }
+    if visited.contains(&(func as *const Function)) {
+        return MemoryAccess::None;
+    }
+
+    if func.exprs.is_empty() {
+        // Extern functions only have their signature to go by
+        return if func.parameters.iter().all(|param| is_scalar(&param.ty)) {
+            MemoryAccess::None
+        } else {
+            MemoryAccess::Read
+        };
+    }
+    if !func.variables.values().all(|var| is_scalar(&var.ty)) {
+        return MemoryAccess::Unknown;
+    }
+
+    visited.push(func);
+    let access = func
+        .exprs
+        .iter()
+        .map(|expr| expr_access(expr, func, visited))
+        .max()
+        .unwrap_or(MemoryAccess::None);
+    visited.pop();
+    access
+}
+
+fn expr_access(expr: &Expr, func: &Function, visited: &mut Vec<*const Function>) -> MemoryAccess {
+    let ty = expr.get_type();
+    let mut access = match expr {
+        Expr::Load {
+            object: box Expr::Variable(Variable::Local(var)),
+            ..
+        } if func.parameters.iter().any(|param| Rc::ptr_eq(param, var)) => {
+            return if is_scalar(&ty) {
+                MemoryAccess::Read
+            } else {
+                MemoryAccess::Unknown
+            };
+        }
+
+        Expr::Call {
+            callee: box Expr::Variable(Variable::Function(callee)),
+            ..
+        } => memory_access(&callee.ty.borrow(), visited),
+
+        _ if is_scalar(&ty)
+            || matches!(ty, Type::None | Type::Any | Type::Never | Type::Function(_)) =>
+        {
+            MemoryAccess::None
+        }
+        _ => MemoryAccess::Unknown,
+    };
+    expr.for_each_child(&mut |child| access = access.max(expr_access(child, func, visited)));
+    access
+}
+
+fn is_scalar(ty: &Type) -> bool {
+    ty.is_number() && !ty.is_variable()
+}
@@ -0,0 +1,119 @@
+use std::fmt::{self, Display, Formatter};
+
+use gir_nodes::{Expr, Literal, Type};
+use inkwell::{
+    module::Linkage,
+    types::BasicTypeEnum,
+    values::{BasicValueEnum, PointerValue},
+};
+
+use super::{IRGenerator, LLPtr, LLValue};
+
+/// Statistics about the readonly constant data of a compiled program,
+/// which replaces work that would otherwise be done at runtime.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ConstantStats {
+    /// String literals compiled, including repeated ones.
+    pub string_literals: usize,
+    /// String literals that reused the data of an identical earlier one.
+    pub interned_strings: usize,
+    /// Bytes of string data not emitted again thanks to interning.
+    pub interned_bytes: usize,
+    /// Array literals emitted as a single readonly global.
+    pub constant_arrays: usize,
+    /// Stores of array elements no longer performed at runtime thanks to them.
+    pub elided_stores: usize,
+}
+
+impl Display for ConstantStats {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "String literals: {} ({} interned, {} bytes saved)",
+            self.string_literals, self.interned_strings, self.interned_bytes
+        )?;
+        write!(
+            f,
+            "Constant array literals: {} ({} element stores saved)",
+            self.constant_arrays, self.elided_stores
+        )
+    }
+}
+
+impl IRGenerator {
+    /// Returns a pointer to the null-terminated data of a string literal.
+    /// Every distinct string is only emitted once, as a readonly global.
+    pub(crate) fn string_constant(&mut self, text: &str) -> PointerValue {
+        self.constant_stats.string_literals += 1;
+        if let Some(ptr) = self.strings.get(text) {
+            self.constant_stats.interned_strings += 1;
+            self.constant_stats.interned_bytes += text.len() + 1;
+            return *ptr;
+        }
+
+        let ptr = self
+            .builder
+            .build_global_string_ptr(text, "str")
+            .as_pointer_value();
+        self.strings.insert(text.into(), ptr);
+        ptr
+    }
+
+    /// Builds an array literal made up only of number and boolean literals
+    /// by loading it from a readonly global, instead of storing each element.
+    /// Returns None if the literal is empty or contains anything else.
+    pub(crate) fn constant_array(&mut self, ty: &Type, values: &[Expr]) -> Option<LLValue> {
+        let constant = !values.is_empty()
+            && values.iter().all(|value| match value {
+                Expr::Literal(literal) => matches!(
+                    literal,
+                    Literal::Bool(_)
+                        | Literal::Int { .. }
+                        | Literal::I8(_)
+                        | Literal::I16(_)
+                        | Literal::I32(_)
+                        | Literal::I64(_)
+                        | Literal::U8(_)
+                        | Literal::U16(_)
+                        | Literal::U32(_)
+                        | Literal::U64(_)
+                        | Literal::F32(_)
+                        | Literal::F64(_)
+                ),
+                _ => false,
+            });
+        if !constant {
+            return None;
+        }
+
+        let values = values
+            .iter()
+            .map(|value| *self.expression(value))
+            .collect::<Vec<_>>();
+        let array = match self.ir_ty_generic(&ty.as_array().ty) {
+            BasicTypeEnum::IntType(int) => int.const_array(
+                &values
+                    .iter()
+                    .map(BasicValueEnum::into_int_value)
+                    .collect::<Vec<_>>(),
+            ),
+            BasicTypeEnum::FloatType(float) => float.const_array(
+                &values
+                    .iter()
+                    .map(BasicValueEnum::into_float_value)
+                    .collect::<Vec<_>>(),
+            ),
+            _ => return None,
+        };
+
+        let global = self.module.add_global(array.get_type(), None, "constarr");
+        global.set_initializer(&array);
+        global.set_constant(true);
+        global.set_unnamed_addr(true);
+        global.set_linkage(Linkage::Private);
+
+        self.constant_stats.constant_arrays += 1;
+        self.constant_stats.elided_stores += values.len();
+        Some(self.load_ptr(&LLPtr::from(global.as_pointer_value(), ty)))
+    }
+}
@@ -34,9 +34,17 @@ impl IRGenerator {
         match expr {
             Expr::Block(block) => {
                 self.push_local_scope();
-                let ret = block
-                    .iter()
-                    .fold(self.none_const.clone(), |_, ex| self.expression(ex));
+                // The last expression is the value of the block; it may need to be
+                // a pointer, like when storing into an array element
+                let ret = match block.split_last() {
+                    Some((last, rest)) => {
+                        for ex in rest {
+                            self.expression(ex);
+                        }
+                        self.expression_(last, no_load)
+                    }
+                    None => self.none_const.clone(),
+                };
                 self.pop_locals_lift(&ret);
                 ret
             }
@@ -72,6 +80,12 @@ impl IRGenerator {
                 }
             }
 
+            Expr::ArrayLiteral { ty, values } => self.array_literal(ty, values),
+
+            Expr::ArrayRepeat { ty, value } => self.array_repeat(ty, value),
+
+            Expr::Index { object, index } => self.array_index(object, index, no_load),
+
             Expr::Store {
                 location,
                 value,
@@ -96,8 +110,12 @@ impl IRGenerator {
                 if *operator == SyntaxKind::Is {
                     self.binary_is(left, &right.get_type_get_type())
                 } else {
+                    let unsigned = match right.get_type() {
+                        Type::Vector(vec) => !vec.ty.is_signed_int(),
+                        ty => !ty.is_signed_int(),
+                    };
                     let right = self.expression(right);
-                    self.binary(left, *operator, right)
+                    self.binary(left, *operator, right, unsigned)
                 }
             }
 
@@ -113,13 +131,23 @@ impl IRGenerator {
                 then_branch,
                 else_branch,
                 phi_type,
-            } => self.if_(condition, then_branch, else_branch, phi_type.is_some()),
+            } => self.if_(condition, then_branch, else_branch, phi_type),
 
             Expr::Switch {
                 branches,
                 else_branch,
                 phi_type,
-            } => self.switch(branches, else_branch, phi_type.is_some()),
+                jump_table: true,
+            } if Self::is_table_switch(branches) => {
+                self.table_switch(branches, else_branch, phi_type)
+            }
+
+            Expr::Switch {
+                branches,
+                else_branch,
+                phi_type,
+                ..
+            } => self.switch(branches, else_branch, phi_type),
 
             Expr::Loop {
                 condition,
@@ -148,6 +176,7 @@ impl IRGenerator {
                 let value = self.expression(value);
                 self.increment_refcount(&value);
                 self.decrement_all_locals();
+                self.maybe_call_leak_report();
 
                 if matches!(value.ty, IRType::None) {
                     self.builder.build_return(None);
@@ -192,6 +221,9 @@ impl IRGenerator {
         let alloc = self.create_alloc(ty.clone(), ir_ty, ty.is_ref_adt());
 
         let adt = ty.try_adt().unwrap();
+        if ty.is_ref_adt() {
+            self.count_alloc(adt);
+        }
         let constructor = self.get_or_create(&Instance::new(
             Rc::clone(constructor),
             Rc::clone(adt.args()),
@@ -245,16 +277,31 @@ impl IRGenerator {
         alloc.into_val()
     }
 
-    fn binary(&self, left_: LLValue, operator: SyntaxKind, right_: LLValue) -> LLValue {
+    fn binary(
+        &self,
+        left_: LLValue,
+        operator: SyntaxKind,
+        right_: LLValue,
+        unsigned: bool,
+    ) -> LLValue {
         match (*left_, *right_) {
             (BasicValueEnum::IntValue(left), BasicValueEnum::IntValue(right)) => LLValue::cpy(
                 BasicValueEnum::IntValue(match operator {
                     SyntaxKind::Plus => self.builder.build_int_add(left, right, "add"),
                     SyntaxKind::Minus => self.builder.build_int_sub(left, right, "sub"),
                     SyntaxKind::Star => self.builder.build_int_mul(left, right, "mul"),
+                    SyntaxKind::Slash if unsigned => {
+                        self.builder.build_int_unsigned_div(left, right, "div")
+                    }
                     SyntaxKind::Slash => self.builder.build_int_signed_div(left, right, "div"),
-                    SyntaxKind::And => self.builder.build_and(left, right, "and"),
-                    SyntaxKind::Or => self.builder.build_or(left, right, "or"),
+                    SyntaxKind::Percent if unsigned => {
+                        self.builder.build_int_unsigned_rem(left, right, "rem")
+                    }
+                    SyntaxKind::Percent => self.builder.build_int_signed_rem(left, right, "rem"),
+                    SyntaxKind::And | SyntaxKind::Ampersand => {
+                        self.builder.build_and(left, right, "and")
+                    }
+                    SyntaxKind::Or | SyntaxKind::Pipe => self.builder.build_or(left, right, "or"),
                     _ => {
                         self.builder
                             .build_int_compare(get_predicate(operator), left, right, "cmp")
@@ -269,6 +316,7 @@ impl IRGenerator {
                     SyntaxKind::Minus => self.builder.build_float_sub(left, right, "sub").into(),
                     SyntaxKind::Star => self.builder.build_float_mul(left, right, "mul").into(),
                     SyntaxKind::Slash => self.builder.build_float_div(left, right, "div").into(),
+                    SyntaxKind::Percent => self.builder.build_float_rem(left, right, "rem").into(),
                     _ => self
                         .builder
                         .build_float_compare(get_float_predicate(operator), left, right, "cmp")
@@ -277,6 +325,13 @@ impl IRGenerator {
                 &left_.ty,
             ),
 
+            (BasicValueEnum::VectorValue(left), BasicValueEnum::VectorValue(right)) => {
+                LLValue::cpy(
+                    self.vector_binary(left, operator, right, unsigned),
+                    &left_.ty,
+                )
+            }
+
             // Pointer comparison, only used for nullable checks ATM
             (BasicValueEnum::PointerValue(ptr1), BasicValueEnum::PointerValue(ptr2)) => {
                 let ty = self.context.i64_type();
@@ -286,6 +341,7 @@ impl IRGenerator {
                     LLValue::cpy(left.into(), &IRType::Primitive),
                     operator,
                     LLValue::cpy(right.into(), &IRType::Primitive),
+                    unsigned,
                 )
             }
 
@@ -298,6 +354,7 @@ impl IRGenerator {
                     LLValue::cpy(left.into(), &IRType::Primitive),
                     operator,
                     LLValue::cpy(right.into(), &IRType::Primitive),
+                    unsigned,
                 )
             }
 
@@ -313,6 +370,7 @@ impl IRGenerator {
                     LLValue::cpy(left, &IRType::Primitive),
                     operator,
                     LLValue::cpy(right.into(), &IRType::Primitive),
+                    unsigned,
                 )
             }
 
@@ -330,6 +388,7 @@ impl IRGenerator {
                     LLValue::cpy(left, &IRType::Primitive),
                     operator,
                     LLValue::cpy(right, &IRType::Primitive),
+                    unsigned,
                 )
             }
 
@@ -387,6 +446,12 @@ impl IRGenerator {
             self.decrement_refcount(&LLValue::cpy(*arg, ty));
         }
 
+        // Functions returning 'Never' do not return, any code after them is unreachable
+        if let Type::Never = ret_type {
+            self.builder.build_unreachable();
+            self.builder.clear_insertion_position();
+        }
+
         LLValue::from(ret, &ret_type)
     }
 
@@ -408,6 +473,8 @@ impl IRGenerator {
         LLValue::from(
             match literal {
                 Literal::Any | Literal::None | Literal::Null => *self.none_const,
+                // Only produced alongside an error, which stops compilation before IR
+                Literal::Error => unreachable!(),
                 Literal::Bool(value) => self
                     .context
                     .bool_type()
@@ -426,6 +493,11 @@ impl IRGenerator {
                 Literal::I64(num) | Literal::U64(num) => {
                     self.context.i64_type().const_int(*num as u64, false).into()
                 }
+                Literal::Int { value, ty } => self
+                    .ir_ty_generic(ty)
+                    .into_int_type()
+                    .const_int(*value, false)
+                    .into(),
 
                 Literal::F32(num) => self.context.f32_type().const_float((*num).into()).into(),
                 Literal::F64(num) => self.context.f64_type().const_float(*num).into(),
@@ -434,7 +506,7 @@ impl IRGenerator {
                     text: string,
                     ty: string_ty,
                 } => {
-                    let const_str = self.builder.build_global_string_ptr(&string, "str");
+                    let const_str = self.string_constant(string);
                     let constructor = Rc::clone(&string_ty.as_adt().ty.borrow().constructors[1]);
 
                     return self.allocate_raw_args(
@@ -452,7 +524,7 @@ impl IRGenerator {
                                 self.context.i64_type().const_int(0, false).into(),
                                 &IRType::Other,
                             ),
-                            LLValue::cpy(const_str.as_pointer_value().into(), &IRType::Other),
+                            LLValue::cpy(const_str.into(), &IRType::Other),
                         ],
                     );
                 }
@@ -461,7 +533,8 @@ impl IRGenerator {
         )
     }
 
-    fn if_(&mut self, cond: &Expr, then: &Expr, else_: &Expr, phi: bool) -> LLValue {
+    fn if_(&mut self, cond: &Expr, then: &Expr, else_: &Expr, phi_type: &Option<Type>) -> LLValue {
+        let phi = phi_type.is_some();
         let cond = self.expression(cond);
         let then_bb = self.append_block("then");
         let else_bb = self.append_block("else");
@@ -489,17 +562,17 @@ impl IRGenerator {
         let (else_val, else_bb) = build_block(else_, else_bb);
 
         self.position_at_block(cont_bb);
-        if phi {
-            LLValue::from(
+        match phi_type {
+            Some(ty) => LLValue::from(
                 self.build_phi(&[(then_val, then_bb), (else_val, else_bb)]),
-                &then.get_type(),
-            )
-        } else {
-            self.none_const.clone()
+                ty,
+            ),
+            None => self.none_const.clone(),
         }
     }
 
-    fn switch(&mut self, cases: &[(Expr, Expr)], else_: &Expr, phi: bool) -> LLValue {
+    fn switch(&mut self, cases: &[(Expr, Expr)], else_: &Expr, phi_type: &Option<Type>) -> LLValue {
+        let phi = phi_type.is_some();
         let cond = self.context.bool_type().const_int(1, false);
         let end_bb = self.append_block("when-end");
 
@@ -547,10 +620,97 @@ impl IRGenerator {
         phi_nodes.push((else_val, else_end_bb));
 
         self.position_at_block(end_bb);
+        match phi_type {
+            Some(ty) => LLValue::from(self.build_phi(&phi_nodes), ty),
+            None => self.none_const.clone(),
+        }
+    }
+
+    /// If the switch still has the shape required for a jump table,
+    /// see `jump_table` on `Expr::Switch`; optimizations after
+    /// marking it might have changed the conditions.
+    fn is_table_switch(cases: &[(Expr, Expr)]) -> bool {
+        cases.iter().all(|(cond, _)| {
+            matches!(
+                cond,
+                Expr::Binary {
+                    operator: SyntaxKind::EqualEqual,
+                    right: box Expr::Literal(_),
+                    ..
+                }
+            )
+        })
+    }
+
+    /// Same as `switch`, but uses a switch instruction on the value
+    /// compared by all cases, which LLVM can turn into a jump table.
+    fn table_switch(
+        &mut self,
+        cases: &[(Expr, Expr)],
+        else_: &Expr,
+        phi_type: &Option<Type>,
+    ) -> LLValue {
+        let phi = phi_type.is_some();
+        let end_bb = self.append_block("when-end");
+        let else_bb = self.append_block("when-else");
+
+        let value = match &cases[0].0 {
+            Expr::Binary { left, .. } => {
+                self.push_local_scope();
+                let value = self.expression(left).into_int_value();
+                self.pop_dec_locals();
+                value
+            }
+            _ => unreachable!(),
+        };
+        let blocks = cases
+            .iter()
+            .map(|(cond, _)| match cond {
+                Expr::Binary { right, .. } => (
+                    self.expression(right).into_int_value(),
+                    self.append_block("when-case"),
+                ),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        self.builder.build_switch(
+            value,
+            &else_bb,
+            &blocks
+                .iter()
+                .map(|(val, bb)| (*val, bb))
+                .collect::<Vec<_>>(),
+        );
+
+        let mut phi_nodes = Vec::with_capacity(cases.len() + 1);
+        for ((_, branch), (_, case_bb)) in cases.iter().zip(blocks.into_iter()) {
+            self.position_at_block(case_bb);
+            self.push_local_scope();
+            let value = self.expression(branch);
+            if phi {
+                self.pop_locals_remove(&value)
+            } else {
+                self.pop_dec_locals()
+            };
+            phi_nodes.push((value, self.last_block()));
+            self.unconditional_branch(&end_bb);
+        }
+
+        self.position_at_block(else_bb);
+        self.push_local_scope();
+        let else_val = self.expression(else_);
         if phi {
-            LLValue::from(self.build_phi(&phi_nodes), &else_.get_type())
+            self.pop_locals_remove(&else_val)
         } else {
-            self.none_const.clone()
+            self.pop_dec_locals()
+        };
+        phi_nodes.push((else_val, self.last_block()));
+        self.unconditional_branch(&end_bb);
+
+        self.position_at_block(end_bb);
+        match phi_type {
+            Some(ty) => LLValue::from(self.build_phi(&phi_nodes), ty),
+            None => self.none_const.clone(),
         }
     }
 
@@ -640,6 +800,8 @@ impl IRGenerator {
         match method {
             CastType::ToInterface(implementor) => self.cast_to_interface(object, implementor, to),
 
+            CastType::Vector => self.vector_cast(object, to),
+
             CastType::Bitcast => {
                 let obj = self.expression(object);
                 let cast_ty = self.ir_ty_generic(to);
@@ -649,12 +811,31 @@ impl IRGenerator {
             CastType::Number => {
                 let obj = self.expression(object);
                 let cast_ty = self.ir_ty_generic(to);
+                // Integers are extended and converted based on their own signedness,
+                // floats are converted based on the signedness of the target
+                let from_ty = object.get_type();
+                let signed = if from_ty.is_int() {
+                    from_ty.is_signed_int()
+                } else {
+                    to.is_signed_int()
+                };
 
                 LLValue::from(
-                    match (obj.get_type(), cast_ty, to.is_signed_int()) {
-                        (BasicTypeEnum::IntType(_), BasicTypeEnum::IntType(ty), _) => self
+                    match (obj.get_type(), cast_ty, signed) {
+                        (BasicTypeEnum::IntType(from), BasicTypeEnum::IntType(ty), _)
+                            if from.get_bit_width() >= ty.get_bit_width() =>
+                        {
+                            self.builder
+                                .build_int_cast(obj.into_int_value(), ty, "cast")
+                                .into()
+                        }
+                        (BasicTypeEnum::IntType(_), BasicTypeEnum::IntType(ty), true) => self
+                            .builder
+                            .build_int_s_extend(obj.into_int_value(), ty, "cast")
+                            .into(),
+                        (BasicTypeEnum::IntType(_), BasicTypeEnum::IntType(ty), false) => self
                             .builder
-                            .build_int_cast(obj.into_int_value(), ty, "cast")
+                            .build_int_z_extend(obj.into_int_value(), ty, "cast")
                             .into(),
                         (BasicTypeEnum::FloatType(_), BasicTypeEnum::FloatType(ty), _) => self
                             .builder
@@ -720,7 +901,7 @@ impl IRGenerator {
 
             CastType::FromNullable => {
                 let value = self.expression(object);
-                if let Type::None | Type::Any | Type::Null = to {
+                if let Type::None | Type::Any | Type::Never | Type::Null = to {
                     self.none_const.clone()
                 } else if !is_ptr(to) {
                     LLValue::from(
@@ -833,11 +1014,77 @@ impl IRGenerator {
 
             Intrinsic::Free(val) => {
                 let val = self.expression(val);
-                if let BasicValueEnum::PointerValue(ptr) = *val {
-                    self.builder.build_free(ptr);
+                match (*val, &val.ty) {
+                    // Weak references might still point to the value,
+                    // in which case the last one to be dropped frees it
+                    (BasicValueEnum::PointerValue(ptr), IRType::RefAdt(adt))
+                        if Self::refcount_fields(&val.ty) != 0 =>
+                    {
+                        let adt = adt.clone();
+                        let weak_count = self.struct_gep_raw(ptr, 1);
+                        let weak_count = self
+                            .builder
+                            .build_load(weak_count, "weakload")
+                            .into_int_value();
+                        let unreferenced = self.builder.build_int_compare(
+                            IntPredicate::EQ,
+                            weak_count,
+                            self.context.i32_type().const_int(0, false),
+                            "weakcond",
+                        );
+                        self.build_free_if(ptr, &adt, unreferenced);
+                    }
+                    (BasicValueEnum::PointerValue(ptr), _) => self.build_heap_free(ptr),
+                    _ => (),
                 }
             }
 
+            Intrinsic::UpgradeWeak(weak) => {
+                let ty = match weak.get_type() {
+                    Type::Weak(inner) => Type::Nullable(inner),
+                    _ => unreachable!(),
+                };
+                let ptr = self.expression(weak).into_pointer_value();
+                let strong_count = self.struct_gep_raw(ptr, 0);
+                let strong_count = self
+                    .builder
+                    .build_load(strong_count, "rcload")
+                    .into_int_value();
+                let alive = self.builder.build_int_compare(
+                    IntPredicate::NE,
+                    strong_count,
+                    self.context.i32_type().const_int(0, false),
+                    "upgradecond",
+                );
+                let value =
+                    self.builder
+                        .build_select(alive, ptr, ptr.get_type().const_null(), "upgrade");
+                return LLValue::from(value, &ty);
+            }
+
+            Intrinsic::VectorSplat { value, ty } => return self.vector_splat(value, ty),
+
+            Intrinsic::VectorExtract { vector, index } => {
+                return self.vector_extract(vector, index)
+            }
+
+            Intrinsic::VectorInsert {
+                vector,
+                index,
+                value,
+            } => return self.vector_insert(vector, index, value),
+
+            Intrinsic::VectorShuffle {
+                left,
+                right,
+                mask,
+                ty,
+            } => return self.vector_shuffle(left, right.as_deref(), mask, ty),
+
+            Intrinsic::Expect { value, expected } => return self.expect(value, *expected),
+
+            Intrinsic::EnumTag(value) => return self.enum_tag(value),
+
             Intrinsic::IfaceCall {
                 iface,
                 index,
@@ -876,10 +1123,12 @@ impl IRGenerator {
                 let method = {
                     let len = self.type_args.len() - 1;
                     let implementor = &self.type_args[len][*index];
+                    // The interface may refer to type parameters (like `K: Hash[K]`)
+                    let interface = interface.resolve(&self.type_args[len]);
 
                     let impls = &self.gir_data.iface_impls[implementor];
                     let impls = impls.borrow();
-                    let imp = &impls.interfaces[interface];
+                    let imp = &impls.interfaces[&interface];
                     imp.methods[&iface_method.borrow().name].to_inst()
                 };
                 return LLValue::of(
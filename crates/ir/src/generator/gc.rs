@@ -7,7 +7,7 @@
 use gir_nodes::{declaration::ADTType, Instance, ADT};
 use inkwell::{
     values::{BasicValueEnum, IntValue, PointerValue, StructValue},
-    IntPredicate,
+    AtomicOrdering, AtomicRMWBinOp, IntPredicate,
 };
 use std::rc::Rc;
 
@@ -28,18 +28,21 @@ impl IRGenerator {
     /// Increment the refcount of a value.
     /// is_ptr specifies if the value is a pointer or a value in
     /// the context of the MIR type system.
-    pub(crate) fn increment_refcount(&mut self, _value: &LLValue) {
-        // self.mod_refcount(value, false)
+    pub(crate) fn increment_refcount(&mut self, value: &LLValue) {
+        if self.gir_data.flags.refcounting {
+            self.mod_refcount(value, false)
+        }
     }
 
     /// Decrement the refcount of a value, and check if it needs to be freed
     /// is_ptr specifies if the value is a pointer or a value in
     /// the context of the MIR type system.
-    pub(crate) fn decrement_refcount(&mut self, _value: &LLValue) {
-        // self.mod_refcount(value, true)
+    pub(crate) fn decrement_refcount(&mut self, value: &LLValue) {
+        if self.gir_data.flags.refcounting {
+            self.mod_refcount(value, true)
+        }
     }
 
-    #[allow(dead_code)]
     fn mod_refcount(&mut self, value: &LLValue, decrement: bool) {
         match (**value, &value.ty) {
             (BasicValueEnum::StructValue(struc), IRType::ValueAdt(inst))
@@ -63,6 +66,10 @@ impl IRGenerator {
                 self.mod_refcount_adt(ptr, &inst, decrement)
             }
 
+            (BasicValueEnum::PointerValue(ptr), IRType::WeakAdt(inst)) => {
+                self.mod_weak_count(ptr, &inst, decrement)
+            }
+
             (BasicValueEnum::PointerValue(_), IRType::Closure(_)) => {
                 self.mod_refcount_closure(value.ptr(), decrement)
             }
@@ -75,7 +82,7 @@ impl IRGenerator {
         if adt.ty.borrow().ty.is_extern_class() {
             return;
         }
-        if let Some(destructor) = &adt.ty.borrow().methods.get("free-sr") {
+        if let Some(destructor) = &adt.ty.borrow().methods.get("free-instance") {
             if ptr.get_type().get_element_type().is_pointer_type() {
                 ptr = self.builder.build_load(ptr, "gcload").into_pointer_value();
             }
@@ -95,6 +102,49 @@ impl IRGenerator {
         }
     }
 
+    /// Modify the weak refcount of an ADT. Should both the strong and
+    /// the weak refcount be 0 afterwards, the ADT is freed; its destructor
+    /// already ran when the strong refcount reached 0.
+    fn mod_weak_count(&mut self, mut ptr: PointerValue, adt: &Instance<ADT>, decrement: bool) {
+        if !adt.ty.borrow().refcounted() {
+            return;
+        }
+        if ptr.get_type().get_element_type().is_pointer_type() {
+            ptr = self.builder.build_load(ptr, "gcload").into_pointer_value();
+        }
+
+        let weak_count = self.struct_gep_raw(ptr, 1);
+        let weak_count = self.write_new_refcount(weak_count, decrement);
+        if decrement {
+            let strong_count = self.struct_gep_raw(ptr, 0);
+            let strong_count = self
+                .builder
+                .build_load(strong_count, "rcload")
+                .into_int_value();
+            let unreferenced = self.builder.build_int_compare(
+                IntPredicate::EQ,
+                self.builder.build_or(weak_count, strong_count, "rcunion"),
+                self.context.i32_type().const_int(0, false),
+                "rccond",
+            );
+            self.build_free_if(ptr, adt, unreferenced)
+        }
+    }
+
+    /// Frees the given ADT instance if the condition is true.
+    pub(super) fn build_free_if(&mut self, ptr: PointerValue, adt: &Instance<ADT>, cond: IntValue) {
+        let free_bb = self.append_block("free");
+        let cont_bb = self.append_block("cont");
+        self.builder
+            .build_conditional_branch(cond, &free_bb, &cont_bb);
+
+        self.position_at_block(free_bb);
+        self.count_free(adt);
+        self.build_heap_free(ptr);
+        self.builder.build_unconditional_branch(&cont_bb);
+        self.position_at_block(cont_bb);
+    }
+
     fn mod_refcount_iface(&self, struc: StructValue, decrement: bool) {
         let func = if decrement {
             self.module.get_function("gelixrs_dec_ref_iface")
@@ -136,8 +186,25 @@ impl IRGenerator {
     }
 
     pub(super) fn write_new_refcount(&self, refcount: PointerValue, decrement: bool) -> IntValue {
-        let rc = self.builder.build_load(refcount, "rcload").into_int_value();
         let added = self.context.i32_type().const_int(1, false);
+        if self.gir_data.flags.atomic_refcounts {
+            let op = if decrement {
+                AtomicRMWBinOp::Sub
+            } else {
+                AtomicRMWBinOp::Add
+            };
+            let previous = self
+                .builder
+                .build_atomicrmw(op, refcount, added, AtomicOrdering::AcquireRelease)
+                .unwrap();
+            return if decrement {
+                self.builder.build_int_sub(previous, added, "rcdec")
+            } else {
+                self.builder.build_int_add(previous, added, "rcinc")
+            };
+        }
+
+        let rc = self.builder.build_load(refcount, "rcload").into_int_value();
         let new_rc = if decrement {
             self.builder.build_int_sub(rc, added, "rcdec")
         } else {
@@ -5,19 +5,117 @@
  */
 
 use common::MutRc;
-use gir_nodes::{types::TypeArguments, Function, Module, Type};
+use gir_nodes::{types::TypeArguments, Expr, Function, Module, Type};
 use inkwell::{
     basic_block::BasicBlock,
-    types::BasicType,
-    values::{BasicValue, FunctionValue, PointerValue},
+    context::Context,
+    memory_buffer::MemoryBuffer,
+    module::Module as LLModule,
+    types::{BasicType, BasicTypeEnum},
+    values::{BasicValue, BasicValueEnum, FunctionValue, InstructionValue, IntValue, PointerValue},
     AddressSpace::Generic,
-    IntPredicate,
+    AtomicOrdering, AtomicRMWBinOp, IntPredicate,
 };
 use std::rc::Rc;
 
-use super::{type_adapter::LLValue, IRGenerator};
+use super::{
+    type_adapter::{LLPtr, LLValue},
+    IRGenerator,
+};
 
 impl IRGenerator {
+    /// Builds a switch over the ordering of an atomic intrinsic, given as its last parameter.
+    /// The ordering codes are the ones used by `std/atomic/Ordering`, in order:
+    /// Relaxed, Acquire, Release, AcqRel and SeqCst (also used for any unknown code).
+    /// This is required since LLVM only accepts constant orderings; `build` is called
+    /// for every ordering and must terminate the block, usually by returning.
+    fn atomic_ordering_switch(
+        &mut self,
+        ir: FunctionValue,
+        build: impl Fn(&mut IRGenerator, AtomicOrdering),
+    ) {
+        let code = ir.get_last_param().unwrap().into_int_value();
+        let orderings = [
+            AtomicOrdering::Monotonic,
+            AtomicOrdering::Acquire,
+            AtomicOrdering::Release,
+            AtomicOrdering::AcquireRelease,
+            AtomicOrdering::SequentiallyConsistent,
+        ];
+        let blocks = orderings
+            .iter()
+            .map(|_| ir.append_basic_block("ordering"))
+            .collect::<Vec<_>>();
+        let cases = blocks
+            .iter()
+            .enumerate()
+            .map(|(i, block)| (code.get_type().const_int(i as u64, false), block))
+            .collect::<Vec<_>>();
+        self.builder
+            .build_switch(code, blocks.last().unwrap(), &cases);
+
+        for (ordering, block) in orderings.iter().zip(blocks.iter()) {
+            self.builder.position_at_end(block);
+            build(self, *ordering);
+        }
+    }
+
+    /// Makes the given load or store atomic. Atomic instructions
+    /// require an explicit alignment, which is the size of the value.
+    fn make_atomic(&self, inst: InstructionValue, ptr: PointerValue, ordering: AtomicOrdering) {
+        let size = ptr
+            .get_type()
+            .get_element_type()
+            .into_int_type()
+            .get_bit_width()
+            / 8;
+        inst.set_atomic_ordering(ordering).unwrap();
+        inst.set_alignment(size).unwrap();
+    }
+
+    /// Builds a call to `llvm.expect`, which LLVM turns into branch weights
+    /// on any branch depending on the value.
+    pub(crate) fn expect(&mut self, value: &Expr, expected: bool) -> LLValue {
+        let bool_ty = self.context.bool_type();
+        let expect = self
+            .module
+            .get_function("llvm.expect.i1")
+            .unwrap_or_else(|| {
+                let ty = bool_ty.fn_type(&[bool_ty.into(), bool_ty.into()], false);
+                self.module.add_function("llvm.expect.i1", ty, None)
+            });
+        let value = self.expression(value);
+        let expected = bool_ty.const_int(expected as u64, false);
+        let result = self
+            .builder
+            .build_call(expect, &[*value, expected.into()], "expect")
+            .try_as_basic_value()
+            .left()
+            .unwrap();
+        LLValue::from(result, &Type::Bool)
+    }
+
+    /// Reads the discriminant of an enum value's case,
+    /// which is stored in the type info of the case.
+    pub(crate) fn enum_tag(&mut self, value: &Expr) -> LLValue {
+        let value = self.expression(value);
+        let type_info = self.get_type_info_field(&value.ptr());
+        let type_info = self.load_ptr(&LLPtr::cpy(type_info, &value.ty));
+        let tag = self.struct_gep_raw(type_info.into_pointer_value(), 0);
+        let tag = self.builder.build_load(tag, "tag");
+        LLValue::from(tag, &Type::I64)
+    }
+
+    /// Links LLVM IR given with `@inline_ir` into the module.
+    /// The IR was already checked to be valid by [crate::check_inline_ir].
+    pub(crate) fn link_inline_ir(&mut self, func: &Function, inline_ir: &str) {
+        let module = parse_inline_ir(&self.context, &func.name, inline_ir)
+            .expect("Inline IR should have been checked");
+        self.module
+            .link_in_module(module)
+            .unwrap_or_else(|e| panic!("Failed to link inline IR of '{}':\n{}", func.name, e));
+    }
+
     pub(crate) fn fill_intrinsic_functions(&mut self, module: &MutRc<Module>) {
         for func in &module.borrow().functions {
             let func = func.borrow();
@@ -43,22 +141,12 @@ impl IRGenerator {
 
         // Strip the "gelixrs_" all IR functions start with
         match &name[8..] {
-            // Returns the size of the type. Implemented by
-            // indexing a null pointer (0x0) at element 1
-            // and returning the offset from 0.
-            // Credit: http://nondot.org/sabre/LLVMNotes/SizeOf-OffsetOf-VariableSizedStructs.txt
             "get_type_size" => {
-                let i = self.context.i64_type();
-                let typ = self.ir_ty_generic(&ty_args.unwrap()[0]).ptr_type(Generic);
-                let ptr_size = unsafe {
-                    self.builder
-                        .build_gep(typ.const_null(), &[i.const_int(1, false)], "size")
-                };
-                self.builder
-                    .build_return(Some(&self.builder.build_ptr_to_int(ptr_size, i, "sizeI")));
+                let size = self.type_size(&ty_args.unwrap()[0]);
+                self.builder.build_return(Some(&size));
             }
 
-            "ptr_to_int" => {
+            "ptr_to_int" | "ref_to_int" => {
                 self.builder.build_return(Some(
                     &self
                         .builder
@@ -71,7 +159,7 @@ impl IRGenerator {
                 ));
             }
 
-            "int_to_ptr" => {
+            "int_to_ptr" | "int_to_ref" => {
                 self.builder.build_return(Some(
                     &self
                         .builder
@@ -84,6 +172,19 @@ impl IRGenerator {
                 ));
             }
 
+            "cast_ptr" => {
+                self.builder.build_return(Some(
+                    &self
+                        .builder
+                        .build_pointer_cast(
+                            ir.get_first_param().unwrap().into_pointer_value(),
+                            ir.get_type().get_return_type().unwrap().into_pointer_type(),
+                            "cast",
+                        )
+                        .as_basic_value_enum(),
+                ));
+            }
+
             "gep" => {
                 let ptr = ir.get_first_param().unwrap().into_pointer_value();
                 let index = ir.get_last_param().unwrap().into_int_value();
@@ -95,6 +196,53 @@ impl IRGenerator {
                 }));
             }
 
+            "is_trivial" => {
+                let trivial = ty_args.unwrap()[0].is_trivially_copyable();
+                let trivial = self.context.bool_type().const_int(trivial as u64, false);
+                self.builder.build_return(Some(&trivial));
+            }
+
+            "memmove" => {
+                let src = ir.get_first_param().unwrap().into_pointer_value();
+                let dst = ir.get_nth_param(1).unwrap().into_pointer_value();
+                let len = ir.get_last_param().unwrap().into_int_value();
+                let size = self.type_size(&ty_args.unwrap()[0]);
+                let bytes = self.builder.build_int_mul(len, size, "bytes");
+
+                let i8_ptr = self.context.i8_type().ptr_type(Generic);
+                let memmove = self.llvm_intrinsic(
+                    "llvm.memmove.p0i8.p0i8.i64",
+                    &[
+                        i8_ptr.into(),
+                        i8_ptr.into(),
+                        self.context.i64_type().into(),
+                        self.context.bool_type().into(),
+                    ],
+                );
+                let src = self.builder.build_pointer_cast(src, i8_ptr, "src");
+                let dst = self.builder.build_pointer_cast(dst, i8_ptr, "dst");
+                let volatile = self.context.bool_type().const_int(0, false);
+                self.builder.build_call(
+                    memmove,
+                    &[dst.into(), src.into(), bytes.into(), volatile.into()],
+                    "memmove",
+                );
+                self.builder.build_return(None);
+            }
+
+            "fill" => {
+                let ptr = ir.get_first_param().unwrap().into_pointer_value();
+                let len = ir.get_nth_param(1).unwrap().into_int_value();
+                let value = ir.get_last_param().unwrap();
+                match value {
+                    BasicValueEnum::IntValue(int) if int.get_type().get_bit_width() <= 8 => {
+                        self.fill_bytes(ptr, len, int)
+                    }
+                    _ => self.fill_loop(ptr, len, value),
+                }
+                self.builder.build_return(None);
+            }
+
             // Write a value to a pointer.
             "write_ptr" => {
                 let value = ir.get_last_param().unwrap();
@@ -105,19 +253,13 @@ impl IRGenerator {
             }
 
             "inc_ref" => {
-                let ll = LLValue::from(
-                    ir.get_first_param().unwrap(),
-                    &func.parameters.first().unwrap().ty,
-                );
+                let ll = Self::refcounted_param(func, ty_args, ir);
                 self.increment_refcount(&ll);
                 self.builder.build_return(None);
             }
 
             "dec_ref" => {
-                let ll = LLValue::from(
-                    ir.get_first_param().unwrap(),
-                    &func.parameters.first().unwrap().ty,
-                );
+                let ll = Self::refcounted_param(func, ty_args, ir);
                 self.decrement_refcount(&ll);
                 self.builder.build_return(None);
             }
@@ -138,15 +280,24 @@ impl IRGenerator {
                         self.builder.build_call(destructor, &[value], "free");
                     }
 
-                    // Primitive, simply calling free is enough since it must be a raw pointer
-                    _ => {
-                        self.builder.build_free(value.into_pointer_value());
-                    }
+                    // Primitive, must be a raw pointer from `heap_alloc`
+                    _ => self.build_heap_free(value.into_pointer_value()),
                 }
 
                 self.builder.build_return(None);
             }
 
+            "heap_alloc" => {
+                let size = ir.get_first_param().unwrap();
+                let ptr = self
+                    .builder
+                    .build_call(self.alloc_fn(), &[size], "alloc")
+                    .try_as_basic_value()
+                    .left()
+                    .unwrap();
+                self.builder.build_return(Some(&ptr));
+            }
+
             "load_value" => {
                 let value = ir.get_first_param().unwrap();
                 self.builder.build_return(Some(
@@ -181,10 +332,182 @@ impl IRGenerator {
                 self.builder.build_return(None);
             }
 
+            // Atomic operations take their ordering as the last parameter,
+            // see `atomic_ordering_switch` for details.
+            "atomic_load" => {
+                let ptr = ir.get_first_param().unwrap().into_pointer_value();
+                self.atomic_ordering_switch(ir, |this, ordering| {
+                    // Loads cannot release, use the strongest ordering possible instead
+                    let ordering = match ordering {
+                        AtomicOrdering::Release => AtomicOrdering::Monotonic,
+                        AtomicOrdering::AcquireRelease => AtomicOrdering::Acquire,
+                        _ => ordering,
+                    };
+                    let load = this.builder.build_load(ptr, "atomicload");
+                    this.make_atomic(load.as_instruction_value().unwrap(), ptr, ordering);
+                    this.builder.build_return(Some(&load));
+                });
+            }
+
+            "atomic_store" => {
+                let ptr = ir.get_first_param().unwrap().into_pointer_value();
+                let value = ir.get_nth_param(1).unwrap();
+                self.atomic_ordering_switch(ir, |this, ordering| {
+                    // Stores cannot acquire, use the strongest ordering possible instead
+                    let ordering = match ordering {
+                        AtomicOrdering::Acquire => AtomicOrdering::Monotonic,
+                        AtomicOrdering::AcquireRelease => AtomicOrdering::Release,
+                        _ => ordering,
+                    };
+                    let store = this.builder.build_store(ptr, value);
+                    this.make_atomic(store, ptr, ordering);
+                    this.builder.build_return(None);
+                });
+            }
+
+            "atomic_add" | "atomic_sub" => {
+                let op = if &name[8..] == "atomic_add" {
+                    AtomicRMWBinOp::Add
+                } else {
+                    AtomicRMWBinOp::Sub
+                };
+                let ptr = ir.get_first_param().unwrap().into_pointer_value();
+                let value = ir.get_nth_param(1).unwrap().into_int_value();
+                self.atomic_ordering_switch(ir, |this, ordering| {
+                    let previous = this
+                        .builder
+                        .build_atomicrmw(op, ptr, value, ordering)
+                        .unwrap();
+                    this.builder.build_return(Some(&previous));
+                });
+            }
+
+            "atomic_compare_exchange" => {
+                let ptr = ir.get_first_param().unwrap().into_pointer_value();
+                let expected = ir.get_nth_param(1).unwrap();
+                let new = ir.get_nth_param(2).unwrap();
+                self.atomic_ordering_switch(ir, |this, ordering| {
+                    // The ordering on failure is only a load, which cannot release
+                    let failure = match ordering {
+                        AtomicOrdering::Release => AtomicOrdering::Monotonic,
+                        AtomicOrdering::AcquireRelease => AtomicOrdering::Acquire,
+                        _ => ordering,
+                    };
+                    let result = this
+                        .builder
+                        .build_cmpxchg(ptr, expected, new, ordering, failure)
+                        .unwrap();
+                    let previous = this
+                        .builder
+                        .build_extract_value(result, 0, "previous")
+                        .unwrap();
+                    this.builder.build_return(Some(&previous));
+                });
+            }
+
             _ => panic!("Unknown intrinsic function: {}", name),
         }
     }
 
+    /// Returns the LLVM intrinsic of the given name, declaring it if needed.
+    /// All intrinsics used here return nothing.
+    fn llvm_intrinsic(&self, name: &str, params: &[BasicTypeEnum]) -> FunctionValue {
+        self.module.get_function(name).unwrap_or_else(|| {
+            let ty = self.context.void_type().fn_type(params, false);
+            self.module.add_function(name, ty, None)
+        })
+    }
+
+    /// Returns the size of the type in bytes. Implemented by
+    /// indexing a null pointer (0x0) at element 1
+    /// and returning the offset from 0.
+    /// Credit: http://nondot.org/sabre/LLVMNotes/SizeOf-OffsetOf-VariableSizedStructs.txt
+    fn type_size(&mut self, ty: &Type) -> IntValue {
+        let i64_ty = self.context.i64_type();
+        let ptr_ty = self.ir_ty_generic(ty).ptr_type(Generic);
+        let size = unsafe {
+            self.builder
+                .build_gep(ptr_ty.const_null(), &[i64_ty.const_int(1, false)], "size")
+        };
+        self.builder.build_ptr_to_int(size, i64_ty, "sizeI")
+    }
+
+    /// Sets `len` elements of a single byte at `ptr` to the value using a memset.
+    fn fill_bytes(&mut self, ptr: PointerValue, len: IntValue, value: IntValue) {
+        let i8_ty = self.context.i8_type();
+        let memset = self.llvm_intrinsic(
+            "llvm.memset.p0i8.i64",
+            &[
+                i8_ty.ptr_type(Generic).into(),
+                i8_ty.into(),
+                self.context.i64_type().into(),
+                self.context.bool_type().into(),
+            ],
+        );
+        let ptr = self
+            .builder
+            .build_pointer_cast(ptr, i8_ty.ptr_type(Generic), "bytes");
+        let value = self
+            .builder
+            .build_int_z_extend_or_bit_cast(value, i8_ty, "byte");
+        let volatile = self.context.bool_type().const_int(0, false);
+        self.builder.build_call(
+            memset,
+            &[ptr.into(), value.into(), len.into(), volatile.into()],
+            "memset",
+        );
+    }
+
+    /// Stores the value into `len` elements at `ptr` using a loop,
+    /// which LLVM turns into a memset if the bytes of the value allow it.
+    fn fill_loop(&mut self, ptr: PointerValue, len: IntValue, value: BasicValueEnum) {
+        let i64_ty = self.context.i64_type();
+        let counter = self.builder.build_alloca(i64_ty, "counter");
+        self.builder
+            .build_store(counter, i64_ty.const_int(0, false));
+        let cond_bb = self.append_block("fill-cond");
+        let loop_bb = self.append_block("fill-loop");
+        let cont_bb = self.append_block("fill-cont");
+        self.builder.build_unconditional_branch(&cond_bb);
+
+        self.position_at_block(cond_bb);
+        let index = self
+            .builder
+            .build_load(counter, "fillindex")
+            .into_int_value();
+        let more = self
+            .builder
+            .build_int_compare(IntPredicate::SLT, index, len, "fillmore");
+        self.builder
+            .build_conditional_branch(more, &loop_bb, &cont_bb);
+
+        self.position_at_block(loop_bb);
+        let elem = unsafe { self.builder.build_gep(ptr, &[index], "fillelem") };
+        self.builder.build_store(elem, value);
+        let next = self
+            .builder
+            .build_int_add(index, i64_ty.const_int(1, false), "fillnext");
+        self.builder.build_store(counter, next);
+        self.builder.build_unconditional_branch(&cond_bb);
+
+        self.position_at_block(cont_bb);
+    }
+
+    /// The value given to `inc_ref` or `dec_ref`. Its type is resolved,
+    /// so that interface values modify the refcount of their object.
+    fn refcounted_param(
+        func: &Function,
+        ty_args: Option<&Rc<TypeArguments>>,
+        ir: FunctionValue,
+    ) -> LLValue {
+        let ty = &func.parameters.first().unwrap().ty;
+        let ty = match ty_args {
+            Some(args) => ty.resolve(args),
+            None => ty.clone(),
+        };
+        LLValue::from(ir.get_first_param().unwrap(), &ty)
+    }
+
     fn iface_ref_method(
         &mut self,
         ir: FunctionValue,
@@ -249,3 +572,20 @@ impl IRGenerator {
         (func, impl_ptr, impl_ptr_i64, end_bb)
     }
 }
+
+/// Parses LLVM IR given with `@inline_ir` as its own module, returning
+/// LLVM's error message if it is invalid or does not define the function.
+pub(crate) fn parse_inline_ir(
+    context: &Context,
+    name: &str,
+    inline_ir: &str,
+) -> Result<LLModule, String> {
+    let buffer = MemoryBuffer::create_from_memory_range_copy(inline_ir.as_bytes(), name);
+    let module = context
+        .create_module_from_ir(buffer)
+        .map_err(|e| e.to_string())?;
+    match module.get_function(name) {
+        Some(func) if func.count_basic_blocks() > 0 => Ok(module),
+        _ => Err(format!("The IR does not define '{}'.", name)),
+    }
+}
@@ -0,0 +1,155 @@
+use gir_nodes::Function;
+use inkwell::{
+    types::BasicTypeEnum,
+    values::{BasicValueEnum, FunctionValue},
+    AddressSpace::Generic,
+    IntPredicate,
+};
+
+use super::{type_adapter::LLValue, IRGenerator};
+
+impl IRGenerator {
+    /// Generates the function of a lazy global, which returns its value.
+    /// The initializer is compiled into a separate function that is only
+    /// called on the first read; its result is stored in a global,
+    /// next to a state recording if it is uninitialized, initializing or done.
+    /// Reading a global while it is initializing would recurse forever,
+    /// like when its initializer reads itself, so it panics instead.
+    /// Not thread-safe: threads reading an uninitialized global at the
+    /// same time can each run the initializer.
+    pub(super) fn lazy_global(&mut self, func: &Function, getter: FunctionValue) {
+        let init_name = format!("{}.init", getter.get_name().to_str().unwrap());
+        let init = self
+            .module
+            .add_function(&init_name, getter.get_type(), None);
+        self.add_symbol(func, init_name);
+        self.function_body(func, init);
+
+        let value = getter.get_type().get_return_type().map(|ty| {
+            let global = self.module.add_global(ty, None, "lazy");
+            global.set_initializer(&zero(ty));
+            global.as_pointer_value()
+        });
+        let state_ty = self.context.i8_type();
+        let state = self.module.add_global(state_ty, None, "lazy.state");
+        state.set_initializer(&state_ty.const_int(UNINITIALIZED, false));
+        let state = state.as_pointer_value();
+
+        self.function = Some(getter);
+        let entry_bb = self.context.append_basic_block(&getter, "entry");
+        let check_bb = self.append_block("check");
+        let cycle_bb = self.append_block("cycle");
+        let init_bb = self.append_block("init");
+        let done_bb = self.append_block("done");
+
+        self.position_at_block(entry_bb);
+        let current = self.builder.build_load(state, "state").into_int_value();
+        let done = self.builder.build_int_compare(
+            IntPredicate::EQ,
+            current,
+            state_ty.const_int(DONE, false),
+            "done",
+        );
+        self.builder
+            .build_conditional_branch(done, &done_bb, &check_bb);
+
+        self.position_at_block(check_bb);
+        let initializing = self.builder.build_int_compare(
+            IntPredicate::EQ,
+            current,
+            state_ty.const_int(INITIALIZING, false),
+            "initializing",
+        );
+        self.builder
+            .build_conditional_branch(initializing, &cycle_bb, &init_bb);
+
+        self.position_at_block(cycle_bb);
+        self.build_panic(&format!(
+            "Lazy global '{}' was read during its own initialization.",
+            func.name
+        ));
+
+        self.position_at_block(init_bb);
+        self.builder
+            .build_store(state, state_ty.const_int(INITIALIZING, false));
+        let result = self
+            .builder
+            .build_call(init, &[], "init")
+            .try_as_basic_value();
+        if let (Some(value), Some(result)) = (value, result.left()) {
+            self.builder.build_store(value, result);
+        }
+        self.builder
+            .build_store(state, state_ty.const_int(DONE, false));
+        self.builder.build_unconditional_branch(&done_bb);
+
+        self.position_at_block(done_bb);
+        match value {
+            Some(value) => {
+                let result = self.builder.build_load(value, "lazy");
+                let result = LLValue::from(result, &func.ret_type);
+                self.increment_refcount(&result);
+                self.builder.build_return(Some(&*result));
+            }
+            None => {
+                self.builder.build_return(None);
+            }
+        }
+    }
+
+    /// Prints the given message like `std/prelude/panic` and exits,
+    /// ending the current block.
+    fn build_panic(&mut self, message: &str) {
+        let ptr_ty = self.context.i8_type().ptr_type(Generic);
+        let printf = self.module.get_function("printf").unwrap_or_else(|| {
+            let ty = self.context.i32_type().fn_type(&[ptr_ty.into()], true);
+            self.module.add_function("printf", ty, None)
+        });
+        let fflush = self.module.get_function("fflush").unwrap_or_else(|| {
+            let ty = self
+                .context
+                .i32_type()
+                .fn_type(&[self.context.i64_type().into()], false);
+            self.module.add_function("fflush", ty, None)
+        });
+        let quick_exit = self.module.get_function("quick_exit").unwrap_or_else(|| {
+            let ty = self
+                .context
+                .void_type()
+                .fn_type(&[self.context.i32_type().into()], false);
+            self.module.add_function("quick_exit", ty, None)
+        });
+
+        let text = format!("[gelix] Panicked at: {}\n[gelix] Exiting.\n", message);
+        let text = self
+            .builder
+            .build_global_string_ptr(&text, "panicmsg")
+            .as_pointer_value();
+        self.builder
+            .build_call(printf, &[text.into()], "panicprint");
+        let all_streams = self.context.i64_type().const_int(0, false);
+        self.builder
+            .build_call(fflush, &[all_streams.into()], "flush");
+        let status = self.context.i32_type().const_int(1, false);
+        self.builder
+            .build_call(quick_exit, &[status.into()], "exit");
+        self.builder.build_unreachable();
+    }
+}
+
+/// The states of a lazy global, see [IRGenerator::lazy_global].
+const UNINITIALIZED: u64 = 0;
+const INITIALIZING: u64 = 1;
+const DONE: u64 = 2;
+
+/// Returns the zero value of a type, used as the initial value of globals.
+fn zero(ty: BasicTypeEnum) -> BasicValueEnum {
+    match ty {
+        BasicTypeEnum::ArrayType(ty) => ty.const_zero().into(),
+        BasicTypeEnum::FloatType(ty) => ty.const_zero().into(),
+        BasicTypeEnum::IntType(ty) => ty.const_zero().into(),
+        BasicTypeEnum::PointerType(ty) => ty.const_null().into(),
+        BasicTypeEnum::StructType(ty) => ty.const_zero().into(),
+        BasicTypeEnum::VectorType(ty) => ty.const_zero().into(),
+    }
+}
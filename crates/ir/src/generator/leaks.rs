@@ -0,0 +1,115 @@
+use gir_nodes::{Instance, ADT};
+use inkwell::{values::FunctionValue, AddressSpace::Generic, IntPredicate};
+
+use super::IRGenerator;
+
+/// Name of the function printing the leak report.
+const LEAK_REPORT_FN: &str = "gelixrs_leak_report";
+
+impl IRGenerator {
+    /// Counts an allocation of the given class, should leak reports be enabled.
+    /// Every class instance has its own global counter of live allocations.
+    pub(crate) fn count_alloc(&mut self, adt: &Instance<ADT>) {
+        self.mod_leak_counter(adt, false)
+    }
+
+    /// Counts the deallocation of the given class, should leak reports be enabled.
+    pub(crate) fn count_free(&mut self, adt: &Instance<ADT>) {
+        self.mod_leak_counter(adt, true)
+    }
+
+    fn mod_leak_counter(&mut self, adt: &Instance<ADT>, decrement: bool) {
+        if !self.gir_data.flags.leak_report || !adt.ty.borrow().refcounted() {
+            return;
+        }
+
+        let name = adt.to_string();
+        let counter = match self.leak_counters.get(&name) {
+            Some(counter) => *counter,
+            None => {
+                let i32_ty = self.context.i32_type();
+                let global = self.module.add_global(i32_ty, None, "leakcount");
+                global.set_initializer(&i32_ty.const_int(0, false));
+                let counter = global.as_pointer_value();
+                self.leak_counters.insert(name, counter);
+                counter
+            }
+        };
+        self.write_new_refcount(counter, decrement);
+    }
+
+    /// Calls the leak report at the current position, should the current function
+    /// be `main` and leak reports be enabled. Used before `main` returns.
+    pub(crate) fn maybe_call_leak_report(&mut self) {
+        let is_main = self
+            .function
+            .map_or(false, |f| f.get_name().to_bytes() == b"main");
+        if self.gir_data.flags.leak_report && is_main {
+            let report = self.leak_report_fn();
+            self.builder.build_call(report, &[], "leakreport");
+        }
+    }
+
+    fn leak_report_fn(&self) -> FunctionValue {
+        self.module.get_function(LEAK_REPORT_FN).unwrap_or_else(|| {
+            let ty = self.context.void_type().fn_type(&[], false);
+            self.module.add_function(LEAK_REPORT_FN, ty, None)
+        })
+    }
+
+    /// Generates the body of the leak report, which prints all classes
+    /// that still have live instances. This needs to run once all other
+    /// code is generated, since all allocated classes need to be known.
+    pub(crate) fn fill_leak_report(&mut self) {
+        let report = match self.module.get_function(LEAK_REPORT_FN) {
+            Some(report) => report,
+            None => return,
+        };
+        self.function = Some(report);
+        let entry = self.context.append_basic_block(&report, "entry");
+        self.position_at_block(entry);
+
+        let printf = self.module.get_function("printf").unwrap_or_else(|| {
+            let format = self.context.i8_type().ptr_type(Generic).into();
+            let ty = self.context.i32_type().fn_type(&[format], true);
+            self.module.add_function("printf", ty, None)
+        });
+        let format = self
+            .builder
+            .build_global_string_ptr("leaked %i instance(s) of %s\n", "leakfmt")
+            .as_pointer_value();
+
+        let counters = std::mem::take(&mut self.leak_counters);
+        for (name, counter) in &counters {
+            let count = self
+                .builder
+                .build_load(*counter, "leakload")
+                .into_int_value();
+            let leaked = self.builder.build_int_compare(
+                IntPredicate::NE,
+                count,
+                self.context.i32_type().const_int(0, false),
+                "leakcond",
+            );
+
+            let print_bb = self.append_block("print");
+            let cont_bb = self.append_block("cont");
+            self.builder
+                .build_conditional_branch(leaked, &print_bb, &cont_bb);
+
+            self.position_at_block(print_bb);
+            let name = self
+                .builder
+                .build_global_string_ptr(name, "leakname")
+                .as_pointer_value();
+            self.builder.build_call(
+                printf,
+                &[format.into(), count.into(), name.into()],
+                "leakprint",
+            );
+            self.builder.build_unconditional_branch(&cont_bb);
+            self.position_at_block(cont_bb);
+        }
+        self.builder.build_return(None);
+    }
+}
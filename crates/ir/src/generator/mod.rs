@@ -4,33 +4,51 @@
  * This file is under the Apache 2.0 license. See LICENSE in the root of this repository for details.
  */
 
-use std::{collections::HashMap, mem, path::Path, rc::Rc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    mem,
+    ops::Range,
+    path::Path,
+    rc::Rc,
+};
 
-use common::{bench, MutRc};
+use common::{bench, log, Level, MutRc};
 use gir_nodes::{
-    declaration::{IRFunction, Variable},
+    declaration::{IRFunction, Variable, Visibility},
+    mangle::mangle,
     types::TypeArguments,
-    Function, Instance, Type,
+    Function, Instance, Module as GIRModule, Type,
 };
 use inkwell::{
     basic_block::BasicBlock,
     builder::Builder,
     context::Context,
-    module::Module,
+    module::{Linkage, Module},
     types::BasicTypeEnum,
-    values::{BasicValueEnum, FunctionValue},
+    values::{BasicValueEnum, FunctionValue, PointerValue},
+    GlobalVisibility,
 };
 
 use gir_generator::CompiledGIR;
 use gir_ir_adapter::IRAdapter;
 use inkwell::types::StructType;
+use smol_str::SmolStr;
 use std::option::Option::Some;
 
+pub use self::constants::ConstantStats;
+pub(crate) use self::intrinsics::parse_inline_ir;
 use self::type_adapter::{IRType, LLPtr, LLValue};
 
+mod alloc;
+mod array;
+mod attributes;
+mod constants;
 mod expr;
 mod gc;
 mod intrinsics;
+mod lazy;
+mod leaks;
+mod simd;
 mod type_adapter;
 mod types;
 mod values;
@@ -74,22 +92,60 @@ pub struct IRGenerator {
     /// adding all referenced functions here.
     /// The actual compilation of them then occurs by removing from this vector until it is empty.
     functions_left: Vec<(MutRc<Function>, Rc<TypeArguments>)>,
+    /// All functions of the LLVM module except for extern ones,
+    /// along with the gelix source they were compiled from.
+    symbols: Vec<FunctionSymbol>,
 
     /// Needed state about the current loop, if compiling one.
     loop_data: Option<LoopData>,
 
+    /// Global counters of live instances for every allocated class,
+    /// by type name. Only used when leak reports are enabled.
+    leak_counters: BTreeMap<String, PointerValue>,
+
+    /// The data of every distinct string literal, see `string_constant`.
+    strings: HashMap<SmolStr, PointerValue>,
+    /// Statistics about the constant data emitted so far.
+    constant_stats: ConstantStats,
+
     /// GIR compilation data.
     gir_data: CompiledGIR,
 }
 
+/// A function compiled into the LLVM module; extern functions are not included.
+/// See [IRGenerator::generate_with_symbols].
+#[derive(Debug, Clone)]
+pub struct FunctionSymbol {
+    /// The name of the function in the LLVM module.
+    pub symbol: String,
+    /// The module the function was declared in.
+    pub module: MutRc<GIRModule>,
+    /// The range of bytes of the function in the source of its module,
+    /// or `None` for functions generated by the compiler.
+    pub span: Option<Range<u32>>,
+}
+
 impl IRGenerator {
     /// Generates IR. Will process all GIR modules given.
     pub fn generate(self) -> Module {
+        self.generate_with_symbols().0
+    }
+
+    /// Generates IR like [IRGenerator::generate], also returning all functions
+    /// compiled into it along with where they were declared.
+    pub fn generate_with_symbols(self) -> (Module, Vec<FunctionSymbol>) {
+        let (module, symbols, _) = self.generate_with_stats();
+        (module, symbols)
+    }
+
+    /// Generates IR like [IRGenerator::generate_with_symbols], also returning
+    /// statistics about the constant data emitted, see [ConstantStats].
+    pub fn generate_with_stats(self) -> (Module, Vec<FunctionSymbol>, ConstantStats) {
         bench!("ir", self.generate_())
     }
 
     #[allow(clippy::needless_collect)] // Not needless! Changes execution order.
-    fn generate_(mut self) -> Module {
+    fn generate_(mut self) -> (Module, Vec<FunctionSymbol>, ConstantStats) {
         // Get required-to-compile fns from intrinsics
         let required_fns = mem::replace(&mut self.gir_data.intrinsics.required_compile_fns, vec![]);
         // Declare them and collect into new vec
@@ -121,20 +177,57 @@ impl IRGenerator {
             })
             .cloned();
         self.fill_intrinsic_functions(&intrinsics_module.unwrap());
+        self.fill_leak_report();
+        self.internalize();
 
         self.module
             .verify()
             .map_err(|e| {
                 self.module.print_to_file(Path::new("invalid_code.ll")).unwrap_or(());
                 if !cfg!(debug_assertions) {
-                    println!("The compiler generated invalid code, which can be found in 'invalid_code.ll'.");
-                    println!("This is a severe internal bug, and should be reported (please include the code when doing so).");
-                    println!("The error message reported by LLVM:\n");
+                    log!(Level::Error, "The compiler generated invalid code, which can be found in 'invalid_code.ll'.");
+                    log!(Level::Error, "This is a severe internal bug, and should be reported (please include the code when doing so).");
+                    log!(Level::Error, "The error message reported by LLVM:\n");
                 }
                 panic!("Invalid IR:\n{}", e.to_string().replace("\\n", "\n"))
             })
             .unwrap();
-        self.module
+        (self.module, self.symbols, self.constant_stats)
+    }
+
+    /// Gives all functions defined in the module internal linkage, except for `main`.
+    /// All gelix modules of an executable are compiled into this single LLVM module,
+    /// so nothing outside of it calls them. This allows LLVM to optimize the program
+    /// as a whole, like inlining small `std` functions and removing unused ones.
+    /// Object files and libraries are linked by another build process,
+    /// so they keep their symbols; see `symbol_visibility` for libraries.
+    fn internalize(&self) {
+        if !self.gir_data.flags.executable {
+            return;
+        }
+        let mut function = self.module.get_first_function();
+        while let Some(func) = function {
+            if func.count_basic_blocks() > 0 && func.get_name().to_bytes() != b"main" {
+                func.set_linkage(Linkage::Internal);
+            }
+            function = func.get_next_function();
+        }
+    }
+
+    /// Returns the symbol visibility of a function instance in a library,
+    /// or `None` outside of libraries, mapping gelix visibility to symbols:
+    /// public functions without type arguments are exported, all other
+    /// functions are hidden, so they can be linked against inside the library
+    /// but are not exported from it.
+    fn symbol_visibility(&self, func: &Function, args: &[Type]) -> Option<GlobalVisibility> {
+        if !self.gir_data.flags.library || func.name == "main" {
+            return None;
+        }
+        let std = func.module.borrow().path.is(&["std"]);
+        Some(match func.visibility {
+            Visibility::Public if args.is_empty() && !std => GlobalVisibility::Default,
+            _ => GlobalVisibility::Hidden,
+        })
     }
 
     fn get_or_create(&mut self, func: &Instance<Function>) -> FunctionValue {
@@ -157,7 +250,7 @@ impl IRGenerator {
         args: Rc<TypeArguments>,
     ) -> FunctionValue {
         self.push_ty_args(Rc::clone(&args));
-        let func_ir = self.declare_function_inst(&func.ty.borrow(), &format!("[{}]", ir.count()));
+        let func_ir = self.declare_function_inst(&func.ty.borrow(), &args);
         ir.add_inst(&args, func_ir);
         self.functions_left.push((Rc::clone(&func.ty), args));
         self.pop_ty_args();
@@ -170,7 +263,7 @@ impl IRGenerator {
         let mut ir = func.ir.borrow_mut();
         match &mut *ir {
             IRAdapter::NoTypeArgs(opt) => {
-                let inst = self.declare_function_inst(&func, "");
+                let inst = self.declare_function_inst(&func, &[]);
                 *opt = Some(inst);
                 Some(inst)
             }
@@ -180,26 +273,48 @@ impl IRGenerator {
 
     /// Declares a single function instance.
     /// Type args should be pushed onto `self.type_args`.
-    fn declare_function_inst(&mut self, func: &Function, suffix: &str) -> FunctionValue {
+    fn declare_function_inst(&mut self, func: &Function, args: &[Type]) -> FunctionValue {
         let params = func.parameters.iter().map(|param| &param.ty);
         let fn_ty = self.fn_type_from_raw(params, &func.ret_type, func.variadic);
 
-        // If extern fn OR main: don't prepend module name
-        let name = if func.ast.as_ref().map(|a| a.body()).flatten().is_none() || func.name == "main"
-        {
-            format!("{}", func.name)
+        // Extern functions and main keep their name for linking,
+        // everything else is mangled to make it unique
+        let is_extern = func.ast.as_ref().map_or(false, |ast| ast.body().is_none());
+        let name = if is_extern || func.name == "main" {
+            func.name.to_string()
         } else {
-            format!("{}::{}{}", func.module.borrow().path, func.name, suffix)
+            mangle(func, args)
         };
 
-        self.module.add_function(&name, fn_ty, None)
+        let func_val = self.module.add_function(&name, fn_ty, None);
+        self.add_attributes(func, func_val);
+        if !is_extern {
+            self.add_symbol(func, name);
+            if let Some(visibility) = self.symbol_visibility(func, args) {
+                func_val.as_global_value().set_visibility(visibility);
+            }
+        }
+        func_val
+    }
+
+    /// Records the symbol of a function compiled from the given GIR function.
+    fn add_symbol(&mut self, func: &Function, symbol: String) {
+        self.symbols.push(FunctionSymbol {
+            symbol,
+            module: Rc::clone(&func.module),
+            span: func.ast.as_ref().map(|ast| ast.cst.text_range()),
+        });
     }
 
-    /// Generates a function, should it have a body.
+    /// Generates a function, should it have a body or inline IR.
     /// Does not handle type arguments.
     fn function(&mut self, func_var: &MutRc<Function>, ir: FunctionValue) {
         let func = func_var.borrow();
-        if !func.exprs.is_empty() {
+        if let Some(inline_ir) = &func.inline_ir {
+            self.link_inline_ir(&func, inline_ir);
+        } else if func.lazy {
+            self.lazy_global(&func, ir);
+        } else if !func.exprs.is_empty() {
             self.function_body(&func, ir);
         }
     }
@@ -225,6 +340,7 @@ impl IRGenerator {
         // Build a return if the end of the function is an implicit return
         if self.builder.get_insert_block().is_some() {
             self.decrement_all_locals();
+            self.maybe_call_leak_report();
             self.builder.build_return(None);
         }
 
@@ -329,8 +445,12 @@ impl IRGenerator {
             none_const: LLValue::cpy(none_const.into(), &IRType::None),
             type_args: Vec::with_capacity(3),
             functions_left: Vec::with_capacity(20),
+            symbols: Vec::new(),
 
             loop_data: None,
+            leak_counters: BTreeMap::new(),
+            strings: HashMap::new(),
+            constant_stats: ConstantStats::default(),
             gir_data,
         }
     }
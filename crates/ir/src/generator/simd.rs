@@ -0,0 +1,145 @@
+use gir_nodes::{Expr, Type};
+use inkwell::{
+    types::{BasicType, BasicTypeEnum, VectorType},
+    values::{BasicValueEnum, VectorValue},
+    AddressSpace::Generic,
+};
+use syntax::kind::SyntaxKind;
+
+use super::{IRGenerator, LLValue};
+
+impl IRGenerator {
+    /// Returns the LLVM vector type of the given vector's element type and length.
+    pub(crate) fn ir_vector_type(elem: BasicTypeEnum, len: u64) -> VectorType {
+        match elem {
+            BasicTypeEnum::IntType(int) => int.vec_type(len as u32),
+            BasicTypeEnum::FloatType(float) => float.vec_type(len as u32),
+            _ => panic!("Invalid vector element type"),
+        }
+    }
+
+    /// Elementwise arithmetic on two vectors of the same type.
+    pub(crate) fn vector_binary(
+        &self,
+        left: VectorValue,
+        operator: SyntaxKind,
+        right: VectorValue,
+        unsigned: bool,
+    ) -> BasicValueEnum {
+        let b = &self.builder;
+        let result = if left.get_type().get_element_type().is_float_type() {
+            match operator {
+                SyntaxKind::Plus => b.build_float_add(left, right, "vadd"),
+                SyntaxKind::Minus => b.build_float_sub(left, right, "vsub"),
+                SyntaxKind::Star => b.build_float_mul(left, right, "vmul"),
+                SyntaxKind::Slash => b.build_float_div(left, right, "vdiv"),
+                SyntaxKind::Percent => b.build_float_rem(left, right, "vrem"),
+                _ => panic!("Invalid vector operator"),
+            }
+        } else {
+            match operator {
+                SyntaxKind::Plus => b.build_int_add(left, right, "vadd"),
+                SyntaxKind::Minus => b.build_int_sub(left, right, "vsub"),
+                SyntaxKind::Star => b.build_int_mul(left, right, "vmul"),
+                SyntaxKind::Slash if unsigned => b.build_int_unsigned_div(left, right, "vdiv"),
+                SyntaxKind::Slash => b.build_int_signed_div(left, right, "vdiv"),
+                SyntaxKind::Percent if unsigned => b.build_int_unsigned_rem(left, right, "vrem"),
+                SyntaxKind::Percent => b.build_int_signed_rem(left, right, "vrem"),
+                _ => panic!("Invalid vector operator"),
+            }
+        };
+        result.into()
+    }
+
+    /// Casts between a fixed-size array and a vector of the same layout
+    /// by going through memory, since LLVM cannot bitcast arrays.
+    /// The memory is allocated as the vector, which has the stricter alignment.
+    pub(crate) fn vector_cast(&mut self, object: &Expr, to: &Type) -> LLValue {
+        let from = object.get_type();
+        let (vector_gir, array_gir) = match &from {
+            Type::Vector(_) => (&from, to),
+            _ => (to, &from),
+        };
+        let vector_ty = self.ir_ty_generic(vector_gir);
+        let array_ty = self.ir_ty_generic(array_gir);
+
+        let obj = self.expression(object);
+        let vector_ptr = self.create_alloc(vector_gir.clone(), vector_ty, false);
+        let array_ptr =
+            self.builder
+                .build_pointer_cast(vector_ptr, array_ty.ptr_type(Generic), "veccast");
+        let (store_ptr, load_ptr) = match &from {
+            Type::Vector(_) => (vector_ptr, array_ptr),
+            _ => (array_ptr, vector_ptr),
+        };
+        self.builder.build_store(store_ptr, *obj);
+        LLValue::from(self.builder.build_load(load_ptr, "vecload"), to)
+    }
+
+    /// Creates a vector with the value in all elements by inserting it
+    /// into the first element and shuffling that into all others.
+    pub(crate) fn vector_splat(&mut self, value: &Expr, ty: &Type) -> LLValue {
+        let vector_ty = self.ir_ty_generic(ty).into_vector_type();
+        let value = self.expression(value);
+        let zero = self.context.i32_type().const_int(0, false);
+        let undef = vector_ty.get_undef();
+        let first = self
+            .builder
+            .build_insert_element(undef, *value, zero, "splatins");
+        let mask = vec![zero; vector_ty.get_size() as usize];
+        let splat = self.builder.build_shuffle_vector(
+            first,
+            undef,
+            VectorType::const_vector(&mask),
+            "splat",
+        );
+        LLValue::from(splat.into(), ty)
+    }
+
+    pub(crate) fn vector_extract(&mut self, vector: &Expr, index: &Expr) -> LLValue {
+        let ty = vector.get_type().as_vector().ty.clone();
+        let vector = self.expression(vector).into_vector_value();
+        let index = self.expression(index).into_int_value();
+        let value = self.builder.build_extract_element(vector, index, "vecextr");
+        LLValue::from(value, &ty)
+    }
+
+    pub(crate) fn vector_insert(&mut self, vector: &Expr, index: &Expr, value: &Expr) -> LLValue {
+        let ty = vector.get_type();
+        let vector = self.expression(vector).into_vector_value();
+        let index = self.expression(index).into_int_value();
+        let value = self.expression(value);
+        let result = self
+            .builder
+            .build_insert_element(vector, *value, index, "vecins");
+        LLValue::from(result.into(), &ty)
+    }
+
+    /// Shuffles one or two vectors. When shuffling a single vector,
+    /// the second LLVM operand is left undefined, as no index refers to it.
+    pub(crate) fn vector_shuffle(
+        &mut self,
+        left: &Expr,
+        right: Option<&Expr>,
+        mask: &[u32],
+        ty: &Type,
+    ) -> LLValue {
+        let left = self.expression(left).into_vector_value();
+        let right = match right {
+            Some(right) => self.expression(right).into_vector_value(),
+            None => left.get_type().get_undef(),
+        };
+        let i32_ty = self.context.i32_type();
+        let mask = mask
+            .iter()
+            .map(|index| i32_ty.const_int(*index as u64, false))
+            .collect::<Vec<_>>();
+        let result = self.builder.build_shuffle_vector(
+            left,
+            right,
+            VectorType::const_vector(&mask),
+            "shuffle",
+        );
+        LLValue::from(result.into(), ty)
+    }
+}
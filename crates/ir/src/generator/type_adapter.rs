@@ -18,8 +18,10 @@ pub(crate) enum IRType {
     RefAdt(Instance<ADT>),
     NullValueAdt(Instance<ADT>),
     NullRefAdt(Instance<ADT>),
+    WeakAdt(Instance<ADT>),
 
     Closure(Rc<ClosureType>),
+    Array,
     Other,
 }
 
@@ -27,7 +29,7 @@ pub(crate) fn is_ptr(ty: &Type) -> bool {
     ty.is_ref_adt()
         || match ty {
             Type::Function(_) | Type::Closure(_) | Type::RawPtr(_) => true,
-            Type::Nullable(inner) => is_ptr(inner),
+            Type::Nullable(inner) | Type::Weak(inner) => is_ptr(inner),
             _ => false,
         }
 }
@@ -43,8 +45,9 @@ impl<T: Copy> V<T> {
         Self {
             v: llvm,
             ty: match ty {
-                Type::Any | Type::None => IRType::None,
+                Type::Any | Type::Never | Type::None => IRType::None,
                 _ if ty.is_primitive() => IRType::Primitive,
+                Type::Vector(_) => IRType::Primitive,
                 Type::Nullable(inner) if inner.is_primitive() => IRType::NullPrimitive,
 
                 Type::RawPtr(inner) if is_ptr(inner) => IRType::RefRawPtr,
@@ -56,8 +59,10 @@ impl<T: Copy> V<T> {
                     IRType::NullRefAdt(r.clone())
                 }
                 Type::Nullable(box Type::Adt(r)) => IRType::NullValueAdt(r.clone()),
+                Type::Weak(box Type::Adt(r)) => IRType::WeakAdt(r.clone()),
 
                 Type::Closure(c) => IRType::Closure(c.clone()),
+                Type::Array(_) => IRType::Array,
                 _ => IRType::Other,
             },
         }
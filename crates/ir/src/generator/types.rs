@@ -44,7 +44,9 @@ impl IRGenerator {
         let raw = self.ir_ty_raw(gir);
         (
             match self.maybe_unwrap_var(gir) {
-                Type::Adt(inst) | Type::Nullable(box Type::Adt(inst))
+                Type::Adt(inst)
+                | Type::Nullable(box Type::Adt(inst))
+                | Type::Weak(box Type::Adt(inst))
                     if inst.ty.borrow().is_ptr() =>
                 {
                     raw.0.ptr_type(Generic).into()
@@ -63,7 +65,7 @@ impl IRGenerator {
 
     pub(crate) fn ir_ty_raw(&mut self, gir: &Type) -> (BasicTypeEnum, Option<PointerValue>) {
         let (ty, ptr) = match gir {
-            Type::Any | Type::None | Type::Null => (self.none_const.get_type(), None),
+            Type::Any | Type::Never | Type::None | Type::Null => (self.none_const.get_type(), None),
             Type::Bool => (self.context.bool_type().into(), None),
             Type::I8 | Type::U8 => (self.context.i8_type().into(), None),
             Type::I16 | Type::U16 => (self.context.i16_type().into(), None),
@@ -100,6 +102,18 @@ impl IRGenerator {
                 (adt.adt.into(), Some(adt.typeinfo))
             }
 
+            Type::Weak(inner) => self.ir_ty_raw(inner),
+
+            Type::Array(arr) => {
+                let inner = self.ir_ty_generic(&arr.ty);
+                (inner.array_type(arr.len as u32).into(), None)
+            }
+
+            Type::Vector(vec) => {
+                let inner = self.ir_ty_generic(&vec.ty);
+                (Self::ir_vector_type(inner, vec.len).into(), None)
+            }
+
             Type::Nullable(inner) => {
                 let inner = self.ir_ty_raw(inner).0;
                 (self.value_nullable_type(inner).into(), None)
@@ -120,6 +134,8 @@ impl IRGenerator {
             }
 
             Type::Type(_) => panic!("invalid type"),
+            // Only produced alongside an error, which stops compilation before IR
+            Type::Error => unreachable!(),
         };
 
         (ty, ptr)
@@ -181,7 +197,7 @@ impl IRGenerator {
                 let info = IRAdtInfo {
                     adt: self.build_adt(&inst, false, ""),
                     nullable: self.build_adt(&inst, true, "nullable-"),
-                    typeinfo: self.build_type_info(),
+                    typeinfo: self.build_type_info(&inst.ty.borrow().ty),
                 };
                 inst.ty.borrow_mut().ir.add_inst(inst.args(), info);
                 info
@@ -242,9 +258,11 @@ impl IRGenerator {
         refcount: bool,
         type_info: bool,
     ) -> StructType {
-        let mut body = Vec::with_capacity(fields.size_hint().0 + 2);
+        let mut body = Vec::with_capacity(fields.size_hint().0 + 3);
         if refcount {
-            body.push(self.context.i32_type().into())
+            // Strong and weak reference count
+            body.push(self.context.i32_type().into());
+            body.push(self.context.i32_type().into());
         }
         if type_info {
             body.push(self.type_info_type.ptr_type(Generic).into())
@@ -349,21 +367,37 @@ impl IRGenerator {
                     .parameters
                     .iter()
                     .skip(1)
-                    .map(|param| self.ir_ty_generic(&param.ty)),
+                    .map(|param| self.iface_method_ir_ty(&param.ty)),
             )
             .collect();
 
         if method.borrow().ret_type == Type::None {
             self.context.void_type().fn_type(params.as_slice(), false)
         } else {
-            let ret_type = self.ir_ty_generic(&method.borrow().ret_type);
+            let ret_type = self.iface_method_ir_ty(&method.borrow().ret_type);
             ret_type.fn_type(params.as_slice(), false)
         }
         .ptr_type(Generic)
         .into()
     }
 
-    fn build_type_info(&self) -> PointerValue {
+    /// Methods referring to `Self` cannot be called on interface values,
+    /// so their vtable entry only needs some type in its place.
+    fn iface_method_ir_ty(&mut self, ty: &Type) -> BasicTypeEnum {
+        if ty.contains_self() {
+            self.void_ptr().into()
+        } else {
+            self.ir_ty_generic(ty)
+        }
+    }
+
+    /// Builds the type info of an ADT instance, which holds
+    /// the discriminant of enum cases and 0 for all other types.
+    fn build_type_info(&self, ty: &ADTType) -> PointerValue {
+        let discriminant = match ty {
+            ADTType::EnumCase { discriminant, .. } => *discriminant,
+            _ => 0,
+        };
         let global = self
             .module
             .add_global(self.type_info_type, None, "typeinfo");
@@ -371,17 +405,22 @@ impl IRGenerator {
             &self.type_info_type.const_named_struct(&[self
                 .context
                 .i64_type()
-                .const_int(0, false)
+                .const_int(discriminant, false)
                 .into()]),
         );
         global.as_pointer_value()
     }
 
-    pub(crate) fn refcount_before_tyinfo(ty: &IRType) -> bool {
-        if let IRType::RefAdt(adt) | IRType::NullRefAdt(adt) = ty {
-            adt.ty.borrow().refcounted()
-        } else {
-            false
+    /// Returns the amount of reference count fields before the type info
+    /// field; either none or both the strong and weak count.
+    pub(crate) fn refcount_fields(ty: &IRType) -> u32 {
+        match ty {
+            IRType::RefAdt(adt) | IRType::NullRefAdt(adt) | IRType::WeakAdt(adt)
+                if adt.ty.borrow().refcounted() =>
+            {
+                2
+            }
+            _ => 0,
         }
     }
 
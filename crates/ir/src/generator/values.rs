@@ -84,6 +84,7 @@ impl IRGenerator {
 
                 (AnyTypeEnum::StructType(_), IRType::RefAdt(i))
                 | (AnyTypeEnum::StructType(_), IRType::NullRefAdt(i))
+                | (AnyTypeEnum::StructType(_), IRType::WeakAdt(i))
                     if i.ty.borrow().is_ptr() =>
                 {
                     (**ptr).into()
@@ -117,7 +118,7 @@ impl IRGenerator {
     }
 
     pub(crate) fn get_type_info_field(&self, ptr: &LLPtr) -> PointerValue {
-        self.struct_gep_raw(**ptr, Self::refcount_before_tyinfo(&ptr.ty) as u32)
+        self.struct_gep_raw(**ptr, Self::refcount_fields(&ptr.ty))
     }
 
     pub(crate) fn get_struct_offset(&self, ptr: &LLPtr) -> u32 {
@@ -125,8 +126,8 @@ impl IRGenerator {
         let struct_type = elem_ty.as_struct_type();
         let mut i = 0;
 
-        // Account for the reference count field, should the struct be GCd
-        i += Self::refcount_before_tyinfo(&ptr.ty) as u32;
+        // Account for the reference count fields, should the struct be GCd
+        i += Self::refcount_fields(&ptr.ty);
         // Account for the type info field, should it be present
         i += (struct_type.get_field_type_at_index(i)
             == Some(self.type_info_type.ptr_type(Generic).into())) as u32;
@@ -145,39 +146,8 @@ impl IRGenerator {
         let builder = self.context.create_builder();
 
         let (builder, ptr) = if heap {
-            let malloc = self
-                .module
-                .get_function("malloc")
-                .unwrap()
-                .as_global_value()
-                .as_pointer_value();
-            let malloc_ty = ty
-                .ptr_type(Generic)
-                .fn_type(&[self.context.i32_type().into()], false);
-            let malloc = self
-                .builder
-                .build_bitcast(malloc, malloc_ty.ptr_type(Generic), "malloccast")
-                .into_pointer_value();
-
-            let i = self.context.i32_type();
-            let ty_size = unsafe {
-                self.builder.build_gep(
-                    ty.ptr_type(Generic).const_null(),
-                    &[i.const_int(1, false)],
-                    "size",
-                )
-            };
-            let ty_size = self.builder.build_ptr_to_int(ty_size, i, "sizeint").into();
-
-            (
-                &self.builder,
-                self.builder
-                    .build_call(malloc, &[ty_size], "malloc")
-                    .try_as_basic_value()
-                    .left()
-                    .unwrap()
-                    .into_pointer_value(),
-            )
+            let ptr = self.build_heap_alloc(ty);
+            (&self.builder, ptr)
         } else {
             let entry = self
                 .builder
@@ -203,8 +173,15 @@ impl IRGenerator {
                 == Some(self.context.i32_type().into())
         {
             // Initialize the refcount to 0
+            let zero = self.context.i32_type().const_int(0, false);
             let rc = unsafe { builder.build_struct_gep(ptr, 0, "rcinit") };
-            builder.build_store(rc, self.context.i32_type().const_int(0, false));
+            builder.build_store(rc, zero);
+
+            // ADTs also have a weak refcount, which needs to start at 0 as well
+            if Self::refcount_fields(&LLPtr::from(ptr, &gir).ty) != 0 {
+                let weak = unsafe { builder.build_struct_gep(ptr, 1, "weakinit") };
+                builder.build_store(weak, zero);
+            }
         }
         ptr
     }
@@ -269,6 +246,8 @@ impl IRGenerator {
                 self.decrement_refcount(&ptr.val())
             }
 
+            IRType::WeakAdt(_) => self.decrement_refcount(&ptr.val()),
+
             IRType::ValueAdt(adt) => {
                 let method = adt.try_get_method("free-instance")?;
                 let ir = self
@@ -5,9 +5,10 @@ mod generator;
 pub mod jit;
 
 use inkwell::module::Module;
-use std::{env, error::Error, ffi::OsStr, fs, process};
+use smol_str::SmolStr;
+use std::{env, error::Error, ffi::OsStr, fs, process, str::FromStr};
 
-pub use generator::IRGenerator;
+pub use generator::{ConstantStats, FunctionSymbol, IRGenerator};
 use inkwell::context;
 
 pub type CompiledIR = Module;
@@ -19,10 +20,91 @@ pub fn ir_context() -> Context {
     Context(context::Context::create())
 }
 
+/// Checks LLVM IR given to an extern function with `@inline_ir`, returning
+/// LLVM's error message if it is invalid or does not define the function.
+/// IR generation expects all inline IR to have been checked with this.
+pub fn check_inline_ir(context: &Context, name: &str, inline_ir: &str) -> Result<(), String> {
+    generator::parse_inline_ir(&context.0, name, inline_ir).map(|_| ())
+}
+
+/// The kind of file to produce from a compiled program.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OutputKind {
+    /// An executable, requiring a `main` function.
+    Executable,
+    /// An object file, to be linked by some other build process.
+    Object,
+    /// A static library (`.a`), exporting all public functions of the program.
+    StaticLib,
+    /// A shared library (`.so`), exporting all public functions of the program.
+    SharedLib,
+}
+
+impl OutputKind {
+    /// If this kind is a library, which does not require a `main` function
+    /// and exports public functions.
+    pub fn is_library(self) -> bool {
+        matches!(self, OutputKind::StaticLib | OutputKind::SharedLib)
+    }
+
+    /// A name for files of this kind, like `executable`.
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputKind::Executable => "executable",
+            OutputKind::Object => "object",
+            OutputKind::StaticLib => "static-library",
+            OutputKind::SharedLib => "shared-library",
+        }
+    }
+}
+
+impl Default for OutputKind {
+    fn default() -> Self {
+        OutputKind::Executable
+    }
+}
+
+impl FromStr for OutputKind {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bin" => Ok(OutputKind::Executable),
+            "obj" => Ok(OutputKind::Object),
+            "staticlib" => Ok(OutputKind::StaticLib),
+            "sharedlib" => Ok(OutputKind::SharedLib),
+            _ => Err("Unknown output type, expected 'bin', 'obj', 'staticlib' or 'sharedlib'."),
+        }
+    }
+}
+
+/// Produces an executable from the given module, see [produce_output].
 pub fn produce_binary(
     module: Module,
     location: &OsStr,
     optimize_level: usize,
+) -> Result<(), Box<dyn Error>> {
+    produce_output(
+        module,
+        location,
+        optimize_level,
+        OutputKind::Executable,
+        &[],
+    )
+}
+
+/// Produces a file of the given kind from the given module using clang,
+/// linking it with the given system libraries, see `CompiledGIR::link_libraries`.
+/// Static libraries are additionally archived with `ar`; their
+/// libraries need to be linked by whatever uses them instead.
+/// Libraries should be compiled with the `library` GIR flag set,
+/// so that their public functions are exported.
+pub fn produce_output(
+    module: Module,
+    location: &OsStr,
+    optimize_level: usize,
+    kind: OutputKind,
+    libraries: &[SmolStr],
 ) -> Result<(), Box<dyn Error>> {
     let mut tmp_dir = env::temp_dir();
     tmp_dir.push("gelixrs");
@@ -30,22 +112,41 @@ pub fn produce_binary(
         fs::create_dir(&tmp_dir)?;
     }
 
-    let mut module_file = tmp_dir;
-    module_file.push("out.bc");
+    let module_file = tmp_dir.join("out.bc");
     module.write_bitcode_to_path(&module_file);
 
     if optimize_level > 3 {
         return Err("Invalid optimize level.".to_string().into());
     }
-    let status = process::Command::new("clang")
-        .arg("-o")
-        .arg(&location)
-        .arg(module_file)
-        .arg(format!("-O{}", optimize_level))
-        .output()?
-        .status;
 
-    if status.success() {
+    let object_file = tmp_dir.join("out.o");
+    let mut clang = process::Command::new("clang");
+    clang.arg(format!("-O{}", optimize_level));
+    match kind {
+        OutputKind::Executable => clang.arg("-o").arg(&location),
+        OutputKind::Object => clang.arg("-c").arg("-o").arg(&location),
+        OutputKind::StaticLib => clang.args(&["-c", "-fPIC", "-o"]).arg(&object_file),
+        OutputKind::SharedLib => clang.args(&["-shared", "-fPIC", "-o"]).arg(&location),
+    };
+    clang.arg(module_file);
+    if matches!(kind, OutputKind::Executable | OutputKind::SharedLib) {
+        clang.args(libraries.iter().map(|lib| format!("-l{}", lib)));
+    }
+    let mut success = clang.output()?.status.success();
+
+    if success && kind == OutputKind::StaticLib {
+        // 'ar' adds to existing archives instead of replacing them
+        fs::remove_file(&location).ok();
+        success = process::Command::new("ar")
+            .arg("rcs")
+            .arg(&location)
+            .arg(&object_file)
+            .output()?
+            .status
+            .success();
+    }
+
+    if success {
         Ok(())
     } else {
         Err(
@@ -3,6 +3,9 @@ mod token;
 pub use logos::{Logos, Span};
 pub use token::Token;
 
+/// A lexer turning source code into tokens.
+/// It never fails; invalid input is returned as `Token::Error`
+/// and lexing continues after it.
 pub struct Lexer<'l> {
     logos: logos::Lexer<'l, Token>,
 }
@@ -24,7 +27,16 @@ impl<'l> Iterator for Lexer<'l> {
 
     fn next(&mut self) -> Option<Self::Item> {
         let kind = self.logos.next()?;
-        let text = self.logos.slice();
-        Some((kind, text))
+        let source = self.logos.source();
+        if kind == Token::Error {
+            // Make sure error tokens contain whole characters,
+            // as slicing in the middle of one would panic
+            let end = self.logos.span().end;
+            let boundary = (end..=source.len())
+                .find(|index| source.is_char_boundary(*index))
+                .unwrap();
+            self.logos.bump(boundary - end);
+        }
+        Some((kind, &source[self.logos.span()]))
     }
 }
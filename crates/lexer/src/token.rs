@@ -1,7 +1,11 @@
-use logos::Logos;
+use logos::{Lexer, Logos};
 
 /// A direct token that implements Logos. Most are keywords or special chars.
-/// The `Error` token is a special token signifying a syntax error.
+/// The `Error` token is a special token signifying a syntax error;
+/// it is produced for unknown characters as well as unterminated strings
+/// and comments, which span until the end of the input.
+/// Whitespace and comments are tokens as well, so that the tokens of
+/// an input always add up to the input itself.
 #[derive(Logos, PartialEq, Eq, Debug, Clone, Copy, Hash)]
 pub enum Token {
     #[token("(")]
@@ -36,10 +40,18 @@ pub enum Token {
     Slash,
     #[token("*")]
     Star,
+    #[token("%")]
+    Percent,
+    #[token("|")]
+    Pipe,
+    #[token("&")]
+    Ampersand,
     #[token("->")]
     Arrow,
     #[token("?")]
     QuestionMark,
+    #[token("@")]
+    At,
 
     #[token("!")]
     Bang,
@@ -79,6 +91,8 @@ pub enum Token {
     Class,
     #[token("construct")]
     Construct,
+    #[token("defer")]
+    Defer,
     #[token("else")]
     Else,
     #[token("enum")]
@@ -117,6 +131,8 @@ pub enum Token {
     Val,
     #[token("when")]
     When,
+    #[token("weak")]
+    Weak,
 
     #[token("mod")]
     Mod,
@@ -128,16 +144,24 @@ pub enum Token {
     Value,
     #[token("variadic")]
     Variadic,
+    #[token("lazy")]
+    Lazy,
+    #[token("sealed")]
+    Sealed,
 
-    #[regex(r"/\*([^*]|\*+[^*/])*\*?")] // https://github.com/maciejhirsz/logos/issues/180
+    #[regex("\"[^\"]*")]
     #[error]
     Error,
 
     #[regex(r"//[^\n]*")]
-    #[regex(r"/\*([^*]|\**[^*/])*\*+/")]
+    #[token("/*", block_comment)]
     Comment,
 
-    #[regex(r"[ \t\n\f]+")]
+    /// A documentation comment in front of a declaration, like `/// Returns a`.
+    #[regex(r"///[^\n]*")]
+    DocComment,
+
+    #[regex(r"[ \t\r\n\f]+")]
     Whitespace,
 
     /// This special token is unused by the lexer itself, but is
@@ -145,3 +169,29 @@ pub enum Token {
     /// Option<Token>, as this enum case makes handling EOF much easier.
     EndOfFile,
 }
+
+/// Lexes the rest of a block comment after its opening `/*`.
+/// Block comments can be nested; an unterminated comment
+/// becomes a single error token spanning the rest of the input.
+fn block_comment(lex: &mut Lexer<Token>) -> bool {
+    let rest = lex.remainder().as_bytes();
+    let mut depth = 1;
+    let mut index = 0;
+    while index < rest.len() {
+        if rest[index..].starts_with(b"/*") {
+            depth += 1;
+            index += 2;
+        } else if rest[index..].starts_with(b"*/") {
+            depth -= 1;
+            index += 2;
+            if depth == 0 {
+                lex.bump(index);
+                return true;
+            }
+        } else {
+            index += 1;
+        }
+    }
+    lex.bump(rest.len());
+    false
+}
@@ -6,12 +6,14 @@ use syntax::kind::SyntaxKind;
 static START_OF_FN_BODY: [SyntaxKind; 2] = [SyntaxKind::LeftBrace, SyntaxKind::Equal];
 
 // All tokens that can be modifiers at all.
-static MODIFIERS: [SyntaxKind; 5] = [
+static MODIFIERS: [SyntaxKind; 7] = [
     SyntaxKind::Mod,
     SyntaxKind::Priv,
     SyntaxKind::Extern,
     SyntaxKind::Variadic,
     SyntaxKind::Value,
+    SyntaxKind::Lazy,
+    SyntaxKind::Sealed,
 ];
 
 // All tokens that can be modifiers on any declaration.
@@ -28,19 +30,31 @@ static CONSTRUCTOR_MODIFIERS: [SyntaxKind; 0] = [];
 static FUNC_MODIFIERS: [SyntaxKind; 2] = [SyntaxKind::Extern, SyntaxKind::Variadic];
 // All tokens that can be modifiers on an import declaration.
 static IMPORT_MODIFIERS: [SyntaxKind; 0] = [];
+// All tokens that can be modifiers on a global variable.
+static GLOBAL_VAR_MODIFIERS: [SyntaxKind; 1] = [SyntaxKind::Lazy];
 
 impl<'p> Parser<'p> {
     pub fn declaration(&mut self) {
+        if self.check_ident("init") && self.check_next(SyntaxKind::LeftBrace) {
+            return self.init_declaration();
+        }
+
         let checkpoint = self.checkpoint();
+        let has_annotations = self.check(SyntaxKind::At);
+        self.consume_annotations();
         self.consume_modifiers();
 
         let ty = match self.peek() {
             SyntaxKind::Func => SyntaxKind::FunctionDecl,
             SyntaxKind::Import | SyntaxKind::Export => SyntaxKind::ImportDecl,
             SyntaxKind::Impl => SyntaxKind::ImplDecl,
+            SyntaxKind::Val | SyntaxKind::Var => SyntaxKind::GlobalDecl,
             _ => SyntaxKind::AdtDecl,
         };
         self.start_node_at(checkpoint, ty);
+        if has_annotations && !matches!(ty, SyntaxKind::AdtDecl | SyntaxKind::FunctionDecl) {
+            self.error_at_current(GErr::E009);
+        }
 
         match self.advance_checked() {
             SyntaxKind::Func => self.function(&FUNC_MODIFIERS),
@@ -50,22 +64,57 @@ impl<'p> Parser<'p> {
             SyntaxKind::Interface => self.generic_adt(IFACE_CONF),
             SyntaxKind::Impl => self.iface_impl(),
             SyntaxKind::Enum => self.generic_adt(ENUM_CONF),
+            SyntaxKind::Val => self.global_variable(false),
+            SyntaxKind::Var => self.global_variable(true),
             _ => self.error_at_current(GErr::E002),
         }
         self.end_node();
     }
 
+    /// Reads a function declared inside the body of another function.
+    /// Nested functions cannot have modifiers or annotations.
+    pub fn nested_function(&mut self) {
+        self.start_node(SyntaxKind::FunctionDecl);
+        self.advance(); // Consume 'func'
+        self.modifiers.clear();
+        self.function(&FUNC_MODIFIERS);
+        self.end_node();
+    }
+
+    fn init_declaration(&mut self) {
+        self.start_node(SyntaxKind::InitDecl);
+        self.advance(); // Consume 'init'
+        self.expression();
+        self.end_node();
+    }
+
+    /// Reads a global variable after 'val' or 'var'.
+    /// Only immutable lazy globals like 'lazy val a = 5' are supported.
+    fn global_variable(&mut self, mutable: bool) {
+        self.check_mods(&GLOBAL_VAR_MODIFIERS, "global variable");
+        if mutable || !self.modifiers.contains(&SyntaxKind::Lazy) {
+            self.error_at_current(GErr::E012);
+        }
+
+        self.consume(SyntaxKind::Identifier, "variable name", "val");
+        if self.matches(SyntaxKind::Colon) {
+            self.type_();
+        }
+        self.consume(SyntaxKind::Equal, "'='", "global variable name");
+        self.node_with(SyntaxKind::Initializer, Self::expression);
+    }
+
     fn function(&mut self, mods: &'static [SyntaxKind]) {
-        self.function_(mods, false)
+        self.function_(mods, false, false)
     }
 
-    fn function_(&mut self, mods: &'static [SyntaxKind], force_ext: bool) {
+    fn function_(&mut self, mods: &'static [SyntaxKind], force_ext: bool, is_method: bool) {
         let is_extern = force_ext
             || self
                 .modifiers
                 .iter()
                 .any(|kind| *kind == SyntaxKind::Extern);
-        self.func_signature(mods);
+        self.func_signature(mods, is_method);
 
         if !is_extern {
             self.start_node(SyntaxKind::FunctionBody);
@@ -81,10 +130,10 @@ impl<'p> Parser<'p> {
         };
     }
 
-    fn func_signature(&mut self, mods: &'static [SyntaxKind]) {
+    fn func_signature(&mut self, mods: &'static [SyntaxKind], is_method: bool) {
         self.start_node(SyntaxKind::FunctionSignature);
         self.check_mods(&mods, "function");
-        self.generic_ident("'func'");
+        self.generic_ident("'func'", is_method);
         self.consume(SyntaxKind::LeftParen, "'('", "function name");
         self.func_parameters();
         if self.matches(SyntaxKind::Arrow) {
@@ -101,7 +150,7 @@ impl<'p> Parser<'p> {
                 self.consume(SyntaxKind::Colon, "':'", "parameter name");
                 self.type_();
                 self.end_node();
-                if !self.matches(SyntaxKind::Comma) {
+                if !self.matches_list_comma(SyntaxKind::RightParen) {
                     break;
                 }
             }
@@ -111,7 +160,7 @@ impl<'p> Parser<'p> {
 
     fn generic_adt(&mut self, conf: ADTConfig) {
         self.check_mods(conf.modifiers, conf.name);
-        self.generic_ident("ADT identifier");
+        self.generic_ident("ADT identifier", false);
 
         self.consume(SyntaxKind::LeftBrace, "'{'", "before body");
 
@@ -119,8 +168,12 @@ impl<'p> Parser<'p> {
             match self.peek_past_modifiers() {
                 SyntaxKind::Var | SyntaxKind::Val if conf.has_members => self.adt_member(),
                 SyntaxKind::Construct if conf.has_constructors => self.constructor(),
-                SyntaxKind::Func => self.method(conf.force_extern),
-                SyntaxKind::Identifier if conf.has_cases => self.enum_case(),
+                SyntaxKind::Func | SyntaxKind::At => self.method(conf.force_extern),
+                SyntaxKind::Identifier if conf.has_cases => {
+                    self.enum_case();
+                    // Cases can optionally be separated by commas, like 'A, B, C,'
+                    self.matches(SyntaxKind::Comma);
+                }
                 _ => self.error_at_current(GErr::E004),
             }
         }
@@ -129,21 +182,28 @@ impl<'p> Parser<'p> {
     }
 
     fn method(&mut self, force_extern: bool) {
-        self.start_node(SyntaxKind::Method);
+        self.start_documented_node(SyntaxKind::Method);
+        self.consume_annotations();
         self.consume_modifiers();
+        if !self.check(SyntaxKind::Func) {
+            // Annotations on something other than a method
+            self.error_at_current(GErr::E009);
+            self.end_node();
+            return;
+        }
 
         self.advance(); // Consume 'func'
-        self.function_(&METHOD_MODIFIERS, force_extern);
+        self.function_(&METHOD_MODIFIERS, force_extern, true);
         self.end_node();
     }
 
     fn adt_member(&mut self) {
-        self.start_node(SyntaxKind::AdtMember);
+        self.start_documented_node(SyntaxKind::AdtMember);
         self.consume_modifiers();
         self.check_mods(&MEMBER_MODIFIERS, "class member");
 
         self.advance(); // Consume 'var' or 'val'
-        self.consume(SyntaxKind::Identifier, "variable name", "var/val");
+        self.consume_member_name("variable name", "var/val");
 
         match self.advance_checked() {
             SyntaxKind::Equal => self.node_with(SyntaxKind::Initializer, Self::expression),
@@ -162,7 +222,7 @@ impl<'p> Parser<'p> {
     }
 
     fn constructor(&mut self) {
-        self.start_node(SyntaxKind::Constructor);
+        self.start_documented_node(SyntaxKind::Constructor);
         self.consume_modifiers();
         self.check_mods(&CONSTRUCTOR_MODIFIERS, "constructor");
 
@@ -173,12 +233,12 @@ impl<'p> Parser<'p> {
         if !self.check(SyntaxKind::RightParen) {
             loop {
                 self.start_node(SyntaxKind::Parameter);
-                self.consume(SyntaxKind::Identifier, "parameter name", "'construct'");
+                self.consume_member_name("parameter name", "'construct'");
                 if self.matches(SyntaxKind::Colon) {
                     self.type_()
                 }
                 self.end_node();
-                if !self.matches(SyntaxKind::Comma) {
+                if !self.matches_list_comma(SyntaxKind::RightParen) {
                     break;
                 }
             }
@@ -204,12 +264,12 @@ impl<'p> Parser<'p> {
     }
 
     fn enum_case(&mut self) {
-        self.start_node(SyntaxKind::EnumCase);
+        self.start_documented_node(SyntaxKind::EnumCase);
 
         if self.peek_next() == SyntaxKind::LeftBrace {
             self.generic_adt(CASE_CONF);
         } else {
-            self.generic_ident("<internal error>");
+            self.generic_ident("<internal error>", false);
 
             if self.matches(SyntaxKind::LeftParen) {
                 while !self.check(SyntaxKind::RightParen) && !self.is_at_end() {
@@ -222,7 +282,7 @@ impl<'p> Parser<'p> {
                         "'var' or 'val'",
                         "left parenthesis",
                     );
-                    self.consume(SyntaxKind::Identifier, "member name", "var/val");
+                    self.consume_member_name("member name", "var/val");
                     self.consume(SyntaxKind::Colon, "':'", "member name");
                     self.type_();
                     self.end_node();
@@ -233,6 +293,13 @@ impl<'p> Parser<'p> {
                 }
                 self.consume(SyntaxKind::RightParen, "')'", "members");
             }
+
+            // Explicit discriminant, like 'Red = 1'
+            if self.matches(SyntaxKind::Equal) {
+                self.node_with(SyntaxKind::Literal, |this| {
+                    this.consume(SyntaxKind::Int, "integer", "'='")
+                });
+            }
         }
 
         self.end_node();
@@ -241,14 +308,41 @@ impl<'p> Parser<'p> {
     fn import_declaration(&mut self) {
         self.check_mods(&IMPORT_MODIFIERS, "import/export");
         self.consume(SyntaxKind::Identifier, "path", "import/export");
+        let mut is_wildcard = false;
         while self.matches(SyntaxKind::Slash) {
+            if self.check(SyntaxKind::LeftParen) {
+                self.import_list();
+                return;
+            }
+            is_wildcard = self.check(SyntaxKind::Plus);
             self.consume_either(
                 SyntaxKind::Identifier,
                 SyntaxKind::Plus,
-                "'+' or path",
+                "'+', '(' or path",
                 "'/'",
             );
         }
+
+        if is_wildcard && self.check_ident("hiding") {
+            self.node_with(SyntaxKind::ImportHiding, |this| {
+                this.advance(); // Consume 'hiding'
+                this.import_list();
+            });
+        }
+    }
+
+    // Reads a list of names to import or hide, like '(a, b)'.
+    fn import_list(&mut self) {
+        self.start_node(SyntaxKind::ImportList);
+        self.consume(SyntaxKind::LeftParen, "'('", "import path");
+        loop {
+            self.consume(SyntaxKind::Identifier, "name", "'('");
+            if !self.matches_list_comma(SyntaxKind::RightParen) {
+                break;
+            }
+        }
+        self.consume(SyntaxKind::RightParen, "')'", "names");
+        self.end_node();
     }
 
     fn iface_impl(&mut self) {
@@ -259,7 +353,7 @@ impl<'p> Parser<'p> {
 
         while !self.check(SyntaxKind::RightBrace) && !self.is_at_end() {
             match self.peek() {
-                SyntaxKind::Func => self.method(false),
+                SyntaxKind::Func | SyntaxKind::At => self.method(false),
                 _ => self.error_at_current(GErr::E004),
             }
         }
@@ -267,9 +361,14 @@ impl<'p> Parser<'p> {
     }
 
     // Reads an identifier followed by optional generic type parameters.
-    fn generic_ident(&mut self, after: &'static str) {
+    // Names of members can also be keywords.
+    fn generic_ident(&mut self, after: &'static str, member: bool) {
         self.start_node(SyntaxKind::Ident);
-        self.consume(SyntaxKind::Identifier, "a name", after);
+        if member {
+            self.consume_member_name("a name", after);
+        } else {
+            self.consume(SyntaxKind::Identifier, "a name", after);
+        }
         if self.matches(SyntaxKind::LeftBracket) {
             while self.check(SyntaxKind::Identifier) {
                 self.start_node(SyntaxKind::TypeParameter);
@@ -287,6 +386,33 @@ impl<'p> Parser<'p> {
         self.end_node();
     }
 
+    // Reads annotations like '@derive(Hash, Equal)' or '@inline_ir("...")'.
+    pub fn consume_annotations(&mut self) {
+        while self.check(SyntaxKind::At) {
+            self.start_node(SyntaxKind::Annotation);
+            self.advance(); // Consume '@'
+            self.consume(SyntaxKind::Identifier, "annotation name", "'@'");
+            if self.matches(SyntaxKind::LeftParen) {
+                if !self.check(SyntaxKind::RightParen) {
+                    loop {
+                        if self.check(SyntaxKind::String) {
+                            self.node_with(SyntaxKind::Literal, |this| {
+                                this.advance();
+                            });
+                        } else {
+                            self.type_();
+                        }
+                        if !self.matches_list_comma(SyntaxKind::RightParen) {
+                            break;
+                        }
+                    }
+                }
+                self.consume(SyntaxKind::RightParen, "')'", "annotation arguments");
+            }
+            self.end_node();
+        }
+    }
+
     fn consume_modifiers(&mut self) {
         self.modifiers.clear();
         while MODIFIERS.contains(&self.peek()) {
@@ -327,6 +453,10 @@ impl<'p> Parser<'p> {
 
     /// Reads a type name.
     pub fn type_(&mut self) {
+        self.nested(Self::type_inner)
+    }
+
+    fn type_inner(&mut self) {
         let check = self.checkpoint();
         self.start_node(SyntaxKind::Type);
         let token = self.advance();
@@ -335,7 +465,7 @@ impl<'p> Parser<'p> {
                 if self.matches(SyntaxKind::LeftBracket) {
                     loop {
                         self.type_();
-                        if !self.matches(SyntaxKind::Comma) {
+                        if !self.matches_list_comma(SyntaxKind::RightBracket) {
                             break;
                         }
                     }
@@ -346,13 +476,21 @@ impl<'p> Parser<'p> {
             }
 
             // Read inner
-            SyntaxKind::Tilde | SyntaxKind::Star => self.type_(),
+            SyntaxKind::Tilde | SyntaxKind::Star | SyntaxKind::Weak => self.type_(),
+
+            // Fixed-size array, like `[f32; 4]`
+            SyntaxKind::LeftBracket => {
+                self.type_();
+                self.consume(SyntaxKind::Semicolon, "';'", "array element type");
+                self.consume(SyntaxKind::Int, "array length", "';'");
+                self.consume(SyntaxKind::RightBracket, "']'", "array length");
+            }
 
             SyntaxKind::LeftParen => {
                 if !self.check(SyntaxKind::RightParen) {
                     loop {
                         self.type_();
-                        if !self.matches(SyntaxKind::Comma) {
+                        if !self.matches_list_comma(SyntaxKind::RightParen) {
                             break;
                         }
                     }
@@ -396,7 +534,7 @@ const CLASS_CONF: ADTConfig = ADTConfig {
 
 const IFACE_CONF: ADTConfig = ADTConfig {
     name: "interface",
-    modifiers: &[],
+    modifiers: &[SyntaxKind::Sealed],
     has_members: false,
     has_constructors: false,
     has_cases: false,
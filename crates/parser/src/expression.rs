@@ -1,5 +1,5 @@
-use crate::Parser;
-use error::GErr;
+use crate::{precedence, util::builder::Checkpoint, Lexeme, Parser};
+use error::{Error, ErrorSpan, GErr};
 use syntax::kind::SyntaxKind;
 
 impl<'p> Parser<'p> {
@@ -9,29 +9,61 @@ impl<'p> Parser<'p> {
     fn higher_expression(&mut self) {
         match self.peek() {
             SyntaxKind::Var | SyntaxKind::Val => self.variable(),
+            SyntaxKind::Defer => self.defer(),
+            SyntaxKind::Func => self.nested_function(),
             _ => self.expression(),
         }
     }
 
+    fn defer(&mut self) {
+        self.start_node(SyntaxKind::DeferExpr);
+        self.advance(); // Consume 'defer'
+        self.expression();
+        self.end_node();
+    }
+
     fn variable(&mut self) {
+        if self.check_next(SyntaxKind::LeftParen) {
+            return self.destructure();
+        }
+
         self.start_node(SyntaxKind::Variable);
         self.advance(); // Consume 'var' or 'val'
         self.consume(SyntaxKind::Identifier, "variable name", "var/val");
+        if self.matches(SyntaxKind::Colon) {
+            self.type_();
+        }
         self.consume(SyntaxKind::Equal, "'='", "variable name");
         self.node_with(SyntaxKind::Initializer, Self::expression);
         self.end_node();
     }
 
-    pub fn expression(&mut self) {
-        match self.peek() {
-            SyntaxKind::LeftBrace => self.block(),
-            SyntaxKind::If => self.if_expression(),
-            SyntaxKind::For => self.for_expression(),
-            SyntaxKind::Return => self.ret_or_break_expr(SyntaxKind::ReturnExpr),
-            SyntaxKind::Break => self.ret_or_break_expr(SyntaxKind::BreakExpr),
-            SyntaxKind::When => self.when_expression(),
-            _ => self.binary(0),
+    fn destructure(&mut self) {
+        self.start_node(SyntaxKind::Destructure);
+        self.advance(); // Consume 'var' or 'val'
+        self.advance(); // Consume '('
+        loop {
+            self.consume(SyntaxKind::Identifier, "variable name", "'('");
+            if !self.matches_list_comma(SyntaxKind::RightParen) {
+                break;
+            }
         }
+        self.consume(SyntaxKind::RightParen, "')'", "variable names");
+        self.consume(SyntaxKind::Equal, "'='", "variable names");
+        self.node_with(SyntaxKind::Initializer, Self::expression);
+        self.end_node();
+    }
+
+    pub fn expression(&mut self) {
+        self.nested(|this| match this.peek() {
+            SyntaxKind::LeftBrace => this.block(),
+            SyntaxKind::If => this.if_expression(),
+            SyntaxKind::For => this.for_expression(),
+            SyntaxKind::Return => this.ret_or_break_expr(SyntaxKind::ReturnExpr),
+            SyntaxKind::Break => this.ret_or_break_expr(SyntaxKind::BreakExpr),
+            SyntaxKind::When => this.when_expression(),
+            _ => this.binary(0),
+        })
     }
 
     fn block(&mut self) {
@@ -130,34 +162,79 @@ impl<'p> Parser<'p> {
         let checkpoint = self.checkpoint();
         self.unary();
 
-        while let Some((lbp, rbp)) = self.peek().infix_binding_power() {
+        let mut previous: Option<Lexeme> = None;
+        while let Some((lbp, rbp)) = precedence::infix_binding_power(self.peek()) {
             if lbp < minimum_binding_power {
                 return;
             }
+            // An operator that can also start an expression, like '-',
+            // begins the next expression when it is the first on its line.
+            // To continue an expression over multiple lines, end the line with the operator.
+            if precedence::prefix_binding_power(self.peek()).is_some() && self.starts_line() {
+                return;
+            }
+
+            let operator = self.source.get_current().unwrap();
+            if let Some(previous) = previous {
+                if precedence::is_ambiguous(previous.kind, operator.kind) {
+                    self.ambiguous_operators(previous, operator);
+                }
+            }
+            previous = Some(operator);
 
             self.node_with(SyntaxKind::Operator, |this| {
                 this.advance();
             });
 
             self.start_node_at(checkpoint, SyntaxKind::BinaryExpr);
-            self.binary(rbp);
+            match self.peek() {
+                // Early exits are only allowed as fallback, like `a ?? return b`
+                SyntaxKind::Return if operator.kind == SyntaxKind::QuestionQuestion => {
+                    self.ret_or_break_expr(SyntaxKind::ReturnExpr)
+                }
+                SyntaxKind::Break if operator.kind == SyntaxKind::QuestionQuestion => {
+                    self.ret_or_break_expr(SyntaxKind::BreakExpr)
+                }
+                _ => self.binary(rbp),
+            }
             self.end_node();
         }
     }
 
     fn unary(&mut self) {
-        if let Some(rbp) = self.peek().prefix_binding_power() {
+        if let Some(rbp) = precedence::prefix_binding_power(self.peek()) {
             self.start_node(SyntaxKind::PrefixExpr);
+            let operator = self.source.get_current().unwrap();
             self.node_with(SyntaxKind::Operator, |this| {
                 this.advance();
             });
-            self.binary(rbp);
+            self.nested(|this| this.binary(rbp));
             self.end_node();
+
+            // `!a is B` would negate `a`, not the check
+            if self.check(SyntaxKind::Is) {
+                let is = self.source.get_current().unwrap();
+                self.ambiguous_operators(operator, is);
+            }
         } else {
             self.call();
         }
     }
 
+    /// Reports the current operator being ambiguous after the given one.
+    /// Unlike other errors, this does not prevent parsing the rest of the expression.
+    fn ambiguous_operators(&mut self, first: Lexeme, second: Lexeme) {
+        if !self.poisoned {
+            self.errors.push(Error {
+                index: ErrorSpan::Token(self.source.position()),
+                kind: GErr::E014 {
+                    first: first.lexeme.into(),
+                    second: second.lexeme.into(),
+                },
+            });
+        }
+    }
+
     fn call(&mut self) {
         let checkpoint = self.checkpoint();
         self.primary();
@@ -181,7 +258,7 @@ impl<'p> Parser<'p> {
                     if !self.check(SyntaxKind::RightParen) {
                         loop {
                             self.node_with(SyntaxKind::CallArgument, Self::expression);
-                            if !self.matches(SyntaxKind::Comma) {
+                            if !self.matches_list_comma(SyntaxKind::RightParen) {
                                 break;
                             }
                         }
@@ -202,7 +279,17 @@ impl<'p> Parser<'p> {
                     self.end_node();
 
                     self.advance(); // Consume '.'
-                    self.identifier();
+                    match self.peek() {
+                        SyntaxKind::Int | SyntaxKind::Float => self.tuple_index(checkpoint),
+                        _ => self.member_identifier(),
+                    }
+                    self.end_node();
+                }
+
+                // Same as with calls, `a ?` is not allowed
+                SyntaxKind::QuestionMark if !self.last_was_whitespace() => {
+                    self.start_node_at(checkpoint, SyntaxKind::TryExpr);
+                    self.advance(); // Consume '?'
                     self.end_node();
                 }
 
@@ -212,7 +299,7 @@ impl<'p> Parser<'p> {
                     self.end_node();
 
                     self.advance(); // Consume ':'
-                    self.consume(SyntaxKind::Identifier, "property name", "':'");
+                    self.consume_member_name("property name", "':'");
                     self.end_node();
                 }
 
@@ -234,6 +321,8 @@ impl<'p> Parser<'p> {
                 self.end_node();
             }
             SyntaxKind::LeftParen => self.grouping_or_closure(),
+            SyntaxKind::LeftBracket => self.array_literal(),
+            SyntaxKind::At => self.annotated_closure(),
             SyntaxKind::Identifier => self.identifier(),
             _ => self.error_at_current(GErr::E008),
         }
@@ -242,41 +331,149 @@ impl<'p> Parser<'p> {
     fn identifier(&mut self) {
         self.start_node(SyntaxKind::Ident);
         self.advance();
+        self.type_arguments();
+        self.end_node()
+    }
 
+    /// Reads the name of a field or method after '.', which can be a keyword.
+    fn member_identifier(&mut self) {
+        self.start_node(SyntaxKind::Ident);
+        self.consume_member_name("property name", "'.'");
+        self.type_arguments();
+        self.end_node()
+    }
+
+    fn type_arguments(&mut self) {
         if self.matches(SyntaxKind::LeftBracket) {
             loop {
                 self.type_();
-                if !self.matches(SyntaxKind::Comma) {
+                if !self.matches_list_comma(SyntaxKind::RightBracket) {
                     break;
                 }
             }
             self.consume(SyntaxKind::RightBracket, "']'", "type parameters");
         }
-        self.end_node()
+    }
+
+    /// Reads a tuple index like the `0` in `tuple.0`.
+    /// It is stored as an identifier, allowing it to be treated like a field name.
+    /// Nested indices like `tuple.0.1` are lexed as a float and split into 2 gets.
+    fn tuple_index(&mut self, checkpoint: Checkpoint) {
+        let Lexeme { lexeme, .. } = self.source.get_current().unwrap();
+        self.source.next();
+
+        let mut indices = lexeme.splitn(2, '.');
+        self.index_ident(indices.next().unwrap());
+        if let Some(inner) = indices.next() {
+            self.end_node(); // End the outer get
+            self.start_node_at(checkpoint, SyntaxKind::Callee);
+            self.start_node_at(checkpoint, SyntaxKind::GetExpr);
+            self.end_node();
+            self.builder.token(SyntaxKind::Dot, ".".into());
+            self.index_ident(inner);
+        }
+    }
+
+    fn index_ident(&mut self, index: &str) {
+        self.builder.start_node(SyntaxKind::Ident);
+        self.builder.token(SyntaxKind::Identifier, index.into());
+        self.builder.end_node();
     }
 
     fn grouping_or_closure(&mut self) {
         let checkpoint = self.checkpoint();
         self.advance(); // Consume '('
 
-        if (self.check(SyntaxKind::Identifier)
-            && (self.check_next(SyntaxKind::Colon) || self.check_next(SyntaxKind::Comma)))
-            || self.check(SyntaxKind::RightParen)
-        {
+        if self.is_closure_start() {
             self.start_node_at(checkpoint, SyntaxKind::ClosureLiteral);
             self.closure()
         } else {
-            self.start_node_at(checkpoint, SyntaxKind::Grouping);
-            self.grouping()
+            self.grouping_or_tuple(checkpoint)
         }
     }
 
-    fn grouping(&mut self) {
+    /// Parses a closure literal with annotations, like `@weak_this () -> ...`.
+    fn annotated_closure(&mut self) {
+        let checkpoint = self.checkpoint();
+        self.consume_annotations();
+        if self.matches(SyntaxKind::LeftParen) && self.is_closure_start() {
+            self.start_node_at(checkpoint, SyntaxKind::ClosureLiteral);
+            self.closure()
+        } else {
+            self.error_at_current(GErr::E009);
+        }
+    }
+
+    /// Checks if the current token, placed right after a '(',
+    /// starts the parameters of a closure literal.
+    fn is_closure_start(&mut self) -> bool {
+        (self.check(SyntaxKind::Identifier)
+            && (self.check_next(SyntaxKind::Colon)
+                || (self.check_next(SyntaxKind::Comma) && self.is_closure_params())))
+            || self.check(SyntaxKind::RightParen)
+    }
+
+    fn grouping_or_tuple(&mut self, checkpoint: Checkpoint) {
         self.expression();
+        if self.check(SyntaxKind::Comma) {
+            self.start_node_at(checkpoint, SyntaxKind::TupleLiteral);
+            while self.matches(SyntaxKind::Comma) {
+                self.expression();
+            }
+        } else {
+            self.start_node_at(checkpoint, SyntaxKind::Grouping);
+        }
         self.consume(SyntaxKind::RightParen, "')'", "expression");
         self.end_node();
     }
 
+    /// Parses a fixed-size array literal, either a list of elements like `[1, 2, 3]`
+    /// or a single value repeated for the given length like `[0; 16]`.
+    fn array_literal(&mut self) {
+        let checkpoint = self.checkpoint();
+        self.advance(); // Consume '['
+        self.expression();
+        if self.matches(SyntaxKind::Semicolon) {
+            self.start_node_at(checkpoint, SyntaxKind::ArrayRepeatLiteral);
+            self.node_with(SyntaxKind::Literal, |this| {
+                this.consume(SyntaxKind::Int, "array length", "';'")
+            });
+        } else {
+            self.start_node_at(checkpoint, SyntaxKind::ArrayLiteral);
+            while self.matches_list_comma(SyntaxKind::RightBracket) {
+                self.expression();
+            }
+        }
+        self.consume(SyntaxKind::RightBracket, "']'", "array literal");
+        self.end_node();
+    }
+
+    /// Checks if the parenthesis the current token is in is followed by
+    /// a closure body or return type, meaning it contains closure parameters
+    /// and not a tuple literal like `(a, b)`.
+    fn is_closure_params(&mut self) -> bool {
+        self.source.save();
+        let mut depth = 1;
+        while depth > 0 {
+            match self.peek_raw() {
+                Some(SyntaxKind::LeftParen) => depth += 1,
+                Some(SyntaxKind::RightParen) => depth -= 1,
+                None => break,
+                _ => (),
+            }
+            self.source.next();
+        }
+        while self.peek_raw().map(|k| k.should_skip()) == Some(true) {
+            self.source.next();
+        }
+        let res = matches!(
+            self.peek_raw(),
+            Some(SyntaxKind::Arrow) | Some(SyntaxKind::Colon)
+        );
+        self.source.restore();
+        res
+    }
+
     fn closure(&mut self) {
         self.start_node(SyntaxKind::FunctionSignature);
         self.func_parameters();
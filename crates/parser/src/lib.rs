@@ -1,6 +1,7 @@
 mod declaration;
 mod expression;
 mod nodes;
+mod precedence;
 mod util;
 
 use crate::util::{
@@ -13,6 +14,11 @@ use lexer::Lexer;
 pub use nodes::*;
 use syntax::kind::SyntaxKind;
 
+/// The maximum nesting depth of expressions and types.
+/// Deeper nesting is reported as an error instead of overflowing the stack
+/// of the recursive parser and later compilation stages.
+const MAX_NESTING: usize = 256;
+
 pub fn parse(input: &str) -> Result<ParseResult, Vec<Error>> {
     let lexer = Lexer::new(input);
     let lexemes = lexer
@@ -44,11 +50,14 @@ struct Parser<'p> {
 
     /// Stores the modifiers of the current global declaration.
     modifiers: Vec<SyntaxKind>,
+    /// How deeply nested the expression or type currently parsed is.
+    nesting: usize,
 }
 
 impl<'p> Parser<'p> {
     fn parse(mut self) -> Result<ParseResult, Vec<Error>> {
         bench!("parser", {
+            self.invalid_tokens();
             while self.peek() != SyntaxKind::EndOfFile {
                 self.declaration();
                 if self.poisoned {
@@ -75,6 +84,13 @@ impl<'p> Parser<'p> {
         matches
     }
 
+    /// Consumes the comma after an item of a list ending with the given token.
+    /// Returns if another item follows, which is not the case
+    /// at the end of the list, even after a trailing comma.
+    fn matches_list_comma(&mut self, end: SyntaxKind) -> bool {
+        self.matches(SyntaxKind::Comma) && !self.check(end)
+    }
+
     /// Same as matches, but matches separators between expressions (only `;` currently)
     fn matches_separator(&mut self) -> bool {
         self.matches(SyntaxKind::Semicolon)
@@ -86,6 +102,19 @@ impl<'p> Parser<'p> {
         }
     }
 
+    /// Consumes the name of a member like a field or method.
+    /// Unlike other names, members can be named like keywords, as they are
+    /// only ever used after '.' or ':', where keywords are not valid.
+    fn consume_member_name(&mut self, want: &'static str, after: &'static str) {
+        if self.check(SyntaxKind::Identifier) || self.peek().is_keyword() {
+            let Lexeme { lexeme, .. } = self.source.get_current().unwrap();
+            self.source.next();
+            self.builder.token(SyntaxKind::Identifier, lexeme.into());
+        } else {
+            self.error_at_current(GErr::E001 { want, after });
+        }
+    }
+
     fn consume_either(
         &mut self,
         kind1: SyntaxKind,
@@ -114,8 +143,32 @@ impl<'p> Parser<'p> {
         self.poisoned = true;
     }
 
+    /// Reports all invalid tokens produced by the lexer.
+    /// The parser skips them afterwards, so that the rest of the
+    /// source is still parsed and kept in the syntax tree.
+    fn invalid_tokens(&mut self) {
+        let errors = self
+            .source
+            .lexemes()
+            .iter()
+            .enumerate()
+            .filter(|(_, lexeme)| lexeme.kind == SyntaxKind::Error)
+            .map(|(index, lexeme)| Error {
+                index: ErrorSpan::Token(index),
+                kind: GErr::E013(if lexeme.lexeme.starts_with('"') {
+                    "unterminated string"
+                } else if lexeme.lexeme.starts_with("/*") {
+                    "unterminated comment"
+                } else {
+                    "unexpected character"
+                }),
+            });
+        self.errors.extend(errors);
+    }
+
     fn try_depoison(&mut self) {
         let recoverable = &[
+            SyntaxKind::At,
             SyntaxKind::Enum,
             SyntaxKind::Class,
             SyntaxKind::Func,
@@ -136,6 +189,12 @@ impl<'p> Parser<'p> {
         self.peek() == kind
     }
 
+    /// Is the current token an identifier with the given name?
+    /// Used for contextual keywords, which are regular identifiers elsewhere.
+    fn check_ident(&mut self, name: &str) -> bool {
+        self.check(SyntaxKind::Identifier) && self.source.get_current().unwrap().lexeme == name
+    }
+
     /// Is the next token the given kind?
     fn check_next(&mut self, kind: SyntaxKind) -> bool {
         self.peek_next() == kind
@@ -179,6 +238,16 @@ impl<'p> Parser<'p> {
         ret
     }
 
+    /// If the current token is the first on its line.
+    fn starts_line(&mut self) -> bool {
+        self.skip_whitespace();
+        self.source.lexemes()[..self.source.position()]
+            .iter()
+            .rev()
+            .take_while(|lexeme| lexeme.kind.should_skip())
+            .any(|lexeme| lexeme.lexeme.contains('\n'))
+    }
+
     fn last_was_whitespace(&mut self) -> bool {
         self.source.get_last().kind.should_skip()
     }
@@ -203,11 +272,50 @@ impl<'p> Parser<'p> {
         self.end_node()
     }
 
+    /// Runs the given parsing function one nesting level deeper.
+    /// Reports an error instead if that exceeds the maximum nesting depth.
+    fn nested<T: FnOnce(&mut Self)>(&mut self, content: T) {
+        if self.nesting >= MAX_NESTING {
+            self.error_at_current(GErr::E010);
+            return;
+        }
+        self.nesting += 1;
+        content(self);
+        self.nesting -= 1;
+    }
+
     fn start_node(&mut self, kind: SyntaxKind) {
         self.skip_whitespace();
         self.builder.start_node(kind);
     }
 
+    /// Starts a node of a declaration inside another declaration.
+    /// Like top-level declarations, it contains the documentation
+    /// comments directly in front of it.
+    fn start_documented_node(&mut self, kind: SyntaxKind) {
+        let lexemes = self.source.lexemes();
+        let start = self.source.position();
+        let end = lexemes[start..]
+            .iter()
+            .position(|lexeme| !lexeme.kind.should_skip())
+            .map_or(lexemes.len(), |len| start + len);
+
+        let mut doc_start = end;
+        for index in (start..end).rev() {
+            match lexemes[index].kind {
+                SyntaxKind::DocComment => doc_start = index,
+                SyntaxKind::Whitespace => (),
+                _ => break,
+            }
+        }
+        while self.source.position() < doc_start {
+            self.advance_inner();
+        }
+
+        self.builder.start_node(kind);
+        self.skip_whitespace();
+    }
+
     fn start_node_at(&mut self, checkpoint: Checkpoint, kind: SyntaxKind) {
         self.builder.start_node_at(kind, checkpoint);
         self.skip_whitespace();
@@ -228,6 +336,7 @@ impl<'p> Parser<'p> {
             errors: vec![],
             poisoned: false,
             modifiers: Vec::with_capacity(4),
+            nesting: 0,
         }
     }
 }
@@ -0,0 +1,67 @@
+//! The precedence of operators, from loosest to tightest binding:
+//!
+//! ```text
+//! =                        right
+//! ??                       right
+//! or                       left
+//! and                      left
+//! == != < <= > >=          none
+//! |                        left
+//! &                        left
+//! + -                      left
+//! * / %                    left
+//! is                       none
+//! - ! (prefix)
+//! ```
+//!
+//! Operators without associativity cannot be chained without parentheses,
+//! as in `a < b < c`; this is reported as an error, as is a prefix operator
+//! directly in front of `is`, which would apply to the value and not the check.
+
+use syntax::kind::SyntaxKind;
+
+/// The binding power of an infix operator on its left and right side.
+/// Left-associative operators bind more tightly to the right,
+/// right-associative operators more tightly to the left.
+pub(crate) fn infix_binding_power(kind: SyntaxKind) -> Option<(u8, u8)> {
+    Some(match kind {
+        SyntaxKind::Equal => (6, 5),
+        SyntaxKind::QuestionQuestion => (8, 7),
+        SyntaxKind::Or => (9, 10),
+        SyntaxKind::And => (11, 12),
+        _ if is_comparison(kind) => (13, 14),
+        SyntaxKind::Pipe => (15, 16),
+        SyntaxKind::Ampersand => (17, 18),
+        SyntaxKind::Plus | SyntaxKind::Minus => (19, 20),
+        SyntaxKind::Star | SyntaxKind::Slash | SyntaxKind::Percent => (21, 22),
+        SyntaxKind::Is => (23, 24),
+        _ => return None,
+    })
+}
+
+/// The binding power of a prefix operator on its operand.
+pub(crate) fn prefix_binding_power(kind: SyntaxKind) -> Option<u8> {
+    Some(match kind {
+        SyntaxKind::Minus | SyntaxKind::Bang => 30,
+        _ => return None,
+    })
+}
+
+/// If the two operators are ambiguous when following each other
+/// without parentheses, like `a == b < c`.
+pub(crate) fn is_ambiguous(first: SyntaxKind, second: SyntaxKind) -> bool {
+    (is_comparison(first) && is_comparison(second))
+        || (first == SyntaxKind::Is && second == SyntaxKind::Is)
+}
+
+fn is_comparison(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::EqualEqual
+            | SyntaxKind::BangEqual
+            | SyntaxKind::Less
+            | SyntaxKind::LessEqual
+            | SyntaxKind::Greater
+            | SyntaxKind::GreaterEqual
+    )
+}
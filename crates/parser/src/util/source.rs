@@ -15,6 +15,10 @@ impl<'s> Source<'s> {
         self.lexemes.get(self.current - 1).copied().unwrap()
     }
 
+    pub fn lexemes(&self) -> &'s [Lexeme<'s>] {
+        self.lexemes
+    }
+
     pub fn position(&self) -> usize {
         self.current
     }
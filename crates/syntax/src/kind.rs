@@ -16,6 +16,15 @@ pub enum SyntaxKind {
     AdtDecl,
     /// A top-level interface implementation declaration
     ImplDecl,
+    /// A top-level module initialization block, like 'init { ... }'
+    InitDecl,
+    /// A top-level global variable, like 'lazy val a = 5'
+    GlobalDecl,
+
+    /// A list of names in parentheses inside an import, like '(a, b)'
+    ImportList,
+    /// The names excluded from a wildcard import, like 'hiding (a, b)'
+    ImportHiding,
 
     /// An identifier of a declaration, containing type parameters.
     Ident,
@@ -23,6 +32,8 @@ pub enum SyntaxKind {
     TypeParameter,
     /// A modifier on a declaration.
     Modifier,
+    /// An annotation on a declaration, like '@derive(Hash)'.
+    Annotation,
 
     /// A function signature.
     FunctionSignature,
@@ -49,6 +60,8 @@ pub enum SyntaxKind {
 
     /// A variable declaration like 'var x: String = "hello"'
     Variable,
+    /// A destructuring variable declaration like 'val (a, b) = tuple'
+    Destructure,
     /// An initializer of a variable or member
     Initializer,
     /// A block containing other expressions
@@ -64,6 +77,8 @@ pub enum SyntaxKind {
     ReturnExpr,
     /// A break expression
     BreakExpr,
+    /// A defer expression, running its inner expression on scope exit
+    DeferExpr,
     /// A when expression
     WhenExpr,
     /// A when branch, containing 1 ExprCondition (missing on else) and 1 ExprBody
@@ -89,6 +104,8 @@ pub enum SyntaxKind {
     GetNullableExpr,
     /// A static get expression ('x:y', 'Callee:Ident')
     GetStaticExpr,
+    /// An error propagation expression ('x?')
+    TryExpr,
     /// Callee of a call or get expression
     Callee,
     /// Argument of a call expression
@@ -99,8 +116,14 @@ pub enum SyntaxKind {
     ClosureLiteral,
     /// A grouping expression, simply '($expr)'
     Grouping,
-
-    /// A type literal like "String", "String?", "(u32, u32): u64"
+    /// A tuple literal like '(5, "hello")'
+    TupleLiteral,
+    /// A fixed-size array literal like '[1, 2, 3]'
+    ArrayLiteral,
+    /// A fixed-size array literal repeating a value like '[0; 16]'
+    ArrayRepeatLiteral,
+
+    /// A type literal like "String", "String?", "(u32, u32): u64", "[f32; 4]"
     Type,
 
     /// This special variant is used for SyntaxKind::is_token.
@@ -122,8 +145,12 @@ pub enum SyntaxKind {
     ColonColon,
     Slash,
     Star,
+    Percent,
+    Pipe,
+    Ampersand,
     Arrow,
     QuestionMark,
+    At,
 
     Bang,
     BangEqual,
@@ -145,6 +172,7 @@ pub enum SyntaxKind {
     Break,
     Class,
     Construct,
+    Defer,
     Else,
     Enum,
     Export,
@@ -164,44 +192,37 @@ pub enum SyntaxKind {
     Var,
     Val,
     When,
+    Weak,
 
     Mod,
     Priv,
     Extern,
     Value,
     Variadic,
+    Lazy,
+    Sealed,
 
     Error,
     Comment,
+    DocComment,
     Whitespace,
     EndOfFile,
 }
 
 impl SyntaxKind {
+    /// If the parser should skip tokens of this kind, which are kept in the
+    /// syntax tree without being part of any node. Invalid tokens are also
+    /// skipped, as the parser reports them before parsing.
     pub fn should_skip(&self) -> bool {
-        matches!(self, Self::Whitespace | Self::Comment)
-    }
-
-    pub fn infix_binding_power(&self) -> Option<(u8, u8)> {
-        Some(match self {
-            Self::Equal => (6, 5),
-            Self::QuestionQuestion => (8, 7),
-            Self::Or => (10, 9),
-            Self::And => (12, 11),
-            Self::BangEqual | Self::EqualEqual => (14, 13),
-            Self::Less | Self::LessEqual | Self::Greater | Self::GreaterEqual => (16, 15),
-            Self::Plus | Self::Minus => (16, 15),
-            Self::Star | Self::Slash => (18, 17),
-            Self::Is => (20, 19),
-            _ => return None,
-        })
+        matches!(
+            self,
+            Self::Whitespace | Self::Comment | Self::DocComment | Self::Error
+        )
     }
 
-    pub fn prefix_binding_power(&self) -> Option<u8> {
-        Some(match self {
-            Self::Minus | Self::Bang => 30,
-            _ => return None,
-        })
+    /// If this is a keyword like 'when' or 'class'.
+    pub fn is_keyword(&self) -> bool {
+        (*self as u16) >= (SyntaxKind::And as u16) && (*self as u16) <= (SyntaxKind::Sealed as u16)
     }
 
     pub fn is_token(&self) -> bool {